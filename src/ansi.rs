@@ -0,0 +1,119 @@
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+const ESCAPE: u8 = 0x1b;
+const BELL: u8 = 0x07;
+
+/// Remove ANSI escape sequences from a line of input. In addition to the SGR sequences that set
+/// colors, this removes the other control sequences that colored input may contain, such as
+/// erase-line sequences and OSC 8 hyperlinks, so that they cannot interfere with parsing.
+pub fn strip_ansi_codes(s: &str) -> Cow<'_, str> {
+    if !s.as_bytes().contains(&ESCAPE) {
+        return Cow::Borrowed(s);
+    }
+    let bytes = s.as_bytes();
+    let mut stripped = String::with_capacity(s.len());
+    let mut i = 0;
+    while let Some(offset) = bytes[i..].iter().position(|&byte| byte == ESCAPE) {
+        stripped.push_str(&s[i..i + offset]);
+        i += offset;
+        i += escape_sequence_length(&bytes[i..]);
+    }
+    stripped.push_str(&s[i..]);
+    Cow::Owned(stripped)
+}
+
+/// Return the number of terminal columns occupied by a string containing ANSI escape sequences.
+/// Unlike summing the widths of its characters, this measures each grapheme cluster as a unit, so
+/// that e.g. an emoji followed by a skin tone modifier or a variation selector is measured as the
+/// single wide glyph that terminals display.
+pub fn measure_text_width(s: &str) -> usize {
+    strip_ansi_codes(s)
+        .graphemes(true)
+        .map(|grapheme| {
+            if grapheme.contains('\u{fe0f}') {
+                // Emoji presentation selector
+                2
+            } else {
+                grapheme
+                    .chars()
+                    .filter_map(|c| c.width())
+                    .max()
+                    .unwrap_or(0)
+            }
+        })
+        .sum()
+}
+
+/// Return the length in bytes of the escape sequence at the start of `bytes`, which starts with
+/// ESC. An unterminated sequence extends to the end of `bytes`.
+pub fn escape_sequence_length(bytes: &[u8]) -> usize {
+    match bytes.get(1) {
+        // CSI: parameters and intermediate bytes, then a final byte, e.g. "ESC[1;31m" or
+        // "ESC[38:2::255:0:0m"
+        Some(b'[') => match bytes[2..]
+            .iter()
+            .position(|byte| (0x40..=0x7e).contains(byte))
+        {
+            Some(j) => j + 3,
+            None => bytes.len(),
+        },
+        // OSC, terminated by BEL or ST ("ESC\"), e.g. an OSC 8 hyperlink
+        Some(b']') => {
+            let mut j = 2;
+            while j < bytes.len() {
+                if bytes[j] == BELL {
+                    return j + 1;
+                } else if bytes[j] == ESCAPE && bytes.get(j + 1) == Some(&b'\\') {
+                    return j + 2;
+                }
+                j += 1;
+            }
+            bytes.len()
+        }
+        // Character set selection, e.g. "ESC(B"
+        Some(b'(') | Some(b')') => bytes.len().min(3),
+        Some(_) => 2,
+        None => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi_codes() {
+        for (s, expected) in &[
+            ("plain", "plain"),
+            ("\x1b[1;31m-a\x1b[m", "-a"),
+            ("\x1b[38:2::255:0:0m+b\x1b[0m\x1b[K", "+b"),
+            (
+                "\x1b]8;;file:///src/a.rs\x1b\\src/a.rs\x1b]8;;\x1b\\",
+                "src/a.rs",
+            ),
+            ("\x1b]8;;https://x.y\x07link\x1b]8;;\x07", "link"),
+            ("\x1b(Bdiff --git", "diff --git"),
+            ("unterminated \x1b[", "unterminated "),
+        ] {
+            assert_eq!(strip_ansi_codes(s), *expected);
+        }
+    }
+
+    #[test]
+    fn test_measure_text_width() {
+        for (s, expected) in &[
+            ("\x1b[31m│\x1b[0m", 1),
+            ("｜", 2),
+            ("👍", 2),
+            ("👍🏽", 2),
+            ("❤\u{fe0f}", 2),
+            ("e\u{301}", 1),
+            ("\x1b]8;;https://x.y\x1b\\12\x1b]8;;\x1b\\", 2),
+        ] {
+            assert_eq!(measure_text_width(s), *expected, "{}", s);
+        }
+    }
+}
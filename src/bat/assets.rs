@@ -7,6 +7,7 @@ use std::path::PathBuf;
 
 use ansi_term::Colour::Green;
 use ansi_term::Style;
+use lazycell::LazyCell;
 use syntect::dumps::{from_binary, from_reader};
 use syntect::highlighting::ThemeSet;
 use syntect::parsing::SyntaxSet;
@@ -15,13 +16,43 @@ use crate::bat::dirs::PROJECT_DIRS;
 use crate::errors::*;
 
 pub struct HighlightingAssets {
-    pub syntax_set: SyntaxSet,
+    pub syntax_set: LazySyntaxSet,
     pub theme_set: ThemeSet,
 }
 
+/// A syntax set that is not deserialized until it is first used. Deserializing the full syntax set
+/// is the most expensive part of delta's startup, and many invocations (e.g. --show-config, or a
+/// diff rendered without syntax highlighting) never need it.
+#[derive(Clone, Debug, Default)]
+pub struct LazySyntaxSet(LazyCell<SyntaxSet>);
+
+impl LazySyntaxSet {
+    pub fn get(&self) -> &SyntaxSet {
+        self.0.borrow_with(|| {
+            HighlightingAssets::syntax_set_from_cache()
+                .unwrap_or_else(|_| HighlightingAssets::get_integrated_syntaxset())
+        })
+    }
+
+    #[cfg(test)]
+    pub fn is_loaded(&self) -> bool {
+        self.0.filled()
+    }
+}
+
+impl Default for HighlightingAssets {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HighlightingAssets {
     pub fn new() -> Self {
-        Self::from_cache().unwrap_or_else(|_| Self::from_binary())
+        HighlightingAssets {
+            syntax_set: LazySyntaxSet::default(),
+            theme_set: Self::theme_set_from_cache()
+                .unwrap_or_else(|_| Self::get_integrated_themeset()),
+        }
     }
 
     fn get_integrated_syntaxset() -> SyntaxSet {
@@ -32,40 +63,26 @@ impl HighlightingAssets {
         from_binary(include_bytes!("../../assets/themes.bin"))
     }
 
-    fn from_cache() -> Result<Self> {
-        let theme_set_path = theme_set_path();
+    fn syntax_set_from_cache() -> Result<SyntaxSet> {
         let syntax_set_file = File::open(&syntax_set_path()).chain_err(|| {
             format!(
                 "Could not load cached syntax set '{}'",
                 syntax_set_path().to_string_lossy()
             )
         })?;
-        let syntax_set: SyntaxSet = from_reader(BufReader::new(syntax_set_file))
-            .chain_err(|| "Could not parse cached syntax set")?;
+        from_reader(BufReader::new(syntax_set_file))
+            .chain_err(|| "Could not parse cached syntax set")
+    }
 
+    fn theme_set_from_cache() -> Result<ThemeSet> {
+        let theme_set_path = theme_set_path();
         let theme_set_file = File::open(&theme_set_path).chain_err(|| {
             format!(
                 "Could not load cached theme set '{}'",
                 theme_set_path.to_string_lossy()
             )
         })?;
-        let theme_set: ThemeSet = from_reader(BufReader::new(theme_set_file))
-            .chain_err(|| "Could not parse cached theme set")?;
-
-        Ok(HighlightingAssets {
-            syntax_set,
-            theme_set,
-        })
-    }
-
-    fn from_binary() -> Self {
-        let syntax_set = Self::get_integrated_syntaxset();
-        let theme_set = Self::get_integrated_themeset();
-
-        HighlightingAssets {
-            syntax_set,
-            theme_set,
-        }
+        from_reader(BufReader::new(theme_set_file)).chain_err(|| "Could not parse cached theme set")
     }
 }
 
@@ -81,6 +98,7 @@ pub fn list_languages() -> std::io::Result<()> {
     let assets = HighlightingAssets::new();
     let mut languages = assets
         .syntax_set
+        .get()
         .syntaxes()
         .iter()
         .filter(|syntax| !syntax.hidden && !syntax.file_extensions.is_empty())
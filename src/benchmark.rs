@@ -0,0 +1,171 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use bytelines::ByteLines;
+
+use crate::config::Config;
+use crate::delta::delta;
+
+/// The stages of rendering that are timed separately. Time not spent in any of these (reading and
+/// decoding input lines and running the state machine) is reported as "parse".
+#[derive(Clone, Copy)]
+pub enum Phase {
+    Highlight,
+    Edits,
+    Paint,
+    Emit,
+}
+
+#[derive(Default)]
+struct Timings {
+    highlight: Duration,
+    edits: Duration,
+    paint: Duration,
+    emit: Duration,
+}
+
+thread_local! {
+    static TIMINGS: RefCell<Option<Timings>> = RefCell::new(None);
+}
+
+/// Call `f`, attributing the time taken to `phase` if a benchmark is in progress.
+pub fn timed<F, T>(phase: Phase, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    if !TIMINGS.with(|timings| timings.borrow().is_some()) {
+        return f();
+    }
+    let start = Instant::now();
+    let value = f();
+    let elapsed = start.elapsed();
+    TIMINGS.with(|timings| {
+        if let Some(timings) = timings.borrow_mut().as_mut() {
+            let duration = match phase {
+                Phase::Highlight => &mut timings.highlight,
+                Phase::Edits => &mut timings.edits,
+                Phase::Paint => &mut timings.paint,
+                Phase::Emit => &mut timings.emit,
+            };
+            *duration += elapsed;
+        }
+    });
+    value
+}
+
+pub struct BenchmarkReport {
+    pub iterations: usize,
+    pub n_lines: usize,
+    pub n_bytes: usize,
+    pub parse: Duration,
+    pub highlight: Duration,
+    pub edits: Duration,
+    pub paint: Duration,
+    pub emit: Duration,
+    pub total: Duration,
+}
+
+/// Render the diff in `path` `iterations` times to a null sink and print a timing breakdown.
+pub fn benchmark(path: &Path, iterations: usize, config: &Config) -> std::io::Result<()> {
+    let input = fs::read(path)?;
+    let report = run_benchmark(&input, iterations, config)?;
+    let stdout = io::stdout();
+    report.write(&mut stdout.lock(), &path.to_string_lossy())
+}
+
+pub fn run_benchmark(
+    input: &[u8],
+    iterations: usize,
+    config: &Config,
+) -> std::io::Result<BenchmarkReport> {
+    // Load the syntax set up front, so that the first iteration is not penalized.
+    config.syntax_set.get();
+    TIMINGS.with(|timings| *timings.borrow_mut() = Some(Timings::default()));
+    let start = Instant::now();
+    for _ in 0..iterations {
+        delta(
+            ByteLines::new(BufReader::new(input)),
+            &mut io::sink(),
+            config,
+        )?;
+    }
+    let total = start.elapsed();
+    let timings = TIMINGS
+        .with(|timings| timings.borrow_mut().take())
+        .unwrap_or_default();
+    let accounted = timings.highlight + timings.edits + timings.paint + timings.emit;
+    Ok(BenchmarkReport {
+        iterations,
+        n_lines: input.iter().filter(|b| **b == b'\n').count(),
+        n_bytes: input.len(),
+        parse: total.checked_sub(accounted).unwrap_or_default(),
+        highlight: timings.highlight,
+        edits: timings.edits,
+        paint: timings.paint,
+        emit: timings.emit,
+        total,
+    })
+}
+
+impl BenchmarkReport {
+    pub fn write(&self, writer: &mut dyn Write, name: &str) -> std::io::Result<()> {
+        writeln!(
+            writer,
+            "Rendered {} ({} lines, {} bytes) {} times",
+            name, self.n_lines, self.n_bytes, self.iterations
+        )?;
+        let iterations = self.iterations.max(1) as f64;
+        let total_secs = self.total.as_secs_f64();
+        for (phase, duration) in &[
+            ("parse", self.parse),
+            ("highlight", self.highlight),
+            ("edits", self.edits),
+            ("paint", self.paint),
+            ("emit", self.emit),
+            ("total", self.total),
+        ] {
+            writeln!(
+                writer,
+                "    {:<10} {:>10.3} ms/iteration {:>6.1}%",
+                phase,
+                1000.0 * duration.as_secs_f64() / iterations,
+                if total_secs > 0.0 {
+                    100.0 * duration.as_secs_f64() / total_secs
+                } else {
+                    0.0
+                }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::line_numbers::tests::ONE_MINUS_ONE_PLUS_LINE_DIFF;
+    use crate::tests::integration_test_utils::integration_test_utils::make_config_from_args;
+
+    #[test]
+    fn test_benchmark_report() {
+        let config = make_config_from_args(&[]);
+        let report = run_benchmark(ONE_MINUS_ONE_PLUS_LINE_DIFF.as_bytes(), 3, &config).unwrap();
+        assert_eq!(report.iterations, 3);
+        assert_eq!(report.n_lines, 8);
+        assert!(report.highlight > Duration::default());
+        assert!(report.emit > Duration::default());
+        assert_eq!(
+            report.parse + report.highlight + report.edits + report.paint + report.emit,
+            report.total
+        );
+
+        let mut output = Vec::new();
+        report.write(&mut output, "a.diff").unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("Rendered a.diff (8 lines, "));
+        assert_eq!(output.lines().count(), 7);
+    }
+}
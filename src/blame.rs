@@ -0,0 +1,595 @@
+use std::collections::HashMap;
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use ansi_term::Color;
+use chrono::{DateTime, FixedOffset, TimeZone};
+use lazy_static::lazy_static;
+use regex::Regex;
+use unicode_width::UnicodeWidthStr;
+
+use crate::color;
+use crate::config::Config;
+use crate::control_chars;
+use crate::delta::State;
+use crate::paint::{Painter, StyleSections};
+use crate::parse;
+use crate::style::Style;
+
+const DARK_THEME_BLAME_PALETTE: &str = "#000000 #1c1c1c #303030";
+const LIGHT_THEME_BLAME_PALETTE: &str = "#ffffff #f0f0f0 #e0e0e0";
+
+// The default `git blame` output format. The file name column is present when git has followed
+// lines across a rename (or when -f is given). Under blame.markIgnoredLines and
+// blame.markUnblamableLines, git marks the lines whose attribution skipped a commit listed in
+// blame.ignoreRevsFile with '?', and those it could not attribute to another commit with '*'.
+//
+// 5d7b36d1 (Dan Davison 2020-06-01 12:34:56 -0400 42)     let x = 1;
+// ^970b008 src/old.rs (Dan Davison 2020-05-30 09:01:02 -0400 43) }
+// ?5d7b36d (Dan Davison 2020-06-01 12:34:56 -0400 44) }
+lazy_static! {
+    static ref BLAME_LINE_REGEX: Regex = Regex::new(
+        r"(?x)
+^
+([?*])?                 # 1. Optional mark
+(\^?[0-9a-f]{4,40})     # 2. Commit hash (^ marks a boundary commit)
+(?:\ [^(]+)?            #    Optional file name
+\ \(
+(.+?)                   # 3. Author
+\ +
+(                       # 4. Timestamp
+  [0-9]{4}-[0-9]{2}-[0-9]{2}\ [0-9]{2}:[0-9]{2}:[0-9]{2}\ [-+][0-9]{4}
+)
+\ +
+([0-9]+)                # 5. Line number
+\)
+(.*)                    # 6. Code, with leading space
+$
+"
+    )
+    .unwrap();
+}
+
+// The first line of each record of `git blame --porcelain` output: the commit hash, the line number
+// in the original file, the line number in the final file, and (on the first line of a group of
+// lines from the same commit) the number of lines in the group. This is followed by header lines
+// such as "author Dan Davison" (only on the first occurrence of each commit, unless
+// --line-porcelain is used), and then the line itself, prefixed by a tab.
+lazy_static! {
+    static ref BLAME_PORCELAIN_HEADER_REGEX: Regex =
+        Regex::new(r"^([0-9a-f]{40}) ([0-9]+) ([0-9]+)(?: ([0-9]+))?$").unwrap();
+}
+
+lazy_static! {
+    static ref BLAME_PLACEHOLDER_REGEX: Regex = Regex::new(
+        r"(?x)
+\{\{ | \}\}                               # An escaped literal brace
+|
+\{
+(commit|author|timestamp|line_number)   # 1. Field name
+(?:                                     # Start optional format spec (non-capturing)
+  :                                     #     Literal colon
+  ([<^>])?                              #     2. Optional alignment
+  (\d+)?                                #     3. Optional width
+  (?:\.(\d+))?                          #     4. Optional maximum length
+)?
+\}
+"
+    )
+    .unwrap();
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BlameLine<'a> {
+    pub mark: Option<char>,
+    pub commit: String,
+    pub author: String,
+    pub time: DateTime<FixedOffset>,
+    pub line_number: usize,
+    pub filename: Option<String>,
+    pub code: &'a str,
+}
+
+#[derive(Debug, PartialEq)]
+struct BlamePlaceholder<'a> {
+    prefix: &'a str,
+    field: &'a str,
+    alignment: Option<&'a str>,
+    width: Option<usize>,
+    max_length: Option<usize>,
+}
+
+#[derive(Debug, PartialEq)]
+struct BlameFormat<'a> {
+    placeholders: Vec<BlamePlaceholder<'a>>,
+    suffix: &'a str,
+}
+
+#[derive(Clone, Default)]
+struct PorcelainCommit {
+    author: String,
+    author_time: i64,
+    author_tz: String,
+    filename: Option<String>,
+}
+
+/// State carried from one line of blame output to the next.
+pub struct BlameState<'a> {
+    format: BlameFormat<'a>,
+    commit_colors: HashMap<String, Color>,
+    previous_commit: Option<String>,
+    syntax_is_set: bool,
+    porcelain_commits: HashMap<String, PorcelainCommit>,
+    // The commit and final line number of the porcelain record currently being read
+    porcelain_record: Option<(String, usize)>,
+}
+
+impl<'a> BlameState<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        Self {
+            format: parse_blame_format(&config.blame_format),
+            commit_colors: HashMap::new(),
+            previous_commit: None,
+            syntax_is_set: false,
+            porcelain_commits: HashMap::new(),
+            porcelain_record: None,
+        }
+    }
+
+    /// Return the color for a commit. Commits are assigned palette colors in turn, in the order in
+    /// which they are first seen.
+    fn get_color(&mut self, commit: &str, config: &Config) -> Color {
+        let n_commits = self.commit_colors.len();
+        *self
+            .commit_colors
+            .entry(commit.to_string())
+            .or_insert_with(|| config.blame_palette[n_commits % config.blame_palette.len()])
+    }
+}
+
+/// Is this line the first line of `git blame` output?
+pub fn is_blame_line(line: &str) -> bool {
+    BLAME_LINE_REGEX.is_match(line) || BLAME_PORCELAIN_HEADER_REGEX.is_match(line)
+}
+
+/// Handle a line of `git blame` output (in the default format, or --porcelain). Return false if the
+/// line was not recognized.
+pub fn handle_blame_line(
+    painter: &mut Painter,
+    line: &str,
+    blame: &mut BlameState,
+    config: &Config,
+) -> std::io::Result<bool> {
+    if let Some(blame_line) = parse_blame_line(line) {
+        paint_blame_line(painter, blame_line, blame, config);
+    } else if let Some(caps) = BLAME_PORCELAIN_HEADER_REGEX.captures(line) {
+        blame.porcelain_record = Some((caps[1].to_string(), caps[3].parse().unwrap_or(0)));
+    } else if let Some((commit, line_number)) = blame.porcelain_record.take() {
+        let commit_data = blame.porcelain_commits.entry(commit.clone()).or_default();
+        if line.starts_with('\t') {
+            let blame_line = BlameLine {
+                mark: None,
+                commit: commit[..8].to_string(),
+                author: commit_data.author.clone(),
+                time: parse_porcelain_time(commit_data.author_time, &commit_data.author_tz),
+                line_number,
+                filename: commit_data.filename.clone(),
+                code: line,
+            };
+            paint_blame_line(painter, blame_line, blame, config);
+        } else {
+            let mut key_value = line.splitn(2, ' ');
+            let (key, value) = (key_value.next(), key_value.next().unwrap_or(""));
+            match key {
+                Some("author") => commit_data.author = value.to_string(),
+                Some("author-time") => commit_data.author_time = value.parse().unwrap_or(0),
+                Some("author-tz") => commit_data.author_tz = value.to_string(),
+                Some("filename") => commit_data.filename = Some(value.to_string()),
+                _ => {}
+            }
+            blame.porcelain_record = Some((commit, line_number));
+        }
+    } else {
+        return Ok(false);
+    }
+    painter.emit()?;
+    Ok(true)
+}
+
+fn parse_blame_line(line: &str) -> Option<BlameLine<'_>> {
+    let caps = BLAME_LINE_REGEX.captures(line)?;
+    Some(BlameLine {
+        mark: caps.get(1).and_then(|m| m.as_str().chars().next()),
+        commit: caps[2].to_string(),
+        author: caps[3].to_string(),
+        time: DateTime::parse_from_str(&caps[4], "%Y-%m-%d %H:%M:%S %z").ok()?,
+        line_number: caps[5].parse().ok()?,
+        filename: None,
+        code: caps.get(6).unwrap().as_str(),
+    })
+}
+
+/// Convert a porcelain author-time (seconds since the epoch) and author-tz (e.g. "-0400").
+fn parse_porcelain_time(time: i64, tz: &str) -> DateTime<FixedOffset> {
+    let offset_seconds = tz
+        .parse::<i32>()
+        .map(|hhmm| hhmm.signum() * ((hhmm.abs() / 100) * 3600 + (hhmm.abs() % 100) * 60))
+        .unwrap_or(0);
+    FixedOffset::east(offset_seconds).timestamp(time, 0)
+}
+
+fn paint_blame_line(
+    painter: &mut Painter,
+    mut blame_line: BlameLine,
+    blame: &mut BlameState,
+    config: &Config,
+) {
+    if !blame.syntax_is_set {
+        painter.set_syntax(
+            blame_line
+                .filename
+                .as_deref()
+                .and_then(parse::get_file_extension_from_file_meta_line_file_path),
+        );
+        painter.set_tab_width(blame_line.filename.as_deref());
+        painter.set_highlighter();
+        blame.syntax_is_set = true;
+    }
+    // Lines attributed to an ignored commit, which git has not skipped, are displayed without
+    // their metadata or commit color, and marked as unblamable.
+    let is_ignored = is_ignored_rev(&blame_line.commit, config);
+    if is_ignored {
+        blame_line.mark = Some('*');
+    }
+    let style = Style {
+        is_syntax_highlighted: true,
+        ..Style::from_colors(
+            None,
+            if is_ignored {
+                None
+            } else {
+                Some(blame.get_color(&blame_line.commit, config))
+            },
+        )
+    };
+
+    // The metadata is displayed only on the first of a run of lines from the same commit.
+    let is_repeat = blame.previous_commit.as_deref() == Some(&blame_line.commit);
+    let metadata =
+        format_blame_metadata(&blame.format, &blame_line, is_repeat || is_ignored, config);
+    blame.previous_commit = Some(blame_line.commit.clone());
+    write!(painter.output_buffer, "{}", style.paint(metadata)).unwrap();
+
+    let (line, symbol_ranges) = painter.prepare(blame_line.code, true);
+    let lines = vec![line];
+    let syntax_style_sections = Painter::get_syntax_style_sections_for_lines(
+        &lines,
+        &State::Blame,
+        &mut painter.highlighter,
+        config,
+    );
+    Painter::paint_lines(
+        StyleSections {
+            syntax: syntax_style_sections,
+            diff: vec![control_chars::style_control_char_sections(
+                vec![(style, lines[0].as_str())],
+                &symbol_ranges,
+                config,
+            )],
+        },
+        &State::Blame,
+        &mut painter.output_buffer,
+        config,
+        &mut None,
+        "",
+        0,
+    );
+}
+
+fn parse_blame_format(format_string: &str) -> BlameFormat<'_> {
+    let mut placeholders = Vec::new();
+    let mut offset = 0;
+    for captures in BLAME_PLACEHOLDER_REGEX.captures_iter(format_string) {
+        let _match = captures.get(0).unwrap();
+        placeholders.push(BlamePlaceholder {
+            prefix: &format_string[offset.._match.start()],
+            // An escaped brace is represented by a field which is the brace itself.
+            field: captures
+                .get(1)
+                .map_or(&_match.as_str()[..1], |m| m.as_str()),
+            alignment: captures.get(2).map(|m| m.as_str()),
+            width: captures.get(3).and_then(|m| m.as_str().parse().ok()),
+            max_length: captures.get(4).and_then(|m| m.as_str().parse().ok()),
+        });
+        offset = _match.end();
+    }
+    BlameFormat {
+        placeholders,
+        suffix: &format_string[offset..],
+    }
+}
+
+/// Is the commit, which may be abbreviated, one of those listed in --blame-ignore-revs-file?
+fn is_ignored_rev(commit: &str, config: &Config) -> bool {
+    let commit = commit.trim_start_matches('^');
+    !commit.is_empty()
+        && config
+            .blame_ignore_revs
+            .iter()
+            .any(|rev| rev.starts_with(commit))
+}
+
+/// Read the commits listed in a blame ignore-revs file: one full commit hash per line, with
+/// comments introduced by '#'. As for git, a relative path is relative to the top of the
+/// repository. A file which cannot be read lists no commits.
+pub fn read_ignore_revs_file(path: &str) -> Vec<String> {
+    let mut path = PathBuf::from(path);
+    if path.is_relative() {
+        if let Some(dir) = git2::Repository::discover(".")
+            .ok()
+            .and_then(|repo| repo.workdir().map(|dir| dir.to_path_buf()))
+        {
+            path = dir.join(path);
+        }
+    }
+    fs::read_to_string(path)
+        .map(|contents| parse_ignore_revs(&contents))
+        .unwrap_or_default()
+}
+
+fn parse_ignore_revs(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap().trim().to_lowercase())
+        .filter(|rev| !rev.is_empty())
+        .collect()
+}
+
+/// Format the metadata for a line according to --blame-format. If `blank` is true, then the
+/// placeholders are replaced by whitespace of the same width, except that the mark of a marked
+/// line is displayed in place of its commit.
+fn format_blame_metadata(
+    format: &BlameFormat,
+    blame_line: &BlameLine,
+    blank: bool,
+    config: &Config,
+) -> String {
+    let mut s = String::new();
+    for placeholder in &format.placeholders {
+        s.push_str(placeholder.prefix);
+        let value = match placeholder.field {
+            brace @ "{" | brace @ "}" => {
+                s.push_str(brace);
+                continue;
+            }
+            "commit" => match blame_line.mark {
+                Some(mark) if blank => mark.to_string(),
+                Some(mark) => format!("{}{}", mark, blame_line.commit),
+                None => blame_line.commit.clone(),
+            },
+            "author" => blame_line.author.clone(),
+            "timestamp" => blame_line
+                .time
+                .format(&config.blame_timestamp_format)
+                .to_string(),
+            "line_number" => blame_line.line_number.to_string(),
+            _ => unreachable!(),
+        };
+        let value: String = match placeholder.max_length {
+            Some(max_length) => value.chars().take(max_length).collect(),
+            None => value,
+        };
+        let width = placeholder.width.unwrap_or(0);
+        let field = match placeholder.alignment.unwrap_or("<") {
+            "^" => format!("{0:^1$}", value, width),
+            ">" => format!("{0:>1$}", value, width),
+            _ => format!("{0:<1$}", value, width),
+        };
+        if blank && !(placeholder.field == "commit" && blame_line.mark.is_some()) {
+            s.push_str(&" ".repeat(field.width()));
+        } else {
+            s.push_str(&field);
+        }
+    }
+    s.push_str(format.suffix);
+    s
+}
+
+/// Parse the --blame-palette option value, falling back to the default palette for the light or
+/// dark mode in effect.
+pub fn make_blame_palette(
+    palette: Option<&str>,
+    is_light_mode: bool,
+    true_color: bool,
+) -> Vec<Color> {
+    let palette = palette.unwrap_or(if is_light_mode {
+        LIGHT_THEME_BLAME_PALETTE
+    } else {
+        DARK_THEME_BLAME_PALETTE
+    });
+    let colors: Vec<Color> = palette
+        .split_whitespace()
+        .filter_map(|s| color::parse_color(s, true_color))
+        .collect();
+    if colors.is_empty() {
+        eprintln!("Invalid blame-palette: {}", palette);
+        process::exit(1);
+    }
+    colors
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    const GIT_BLAME: &str = "\
+d8f1ad1f (Dan Davison 2020-06-06 21:29:57 -0400 1) use std::cmp::max;
+d8f1ad1f (Dan Davison 2020-06-06 21:29:57 -0400 2)
+^3b2c2b9 (Tako      2020-05-30 09:01:02 +0900 3) use ansi_term;
+";
+
+    const GIT_BLAME_PORCELAIN: &str = "\
+d8f1ad1f3e4b1c4b8a8b7a6e0f4d0f1c2b3a4d5e 1 1 2
+author Dan Davison
+author-mail <dandavison7@gmail.com>
+author-time 1591493397
+author-tz -0400
+summary Add line numbers
+filename src/line_numbers.rs
+\tuse std::cmp::max;
+d8f1ad1f3e4b1c4b8a8b7a6e0f4d0f1c2b3a4d5e 2 2
+\t
+";
+
+    #[test]
+    fn test_parse_blame_line() {
+        let line = "^3b2c2b9 src/old.rs (Tako      2020-05-30 09:01:02 +0900 3)     let x = 1;";
+        let blame_line = parse_blame_line(line).unwrap();
+        assert_eq!(blame_line.commit, "^3b2c2b9");
+        assert_eq!(blame_line.author, "Tako");
+        assert_eq!(
+            blame_line.time,
+            FixedOffset::east(9 * 3600)
+                .ymd(2020, 5, 30)
+                .and_hms(9, 1, 2)
+        );
+        assert_eq!(blame_line.line_number, 3);
+        assert_eq!(blame_line.code, "     let x = 1;");
+        assert_eq!(blame_line.mark, None);
+        let line = "?d8f1ad1 (Dan Davison 2020-06-06 21:29:57 -0400 1) use std::cmp::max;";
+        let blame_line = parse_blame_line(line).unwrap();
+        assert_eq!(blame_line.mark, Some('?'));
+        assert_eq!(blame_line.commit, "d8f1ad1");
+        assert!(parse_blame_line("@@ -1,2 +1,2 @@").is_none());
+    }
+
+    #[test]
+    fn test_parse_porcelain_time() {
+        assert_eq!(
+            parse_porcelain_time(1591493397, "-0430").to_rfc3339(),
+            "2020-06-06T20:59:57-04:30"
+        );
+    }
+
+    #[test]
+    fn test_format_blame_metadata() {
+        let config = make_config_from_args(&[]);
+        let blame_line = parse_blame_line(GIT_BLAME.lines().next().unwrap()).unwrap();
+        let format = parse_blame_format("{author:>8.3}|{line_number:^5}|{commit}|{timestamp} ");
+        assert_eq!(
+            format_blame_metadata(&format, &blame_line, false, &config),
+            "     Dan|  1  |d8f1ad1f|2020-06-06 21:29 "
+        );
+        assert_eq!(
+            format_blame_metadata(&format, &blame_line, true, &config),
+            "        |     |        |                 "
+        );
+        let format = parse_blame_format("{{{commit:.4}}} {{author}} ");
+        assert_eq!(
+            format_blame_metadata(&format, &blame_line, false, &config),
+            "{d8f1} {author} "
+        );
+        assert_eq!(
+            format_blame_metadata(&format, &blame_line, true, &config),
+            "{    } {author} "
+        );
+    }
+
+    #[test]
+    fn test_blame() {
+        let config = make_config_from_args(&[]);
+        let output = run_delta(GIT_BLAME, &config);
+        let output = strip_ansi_codes(&output);
+        let mut lines = output.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "2020-06-06 21:29 Dan Davison     d8f1ad1f │ use std::cmp::max;"
+        );
+        assert_eq!(lines.next().unwrap(), format!("{}│ ", " ".repeat(42)));
+        assert_eq!(
+            lines.next().unwrap(),
+            "2020-05-30 09:01 Tako            ^3b2c2b9 │ use ansi_term;"
+        );
+    }
+
+    #[test]
+    fn test_blame_porcelain() {
+        let config = make_config_from_args(&[]);
+        let output = run_delta(GIT_BLAME_PORCELAIN, &config);
+        let output = strip_ansi_codes(&output);
+        let mut lines = output.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "2020-06-06 21:29 Dan Davison     d8f1ad1f │ use std::cmp::max;"
+        );
+        assert_eq!(lines.next().unwrap(), format!("{}│ ", " ".repeat(42)));
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn test_blame_is_only_detected_on_first_line() {
+        let config = make_config_from_args(&[]);
+        let input = format!("Blamed lines:\n{}", GIT_BLAME);
+        let output = strip_ansi_codes(&run_delta(&input, &config)).to_string();
+        assert!(!output.contains("Dan Davison     d8f1ad1f │"));
+        assert!(output.contains(GIT_BLAME.lines().next().unwrap()));
+    }
+
+    #[test]
+    fn test_parse_ignore_revs() {
+        assert_eq!(
+            parse_ignore_revs(
+                "# Reformat with rustfmt\nD8F1AD1F3E4B1C4B8A8B7A6E0F4D0F1C2B3A4D5E\n\n3b2c2b9 # comment\n"
+            ),
+            vec!["d8f1ad1f3e4b1c4b8a8b7a6e0f4d0f1c2b3a4d5e", "3b2c2b9"]
+        );
+    }
+
+    #[test]
+    fn test_blame_ignore_revs() {
+        let path =
+            std::env::temp_dir().join(format!("delta-test-blame-ignore-revs-{}", process::id()));
+        fs::write(&path, "d8f1ad1f3e4b1c4b8a8b7a6e0f4d0f1c2b3a4d5e\n").unwrap();
+        let config = make_config_from_args(&["--blame-ignore-revs-file", path.to_str().unwrap()]);
+        fs::remove_file(&path).unwrap();
+        let input = "\
+d8f1ad1f (Dan Davison 2020-06-06 21:29:57 -0400 1) use std::cmp::max;
+?3b2c2b9 (Tako      2020-05-30 09:01:02 +0900 2) use ansi_term;
+?3b2c2b9 (Tako      2020-05-30 09:01:02 +0900 3) use itertools;
+";
+        let output = run_delta(input, &config);
+        let output = strip_ansi_codes(&output);
+        let mut lines = output.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            format!("{}*        │ use std::cmp::max;", " ".repeat(33))
+        );
+        assert_eq!(
+            lines.next().unwrap(),
+            "2020-05-30 09:01 Tako            ?3b2c2b9 │ use ansi_term;"
+        );
+        assert_eq!(
+            lines.next().unwrap(),
+            format!("{}?        │ use itertools;", " ".repeat(33))
+        );
+    }
+
+    #[test]
+    fn test_blame_commit_colors() {
+        let config = make_config_from_args(&["--blame-palette", "red blue"]);
+        let mut blame = BlameState::new(&config);
+        let red = color::parse_color("red", config.true_color).unwrap();
+        let blue = color::parse_color("blue", config.true_color).unwrap();
+        assert_eq!(blame.get_color("a", &config), red);
+        assert_eq!(blame.get_color("b", &config), blue);
+        assert_eq!(blame.get_color("c", &config), red);
+        assert_eq!(blame.get_color("b", &config), blue);
+    }
+}
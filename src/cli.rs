@@ -1,5 +1,4 @@
 use std::collections::{HashMap, HashSet};
-#[cfg(test)]
 use std::ffi::OsString;
 use std::path::PathBuf;
 
@@ -8,14 +7,14 @@ use lazy_static::lazy_static;
 use structopt::clap::AppSettings::{ColorAlways, ColoredHelp, DeriveDisplayOrder};
 use structopt::{clap, StructOpt};
 use syntect::highlighting::Theme as SyntaxTheme;
-use syntect::parsing::SyntaxSet;
 
-use crate::bat::assets::HighlightingAssets;
+use crate::bat::assets::{HighlightingAssets, LazySyntaxSet};
 use crate::bat::output::PagingMode;
 use crate::git_config::GitConfig;
 use crate::options;
+use crate::terminal_profile::TerminalProfile;
 
-#[derive(StructOpt, Clone, Default)]
+#[derive(StructOpt, Clone, Debug, Default)]
 #[structopt(
     name = "delta",
     about = "A viewer for git and diff output",
@@ -23,6 +22,19 @@ use crate::options;
     setting(ColoredHelp),
     setting(DeriveDisplayOrder),
     after_help = "\
+RUNNING GIT
+-----------
+
+Where delta cannot be configured as git's pager, it can run git itself:
+
+delta diff [git-diff-args...]
+delta log [git-log-args...]
+delta show [git-show-args...]
+
+Git is run without color or a pager, and `delta log` runs `git log -p`. Delta's own options are
+given before the git command, e.g. `delta --side-by-side diff HEAD~`. Delta exits with the exit
+status of git.
+
 GIT CONFIG
 ----------
 
@@ -55,6 +67,11 @@ A feature name may not contain whitespace. You can activate multiple features:
 
 If more than one feature sets the same option, the last one wins.
 
+Features given with --features replace those in the [delta] section. To enable or disable features
+in addition to those, prefix every name with + or -:
+
+delta --features '+side-by-side -line-numbers'
+
 STYLES
 ------
 
@@ -195,6 +212,17 @@ width of 4 characters, followed by a unicode dividing-line character (│).
 
 Use '<' for left-align, '^' for center-align, and '>' for right-align.
 
+Follow the width with ',' to group the digits of the line number in thousands, e.g. '{np:>7,}'.
+The separator is that of the locale given by the LC_ALL, LC_NUMERIC or LANG environment
+variables: a comma unless the locale uses a period or a space.
+
+To display a literal brace, write it twice: '{{' or '}}'.
+
+To make one version of the file the primary reference, e.g. the old version when reviewing a revert
+or a backport, use --line-numbers-primary-side=old. Its line numbers are then emphasized with
+--line-numbers-primary-style (bold by default), and those of the other version are dimmed with
+--line-numbers-secondary-style.
+
 
 If something isn't working correctly, or you have a feature request, please open an issue at
 https://github.com/dandavison/delta/issues.
@@ -207,7 +235,10 @@ pub struct Opt {
     pub light: bool,
 
     /// Use default colors appropriate for a dark terminal background. For more control, see the
-    /// style options and --syntax-theme.
+    /// style options and --syntax-theme. While delta displays its input, the signal SIGUSR1 makes
+    /// it reload its configuration at the start of the next commit or file, switching between
+    /// light and dark mode unless the reloaded configuration switches it, e.g. `pkill -USR1
+    /// delta`.
     #[structopt(long = "dark")]
     pub dark: bool,
 
@@ -215,10 +246,282 @@ pub struct Opt {
     #[structopt(short = "n", long = "line-numbers")]
     pub line_numbers: bool,
 
-    /// Display a side-by-side diff view instead of the traditional view.
+    /// Display a side-by-side diff view instead of the traditional view. Changes of file mode,
+    /// and the similarity of renamed and copied files, are displayed under the file header,
+    /// centered across both panels.
     #[structopt(short = "s", long = "side-by-side")]
     pub side_by_side: bool,
 
+    /// In side-by-side mode, display the context line which precedes a run of added lines, dimmed,
+    /// in the first row of the otherwise empty left panel, and likewise for a run of removed
+    /// lines in the right panel, to show where in the other version of the file the change lands.
+    #[structopt(long = "side-by-side-context-fill")]
+    pub side_by_side_context_fill: bool,
+
+    /// In side-by-side mode, align the unchanged text of a pair of modified lines at the same
+    /// columns in the two panels, by padding the shorter of the changes which precede it, so that
+    /// the changes can be compared directly across the panels. A pair of lines is left as it is if
+    /// the unchanged text differs, e.g. in whitespace.
+    #[structopt(long = "side-by-side-align-edits")]
+    pub side_by_side_align_edits: bool,
+
+    /// Treat the input as `git blame` output (either the default format or --porcelain), rather than
+    /// a diff. Blame output is also detected automatically, so this is only needed if detection
+    /// fails. See --blame-format, --blame-palette, and --blame-timestamp-format.
+    #[structopt(long = "blame")]
+    pub blame: bool,
+
+    /// Treat the input as the output of a grep tool with line numbers, such as `git grep -n` or
+    /// `rg -n`, rather than a diff. Grep output is also detected automatically when the path on its
+    /// first line is that of a file, relative to the current directory or to the top of the
+    /// repository, so this is only needed if detection fails, e.g. when grepping in another
+    /// directory. See --grep-match-style and --grep-line-number-style.
+    #[structopt(long = "grep")]
+    pub grep: bool,
+
+    /// Display only the given file, or hunk of a file, from the input. The value is PATH or
+    /// PATH:N, where N is the number of the hunk within the file, counting from 1, or F or F.N,
+    /// where F is the number of the file within the input, as displayed by --numbered. See
+    /// --emit-patch.
+    #[structopt(long = "select")]
+    pub select: Option<String>,
+
+    /// Prefix each file header with the number of the file in the input, e.g. [3], and each hunk
+    /// header with the numbers of its file and of the hunk within the file, e.g. [3.2], and end
+    /// the output with a table of the numbered files and hunks. The numbers can be given to
+    /// --select, e.g. `--select 3.2`.
+    #[structopt(long = "numbered")]
+    pub numbered: bool,
+
+    /// Output the section of the input chosen by --select as a plain patch, rather than rendering
+    /// it. The output can be applied with `git apply`.
+    #[structopt(long = "emit-patch")]
+    pub emit_patch: bool,
+
+    /// Write the location of each hunk to this file, as a line of the form
+    /// 'PATH:LINE:1: hunk N.M CONTEXT', where LINE is the line in the new version of the file at
+    /// which the hunk starts, and N.M are the numbers of the file and hunk, as used by --numbered
+    /// and --select. This is the layout of compiler error messages, so that the file can be loaded
+    /// into an editor's list of locations, e.g. with `:cfile FILE` in vim or `M-x compilation-mode`
+    /// in emacs, to jump from hunk to hunk.
+    #[structopt(long = "emit-locations")]
+    pub emit_locations: Option<String>,
+
+    /// Display only files whose path matches this glob pattern. May be given more than once, in
+    /// which case a file is displayed if its path matches any of the patterns. A pattern
+    /// containing no '/' is matched against file and directory names at any depth, '*' matches
+    /// within a path component, and '**' matches across path components. A pattern ending in '/'
+    /// matches a directory. See --exclude-path.
+    #[structopt(long = "include-path", number_of_values = 1)]
+    pub include_path: Vec<String>,
+
+    /// Do not display files whose path matches this glob pattern, e.g. 'dist/' or '*.lock'. May
+    /// be given more than once. The names of the skipped files are listed at the end of the
+    /// output. See --include-path for the pattern syntax.
+    #[structopt(long = "exclude-path", number_of_values = 1)]
+    pub exclude_path: Vec<String>,
+
+    /// Compare the versions of files whose path matches GLOB after passing each through COMMAND,
+    /// given as 'GLOB=COMMAND', e.g. '*.json=jq .' or '*.sql=sqlformat -r -'. The changes are then
+    /// displayed in terms of the filtered content, so that reformatting does not hide the changes
+    /// that matter. The versions of a file are read from the repository, using the blob ids of
+    /// git's "index" line, or from the file system. If they cannot be read, or the command fails,
+    /// the original hunks are displayed. May be given more than once; the first matching glob is
+    /// used. The diff of the filtered versions has as many context lines as git's diff.context
+    /// setting. See --include-path for the pattern syntax.
+    #[structopt(long = "pre-render-filter", number_of_values = 1)]
+    pub pre_render_filter: Vec<String>,
+
+    /// Compare JSON and YAML files as data rather than as text. Both versions of such a file are
+    /// parsed and flattened into lines of the form '.path.to[0].key = value', sorted by path, and
+    /// the diff of the flattened versions is displayed in place of the file's hunks, so that keys
+    /// which have been reordered, and changes of formatting, make no difference. The versions of
+    /// the file are read as for --pre-render-filter; if they cannot be read or parsed, the
+    /// original hunks are displayed.
+    #[structopt(long = "structured-diff")]
+    pub structured_diff: bool,
+
+    /// Compare Jupyter notebooks (.ipynb files) cell by cell rather than as JSON. Each cell is
+    /// labeled with its type by a "# %%" line, the source of code cells is highlighted as Python,
+    /// and the source of markdown and raw cells is commented out. The outputs and execution
+    /// counts of cells are not displayed, so that changes to them make no difference. The
+    /// versions of a notebook are read as for --pre-render-filter; if they cannot be read or
+    /// parsed, the original hunks are displayed.
+    #[structopt(long = "notebook-diff")]
+    pub notebook_diff: bool,
+
+    /// Do not summarize the diffs of lockfiles. By default, the hunks of Cargo.lock,
+    /// package-lock.json, yarn.lock and poetry.lock files are replaced by a summary of the
+    /// packages which were added, removed, upgraded or downgraded, with their old and new
+    /// versions.
+    #[structopt(long = "no-lockfile-summary")]
+    pub no_lockfile_summary: bool,
+
+    /// Display control characters in the content of hunk lines as they are. By default, control
+    /// characters other than tab are displayed as visible symbols in --control-char-style, e.g. ␛
+    /// for escape and ␍ for carriage return, so that a crafted file cannot write escape sequences
+    /// to the terminal, or hide text, through its diff. Escape sequences in uncolored input are
+    /// displayed in the same way; those in colored input cannot be told apart from git's colors,
+    /// and are removed. Under --raw and --color-only, whose output must be the input with colors
+    /// added, control characters are not replaced.
+    #[structopt(long = "no-sanitize-control-chars")]
+    pub no_sanitize_control_chars: bool,
+
+    /// Do not display hunks in which every added and removed line matches this regular
+    /// expression, e.g. '^\s*"?version"?\s*[:=]' to hide version bumps. A short notice is
+    /// displayed in place of each hidden hunk. May be given more than once.
+    #[structopt(long = "hide-hunks-matching", number_of_values = 1)]
+    pub hide_hunks_matching: Vec<String>,
+
+    /// Do not display files in which any line of the diff matches this regular expression, e.g.
+    /// 'AUTO-GENERATED|DO NOT EDIT'. A short notice is displayed in place of each hidden file. May
+    /// be given more than once.
+    #[structopt(long = "hide-files-matching", number_of_values = 1)]
+    pub hide_files_matching: Vec<String>,
+
+    /// Replace text matching this regular expression in the displayed lines of hunks, so that
+    /// tokens, passwords and keys are not revealed when a diff is shown on screen, e.g.
+    /// '(?i)(?:password|token|secret)\s*[:=]\s*(\S+)'. If the regular expression has capture
+    /// groups then only the text of the groups is replaced. A value of the form
+    /// 'REGEX=>REPLACEMENT' sets the replacement text, which is otherwise '********'. The
+    /// replacement text is displayed in --redacted-style. May be given more than once.
+    #[structopt(long = "redact", number_of_values = 1)]
+    pub redact: Vec<String>,
+
+    /// Highlight text matching this regular expression in the lines of hunks, on top of their
+    /// syntax highlighting and diff styling, e.g. 'TODO|FIXME'. A value of the form 'REGEX=>STYLE'
+    /// sets the style of the matches, which is otherwise --highlight-style. May be given more than
+    /// once.
+    #[structopt(long = "highlight", number_of_values = 1)]
+    pub highlight: Vec<String>,
+
+    /// Run this command as a plugin which may change the content or style of each hunk line. The
+    /// command is sent a JSON record of each line on its standard input, e.g. {"content": "b =
+    /// 2", "file": "a.py", "minus_line_number": 2, "plus_line_number": null, "state": "minus"}, and
+    /// must respond to each with a line of JSON on its standard output: {} to leave the line as it
+    /// is, or an object with a "content" field, replacing the line's text, and/or a "style" field,
+    /// a style string replacing the line's style. Since it can be set in a feature, different
+    /// plugins can be used by different features. If the command fails, the remainder of the input
+    /// is displayed without it.
+    #[structopt(long = "line-plugin")]
+    pub line_plugin: Option<String>,
+
+    /// Remove this prefix from file paths where it occurs, e.g. 'a/' for a diff made with `diff -ru
+    /// a b`, or 'build/src/' for a diff made inside a container. A value of the form
+    /// 'PREFIX=>REPLACEMENT' replaces the prefix instead. May be given more than once, in which
+    /// case the first matching prefix is used. See --relative-to.
+    #[structopt(long = "strip-path-prefix", number_of_values = 1)]
+    pub strip_path_prefix: Vec<String>,
+
+    /// Display file paths relative to this directory. Paths outside the directory are displayed
+    /// unchanged. This applies before --strip-path-prefix; --select, --include-path and
+    /// --exclude-path match the displayed paths.
+    #[structopt(long = "relative-to")]
+    pub relative_to: Option<String>,
+
+    /// Labels for the two sides of the diff, of the form 'OLD,NEW', to be displayed in file
+    /// headers instead of the file paths. This is useful when the paths are meaningless, as with
+    /// `diff -u <(cmd1) <(cmd2) | delta --file-labels before,after`.
+    #[structopt(long = "file-labels")]
+    pub file_labels: Option<String>,
+
+    /// Display the language of each file, and the numbers of lines added to and removed from it,
+    /// after its path in its header, e.g. 'src/lib.rs (Rust, +24 −3)'. This helps to decide which
+    /// files to review first. The hunks of each file are read before its header is displayed.
+    #[structopt(long = "file-stats")]
+    pub file_stats: bool,
+
+    /// The language used for syntax highlighting when it cannot be determined from the file name,
+    /// e.g. 'rs' or 'Rust'. This is useful for diffs of process substitutions (/dev/fd/63) or of
+    /// files without an extension. Run --list-languages to see the available names.
+    #[structopt(long = "default-language", alias = "lang")]
+    pub default_language: Option<String>,
+
+    /// Display hunks with more than this many added and removed lines as a one-line summary of
+    /// their numbers, so that churn in generated files does not drown out other changes. Context
+    /// lines are not counted. Use 0 for no limit.
+    #[structopt(long = "max-hunk-lines", default_value = "0")]
+    pub max_hunk_lines: usize,
+
+    /// Stop displaying the input once this many lines have been output, at the end of the hunk,
+    /// and note how many hunks and files were not displayed. This is for CI logs and commit hooks,
+    /// where the output must be bounded. Use 0 for no limit.
+    #[structopt(long = "max-output-lines", default_value = "0")]
+    pub max_output_lines: usize,
+
+    /// Write the output for each input line as soon as it has been processed, rather than in
+    /// blocks. This is for input which arrives slowly, e.g. from `tail -f` or a network stream, at
+    /// the cost of throughput. Removed and added lines are still held back until the end of their
+    /// run, since they are highlighted against each other.
+    #[structopt(long = "flush-per-line")]
+    pub flush_per_line: bool,
+
+    /// The format of the warnings which delta writes to stderr: "text", in which each warning is a
+    /// line starting with "delta: warning: KIND: ", or "json", in which each warning is a line
+    /// holding a JSON object with the fields "source" ("delta"), "level" ("warning"), "kind", and
+    /// "message". The kinds are "degraded" (expensive features were disabled for a large input),
+    /// "input-not-processed" (the input could not be processed and is displayed unaltered),
+    /// "line-plugin" (the --line-plugin command failed), "no-selection" (the --select file or hunk
+    /// is not in the input), and "unknown-option" (a delta setting in the git config is not an
+    /// option).
+    #[structopt(long = "warning-format", default_value = "text")]
+    pub warning_format: String,
+
+    /// Display the hunks of each file in reverse order, last hunk first. This is for reviewing
+    /// changes to files which are mostly appended to, such as changelogs, where the interesting
+    /// change is at the end of the file.
+    #[structopt(long = "reverse-hunks")]
+    pub reverse_hunks: bool,
+
+    /// Do not remove git's a/ and b/ prefixes, or the prefixes given by its diff.mnemonicPrefix,
+    /// diff.srcPrefix and diff.dstPrefix settings, from the file paths of git diffs.
+    #[structopt(long = "keep-diff-prefixes")]
+    pub keep_diff_prefixes: bool,
+
+    /// Display the reverse of the diff: removed lines are displayed as added lines and vice versa,
+    /// as are the versions of each file in file labels, side-by-side panels and line numbers. E.g.
+    /// `git diff HEAD upstream | delta --invert` displays what upstream would change in HEAD.
+    #[structopt(long = "invert")]
+    pub invert: bool,
+
+    /// Extend hunks with fewer than this many lines of context before or after their changes, e.g.
+    /// those of `git diff -U0`, to this many, with lines read from the new version of the file:
+    /// from the repository if the diff's index line names a blob in it, and otherwise from the
+    /// file system. The added lines are displayed in --expanded-context-style, and never overlap
+    /// a neighboring hunk. 0 means that hunks are displayed as they are. Hunks are not extended
+    /// under --color-only.
+    #[structopt(long = "expand-context", default_value = "0")]
+    pub expand_context: usize,
+
+    /// Style (foreground, background, attributes) for the context lines added by
+    /// --expand-context. See STYLES section.
+    #[structopt(long = "expanded-context-style", default_value = "dim")]
+    pub expanded_context_style: String,
+
+    /// In hunks with long runs of context before or after their changes, such as those of `git
+    /// diff -W` (--function-context), display the context lines which are more than this many
+    /// lines from the nearest added or removed line in --distant-context-style, or collapse them
+    /// under --collapse-distant-context. 0 means that all context lines are displayed as usual.
+    #[structopt(long = "distant-context", default_value = "0")]
+    pub distant_context: usize,
+
+    /// Replace the context lines beyond --distant-context by a notice giving their number, rather
+    /// than dimming them. The line numbers in the hunk header are adjusted accordingly.
+    #[structopt(long = "collapse-distant-context")]
+    pub collapse_distant_context: bool,
+
+    /// Style (foreground, background, attributes) for the context lines beyond
+    /// --distant-context. See STYLES section.
+    #[structopt(long = "distant-context-style", default_value = "dim")]
+    pub distant_context_style: String,
+
+    /// Style (foreground, background, attributes) for the notices that delta writes in place of
+    /// the parts of the input that it has not displayed, and for other notices about the input,
+    /// such as the heading of the current contents of a file following a rejected hunk. See
+    /// STYLES section.
+    #[structopt(long = "filter-notice-style", default_value = "dim")]
+    pub filter_notice_style: String,
+
     #[structopt(long = "diff-highlight")]
     /// Emulate diff-highlight (https://github.com/git/git/tree/master/contrib/diff-highlight)
     pub diff_highlight: bool,
@@ -227,12 +530,33 @@ pub struct Opt {
     /// Emulate diff-so-fancy (https://github.com/so-fancy/diff-so-fancy)
     pub diff_so_fancy: bool,
 
+    #[structopt(long = "foreground-emph")]
+    /// Emphasize the changed sections of removed and added lines with foreground attributes (bold,
+    /// underline, color) only, instead of with a darker or brighter background. Under this option,
+    /// a background of "auto" in --minus-emph-style and --plus-emph-style is the background of the
+    /// line, rather than the default emph background color.
+    pub foreground_emph: bool,
+
     #[structopt(long = "navigate")]
     /// Activate diff navigation: use n to jump forwards and N to jump backwards. To change the
     /// file labels used see --file-modified-label, --file-removed-label, --file-added-label,
-    /// --file-renamed-label.
+    /// --file-renamed-label. Hunk headers are labeled too (see --hunk-label), so that searching
+    /// for the hunk label in the pager jumps from hunk to hunk.
     pub navigate: bool,
 
+    #[structopt(long = "accessible")]
+    /// Make the output suitable for screen readers: label each line of a hunk as "removed:",
+    /// "added:" or "context:" instead of relying on color to distinguish them, do not draw
+    /// decorations or line-number separators with box-drawing characters, and do not use
+    /// side-by-side display.
+    pub accessible: bool,
+
+    #[structopt(long = "ascii")]
+    /// Use only ASCII characters in the output that delta adds to the input: line-number
+    /// separators, decorations, side-by-side panel separators and truncation markers. This is
+    /// for terminals that cannot display Unicode, and for log files.
+    pub ascii: bool,
+
     #[structopt(long = "keep-plus-minus-markers")]
     /// Prefix added/removed lines with a +/- character, exactly as git does. By default, delta
     /// does not emit any prefix, so code can be copied directly from delta's output.
@@ -245,6 +569,25 @@ pub struct Opt {
     #[structopt(long = "show-config")]
     pub show_config: bool,
 
+    /// Display a diff in which every element is labeled with the name of the option that controls
+    /// its style: the commit, file and hunk header lines and their decorations, minus, plus and
+    /// zero lines, emphasized and non-emphasized sections, empty line markers, whitespace errors,
+    /// and, if line numbers are enabled, line numbers. The active configuration is used, so this
+    /// can be used when building a custom theme.
+    #[structopt(long = "show-styles")]
+    pub show_styles: bool,
+
+    /// Render the diff in FILE repeatedly, discarding the output, and report how long each stage
+    /// of rendering took (parsing, syntax highlighting, within-line edit inference, painting, and
+    /// emitting). The active configuration is used, so this can be used to find out which options
+    /// are responsible for slow rendering of large diffs. See --benchmark-iterations.
+    #[structopt(long = "benchmark", parse(from_os_str))]
+    pub benchmark: Option<PathBuf>,
+
+    /// The number of times to render the diff when using --benchmark.
+    #[structopt(long = "benchmark-iterations", default_value = "10")]
+    pub benchmark_iterations: usize,
+
     /// List supported languages and associated file extensions.
     #[structopt(long = "list-languages")]
     pub list_languages: bool,
@@ -253,6 +596,16 @@ pub struct Opt {
     #[structopt(long = "list-syntax-themes")]
     pub list_syntax_themes: bool,
 
+    /// List all delta options, with their types, default values and descriptions, followed by
+    /// the builtin features and the option values that they set. See --list-options-format.
+    #[structopt(long = "list-options")]
+    pub list_options: bool,
+
+    /// The format of the output of --list-options: 'text' or 'json'. The JSON output is intended
+    /// for programs, such as configuration editors, which need to know about delta's options.
+    #[structopt(long = "list-options-format", default_value = "text")]
+    pub list_options_format: String,
+
     /// Show all available syntax-highlighting themes, each with an example of highlighted diff output.
     /// If diff output is supplied on standard input then this will be used for the demo. For
     /// example: `git show --color=always | delta --show-syntax-themes`.
@@ -273,10 +626,24 @@ pub struct Opt {
     /// delta.
     pub color_only: bool,
 
+    /// Act as git's interactive.diffFilter (used by `git add -p`), which must output exactly one
+    /// line for each line of its input: the color-only feature is used, and options which add,
+    /// remove or rearrange lines, such as --side-by-side, --line-numbers, --word-diff-mode, the
+    /// lockfile summary, and the file and hunk filters, are disabled. This is also done when delta
+    /// detects that it has been run as the diff filter: by git (GIT_EXEC_PATH is set), but not as
+    /// git's pager (GIT_PAGER_IN_USE is not set) or in a git alias (GIT_PREFIX is not set), with
+    /// neither standard input nor standard output connected to a terminal, and, directly or
+    /// through a shell, by an interactive git command such as `git add -p`. Processes are
+    /// inspected through /proc, so elsewhere, e.g. on macOS, --diff-filter-mode must be given.
+    #[structopt(long = "diff-filter-mode")]
+    pub diff_filter_mode: bool,
+
     ////////////////////////////////////////////////////////////////////////////////////////////
-    #[structopt(long = "features", default_value = "")]
+    #[structopt(long = "features", default_value = "", allow_hyphen_values = true)]
     /// Name of delta features to use (space-separated). A feature is a named collection of delta
-    /// options in ~/.gitconfig. See FEATURES section.
+    /// options in ~/.gitconfig. See FEATURES section. If every name is prefixed with '+' or '-',
+    /// e.g. '+side-by-side -line-numbers', then the features are enabled or disabled in addition
+    /// to the features set in git config, rather than replacing them.
     pub features: String,
 
     #[structopt(long = "syntax-theme", env = "BAT_THEME")]
@@ -318,6 +685,22 @@ pub struct Opt {
     /// have an emphasized section. Defaults to --plus-style. See STYLES section.
     pub plus_non_emph_style: String,
 
+    #[structopt(
+        long = "side-by-side-minus-non-emph-style",
+        default_value = "auto auto"
+    )]
+    /// Style (foreground, background, attributes) for non-emphasized sections of removed lines
+    /// that have an emphasized section, in the left panel of side-by-side display. This allows
+    /// the unchanged remainder of an edited line to be dimmed further where space is scarce.
+    /// Defaults to --minus-non-emph-style. See STYLES section.
+    pub side_by_side_minus_non_emph_style: String,
+
+    #[structopt(long = "side-by-side-plus-non-emph-style", default_value = "auto auto")]
+    /// Style (foreground, background, attributes) for non-emphasized sections of added lines that
+    /// have an emphasized section, in the right panel of side-by-side display. Defaults to
+    /// --plus-non-emph-style. See STYLES section.
+    pub side_by_side_plus_non_emph_style: String,
+
     #[structopt(long = "commit-style", default_value = "raw")]
     /// Style (foreground, background, attributes) for the commit hash line. See STYLES section.
     /// The style 'omit' can be used to remove the commit hash line from the output.
@@ -351,18 +734,137 @@ pub struct Opt {
     /// (underline), 'ol' (overline), or the combination 'ul ol'.
     pub hunk_header_decoration_style: String,
 
+    #[structopt(long = "hunk-header-format", default_value = "")]
+    /// Format string for the hunk header. It may contain the placeholders {file} (the file path),
+    /// {line} (the line number in the new version of the file at which the hunk starts),
+    /// {context} (the code fragment that git places after the line numbers, e.g. the enclosing
+    /// function), and {funcname} (the function name in the code fragment, as matched by the
+    /// diff.DRIVER.xfuncname patterns of the file's diff driver, or else the code fragment). For
+    /// example, diff-so-fancy's hunk headers are '@ {file}:{line} @ {context}'. If empty, the code
+    /// fragment is displayed with syntax highlighting, followed by the line number on the next
+    /// line. Write '{{' or '}}' for a literal brace. A number may be added to
+    /// or subtracted from the line number, e.g. {line+3}: with git's default of 3 lines of
+    /// context, this is the line number of the first changed line of most hunks.
+    pub hunk_header_format: String,
+
+    #[structopt(long = "hunk-header-funcname-style", default_value = "bold")]
+    /// Style (foreground, background, attributes) applied to the function name in the code
+    /// fragment of a hunk header, for files whose diff driver, set by a diff=DRIVER attribute in
+    /// .gitattributes, has a diff.DRIVER.xfuncname git config setting. The function name is also
+    /// available as the {funcname} placeholder of --hunk-header-format. See STYLES section.
+    pub hunk_header_funcname_style: String,
+
+    /// Instead of displaying a hunk header above each hunk, prefix the first line of the hunk
+    /// with the range of line numbers that the hunk covers in the new version of the file,
+    /// displayed in --inline-hunk-header-style. This saves the vertical space taken by hunk
+    /// headers and their decoration.
+    #[structopt(long = "inline-hunk-headers")]
+    pub inline_hunk_headers: bool,
+
+    /// Style (foreground, background, attributes) for the line number range displayed by
+    /// --inline-hunk-headers. See STYLES section.
+    #[structopt(long = "inline-hunk-header-style", default_value = "dim")]
+    pub inline_hunk_header_style: String,
+
+    /// Compare delta's output, under the diff-highlight or diff-so-fancy feature, with the output
+    /// of the diff-highlight or diff-so-fancy script, for every file in the given directory. The
+    /// comparison ignores colors. The script must be installed.
+    #[structopt(long = "verify-emulation", parse(from_os_str))]
+    pub verify_emulation: Option<PathBuf>,
+
+    /// Instead of displaying the diff, write each file's part of it, rendered with the current
+    /// options, to a file in the given directory. The file is named after the path of the file in
+    /// the diff, with ".diff" appended.
+    #[structopt(long = "split-output", parse(from_os_str))]
+    pub split_output: Option<PathBuf>,
+
+    /// Display the input as usual, and then check that the output corresponds to the input: that
+    /// each line of each hunk is displayed as one line of the output, in order, or, under --raw
+    /// and --color-only, that the output with colors removed is the input. Violations are
+    /// reported on standard error, and the exit status is then 1. This is intended for checking
+    /// delta's output when it is used by other tools, e.g. as git's interactive.diffFilter.
+    /// Side-by-side hunks are not checked, and options which replace hunk lines, such as
+    /// --word-diff-mode and the lockfile summary, are reported as violations.
+    #[structopt(long = "validate")]
+    pub validate: bool,
+
+    /// Read the input from this source rather than from standard input. The source is '-' for
+    /// standard input, 'fd:N' for file descriptor N, or a file path, such as that given by process
+    /// substitution. With several --input options, e.g. `delta --input fd:3 --input fd:4`, the
+    /// inputs are displayed one after another, each under a header naming it, and nothing is
+    /// carried over from one input to the next.
+    #[structopt(long = "input", number_of_values = 1)]
+    pub input: Vec<String>,
+
+    /// Display the files given as arguments as patches, one after another, rather than comparing
+    /// them: `delta --patches a.patch b.patch.gz` is equivalent to `cat a.patch <(zcat
+    /// b.patch.gz) | delta`. A file whose name ends in .gz is decompressed, and one whose name
+    /// ends in .zst is decompressed by running the zstd program, which must be installed. A
+    /// single patch file (.patch or .diff, optionally compressed) is displayed in this way
+    /// without --patches.
+    #[structopt(long = "patches")]
+    pub patches: bool,
+
     /// The regular expression used to decide what a word is for the within-line highlight
     /// algorithm. For less fine-grained matching than the default try --word-diff-regex="\S+"
-    /// --max-line-distance=1.0 (this is more similar to `git --word-diff`).
+    /// --max-line-distance=1.0 (this is more similar to `git --word-diff`). Defaults to
+    /// diff.wordRegex if that is set in git config.
     #[structopt(long = "word-diff-regex", default_value = r"\w+")]
     pub tokenization_regex: String,
 
+    /// Display each pair of a removed line and the added line which replaces it as a single line,
+    /// in which the removed words are struck through, in --minus-emph-style, and followed by the
+    /// added words, in --plus-emph-style. The unchanged words are displayed in --zero-style.
+    /// Removed and added lines without such a counterpart are displayed as usual. Has no effect
+    /// with --side-by-side, or with --accessible, whose labels of removed and added lines a merged
+    /// line would lack.
+    #[structopt(long = "word-diff-mode")]
+    pub word_diff_mode: bool,
+
     /// The maximum distance between two lines for them to be inferred to be homologous. Homologous
     /// line pairs are highlighted according to the deletion and insertion operations transforming
     /// one into the other.
     #[structopt(long = "max-line-distance", default_value = "0.6")]
     pub max_line_distance: f64,
 
+    /// If a hunk contains more than this many lines, then syntax highlighting, within-line
+    /// highlighting, and side-by-side display are disabled for the remainder of the input, so
+    /// that delta remains responsive on pathological diffs. Use 0 for no limit.
+    #[structopt(long = "auto-degrade-hunk-lines", default_value = "10000")]
+    pub auto_degrade_hunk_lines: usize,
+
+    /// Truncate lines longer than this many characters, marking the truncation with '…', so that
+    /// files which are effectively a single enormous line, such as minified JavaScript, remain
+    /// readable and quick to display. Use 0 for no limit.
+    #[structopt(long = "max-line-length", default_value = "1000")]
+    pub max_line_length: usize,
+
+    /// Cache the rendered hunks of files of git diffs in the user's cache directory (e.g.
+    /// ~/.cache/delta), so that a diff which is viewed repeatedly, e.g. during a rebase or on
+    /// re-running `git log -p`, is displayed without being highlighted again. A cached rendering
+    /// is used only if the file's blob ids, path, hunks and delta's options are unchanged. The
+    /// cache is not used with --line-plugin, whose output may change. When the cache grows larger
+    /// than 64 MB, the entries written longest ago are deleted. The directory may be deleted at
+    /// any time.
+    #[structopt(long = "render-cache")]
+    pub render_cache: bool,
+
+    /// Exit with an error if delta fails to process its input. By default, delta writes a warning
+    /// and displays the remainder of the input unaltered.
+    #[structopt(long = "strict")]
+    pub strict: bool,
+
+    /// After the diff, display the number of lines added and removed in each file, largest
+    /// changes first, and the totals, as computed from delta's own parsing of the input. This is
+    /// similar to `git diff --stat`.
+    #[structopt(long = "stats")]
+    pub stats: bool,
+
+    /// If the input exceeds this many bytes, then syntax highlighting, within-line highlighting,
+    /// and side-by-side display are disabled for the remainder of the input. Use 0 for no limit.
+    #[structopt(long = "auto-degrade-bytes", default_value = "100000000")]
+    pub auto_degrade_bytes: usize,
+
     /// Style (foreground, background, attributes) for line numbers in the old (minus) version of
     /// the file. See STYLES and LINE NUMBERS sections.
     #[structopt(long = "line-numbers-minus-style", default_value = "auto")]
@@ -402,6 +904,178 @@ pub struct Opt {
     #[structopt(long = "line-numbers-right-style", default_value = "auto")]
     pub line_numbers_right_style: String,
 
+    /// The version of the file whose line numbers are the primary reference: 'old', 'new', or
+    /// 'none'. The line numbers of that version are displayed with --line-numbers-primary-style
+    /// applied on top of their style, and those of the other version with
+    /// --line-numbers-secondary-style. Use 'old' when reviewing reverts and backports, where the
+    /// old version is the one to check against. See LINE NUMBERS section.
+    #[structopt(long = "line-numbers-primary-side", default_value = "none")]
+    pub line_numbers_primary_side: String,
+
+    /// Colors and attributes applied on top of the style of the line numbers of the version of the
+    /// file chosen by --line-numbers-primary-side.
+    #[structopt(long = "line-numbers-primary-style", default_value = "bold")]
+    pub line_numbers_primary_style: String,
+
+    /// Colors and attributes applied on top of the style of the line numbers of the version of the
+    /// file not chosen by --line-numbers-primary-side.
+    #[structopt(long = "line-numbers-secondary-style", default_value = "dim")]
+    pub line_numbers_secondary_style: String,
+
+    /// Format string for the commit metadata displayed to the left of each line of `git blame`
+    /// output. The placeholders {commit}, {author}, {timestamp}, and {line_number} are available;
+    /// these may be followed by a format spec such as {author:<15.14}, meaning left-align, pad to
+    /// a width of 15, and truncate to at most 14 characters. Alignment may be '<', '^', or '>'.
+    /// Write '{{' or '}}' for a literal brace.
+    #[structopt(
+        long = "blame-format",
+        default_value = "{timestamp:<16} {author:<15.14} {commit:<8} │ "
+    )]
+    pub blame_format: String,
+
+    /// Background colors used to distinguish commits in `git blame` output: a space-separated list
+    /// of colors, which are assigned to commits in turn. Defaults to a palette of dark grays, or
+    /// light grays when --light is in effect.
+    #[structopt(long = "blame-palette")]
+    pub blame_palette: Option<String>,
+
+    /// A file listing commits whose changes are not to be blamed, one full commit hash per line,
+    /// as for `git blame --ignore-revs-file`. Lines of `git blame` output attributed to one of
+    /// these commits, e.g. a mass reformatting, are displayed without their commit metadata and
+    /// marked '*'. The marks made by git under blame.markIgnoredLines ('?', for a line attributed
+    /// to an earlier commit than an ignored one) and blame.markUnblamableLines ('*') are
+    /// displayed in the {commit} field. Defaults to git's blame.ignoreRevsFile setting; a relative
+    /// path is relative to the top of the repository.
+    #[structopt(long = "blame-ignore-revs-file")]
+    pub blame_ignore_revs_file: Option<String>,
+
+    /// The format of the {timestamp} placeholder in --blame-format, using strftime-style
+    /// specifiers (see https://docs.rs/chrono/0.4/chrono/format/strftime).
+    #[structopt(long = "blame-timestamp-format", default_value = "%Y-%m-%d %H:%M")]
+    pub blame_timestamp_format: String,
+
+    /// Reformat the "Date:" lines of commits in `git log -p` and `git show` output, using
+    /// strftime-style specifiers (see https://docs.rs/chrono/0.4/chrono/format/strftime), in
+    /// which {relative} is replaced by the time elapsed since the date, e.g. '2 weeks ago'. For
+    /// example, '{relative}' displays only the relative time, and '%c ({relative})' appends it to
+    /// the date. Write '{{' or '}}' for a literal brace.
+    #[structopt(long = "commit-date-format")]
+    pub commit_date_format: Option<String>,
+
+    /// Render the messages of commits in `git log -p` and `git show` output with light markdown
+    /// styling: headers ('# ...') are displayed in bold, bullet list markers are replaced by
+    /// bullets, and lines which are too wide for the terminal are wrapped. The diff is not
+    /// affected.
+    #[structopt(long = "commit-message-markdown")]
+    pub commit_message_markdown: bool,
+
+    /// Make issue references such as #1234, and the commit hashes of trailers such as 'Fixes:
+    /// <hash>', in the messages of commits in `git log -p` and `git show` output into hyperlinks to
+    /// the web pages of the repository at the remote named by --commit-link-remote, according to
+    /// its hosting service (see --commit-link-service). Hyperlinks are only emitted if the
+    /// terminal supports them (see --terminal-profile).
+    #[structopt(long = "commit-links")]
+    pub commit_links: bool,
+
+    /// Make the text matching a regular expression in commit messages into a hyperlink, e.g.
+    /// '[A-Z]+-\d+=>https://jira.example.com/browse/{0}'. The value has the form 'REGEX=>URL', in
+    /// which {0} is replaced by the matched text, {1}, {2}, ... by the text of the capture groups,
+    /// and {remote} by the web URL of the remote named by --commit-link-remote. If the regular
+    /// expression has capture groups then only the text of the first group is linked. May be given
+    /// more than once. Rules for a particular remote can be set in a git config section such as
+    /// [delta "remote.origin"]. See --commit-links.
+    #[structopt(long = "commit-link-rule", number_of_values = 1)]
+    pub commit_link_rule: Vec<String>,
+
+    /// The git remote whose web URL is used by --commit-links and --commit-link-rule.
+    #[structopt(long = "commit-link-remote", default_value = "origin")]
+    pub commit_link_remote: String,
+
+    /// The hosting service of the remote named by --commit-link-remote, which determines its web
+    /// URL and the URLs of the hyperlinks made by --commit-links: "github", "gitlab", "gitea",
+    /// "bitbucket" (bitbucket.org), "bitbucket-server", "azure-devops", or "auto". Under "auto",
+    /// the service is recognized by the host name of the remote, e.g. gitlab.example.com, and is
+    /// otherwise taken to be GitHub. The service of a self-hosted instance can be set for a
+    /// particular remote in a git config section such as [delta "remote.origin"], which takes
+    /// precedence.
+    #[structopt(long = "commit-link-service", default_value = "auto")]
+    pub commit_link_service: String,
+
+    /// Style (foreground, background, attributes) for the matched text in grep output. The match
+    /// can only be identified if the grep tool was told to emit color (e.g. `git grep
+    /// --color=always`). Defaults to --plus-emph-style. See STYLES section.
+    #[structopt(long = "grep-match-style", default_value = "auto auto")]
+    pub grep_match_style: String,
+
+    /// Style (foreground, background, attributes) for line numbers in grep output. See STYLES
+    /// section.
+    #[structopt(long = "grep-line-number-style", default_value = "green")]
+    pub grep_line_number_style: String,
+
+    /// Style (foreground, background, attributes) for commit hashes in `git log --oneline` and
+    /// `git reflog` output. See STYLES section.
+    #[structopt(long = "log-hash-style", default_value = "yellow")]
+    pub log_hash_style: String,
+
+    /// Style (foreground, background, attributes) for the parentheses and separators around ref
+    /// decorations in `git log --oneline` and `git reflog` output. See STYLES section.
+    #[structopt(long = "log-decoration-style", default_value = "yellow")]
+    pub log_decoration_style: String,
+
+    /// Style (foreground, background, attributes) for HEAD in ref decorations. See STYLES
+    /// section.
+    #[structopt(long = "log-head-style", default_value = "bold cyan")]
+    pub log_head_style: String,
+
+    /// Style (foreground, background, attributes) for branch names in ref decorations. See STYLES
+    /// section.
+    #[structopt(long = "log-branch-style", default_value = "bold green")]
+    pub log_branch_style: String,
+
+    /// Style (foreground, background, attributes) for tag names in ref decorations. See STYLES
+    /// section.
+    #[structopt(long = "log-tag-style", default_value = "bold yellow")]
+    pub log_tag_style: String,
+
+    /// Style (foreground, background, attributes) for reflog selectors such as HEAD@{0} in `git
+    /// reflog` output. See STYLES section.
+    #[structopt(long = "log-reflog-selector-style", default_value = "blue")]
+    pub log_reflog_selector_style: String,
+
+    /// Style (foreground, background, attributes) for the lines reporting a good signature, such
+    /// as "gpg: Good signature from ...", in `git log --show-signature` output. See STYLES
+    /// section.
+    #[structopt(long = "signature-good-style", default_value = "green")]
+    pub signature_good_style: String,
+
+    /// Style (foreground, background, attributes) for the lines of `git log --show-signature`
+    /// output which warn about a signature, such as a good signature made with a key which is
+    /// not trusted or has expired. See STYLES section.
+    #[structopt(long = "signature-warning-style", default_value = "yellow")]
+    pub signature_warning_style: String,
+
+    /// Style (foreground, background, attributes) for the lines of `git log --show-signature`
+    /// output which report a bad signature, or one which cannot be checked. See STYLES section.
+    #[structopt(long = "signature-bad-style", default_value = "bold red")]
+    pub signature_bad_style: String,
+
+    /// Mark the start of each file in the output, so that terminals which can jump between marks
+    /// can move from file to file. The value may be "none", "osc133" (semantic prompt sequences:
+    /// each file is marked as the output of a command, as supported by e.g. kitty, WezTerm and
+    /// Windows Terminal), or "iterm2" (iTerm2's SetMark sequence). The pager must pass the
+    /// sequences through to the terminal. With "auto", the marks supported by the terminal are
+    /// used, if any: see --terminal-profile.
+    #[structopt(long = "file-marks", default_value = "none")]
+    pub file_marks: String,
+
+    /// Copy the location of the first hunk displayed, as path:line, to the clipboard, using the OSC
+    /// 52 escape sequence, so that it can be pasted elsewhere, e.g. into a chat message. Use it
+    /// with --select to copy the location of a given hunk, e.g. `--select src/main.rs:3`. The
+    /// terminal must support OSC 52 and allow it to write to the clipboard, and the pager must
+    /// pass the sequence through to the terminal (e.g. less -r).
+    #[structopt(long = "copy-hunk-location")]
+    pub copy_hunk_location: bool,
+
     #[structopt(long = "file-modified-label", default_value = "")]
     /// Text to display in front of a modified file path.
     pub file_modified_label: String,
@@ -418,9 +1092,50 @@ pub struct Opt {
     /// Text to display in front of a renamed file path.
     pub file_renamed_label: String,
 
+    #[structopt(long = "hunk-label", default_value = "")]
+    /// Text to display in front of a hunk header.
+    pub hunk_label: String,
+
+    /// Display badges in front of hunk headers which classify the hunk's changes:
+    /// [whitespace-only], [comment-only], [import-changes] (e.g. import, use and #include lines)
+    /// and [rename-var] (one identifier consistently replaced by another). See --hunk-badge for
+    /// rules of your own.
+    #[structopt(long = "hunk-badges")]
+    pub hunk_badges: bool,
+
+    /// Collapse each hunk which only reorders imports, or only reformats code, into a line naming
+    /// the hunk, with the badge [import-reordering] or [formatting]. Imports are reordered when
+    /// all the changed lines are imports, with the same tokens removed as added. Code is
+    /// reformatted when the removed and added lines differ only in whitespace and, in languages
+    /// in which they are optional (such as Rust, Go, JavaScript, TypeScript and Python), trailing
+    /// commas. A collapsed hunk is displayed by selecting it with --select PATH:N.
+    #[structopt(long = "collapse-noise-hunks")]
+    pub collapse_noise_hunks: bool,
+
+    /// A rule for a hunk badge, of the form 'NAME=REGEX': hunks in which every removed and added
+    /// line matches the regular expression are given the badge [NAME], e.g.
+    /// 'deps=^\s*"[\w-]+": "[^"]+",?$'. A rule with the name of a badge of --hunk-badges replaces
+    /// it. May be given more than once.
+    #[structopt(long = "hunk-badge", number_of_values = 1)]
+    pub hunk_badge: Vec<String>,
+
+    /// Recognize directives of the form 'delta: lang=LANGUAGE', e.g. in a comment, in the lines of
+    /// a hunk: the hunk is highlighted as that language rather than as the language of its file.
+    /// This is useful for embedded code, such as SQL queries in strings.
+    #[structopt(long = "hunk-lang-directives")]
+    pub hunk_lang_directives: bool,
+
+    /// A rule of the form 'REGEX=LANGUAGE': hunks with a line which matches the regular expression
+    /// are highlighted as the language, e.g. '^\s*(SELECT|INSERT|UPDATE)\b=sql'. The first
+    /// matching rule applies, and a --hunk-lang-directives directive takes precedence. May be
+    /// given more than once.
+    #[structopt(long = "lang-for-hunks-matching", number_of_values = 1)]
+    pub lang_for_hunks_matching: Vec<String>,
+
     /// The width of underline/overline decorations. Use --width=variable to extend decorations and
     /// background colors to the end of the text only. Otherwise background colors extend to the
-    /// full terminal width.
+    /// full terminal width. Unless a number is given, the terminal width is checked again at the
+    /// start of each file and commit, so that the output follows the terminal if it is resized.
     #[structopt(short = "w", long = "width")]
     pub width: Option<String>,
 
@@ -431,14 +1146,59 @@ pub struct Opt {
     #[structopt(long = "tabs", default_value = "4")]
     pub tab_width: usize,
 
+    /// The number of spaces to replace tab characters with in files whose path matches GLOB,
+    /// given as 'GLOB=N', e.g. '*.c=8'. May be given more than once; the first matching glob is
+    /// used. Unless --tabs=0, Makefiles and Go files have a tab width of 8 by default, as is usual
+    /// for them. See --include-path for the pattern syntax.
+    #[structopt(long = "file-tabs", number_of_values = 1)]
+    pub file_tabs: Vec<String>,
+
+    /// Mark added lines which are wider than N columns, after tab expansion, with their width at
+    /// the end of the line, displayed in --line-length-marker-style. 0 means that lines are not
+    /// marked.
+    #[structopt(long = "line-length-limit", default_value = "0")]
+    pub line_length_limit: usize,
+
+    /// The --line-length-limit for files whose path matches GLOB, given as 'GLOB=N', e.g.
+    /// '*.py=79'. May be given more than once; the first matching glob is used. See
+    /// --include-path for the pattern syntax.
+    #[structopt(long = "file-line-length-limits", number_of_values = 1)]
+    pub file_line_length_limits: Vec<String>,
+
+    /// Style (foreground, background, attributes) for the markers of --line-length-limit. See
+    /// STYLES section.
+    #[structopt(long = "line-length-marker-style", default_value = "dim red")]
+    pub line_length_marker_style: String,
+
     /// Whether to emit 24-bit ("true color") RGB color codes. Options are auto, always, and never.
     /// "auto" means that delta will emit 24-bit color codes if the environment variable COLORTERM
     /// has the value "truecolor" or "24bit". If your terminal application (the application you use
     /// to enter commands at a shell prompt) supports 24 bit colors, then it probably already sets
-    /// this environment variable, in which case you don't need to do anything.
+    /// this environment variable, in which case you don't need to do anything. Otherwise, 24-bit
+    /// color codes are emitted if the terminal supports them: see --terminal-profile.
     #[structopt(long = "24-bit-color", default_value = "auto")]
     pub true_color: String,
 
+    /// The profile of the terminal's capabilities: whether it supports 24-bit color and
+    /// hyperlinks, and which file marks it supports (see --file-marks). With "auto", the terminal
+    /// is detected from the WT_SESSION, TERM_PROGRAM and TERM environment variables. Otherwise,
+    /// the value is the name of a profile: alacritty, apple-terminal, ghostty, iterm2, kitty,
+    /// linux, tmux, vscode, wezterm, windows-terminal, or one defined in git config. The
+    /// capabilities of a profile are overridden in a git config section such as
+    /// [delta "terminal.kitty"], with the keys true-color and hyperlinks (true or false), and
+    /// marks (none, osc133 or iterm2). An unknown terminal has none of the capabilities.
+    #[structopt(long = "terminal-profile", default_value = "auto")]
+    pub terminal_profile: String,
+
+    /// The output profile: color, mono, or auto. The mono profile is for printing and for
+    /// monochrome terminals: the colors of all styles are removed (a background color becomes
+    /// reverse video), syntax highlighting is disabled, +/- markers are kept, the emphasized
+    /// sections of removed and added lines are displayed in reverse video and bold underline, and
+    /// the ascii feature is used. With "auto", the mono profile is used when standard output is a
+    /// terminal whose terminfo entry (for the TERM environment variable) reports at most 2 colors.
+    #[structopt(long = "profile", default_value = "auto")]
+    pub profile: String,
+
     /// Whether to use a pager when displaying output. Options are: auto, always, and never. The
     /// default pager is `less`: this can be altered by setting the environment variables BAT_PAGER
     /// or PAGER (BAT_PAGER has priority).
@@ -446,7 +1206,14 @@ pub struct Opt {
     pub paging_mode: String,
 
     /// First file to be compared when delta is being used in diff mode: `delta file_1 file_2` is
-    /// equivalent to `diff -u file_1 file_2 | delta`.
+    /// equivalent to `diff -u file_1 file_2 | delta`, and delta exits with the exit status of
+    /// diff: 0 if the files are the same, 1 if they differ, 2 if there was a problem. If only one
+    /// file is given, it is displayed with syntax highlighting, unless it is a patch file: see
+    /// --patches. Input which is not a diff is displayed in the same way if its language is known,
+    /// from its first line (e.g. a shebang line) or from --default-language. A .rej file left by
+    /// `git apply --reject` or `patch` is displayed as a diff, with each rejected hunk followed by
+    /// the current contents of the lines it expected to change. A .orig backup left by `patch` is
+    /// compared with the file next to it.
     #[structopt(parse(from_os_str))]
     pub minus_file: Option<PathBuf>,
 
@@ -454,6 +1221,10 @@ pub struct Opt {
     #[structopt(parse(from_os_str))]
     pub plus_file: Option<PathBuf>,
 
+    /// Further patch files, under --patches.
+    #[structopt(parse(from_os_str))]
+    pub more_files: Vec<PathBuf>,
+
     /// Style for removed empty line marker (used only if --minus-style has no background color)
     #[structopt(
         long = "--minus-empty-line-marker-style",
@@ -465,11 +1236,31 @@ pub struct Opt {
     #[structopt(long = "--plus-empty-line-marker-style", default_value = "normal auto")]
     pub plus_empty_line_marker_style: String,
 
+    /// Style for the badge which, under --side-by-side, is displayed at the bottom of a panel whose
+    /// file has no newline at its end, in place of git's "\ No newline at end of file" line.
+    #[structopt(long = "no-newline-badge-style", default_value = "dim reverse")]
+    pub no_newline_badge_style: String,
+
     /// Style for whitespace errors. Defaults to color.diff.whitespace if that is set in git
     /// config, or else 'magenta reverse'.
     #[structopt(long = "whitespace-error-style", default_value = "auto auto")]
     pub whitespace_error_style: String,
 
+    /// Style (foreground, background, attributes) for text matching a --highlight regular
+    /// expression which has no style of its own. The colors and attributes of this style are
+    /// applied on top of the style of the line.
+    #[structopt(long = "highlight-style", default_value = "reverse")]
+    pub highlight_style: String,
+
+    /// Style (foreground, background, attributes) for the replacement text of --redact.
+    #[structopt(long = "redacted-style", default_value = "reverse")]
+    pub redacted_style: String,
+
+    /// Style (foreground, background, attributes) for the symbols which are displayed in place of
+    /// control characters in hunk lines. See --no-sanitize-control-chars.
+    #[structopt(long = "control-char-style", default_value = "bold yellow reverse")]
+    pub control_char_style: String,
+
     #[structopt(long = "minus-color")]
     /// Deprecated: use --minus-style='normal my_background_color'.
     pub deprecated_minus_background_color: Option<String>,
@@ -522,22 +1313,75 @@ pub struct Opt {
 #[derive(Default, Clone, Debug)]
 pub struct ComputedValues {
     pub is_light_mode: bool,
-    pub syntax_set: SyntaxSet,
+    pub syntax_set: LazySyntaxSet,
     pub syntax_theme: Option<SyntaxTheme>,
-    pub syntax_dummy_theme: SyntaxTheme,
     pub true_color: bool,
     pub available_terminal_width: usize,
     pub decorations_width: Width,
     pub background_color_extends_to_terminal_width: bool,
     pub paging_mode: PagingMode,
+    // git's diff.context setting
+    pub diff_context_lines: Option<u32>,
+    // The prefixes of the paths of git diffs, from git's diff.noprefix, diff.srcPrefix and
+    // diff.dstPrefix settings
+    pub diff_prefixes: Vec<String>,
+    // The types of the values of the options, by option name, for --list-options
+    pub option_types: HashMap<String, &'static str>,
+    pub terminal_profile: TerminalProfile,
+    // Whether delta is acting as git's interactive.diffFilter: see --diff-filter-mode
+    pub is_diff_filter: bool,
+    // Whether the output is monochrome: see --profile
+    pub is_mono: bool,
+    // The URL of the remote named by --commit-link-remote
+    pub commit_link_remote_url: Option<String>,
+    // The features disabled by a name prefixed with '-' in --features
+    pub disabled_features: Vec<String>,
+    // The (driver, pattern) pairs of git's diff.<driver>.xfuncname settings
+    pub xfuncname_patterns: Vec<(String, String)>,
+    // The keys of delta settings in the git config which are not options
+    pub unknown_git_config_options: Vec<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum Width {
     Fixed(usize),
     Variable,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileMarks {
+    None,
+    Osc133,
+    ITerm2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WarningFormat {
+    Text,
+    Json,
+}
+
+impl WarningFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(WarningFormat::Text),
+            "json" => Some(WarningFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+impl FileMarks {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(FileMarks::None),
+            "osc133" => Some(FileMarks::Osc133),
+            "iterm2" => Some(FileMarks::ITerm2),
+            _ => None,
+        }
+    }
+}
+
 impl Default for Width {
     fn default() -> Self {
         Width::Variable
@@ -550,6 +1394,18 @@ impl Default for PagingMode {
     }
 }
 
+impl Default for FileMarks {
+    fn default() -> Self {
+        FileMarks::None
+    }
+}
+
+impl Default for WarningFormat {
+    fn default() -> Self {
+        WarningFormat::Text
+    }
+}
+
 impl Opt {
     pub fn from_args_and_git_config(
         git_config: &mut Option<GitConfig>,
@@ -558,16 +1414,37 @@ impl Opt {
         Self::from_clap_and_git_config(Self::clap().get_matches(), git_config, assets)
     }
 
-    #[cfg(test)]
-    pub fn from_iter_and_git_config<I>(iter: I, git_config: &mut Option<GitConfig>) -> Self
+    pub fn from_iter_and_git_config<I>(
+        iter: I,
+        git_config: &mut Option<GitConfig>,
+        assets: HighlightingAssets,
+    ) -> Self
     where
         I: IntoIterator,
         I::Item: Into<OsString> + Clone,
     {
-        let assets = HighlightingAssets::new();
         Self::from_clap_and_git_config(Self::clap().get_matches_from(iter), git_config, assets)
     }
 
+    /// As from_iter_and_git_config, but invalid arguments are returned as an error rather than
+    /// ending the process.
+    #[allow(dead_code)] // Used by the library's make_config: the delta binary exits instead.
+    pub fn from_iter_safe_and_git_config<I>(
+        iter: I,
+        git_config: &mut Option<GitConfig>,
+        assets: HighlightingAssets,
+    ) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString> + Clone,
+    {
+        Ok(Self::from_clap_and_git_config(
+            Self::clap().get_matches_from_safe(iter)?,
+            git_config,
+            assets,
+        ))
+    }
+
     fn from_clap_and_git_config(
         arg_matches: clap::ArgMatches,
         git_config: &mut Option<GitConfig>,
@@ -613,9 +1490,19 @@ lazy_static! {
         "deprecated-highlight-minus-lines",
         "deprecated-theme",
         "deprecated-commit-color",
+        "benchmark",
+        "benchmark-iterations",
+        "verify-emulation",
+        "split-output",
+        "validate",
+        "input",
+        "patches",
         "list-languages",
+        "list-options",
+        "list-options-format",
         "list-syntax-themes",
         "show-config",
+        "show-styles",
         "show-syntax-themes",
     ]
     .into_iter()
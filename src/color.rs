@@ -10,23 +10,28 @@ use crate::bat::terminal::to_ansi_color;
 use crate::syntect_color;
 
 pub fn parse_color(s: &str, true_color: bool) -> Option<Color> {
+    try_parse_color(s, true_color).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    })
+}
+
+/// As parse_color, but return an error, rather than exiting, if the color is invalid.
+pub fn try_parse_color(s: &str, true_color: bool) -> Result<Option<Color>, String> {
     if s == "normal" {
-        return None;
+        return Ok(None);
     }
-    let die = || {
-        eprintln!("Invalid color or style attribute: {}", s);
-        process::exit(1);
-    };
+    let error = || format!("Invalid color or style attribute: {}", s);
     let syntect_color = if s.starts_with("#") {
-        SyntectColor::from_str(s).unwrap_or_else(|_| die())
+        SyntectColor::from_str(s).map_err(|_| error())?
     } else {
         s.parse::<u8>()
             .ok()
             .and_then(syntect_color::syntect_color_from_ansi_number)
             .or_else(|| syntect_color::syntect_color_from_ansi_name(s))
-            .unwrap_or_else(die)
+            .ok_or_else(error)?
     };
-    Some(to_ansi_color(syntect_color, true_color))
+    Ok(Some(to_ansi_color(syntect_color, true_color)))
 }
 
 pub fn color_to_string(color: Color) -> String {
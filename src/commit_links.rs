@@ -0,0 +1,464 @@
+use std::process;
+
+use itertools::Itertools;
+use regex::{Captures, Regex};
+
+// References in the messages of commits in `git log -p` and `git show` output, such as issue
+// numbers and the commit hashes of trailers, are made into OSC 8 hyperlinks. The URLs are made
+// from templates, in which {remote} is replaced by the web URL of a git remote, so that each
+// repository links to its own forge. The URLs of the builtin rules, and the web URL of a remote,
+// depend on the hosting service of the remote, which is recognized by its host name, or else set
+// for a particular remote in git config, as its rules can be:
+//
+// [delta "remote.origin"]
+//     commit-link-service = gitea
+//     commit-link-rule = [A-Z]+-\\d+=>https://jira.example.com/browse/{0}
+
+/// The hosting services whose URL schemes are known.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HostingService {
+    GitHub,
+    GitLab,
+    Gitea,
+    Bitbucket,
+    BitbucketServer,
+    AzureDevOps,
+}
+
+impl HostingService {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "github" => Some(HostingService::GitHub),
+            "gitlab" => Some(HostingService::GitLab),
+            "gitea" => Some(HostingService::Gitea),
+            "bitbucket" => Some(HostingService::Bitbucket),
+            "bitbucket-server" => Some(HostingService::BitbucketServer),
+            "azure-devops" => Some(HostingService::AzureDevOps),
+            _ => None,
+        }
+    }
+
+    /// Return the hosting service of the git remote URL `url`, judging by its host name. A host
+    /// which is not recognized is taken to be GitHub, whose URL scheme is the most common.
+    pub fn detect(url: &str) -> Self {
+        let host = match split_remote_url(url) {
+            Some((host, _)) => host,
+            None => return HostingService::GitHub,
+        };
+        if host.ends_with("dev.azure.com") || host.ends_with(".visualstudio.com") {
+            HostingService::AzureDevOps
+        } else if host == "bitbucket.org" {
+            HostingService::Bitbucket
+        } else if host.contains("bitbucket") {
+            HostingService::BitbucketServer
+        } else if host.contains("gitlab") {
+            HostingService::GitLab
+        } else if host.contains("gitea") || host == "codeberg.org" {
+            HostingService::Gitea
+        } else {
+            HostingService::GitHub
+        }
+    }
+}
+
+/// A rule linking the text matched by a regex to a URL.
+#[derive(Clone, Debug)]
+pub struct CommitLinkRule {
+    regex: Regex,
+    url_template: String,
+}
+
+impl CommitLinkRule {
+    /// Parse a --commit-link-rule value, 'REGEX=>URL', in which {remote} is replaced by
+    /// `remote_url`. Return None if the URL refers to the remote and there is no remote URL.
+    pub fn from_str(s: &str, remote_url: Option<&str>) -> Option<Self> {
+        let (regex, url_template) = s.rsplit_once("=>").unwrap_or_else(|| {
+            eprintln!(
+                "Invalid commit link rule: {}. The value must be of the form 'REGEX=>URL'.",
+                s
+            );
+            process::exit(1);
+        });
+        let regex = Regex::new(regex).unwrap_or_else(|_| {
+            eprintln!(
+                "Invalid commit link rule: {}. \
+                 The regular expression must be a valid Rust regular expression. \
+                 See https://docs.rs/regex.",
+                s
+            );
+            process::exit(1);
+        });
+        let url_template = if url_template.contains("{remote}") {
+            url_template.replace("{remote}", remote_url?)
+        } else {
+            url_template.to_string()
+        };
+        Some(Self {
+            regex,
+            url_template,
+        })
+    }
+
+    fn make_url(&self, caps: &Captures) -> String {
+        let mut url = self.url_template.clone();
+        for i in 0..caps.len() {
+            url = url.replace(
+                &format!("{{{}}}", i),
+                caps.get(i).map_or("", |m| m.as_str()),
+            );
+        }
+        url
+    }
+}
+
+/// Return the rules linking issue references such as #1234, and the commit hashes of trailers
+/// such as 'Fixes: <hash>', to the web pages of the repository at `remote_url`, which is hosted by
+/// `service`.
+pub fn get_builtin_rules(remote_url: &str, service: HostingService) -> Vec<CommitLinkRule> {
+    let (issues, commits) = match service {
+        HostingService::GitHub | HostingService::Gitea => (
+            Some("{remote}/issues/{2}".to_string()),
+            "{remote}/commit/{1}",
+        ),
+        HostingService::GitLab => (
+            Some("{remote}/-/issues/{2}".to_string()),
+            "{remote}/-/commit/{1}",
+        ),
+        HostingService::Bitbucket => (
+            Some("{remote}/issues/{2}".to_string()),
+            "{remote}/commits/{1}",
+        ),
+        // Bitbucket Server has no issue tracker of its own.
+        HostingService::BitbucketServer => (None, "{remote}/commits/{1}"),
+        // Issue references are to the work items of the project of the repository.
+        HostingService::AzureDevOps => (
+            remote_url
+                .rsplit_once("/_git/")
+                .map(|(project_url, _)| format!("{}/_workitems/edit/{{2}}", project_url)),
+            "{remote}/commit/{1}",
+        ),
+    };
+    let mut rules = Vec::new();
+    if let Some(issues) = issues {
+        rules.push(format!(r"\B(#(\d+))\b=>{}", issues));
+    }
+    rules.push(format!(
+        r"(?i)(?:^\s*(?:fixes|refs|reverts|see-also):\s+|this reverts commit |cherry picked from commit )([0-9a-f]{{7,40}})\b=>{}",
+        commits
+    ));
+    rules
+        .iter()
+        .filter_map(|rule| CommitLinkRule::from_str(rule, Some(remote_url)))
+        .collect()
+}
+
+/// Return the URL of the web pages of the repository at the git remote URL `url`, which is hosted
+/// by `service`, e.g. https://github.com/dandavison/delta for
+/// git@github.com:dandavison/delta.git.
+pub fn get_remote_web_url(url: &str, service: HostingService) -> Option<String> {
+    let (host, path) = split_remote_url(url)?;
+    match service {
+        // git@ssh.dev.azure.com:v3/ORG/PROJECT/REPO is https://dev.azure.com/ORG/PROJECT/_git/REPO
+        HostingService::AzureDevOps if path.starts_with("v3/") => {
+            let (project, repo) = path["v3/".len()..].rsplit_once('/')?;
+            let host = host.strip_prefix("ssh.").unwrap_or(host);
+            Some(format!("https://{}/{}/_git/{}", host, project, repo))
+        }
+        // The repository is cloned from [CONTEXT/]scm/KEY/REPO over https, or [CONTEXT/]KEY/REPO
+        // over ssh, and displayed at [CONTEXT/]projects/KEY/repos/REPO, or
+        // [CONTEXT/]users/USER/repos/REPO for the personal project ~USER.
+        HostingService::BitbucketServer => {
+            let (rest, repo) = path.rsplit_once('/')?;
+            let (context, key) = rest.rsplit_once('/').unwrap_or(("", rest));
+            let context = match context {
+                "scm" => "",
+                context => context.strip_suffix("/scm").unwrap_or(context),
+            };
+            let project = match key.strip_prefix('~') {
+                Some(user) => format!("users/{}", user),
+                None => format!("projects/{}", key.to_uppercase()),
+            };
+            let path = [context, &project, "repos", repo]
+                .iter()
+                .filter(|segment| !segment.is_empty())
+                .join("/");
+            Some(format!("https://{}/{}", host, path))
+        }
+        _ => Some(format!("https://{}/{}", host, path)),
+    }
+}
+
+/// Split the git remote URL `url` into its host, without user or port, and the path of the
+/// repository, without ".git".
+fn split_remote_url(url: &str) -> Option<(&str, &str)> {
+    let url = url.trim().trim_end_matches('/');
+    let url = url.strip_suffix(".git").unwrap_or(url);
+    let (host, path) = if let Some(rest) = ["https://", "http://", "ssh://", "git://"]
+        .iter()
+        .find_map(|scheme| url.strip_prefix(scheme))
+    {
+        rest.split_once('/')?
+    } else {
+        // The scp-like syntax, [user@]host:path
+        let (host, path) = url.split_once(':')?;
+        if host.contains('/') {
+            return None;
+        }
+        (host, path)
+    };
+    // Remove the user and the port.
+    let host = host.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+    let path = path.trim_start_matches('/');
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+    Some((host, path))
+}
+
+/// Make the text matched by the rules in a line of a commit message into OSC 8 hyperlinks. The
+/// text of the first capture group of a rule, or else of its whole match, is linked. Where the
+/// matches of several rules overlap, the first rule wins. Return None if nothing matches.
+pub fn linkify(line: &str, rules: &[CommitLinkRule]) -> Option<String> {
+    let mut links: Vec<(usize, usize, String)> = Vec::new();
+    for rule in rules {
+        for caps in rule.regex.captures_iter(line) {
+            let linked = caps.get(1).or_else(|| caps.get(0)).unwrap();
+            if linked.start() == linked.end()
+                || links
+                    .iter()
+                    .any(|(start, end, _)| linked.start() < *end && *start < linked.end())
+            {
+                continue;
+            }
+            links.push((linked.start(), linked.end(), rule.make_url(&caps)));
+        }
+    }
+    if links.is_empty() {
+        return None;
+    }
+    links.sort_by_key(|(start, _, _)| *start);
+    let mut linkified = String::new();
+    let mut offset = 0;
+    for (start, end, url) in links {
+        linkified.push_str(&line[offset..start]);
+        linkified.push_str(&format!(
+            "\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\",
+            url,
+            &line[start..end]
+        ));
+        offset = end;
+    }
+    linkified.push_str(&line[offset..]);
+    Some(linkified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    fn link(url: &str, text: &str) -> String {
+        format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+    }
+
+    #[test]
+    fn test_get_remote_web_url() {
+        for url in &[
+            "git@github.com:dandavison/delta.git",
+            "https://github.com/dandavison/delta.git",
+            "https://user@github.com/dandavison/delta",
+            "ssh://git@github.com:22/dandavison/delta.git",
+            "github.com:dandavison/delta/",
+        ] {
+            assert_eq!(
+                get_remote_web_url(url, HostingService::GitHub),
+                Some("https://github.com/dandavison/delta".to_string()),
+                "{}",
+                url
+            );
+        }
+        assert_eq!(
+            get_remote_web_url("/home/user/delta", HostingService::GitHub),
+            None
+        );
+        assert_eq!(
+            get_remote_web_url("../delta.git", HostingService::GitHub),
+            None
+        );
+
+        for (url, service, web_url) in &[
+            (
+                "git@ssh.dev.azure.com:v3/org/project/repo",
+                HostingService::AzureDevOps,
+                "https://dev.azure.com/org/project/_git/repo",
+            ),
+            (
+                "https://org@dev.azure.com/org/project/_git/repo",
+                HostingService::AzureDevOps,
+                "https://dev.azure.com/org/project/_git/repo",
+            ),
+            (
+                "https://git.example.com/scm/key/repo.git",
+                HostingService::BitbucketServer,
+                "https://git.example.com/projects/KEY/repos/repo",
+            ),
+            (
+                "https://example.com/bitbucket/scm/~user/repo.git",
+                HostingService::BitbucketServer,
+                "https://example.com/bitbucket/users/user/repos/repo",
+            ),
+            (
+                "ssh://git@git.example.com:7999/key/repo.git",
+                HostingService::BitbucketServer,
+                "https://git.example.com/projects/KEY/repos/repo",
+            ),
+            (
+                "git@git.example.com:group/repo.git",
+                HostingService::Gitea,
+                "https://git.example.com/group/repo",
+            ),
+        ] {
+            assert_eq!(
+                get_remote_web_url(url, *service),
+                Some(web_url.to_string()),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn test_detect_hosting_service() {
+        for (url, service) in &[
+            (
+                "git@github.com:dandavison/delta.git",
+                HostingService::GitHub,
+            ),
+            ("https://git.example.com/a/b", HostingService::GitHub),
+            ("git@gitlab.example.com:a/b.git", HostingService::GitLab),
+            ("https://codeberg.org/a/b", HostingService::Gitea),
+            ("https://bitbucket.org/a/b", HostingService::Bitbucket),
+            (
+                "https://bitbucket.example.com/scm/a/b.git",
+                HostingService::BitbucketServer,
+            ),
+            (
+                "git@ssh.dev.azure.com:v3/a/b/c",
+                HostingService::AzureDevOps,
+            ),
+            (
+                "https://a.visualstudio.com/b/_git/c",
+                HostingService::AzureDevOps,
+            ),
+            // The host name, rather than the path, is examined.
+            ("https://github.com/a/gitlab", HostingService::GitHub),
+        ] {
+            assert_eq!(HostingService::detect(url), *service, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let rules = get_builtin_rules(
+            "https://dev.azure.com/org/project/_git/repo",
+            HostingService::AzureDevOps,
+        );
+        assert_eq!(
+            linkify("    Fixes: 8c1d2f3 (#12)", &rules),
+            Some(format!(
+                "    Fixes: {} ({})",
+                link(
+                    "https://dev.azure.com/org/project/_git/repo/commit/8c1d2f3",
+                    "8c1d2f3"
+                ),
+                link(
+                    "https://dev.azure.com/org/project/_workitems/edit/12",
+                    "#12"
+                )
+            ))
+        );
+
+        // References to issues are not linked, since Bitbucket Server has no issue tracker.
+        let rules = get_builtin_rules(
+            "https://git.example.com/projects/KEY/repos/repo",
+            HostingService::BitbucketServer,
+        );
+        assert_eq!(
+            linkify("    Fixes: 8c1d2f3 (#12)", &rules),
+            Some(format!(
+                "    Fixes: {} (#12)",
+                link(
+                    "https://git.example.com/projects/KEY/repos/repo/commits/8c1d2f3",
+                    "8c1d2f3"
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_linkify() {
+        let remote = "https://gitlab.com/group/project";
+        let mut rules = get_builtin_rules(remote, HostingService::GitLab);
+        rules.push(
+            CommitLinkRule::from_str(r"JIRA-\d+=>https://jira.example.com/browse/{0}", None)
+                .unwrap(),
+        );
+        assert!(CommitLinkRule::from_str(r"#\d+=>{remote}/{0}", None).is_none());
+
+        assert_eq!(
+            linkify("    Fix crash (#1234, JIRA-567)", &rules),
+            Some(format!(
+                "    Fix crash ({}, {})",
+                link("https://gitlab.com/group/project/-/issues/1234", "#1234"),
+                link("https://jira.example.com/browse/JIRA-567", "JIRA-567")
+            ))
+        );
+        assert_eq!(
+            linkify("    Fixes: 8c1d2f3 (\"Add feature\")", &rules),
+            Some(format!(
+                "    Fixes: {} (\"Add feature\")",
+                link(
+                    "https://gitlab.com/group/project/-/commit/8c1d2f3",
+                    "8c1d2f3"
+                )
+            ))
+        );
+        assert_eq!(linkify("    See a#1 and 8c1d2f3", &rules), None);
+    }
+
+    #[test]
+    fn test_commit_links() {
+        let input = "\
+commit 8c1d2f3
+Author: Delta <delta@example.com>
+Date:   Thu Jan 1 00:00:00 1970 +0000
+
+    Fix #12
+
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1 +1 @@
+-a = 1 # 12
++a = 2 #12
+";
+        let rule = r"#(\d+)=>https://example.com/issues/{1}";
+        let config =
+            make_config_from_args(&["--commit-link-rule", rule, "--terminal-profile", "kitty"]);
+        let output = run_delta(input, &config);
+        assert!(output.contains(&format!(
+            "\n    Fix #{}\n",
+            link("https://example.com/issues/12", "12")
+        )));
+        assert_eq!(output.matches("\x1b]8;;").count(), 2);
+
+        let config =
+            make_config_from_args(&["--commit-link-rule", rule, "--terminal-profile", "linux"]);
+        let output = run_delta(input, &config);
+        assert!(!output.contains("\x1b]8;;"));
+    }
+}
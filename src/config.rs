@@ -5,20 +5,38 @@ use regex::Regex;
 use structopt::clap;
 use syntect::highlighting::Style as SyntectStyle;
 use syntect::highlighting::Theme as SyntaxTheme;
-use syntect::parsing::SyntaxSet;
 
+use crate::bat::assets::LazySyntaxSet;
 use crate::bat::output::PagingMode;
+use crate::blame;
 use crate::cli;
 use crate::color;
+use crate::commit_links::{self, CommitLinkRule, HostingService};
 use crate::delta::State;
 use crate::env;
 use crate::features::side_by_side;
+use crate::filter;
+use crate::funcname;
+use crate::highlight_patterns;
+use crate::hunk_badges;
+use crate::hunk_language;
+use crate::redact;
+use crate::render_cache;
 use crate::style::Style;
+use crate::terminal_profile::TerminalProfile;
 
 pub struct Config {
+    pub accessible: bool,
+    pub ascii: bool,
     pub available_terminal_width: usize,
     pub background_color_extends_to_terminal_width: bool,
+    pub collapse_distant_context: bool,
+    pub collapse_noise_hunks: bool,
+    pub commit_date_format: Option<String>,
+    pub commit_link_rules: Vec<CommitLinkRule>,
+    pub commit_message_markdown: bool,
     pub commit_style: Style,
+    pub copy_hunk_location: bool,
     pub decorations_width: cli::Width,
     pub file_added_label: String,
     pub file_modified_label: String,
@@ -26,9 +44,80 @@ pub struct Config {
     pub file_renamed_label: String,
     pub file_style: Style,
     pub keep_plus_minus_markers: bool,
+    pub log_branch_style: Style,
+    pub log_decoration_style: Style,
+    pub log_hash_style: Style,
+    pub log_head_style: Style,
+    pub log_reflog_selector_style: Style,
+    pub log_tag_style: Style,
+    pub signature_bad_style: Style,
+    pub signature_good_style: Style,
+    pub signature_warning_style: Style,
+    pub hunk_header_format: String,
+    pub hunk_badge_rules: Vec<hunk_badges::HunkBadgeRule>,
+    pub hunk_lang_directives: bool,
+    pub hunk_language_rules: Vec<hunk_language::HunkLanguageRule>,
+    pub hunk_label: String,
     pub hunk_header_style: Style,
+    pub hunk_header_funcname_style: Style,
+    pub funcname_rules: Vec<funcname::FuncnameRule>,
+    pub inline_hunk_headers: bool,
+    pub inline_hunk_header_style: Style,
     pub max_buffered_lines: usize,
     pub max_line_distance: f64,
+    pub blame: bool,
+    pub blame_format: String,
+    pub blame_ignore_revs: Vec<String>,
+    pub blame_palette: Vec<ansi_term::Color>,
+    pub blame_timestamp_format: String,
+    pub grep: bool,
+    pub select: Option<filter::Selection>,
+    pub emit_patch: bool,
+    pub emit_locations: Option<String>,
+    pub include_path: Vec<filter::PathGlob>,
+    pub exclude_path: Vec<filter::PathGlob>,
+    pub pre_render_filters: Vec<(filter::PathGlob, String)>,
+    pub structured_diff: bool,
+    pub notebook_diff: bool,
+    pub lockfile_summary: bool,
+    pub highlight_patterns: Vec<highlight_patterns::HighlightPattern>,
+    pub redactions: Vec<redact::Redaction>,
+    pub redacted_style: Style,
+    pub sanitize_control_chars: bool,
+    pub control_char_style: Style,
+    pub line_plugin: Option<String>,
+    // The directory of the render cache, and the hash of the options, under --render-cache
+    pub render_cache: Option<(PathBuf, u64)>,
+    pub diff_context_lines: Option<u32>,
+    // The prefixes which git may have added to the file paths of a diff
+    pub diff_prefixes: Vec<String>,
+    pub keep_diff_prefixes: bool,
+    pub hide_hunks_matching: Vec<Regex>,
+    pub hide_files_matching: Vec<Regex>,
+    pub invert: bool,
+    pub max_hunk_lines: usize,
+    pub max_output_lines: usize,
+    pub flush_per_line: bool,
+    pub warning_format: cli::WarningFormat,
+    pub max_line_length: usize,
+    pub reverse_hunks: bool,
+    pub stats: bool,
+    pub strict: bool,
+    pub strip_path_prefix: Vec<(String, String)>,
+    pub relative_to: Option<String>,
+    pub file_labels: Option<(String, String)>,
+    pub file_marks: cli::FileMarks,
+    pub file_stats: bool,
+    pub default_language: Option<String>,
+    pub distant_context: usize,
+    pub distant_context_style: Style,
+    pub expand_context: usize,
+    pub expanded_context_style: Style,
+    pub filter_notice_style: Style,
+    pub grep_line_number_style: Style,
+    pub grep_match_style: Style,
+    pub auto_degrade_hunk_lines: usize,
+    pub auto_degrade_bytes: usize,
     pub max_line_distance_for_naively_paired_lines: f64,
     pub minus_emph_style: Style,
     pub minus_empty_line_marker_style: Style,
@@ -36,12 +125,16 @@ pub struct Config {
     pub minus_non_emph_style: Style,
     pub minus_style: Style,
     pub navigate: bool,
+    pub numbered: bool,
     pub null_style: Style,
     pub null_syntect_style: SyntectStyle,
     pub line_numbers_left_format: String,
     pub line_numbers_left_style: Style,
     pub line_numbers_minus_style: Style,
     pub line_numbers_plus_style: Style,
+    pub line_numbers_primary_side: Option<side_by_side::PanelSide>,
+    pub line_numbers_primary_style: Style,
+    pub line_numbers_secondary_style: Style,
     pub line_numbers_right_format: String,
     pub line_numbers_right_style: Style,
     pub line_numbers_zero_style: Style,
@@ -53,15 +146,25 @@ pub struct Config {
     pub plus_style: Style,
     pub line_numbers: bool,
     pub side_by_side: bool,
-    pub side_by_side_data: side_by_side::SideBySideData,
-    pub syntax_dummy_theme: SyntaxTheme,
-    pub syntax_set: SyntaxSet,
+    pub side_by_side_context_fill: bool,
+    pub side_by_side_align_edits: bool,
+    pub no_newline_badge_style: Style,
+    pub syntax_set: LazySyntaxSet,
     pub syntax_theme: Option<SyntaxTheme>,
     pub tab_width: usize,
+    pub file_tab_widths: Vec<(filter::PathGlob, usize)>,
+    pub line_length_limit: usize,
+    pub file_line_length_limits: Vec<(filter::PathGlob, usize)>,
+    pub line_length_marker_style: Style,
+    pub terminal_profile: TerminalProfile,
+    // Whether the widths which depend on the terminal width follow it when the terminal is
+    // resized, i.e. whether --width was not given as a number: see Painter::update_terminal_width
+    pub track_terminal_width: bool,
     pub true_color: bool,
     pub truncation_symbol: String,
     pub tokenization_regex: Regex,
     pub whitespace_error_style: Style,
+    pub word_diff_mode: bool,
     pub zero_style: Style,
 }
 
@@ -74,10 +177,46 @@ impl Config {
             _ => delta_unreachable("Unreachable code reached in get_style."),
         }
     }
+
+    /// Return the prefixes to remove from the file paths of git diffs.
+    pub fn get_diff_prefixes_to_remove(&self) -> &[String] {
+        if self.keep_diff_prefixes {
+            &[]
+        } else {
+            &self.diff_prefixes
+        }
+    }
+
+    /// Return the tab width of the file with this path.
+    pub fn get_tab_width(&self, path: &str) -> usize {
+        self.file_tab_widths
+            .iter()
+            .find(|(glob, _)| glob.is_match(path))
+            .map(|(_, n)| *n)
+            .unwrap_or(self.tab_width)
+    }
+
+    /// Return the line length limit of the file with this path.
+    pub fn get_line_length_limit(&self, path: &str) -> usize {
+        self.file_line_length_limits
+            .iter()
+            .find(|(glob, _)| glob.is_match(path))
+            .map(|(_, n)| *n)
+            .unwrap_or(self.line_length_limit)
+    }
 }
 
 impl From<cli::Opt> for Config {
     fn from(opt: cli::Opt) -> Self {
+        // The output of a hunk is cached only if it depends on nothing but the options and the
+        // hunk, which is not so under --line-plugin, nor under --copy-hunk-location, which copies
+        // the location of the first hunk displayed only.
+        let render_cache =
+            if opt.render_cache && opt.line_plugin.is_none() && !opt.copy_hunk_location {
+                render_cache::make_render_cache(&opt)
+            } else {
+                None
+            };
         let (
             minus_style,
             minus_emph_style,
@@ -100,6 +239,8 @@ impl From<cli::Opt> for Config {
             line_numbers_plus_style,
             line_numbers_left_style,
             line_numbers_right_style,
+            line_numbers_primary_style,
+            line_numbers_secondary_style,
         ) = make_line_number_styles(&opt);
 
         let max_line_distance_for_naively_paired_lines =
@@ -117,27 +258,400 @@ impl From<cli::Opt> for Config {
             process::exit(1);
         });
 
-        let side_by_side_data = side_by_side::SideBySideData::new(
-            &opt.computed.decorations_width,
-            &opt.computed.available_terminal_width,
+        let grep_match_style = Style::from_str(
+            &opt.grep_match_style,
+            Some(plus_emph_style),
+            None,
+            opt.computed.true_color,
+            true,
+        );
+        let grep_line_number_style = Style::from_str(
+            &opt.grep_line_number_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let log_branch_style = Style::from_str(
+            &opt.log_branch_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+        let log_decoration_style = Style::from_str(
+            &opt.log_decoration_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+        let log_hash_style = Style::from_str(
+            &opt.log_hash_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+        let log_head_style = Style::from_str(
+            &opt.log_head_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+        let log_reflog_selector_style = Style::from_str(
+            &opt.log_reflog_selector_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+        let log_tag_style = Style::from_str(
+            &opt.log_tag_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let signature_bad_style = Style::from_str(
+            &opt.signature_bad_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+        let signature_good_style = Style::from_str(
+            &opt.signature_good_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+        let signature_warning_style = Style::from_str(
+            &opt.signature_warning_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let expanded_context_style = Style::from_str(
+            &opt.expanded_context_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let distant_context_style = Style::from_str(
+            &opt.distant_context_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let filter_notice_style = Style::from_str(
+            &opt.filter_notice_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let highlight_style = Style::from_str(
+            &opt.highlight_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let highlight_patterns = opt
+            .highlight
+            .iter()
+            .map(|s| {
+                highlight_patterns::HighlightPattern::from_str(
+                    s,
+                    highlight_style,
+                    opt.computed.true_color,
+                )
+            })
+            .collect();
+
+        let commit_link_service = match opt.commit_link_service.as_str() {
+            "auto" => HostingService::detect(
+                opt.computed
+                    .commit_link_remote_url
+                    .as_deref()
+                    .unwrap_or(""),
+            ),
+            service => HostingService::from_name(service).unwrap_or_else(|| {
+                eprintln!(
+                    "Invalid value for --commit-link-service option: {} (valid values are \"github\", \"gitlab\", \"gitea\", \"bitbucket\", \"bitbucket-server\", \"azure-devops\", and \"auto\")",
+                    service
+                );
+                process::exit(1);
+            }),
+        };
+        let commit_link_rules = if opt.computed.terminal_profile.hyperlinks {
+            let remote_url = opt
+                .computed
+                .commit_link_remote_url
+                .as_deref()
+                .and_then(|url| commit_links::get_remote_web_url(url, commit_link_service));
+            let remote_url = remote_url.as_deref();
+            let mut rules = match remote_url {
+                Some(remote_url) if opt.commit_links => {
+                    commit_links::get_builtin_rules(remote_url, commit_link_service)
+                }
+                _ => Vec::new(),
+            };
+            rules.extend(
+                opt.commit_link_rule
+                    .iter()
+                    .filter_map(|s| CommitLinkRule::from_str(s, remote_url)),
+            );
+            rules
+        } else {
+            Vec::new()
+        };
+
+        let warning_format = cli::WarningFormat::from_name(&opt.warning_format).unwrap_or_else(|| {
+            eprintln!(
+                "Invalid value for --warning-format option: {} (valid values are \"text\" and \"json\")",
+                opt.warning_format
+            );
+            process::exit(1);
+        });
+
+        let no_newline_badge_style = Style::from_str(
+            &opt.no_newline_badge_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let line_length_marker_style = Style::from_str(
+            &opt.line_length_marker_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let hunk_header_funcname_style = Style::from_str(
+            &opt.hunk_header_funcname_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let inline_hunk_header_style = Style::from_str(
+            &opt.inline_hunk_header_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let control_char_style = Style::from_str(
+            &opt.control_char_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let redacted_style = Style::from_str(
+            &opt.redacted_style,
+            None,
+            None,
+            opt.computed.true_color,
+            false,
+        );
+
+        let blame_palette = blame::make_blame_palette(
+            opt.blame_palette.as_deref(),
+            opt.computed.is_light_mode,
+            opt.computed.true_color,
         );
 
         Self {
+            accessible: opt.accessible,
+            ascii: opt.ascii,
             available_terminal_width: opt.computed.available_terminal_width,
             background_color_extends_to_terminal_width: opt
                 .computed
                 .background_color_extends_to_terminal_width,
+            blame: opt.blame,
+            blame_format: opt.blame_format,
+            blame_ignore_revs: opt
+                .blame_ignore_revs_file
+                .as_deref()
+                .map(blame::read_ignore_revs_file)
+                .unwrap_or_default(),
+            blame_palette,
+            blame_timestamp_format: opt.blame_timestamp_format,
+            collapse_distant_context: opt.collapse_distant_context,
+            collapse_noise_hunks: opt.collapse_noise_hunks,
+            commit_date_format: opt.commit_date_format,
+            commit_link_rules,
+            commit_message_markdown: opt.commit_message_markdown,
             commit_style,
+            copy_hunk_location: opt.copy_hunk_location,
             decorations_width: opt.computed.decorations_width,
             file_added_label: opt.file_added_label,
             file_modified_label: opt.file_modified_label,
             file_removed_label: opt.file_removed_label,
             file_renamed_label: opt.file_renamed_label,
             file_style,
+            grep: opt.grep,
+            select: opt.select.as_deref().map(filter::Selection::from_str),
+            emit_patch: opt.emit_patch,
+            emit_locations: opt.emit_locations,
+            include_path: opt
+                .include_path
+                .iter()
+                .map(|s| filter::PathGlob::new(s))
+                .collect(),
+            exclude_path: opt
+                .exclude_path
+                .iter()
+                .map(|s| filter::PathGlob::new(s))
+                .collect(),
+            structured_diff: opt.structured_diff,
+            notebook_diff: opt.notebook_diff,
+            lockfile_summary: !opt.no_lockfile_summary && !opt.emit_patch,
+            pre_render_filters: opt
+                .pre_render_filter
+                .iter()
+                .map(|s| match s.split_once('=') {
+                    Some((glob, command)) => (filter::PathGlob::new(glob), command.to_string()),
+                    None => {
+                        eprintln!(
+                            "Invalid value for --pre-render-filter: '{}'. It should be of the \
+                             form 'GLOB=COMMAND'.",
+                            s
+                        );
+                        process::exit(1);
+                    }
+                })
+                .collect(),
+            highlight_patterns,
+            redactions: opt
+                .redact
+                .iter()
+                .map(|s| redact::Redaction::from_str(s))
+                .collect(),
+            redacted_style,
+            sanitize_control_chars: !opt.no_sanitize_control_chars,
+            control_char_style,
+            hide_hunks_matching: make_filter_regexes(
+                &opt.hide_hunks_matching,
+                "hide-hunks-matching",
+            ),
+            hide_files_matching: make_filter_regexes(
+                &opt.hide_files_matching,
+                "hide-files-matching",
+            ),
+            line_plugin: opt.line_plugin,
+            render_cache,
+            diff_context_lines: opt.computed.diff_context_lines,
+            diff_prefixes: opt.computed.diff_prefixes,
+            keep_diff_prefixes: opt.keep_diff_prefixes,
+            invert: opt.invert,
+            max_hunk_lines: opt.max_hunk_lines,
+            max_output_lines: opt.max_output_lines,
+            flush_per_line: opt.flush_per_line,
+            warning_format,
+            max_line_length: opt.max_line_length,
+            reverse_hunks: opt.reverse_hunks,
+            stats: opt.stats,
+            strict: opt.strict,
+            strip_path_prefix: opt
+                .strip_path_prefix
+                .iter()
+                .map(|value| match value.split_once("=>") {
+                    Some((prefix, replacement)) => (prefix.to_string(), replacement.to_string()),
+                    None => (value.to_string(), "".to_string()),
+                })
+                .collect(),
+            file_stats: opt.file_stats,
+            file_labels: opt
+                .file_labels
+                .as_deref()
+                .map(|labels| match labels.split_once(',') {
+                    Some((minus_label, plus_label)) => {
+                        (minus_label.to_string(), plus_label.to_string())
+                    }
+                    None => {
+                        eprintln!(
+                            "Invalid value for --file-labels: '{}'. It should be of the form \
+                             'OLD,NEW'.",
+                            labels
+                        );
+                        process::exit(1);
+                    }
+                }),
+            file_marks: match opt.file_marks.as_str() {
+                "auto" => opt.computed.terminal_profile.marks,
+                file_marks => cli::FileMarks::from_name(file_marks).unwrap_or_else(|| {
+                    eprintln!(
+                        "Invalid value for --file-marks option: {} (valid values are \"none\", \"osc133\", \"iterm2\", and \"auto\")",
+                        file_marks
+                    );
+                    process::exit(1);
+                }),
+            },
+            default_language: opt.default_language.clone(),
+            relative_to: opt
+                .relative_to
+                .as_deref()
+                .map(|dir| dir.trim_end_matches('/').to_string()),
+            distant_context: opt.distant_context,
+            distant_context_style,
+            // Under --color-only, hunks keep their lines.
+            expand_context: if opt.color_only {
+                0
+            } else {
+                opt.expand_context
+            },
+            expanded_context_style,
+            filter_notice_style,
+            grep_line_number_style,
+            grep_match_style,
             keep_plus_minus_markers: opt.keep_plus_minus_markers,
+            log_branch_style,
+            log_decoration_style,
+            log_hash_style,
+            log_head_style,
+            log_reflog_selector_style,
+            log_tag_style,
+            signature_bad_style,
+            signature_good_style,
+            signature_warning_style,
+            hunk_header_format: opt.hunk_header_format,
+            hunk_badge_rules: hunk_badges::make_hunk_badge_rules(opt.hunk_badges, &opt.hunk_badge),
+            hunk_lang_directives: opt.hunk_lang_directives,
+            hunk_language_rules: hunk_language::make_hunk_language_rules(
+                &opt.lang_for_hunks_matching,
+            ),
+            hunk_label: opt.hunk_label,
+            inline_hunk_headers: opt.inline_hunk_headers,
+            inline_hunk_header_style,
             hunk_header_style,
+            hunk_header_funcname_style,
+            funcname_rules: funcname::make_funcname_rules(&opt.computed.xfuncname_patterns),
             max_buffered_lines: 32,
             max_line_distance: opt.max_line_distance,
+            auto_degrade_hunk_lines: opt.auto_degrade_hunk_lines,
+            auto_degrade_bytes: opt.auto_degrade_bytes,
             max_line_distance_for_naively_paired_lines,
             minus_emph_style,
             minus_empty_line_marker_style,
@@ -145,12 +659,28 @@ impl From<cli::Opt> for Config {
             minus_non_emph_style,
             minus_style,
             navigate: opt.navigate,
+            numbered: opt.numbered,
             null_style: Style::new(),
             null_syntect_style: SyntectStyle::default(),
             line_numbers_left_format: opt.line_numbers_left_format,
             line_numbers_left_style,
             line_numbers_minus_style,
             line_numbers_plus_style,
+            line_numbers_primary_side: match opt.line_numbers_primary_side.as_str() {
+                "none" => None,
+                "old" => Some(side_by_side::PanelSide::Left),
+                "new" => Some(side_by_side::PanelSide::Right),
+                side => {
+                    eprintln!(
+                        "Invalid value for --line-numbers-primary-side option: {} \
+                         (valid values are \"none\", \"old\", and \"new\")",
+                        side
+                    );
+                    process::exit(1);
+                }
+            },
+            line_numbers_primary_style,
+            line_numbers_secondary_style,
             line_numbers_right_format: opt.line_numbers_right_format,
             line_numbers_right_style,
             line_numbers_zero_style,
@@ -162,20 +692,82 @@ impl From<cli::Opt> for Config {
             plus_style,
             line_numbers: opt.line_numbers,
             side_by_side: opt.side_by_side,
-            side_by_side_data,
-            syntax_dummy_theme: SyntaxTheme::default(),
+            side_by_side_context_fill: opt.side_by_side_context_fill,
+            side_by_side_align_edits: opt.side_by_side_align_edits,
+            no_newline_badge_style,
             syntax_set: opt.computed.syntax_set,
             syntax_theme: opt.computed.syntax_theme,
             tab_width: opt.tab_width,
+            file_tab_widths: make_file_tab_widths(&opt.file_tabs, opt.tab_width),
+            line_length_limit: opt.line_length_limit,
+            file_line_length_limits: make_file_line_length_limits(&opt.file_line_length_limits),
+            line_length_marker_style,
+            terminal_profile: opt.computed.terminal_profile.clone(),
+            track_terminal_width: opt.width.as_deref().unwrap_or("variable") == "variable",
             tokenization_regex,
             true_color: opt.computed.true_color,
-            truncation_symbol: "→".to_string(),
+            truncation_symbol: if opt.ascii { ">" } else { "→" }.to_string(),
             whitespace_error_style,
+            word_diff_mode: opt.word_diff_mode,
             zero_style,
         }
     }
 }
 
+// The tab widths of files in which tabs are conventionally 8 columns wide
+const DEFAULT_FILE_TAB_WIDTHS: &[(&str, usize)] = &[
+    ("Makefile", 8),
+    ("makefile", 8),
+    ("GNUmakefile", 8),
+    ("*.mk", 8),
+    ("*.go", 8),
+];
+
+fn make_file_tab_widths(file_tabs: &[String], tab_width: usize) -> Vec<(filter::PathGlob, usize)> {
+    let mut file_tab_widths: Vec<_> = file_tabs
+        .iter()
+        .map(
+            |s| match s.split_once('=').map(|(glob, n)| (glob, n.parse())) {
+                Some((glob, Ok(n))) => (filter::PathGlob::new(glob), n),
+                _ => {
+                    eprintln!(
+                        "Invalid value for --file-tabs: '{}'. It should be of the form 'GLOB=N'.",
+                        s
+                    );
+                    process::exit(1);
+                }
+            },
+        )
+        .collect();
+    if tab_width > 0 {
+        file_tab_widths.extend(
+            DEFAULT_FILE_TAB_WIDTHS
+                .iter()
+                .map(|(glob, n)| (filter::PathGlob::new(glob), *n)),
+        );
+    }
+    file_tab_widths
+}
+
+fn make_file_line_length_limits(values: &[String]) -> Vec<(filter::PathGlob, usize)> {
+    values
+        .iter()
+        .map(
+            |s| match s.split_once('=').map(|(glob, n)| (glob, n.parse())) {
+                Some((glob, Ok(n))) => (filter::PathGlob::new(glob), n),
+                _ => {
+                    eprintln!(
+                        "Invalid value for --file-line-length-limits: '{}'. It should be of the \
+                         form 'GLOB=N'.",
+                        s
+                    );
+                    process::exit(1);
+                }
+            },
+        )
+        .collect()
+}
+
 fn make_hunk_styles<'a>(
     opt: &'a cli::Opt,
 ) -> (
@@ -206,14 +798,20 @@ fn make_hunk_styles<'a>(
         false,
     );
 
+    // Under --foreground-emph, emph styles take their default background from the line, so that
+    // emphasis is by foreground attributes only.
     let minus_emph_style = Style::from_str(
         &opt.minus_emph_style,
         Some(Style::from_colors(
             None,
-            Some(color::get_minus_emph_background_color_default(
-                is_light_mode,
-                true_color,
-            )),
+            if opt.foreground_emph {
+                minus_style.ansi_term_style.background
+            } else {
+                Some(color::get_minus_emph_background_color_default(
+                    is_light_mode,
+                    true_color,
+                ))
+            },
         )),
         None,
         true_color,
@@ -227,6 +825,17 @@ fn make_hunk_styles<'a>(
         true_color,
         false,
     );
+    let minus_non_emph_style = if opt.side_by_side {
+        Style::from_str(
+            &opt.side_by_side_minus_non_emph_style,
+            Some(minus_non_emph_style),
+            None,
+            true_color,
+            false,
+        )
+    } else {
+        minus_non_emph_style
+    };
 
     // The style used to highlight a removed empty line when otherwise it would be invisible due to
     // lack of background color in minus-style.
@@ -264,10 +873,14 @@ fn make_hunk_styles<'a>(
         &opt.plus_emph_style,
         Some(Style::from_colors(
             None,
-            Some(color::get_plus_emph_background_color_default(
-                is_light_mode,
-                true_color,
-            )),
+            if opt.foreground_emph {
+                plus_style.ansi_term_style.background
+            } else {
+                Some(color::get_plus_emph_background_color_default(
+                    is_light_mode,
+                    true_color,
+                ))
+            },
         )),
         None,
         true_color,
@@ -281,6 +894,17 @@ fn make_hunk_styles<'a>(
         true_color,
         false,
     );
+    let plus_non_emph_style = if opt.side_by_side {
+        Style::from_str(
+            &opt.side_by_side_plus_non_emph_style,
+            Some(plus_non_emph_style),
+            None,
+            true_color,
+            false,
+        )
+    } else {
+        plus_non_emph_style
+    };
 
     // The style used to highlight an added empty line when otherwise it would be invisible due to
     // lack of background color in plus-style.
@@ -315,7 +939,9 @@ fn make_hunk_styles<'a>(
     )
 }
 
-fn make_line_number_styles<'a>(opt: &'a cli::Opt) -> (Style, Style, Style, Style, Style) {
+fn make_line_number_styles<'a>(
+    opt: &'a cli::Opt,
+) -> (Style, Style, Style, Style, Style, Style, Style) {
     let true_color = opt.computed.true_color;
     let line_numbers_left_style =
         Style::from_str(&opt.line_numbers_left_style, None, None, true_color, false);
@@ -332,12 +958,30 @@ fn make_line_number_styles<'a>(opt: &'a cli::Opt) -> (Style, Style, Style, Style
     let line_numbers_right_style =
         Style::from_str(&opt.line_numbers_right_style, None, None, true_color, false);
 
+    let line_numbers_primary_style = Style::from_str(
+        &opt.line_numbers_primary_style,
+        None,
+        None,
+        true_color,
+        false,
+    );
+
+    let line_numbers_secondary_style = Style::from_str(
+        &opt.line_numbers_secondary_style,
+        None,
+        None,
+        true_color,
+        false,
+    );
+
     (
         line_numbers_minus_style,
         line_numbers_zero_style,
         line_numbers_plus_style,
         line_numbers_left_style,
         line_numbers_right_style,
+        line_numbers_primary_style,
+        line_numbers_secondary_style,
     )
 }
 
@@ -371,6 +1015,23 @@ fn make_commit_file_hunk_header_styles(opt: &cli::Opt) -> (Style, Style, Style)
     )
 }
 
+fn make_filter_regexes(regexes: &[String], option_name: &str) -> Vec<Regex> {
+    regexes
+        .iter()
+        .map(|regex| {
+            Regex::new(regex).unwrap_or_else(|_| {
+                eprintln!(
+                    "Invalid {}: {}. \
+                     The value must be a valid Rust regular expression. \
+                     See https://docs.rs/regex.",
+                    option_name, regex
+                );
+                process::exit(1);
+            })
+        })
+        .collect()
+}
+
 /// Did the user supply `option` on the command line?
 pub fn user_supplied_option(option: &str, arg_matches: &clap::ArgMatches) -> bool {
     arg_matches.occurrences_of(option) > 0
@@ -0,0 +1,195 @@
+use std::borrow::Cow;
+
+use crate::config::Config;
+use crate::paint::LineSections;
+use crate::style::Style;
+
+// A file under review can contain control characters which, written to the terminal as they are,
+// would move the cursor, erase or overwrite text, or start escape sequences, so that the diff
+// displays something other than the change. In the content of hunk lines, control characters other
+// than tab are therefore replaced by the Unicode symbols for them ("control pictures"), which are
+// displayed in --control-char-style. A C1 control character, e.g. U+009B, is displayed as the
+// symbol for escape followed by its 7-bit equivalent, e.g. "␛[". Only the symbols which replaced
+// control characters are displayed in --control-char-style: a file may contain the symbols
+// themselves. Under raw and color-only, the lines are written as they are.
+
+const SYMBOL_FOR_NULL: u32 = 0x2400;
+const SYMBOL_FOR_ESCAPE: char = '\u{241b}';
+const SYMBOL_FOR_DELETE: char = '\u{2421}';
+
+/// The byte ranges, in ascending order, of the symbols which replaced control characters in a line.
+pub type SymbolRanges = Vec<(usize, usize)>;
+
+/// Return the line with its control characters, other than tab, replaced by visible symbols, and
+/// the byte ranges of the symbols in the returned line.
+pub fn sanitize(line: &str) -> (Cow<'_, str>, SymbolRanges) {
+    if !line.chars().any(is_control_char) {
+        return (Cow::Borrowed(line), Vec::new());
+    }
+    let mut sanitized = String::with_capacity(line.len() + 8);
+    let mut ranges = Vec::new();
+    for c in line.chars() {
+        let start = sanitized.len();
+        match c as u32 {
+            0x09 => sanitized.push(c),
+            code @ 0x00..=0x1f => {
+                sanitized.push(std::char::from_u32(SYMBOL_FOR_NULL + code).unwrap())
+            }
+            0x7f => sanitized.push(SYMBOL_FOR_DELETE),
+            code @ 0x80..=0x9f => {
+                sanitized.push(SYMBOL_FOR_ESCAPE);
+                sanitized.push(std::char::from_u32(code - 0x40).unwrap());
+            }
+            _ => sanitized.push(c),
+        }
+        if is_control_char(c) {
+            ranges.push((start, sanitized.len()));
+        }
+    }
+    (Cow::Owned(sanitized), ranges)
+}
+
+fn is_control_char(c: char) -> bool {
+    c != '\t' && c.is_control()
+}
+
+/// Split the diff style sections of a line so that the symbols at `ranges` of the line are
+/// displayed in control-char-style.
+pub fn style_control_char_sections<'a>(
+    sections: LineSections<'a, Style>,
+    ranges: &[(usize, usize)],
+    config: &Config,
+) -> LineSections<'a, Style> {
+    if ranges.is_empty() {
+        return sections;
+    }
+    let mut styled_sections = Vec::with_capacity(sections.len() + 2 * ranges.len());
+    let mut offset = 0;
+    for section in sections {
+        let length = section.1.len();
+        styled_sections.extend(style_control_chars_in_section(
+            section, offset, ranges, config,
+        ));
+        offset += length;
+    }
+    styled_sections
+}
+
+/// Split a diff style section, which starts at byte `offset` of its line, so that the symbols at
+/// `ranges` of the line are displayed in control-char-style.
+pub fn style_control_chars_in_section<'a>(
+    (style, text): (Style, &'a str),
+    offset: usize,
+    ranges: &[(usize, usize)],
+    config: &Config,
+) -> LineSections<'a, Style> {
+    let mut sections = Vec::new();
+    let mut i = 0;
+    for &(start, end) in ranges {
+        let (start, end) = (
+            start.clamp(offset, offset + text.len()) - offset,
+            end.clamp(offset, offset + text.len()) - offset,
+        );
+        if start == end {
+            continue;
+        }
+        if i < start {
+            sections.push((style, &text[i..start]));
+        }
+        sections.push((config.control_char_style, &text[start..end]));
+        i = end;
+    }
+    if i < text.len() || sections.is_empty() {
+        sections.push((style, &text[i..]));
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(sanitize("a\tb"), (Cow::Borrowed("a\tb"), vec![]));
+        assert_eq!(sanitize("a\x1b[2Jb\r").0, "a␛[2Jb␍");
+        assert_eq!(sanitize("\x08\x07\x7f").0, "␈␇␡");
+        assert_eq!(
+            sanitize("a\u{9b}31m"),
+            (Cow::Borrowed("a␛[31m"), vec![(1, 5)])
+        );
+        assert_eq!(sanitize("␛\x1b"), (Cow::Borrowed("␛␛"), vec![(3, 6)]));
+    }
+
+    #[test]
+    fn test_style_control_char_sections() {
+        let config = make_config_from_args(&["--control-char-style", "bold red"]);
+        let style = config.plus_style;
+        let line = " a␛[2Jb␍";
+        let sections = style_control_char_sections(
+            vec![(style, &line[..5]), (style, &line[5..])],
+            &[(2, 5), (9, 12)],
+            &config,
+        );
+        assert_eq!(
+            sections,
+            vec![
+                (style, " a"),
+                (config.control_char_style, "␛"),
+                (style, "[2Jb"),
+                (config.control_char_style, "␍"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_control_chars_in_hunk_lines() {
+        let config = make_config_from_args(&["--control-char-style", "bold red"]);
+        let output = run_delta(DIFF, &config);
+        assert!(!output.contains("\x1b[2J"));
+        assert!(!output.contains('\r'));
+        // The symbol for escape in the file itself is not displayed in control-char-style.
+        let escape_symbol = config.control_char_style.paint("␛").to_string();
+        assert_eq!(output.matches(&escape_symbol).count(), 1);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("echo \"␛[2J\"␍ok"));
+        assert!(output.contains("secret␈␈␈␈␈␈public"));
+        assert!(output.contains("␛ is escape"));
+
+        // Under --color-only, the output must be the input with colors added.
+        let config = make_config_from_args(&["--color-only"]);
+        let output = run_delta(DIFF, &config);
+        assert_eq!(strip_ansi_codes(&output), strip_ansi_codes(DIFF));
+        assert!(output.contains("secret\x08\x08\x08\x08\x08\x08public"));
+
+        let config = make_config_from_args(&["--no-sanitize-control-chars"]);
+        let output = run_delta(DIFF, &config);
+        assert!(!output.contains("\x1b[2J"));
+        assert!(output.contains("secret\x08\x08\x08\x08\x08\x08public"));
+    }
+
+    #[test]
+    fn test_escape_sequences_in_colored_input_are_removed() {
+        let config = make_config_from_args(&[]);
+        let input = format!("\x1b[1m{}", DIFF);
+        let output = strip_ansi_codes(&run_delta(&input, &config)).to_string();
+        assert!(output.contains("echo \"\"␍ok"));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.sh b/a.sh
+index 223ca50..e69de29 100644
+--- a/a.sh
++++ b/a.sh
+@@ -1,3 +1,3 @@
+-echo \"hello\"
++echo \"\x1b[2J\"\rok
+ # secret\x08\x08\x08\x08\x08\x08public
+ # ␛ is escape
+";
+}
@@ -1,15 +1,42 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::io::BufRead;
-use std::io::Write;
+use std::io::{BufWriter, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::Once;
 
 use bytelines::ByteLines;
-use console::strip_ansi_codes;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
 use unicode_segmentation::UnicodeSegmentation;
 
+use crate::ansi;
+use crate::blame;
+use crate::cli::FileMarks;
+use crate::commit_links;
 use crate::config::Config;
+use crate::control_chars;
 use crate::draw;
-use crate::paint::Painter;
-use crate::parse;
-use crate::style::DecorationStyle;
+use crate::features::line_numbers::LineNumbersData;
+use crate::features::side_by_side;
+use crate::filter::{self, FilteredLine, HunkLineCounter};
+use crate::funcname;
+use crate::grep;
+use crate::highlight_patterns;
+use crate::line_plugin::LinePlugin;
+use crate::log;
+use crate::numbering::{self, Numbering};
+use crate::paint::{Painter, StyleSections};
+use crate::parse::{self, LinePrefix};
+use crate::pre_render;
+use crate::rej;
+use crate::render_cache::{BufferedHunkLine, BufferedHunks, RecordingWriter, RenderCache};
+use crate::sdiff;
+use crate::show;
+use crate::stats::{self, DiffStats};
+use crate::style::{DecorationStyle, Style};
+use crate::warnings;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum State {
@@ -19,9 +46,22 @@ pub enum State {
     HunkZero,   // In hunk; unchanged line
     HunkMinus,  // In hunk; removed line
     HunkPlus,   // In hunk; added line
+    Blame,      // In `git blame` output
+    Grep,       // In grep output
+    Log,        // In `git log --oneline` or `git reflog` output
+    Submodule,  // Following a "Submodule" line, e.g. in the commit list of --submodule=log
     Unknown,
 }
 
+const ESCAPE: u8 = 0x1b;
+
+// Output is written in large chunks, since the writer is typically an unbuffered pipe to the pager.
+const OUTPUT_BUFFER_SIZE: usize = 128 * 1024;
+
+// The number of lines of input which is not a diff held back before it is displayed, so that
+// `diff -y` output, which is converted to a hunk as a whole, can be displayed as a diff.
+const MAX_HELD_LINES: usize = 1000;
+
 #[derive(Debug, PartialEq)]
 pub enum Source {
     GitDiff,     // Coming from a `git diff` command
@@ -50,120 +90,1175 @@ impl State {
 // | HunkMinus   | flush, emit | flush, emit | flush, emit | flush, emit | push        | push     |
 // | HunkPlus    | flush, emit | flush, emit | flush, emit | flush, emit | flush, push | push     |
 
-pub fn delta<I>(
-    mut lines: ByteLines<I>,
+pub fn delta<I>(lines: ByteLines<I>, writer: &mut dyn Write, config: &Config) -> std::io::Result<()>
+where
+    I: BufRead,
+{
+    let mut progress = InputProgress::default();
+    delta_part(lines, writer, config, &mut progress)?;
+    end_input(writer, &progress, config)
+}
+
+/// The state which spans the whole input, when it is displayed in parts, each with its own
+/// configuration: see reload.rs.
+#[derive(Default)]
+pub struct InputProgress {
+    diff_stats: DiffStats,
+    numbering: Numbering,
+    n_lines_written: usize,
+    omitted: Option<OmittedInput>,
+    // The number of files in the input so far, by which --select may choose a file
+    n_files: usize,
+    is_selection_found: bool,
+    available_terminal_width: usize,
+}
+
+/// Display a part of the input, which is continued by the next part, or ended by end_input.
+pub fn delta_part<I>(
+    lines: ByteLines<I>,
     writer: &mut dyn Write,
     config: &Config,
+    progress: &mut InputProgress,
 ) -> std::io::Result<()>
 where
     I: BufRead,
 {
-    let mut painter = Painter::new(writer, config);
+    let mut writer = RecordingWriter::new(BufWriter::with_capacity(OUTPUT_BUFFER_SIZE, writer));
+    let recording = writer.recording();
+    let n_lines_written = writer.line_count();
+    n_lines_written.set(progress.n_lines_written);
+    let mut painter = Painter::new(&mut writer, config);
     let mut minus_file = "".to_string();
-    let mut plus_file;
+    let mut plus_file = "".to_string();
     let mut state = State::Unknown;
     let mut source = Source::Unknown;
+    let mut n_bytes = 0;
+    let mut n_hunk_lines = 0;
+    let mut blame_state = blame::BlameState::new(config);
+    let mut grep_state = grep::GrepState::default();
+    let mut plain_file_input = PlainFileInput::new();
+    let mut rejected_hunks = RejectedHunks::default();
+    // The submodule whose changes are being displayed, under `git diff --submodule=diff`
+    let mut submodule: Option<String> = None;
+    let mut hunk_line_counter: Option<HunkLineCounter> = None;
+    let mut line_plugin = LinePlugin::new(config);
+    let mut cached_rendering = CachedRendering::new(config, recording);
+    let mut annotations = PendingAnnotations::default();
+    let mut file_meta_rows = side_by_side::FileMetaRows::default();
+    // Whether the input is colored, as git's output is, judging by its first line. The escape
+    // sequences in hunk lines of uncolored input belong to the content of the files.
+    let mut is_input_colored = false;
 
-    while let Some(Ok(raw_line_bytes)) = lines.next() {
-        let raw_line = String::from_utf8_lossy(&raw_line_bytes);
-        let line = strip_ansi_codes(&raw_line).to_string();
-        if source == Source::Unknown {
-            source = detect_source(&line);
-        }
-        if line.starts_with("commit ") {
-            painter.paint_buffered_minus_and_plus_lines();
-            state = State::CommitMeta;
-            if should_handle(&state, config) {
+    let mut lines = filter::InputFilter::new(lines, config);
+    lines.n_files = progress.n_files;
+    lines.is_selection_found = progress.is_selection_found;
+    // The line being processed, to be written as it is if processing fails
+    let mut current_line = String::new();
+    let process_lines = || -> std::io::Result<()> {
+        loop {
+            // Under --flush-per-line, the output so far is written before waiting for more input.
+            if config.flush_per_line {
                 painter.emit()?;
-                handle_commit_meta_header_line(&mut painter, &line, &raw_line, config)?;
+                painter.writer.flush()?;
+            }
+            let filtered_line = match lines.next_line() {
+                Some(filtered_line) => filtered_line,
+                None => break,
+            };
+            // The style of a context line added by --expand-context or beyond --distant-context
+            let mut context_style = None;
+            let raw_line_bytes = match filtered_line {
+                FilteredLine::Line(line) => line,
+                FilteredLine::ExpandedContextLine(line) => {
+                    context_style = Some(config.expanded_context_style);
+                    Cow::Owned(line)
+                }
+                FilteredLine::DistantContextLine(line) => {
+                    context_style = Some(config.distant_context_style);
+                    Cow::Owned(line)
+                }
+                FilteredLine::FileStats(n_added, n_removed) => {
+                    annotations.file_stats = Some((n_added, n_removed));
+                    continue;
+                }
+                FilteredLine::HunkBadges(badges) => {
+                    annotations.hunk_badges = Some(badges);
+                    continue;
+                }
+                FilteredLine::HunkLanguage(language) => {
+                    annotations.hunk_language = Some(language);
+                    continue;
+                }
+                FilteredLine::Notice(_) if progress.omitted.is_some() => continue,
+                FilteredLine::Notice(notice) => {
+                    cached_rendering.write_buffered_hunks(&mut painter, &plus_file, config)?;
+                    painter.paint_buffered_minus_and_plus_lines();
+                    painter.emit()?;
+                    writeln!(
+                        painter.writer,
+                        "{}",
+                        config.filter_notice_style.paint(notice)
+                    )?;
+                    continue;
+                }
+            };
+            let is_first_line = n_bytes == 0;
+            if is_first_line {
+                is_input_colored = raw_line_bytes.first() == Some(&ESCAPE);
+            }
+            n_bytes += raw_line_bytes.len() + 1;
+            let raw_line = String::from_utf8_lossy(&raw_line_bytes);
+            current_line.clear();
+            current_line.push_str(&raw_line);
+            // Stripping ANSI codes is relatively expensive, so skip it for lines without an escape.
+            let line = if raw_line_bytes.contains(&ESCAPE) {
+                ansi::strip_ansi_codes(&raw_line)
+            } else {
+                Cow::Borrowed(raw_line.as_ref())
+            };
+            if config.emit_patch {
+                writeln!(painter.writer, "{}", line)?;
                 continue;
             }
-        } else if line.starts_with("diff ") {
-            painter.paint_buffered_minus_and_plus_lines();
-            state = State::FileMeta;
-        } else if (state == State::FileMeta || source == Source::DiffUnified)
-            && (line.starts_with("--- ") || line.starts_with("rename from "))
-        {
-            minus_file = parse::get_file_path_from_file_meta_line(&line, source == Source::GitDiff);
-            if source == Source::DiffUnified {
-                state = State::FileMeta;
-                painter.set_syntax(parse::get_file_extension_from_marker_line(&line));
+            // Within a hunk, the line counts in the hunk header determine where the hunk ends, so that
+            // hunk lines which look like file metadata (as in a diff of a .patch file, where a
+            // removed line may start with "--- ") do not end it.
+            let is_hunk_line = state.is_in_hunk()
+                && hunk_line_counter
+                    .as_mut()
+                    .map_or(false, |counter| counter.accepts(&line));
+            let is_past_end_of_hunk =
+                state.is_in_hunk() && hunk_line_counter.is_some() && !is_hunk_line;
+            let prefix = if is_hunk_line {
+                LinePrefix::Other
             } else {
-                painter.set_syntax(parse::get_file_extension_from_file_meta_line_file_path(
-                    &minus_file,
-                ));
+                hunk_line_counter = None;
+                parse::classify_line_prefix(line.as_bytes())
+            };
+            if !is_hunk_line && prefix != LinePrefix::HunkHeader {
+                cached_rendering.write_buffered_hunks(&mut painter, &plus_file, config)?;
             }
-        } else if (state == State::FileMeta || source == Source::DiffUnified)
-            && (line.starts_with("+++ ") || line.starts_with("rename to "))
-        {
-            plus_file = parse::get_file_path_from_file_meta_line(&line, source == Source::GitDiff);
-            painter.set_syntax(parse::get_file_extension_from_file_meta_line_file_path(
-                &plus_file,
-            ));
-            if should_handle(&State::FileMeta, config) {
+            if let Some(omitted) = progress.omitted.as_mut() {
+                if !is_hunk_line {
+                    omitted.count(&prefix);
+                }
+                continue;
+            }
+            // The output is stopped at the start of a commit, file or hunk, but not between the
+            // header of a file and its first hunk.
+            if config.max_output_lines > 0
+                && !is_hunk_line
+                && match prefix {
+                    LinePrefix::Commit | LinePrefix::Diff | LinePrefix::OnlyIn => true,
+                    LinePrefix::MinusFile => state != State::FileMeta,
+                    // Hunks held back for the render cache are written at the end of the file.
+                    LinePrefix::HunkHeader => {
+                        state.is_in_hunk()
+                            && state != State::HunkHeader
+                            && cached_rendering.buffered_hunks.is_none()
+                    }
+                    _ => false,
+                }
+                && n_lines_written.get() >= config.max_output_lines
+            {
+                painter.paint_buffered_minus_and_plus_lines();
+                state = State::Unknown;
+                let mut omitted_input = OmittedInput::default();
+                omitted_input.count(&prefix);
+                progress.omitted = Some(omitted_input);
+                continue;
+            }
+            if is_past_end_of_hunk && prefix == LinePrefix::Other {
+                // Lines following a hunk which are not part of it, such as the signature of a
+                // patch email, are displayed as they are.
+                painter.paint_buffered_minus_and_plus_lines();
+                state = State::Unknown;
+            }
+            if matches!(prefix, LinePrefix::Commit | LinePrefix::Diff) {
+                // The input may be several independent diffs concatenated, so nothing is carried
+                // over from the previous file.
+                source = detect_source(&line);
+                minus_file.clear();
+                plus_file.clear();
+                cached_rendering.start_file();
+                painter.set_syntax(None);
+                painter.set_tab_width(None);
+                painter.set_diff_driver(None);
+                painter.set_line_length_limit(None);
+                painter.update_terminal_width();
+                if config.side_by_side {
+                    write_file_meta_rows(&mut painter, &mut file_meta_rows, config)?;
+                    file_meta_rows.start_file(
+                        parse::get_file_path_from_diff_line(
+                            &line,
+                            config.get_diff_prefixes_to_remove(),
+                        )
+                        .map(|path| parse::rewrite_file_path(path, config)),
+                    );
+                }
+            }
+            match prefix {
+                LinePrefix::Commit => progress.numbering.handle_end_of_file(),
+                LinePrefix::Diff => progress.numbering.handle_diff_line(),
+                LinePrefix::MinusFile | LinePrefix::PlusFile => {
+                    progress.numbering.handle_file_line()
+                }
+                _ => {}
+            }
+            if state == State::Unknown {
+                // Blame, grep and log output are only detected on the first line, since lines of
+                // these forms (such as "a:1:b") are otherwise too common.
+                if config.blame || is_first_line && blame::is_blame_line(&line) {
+                    state = State::Blame;
+                } else if config.grep || is_first_line && grep::is_grep_line(&line) {
+                    state = State::Grep;
+                } else if is_first_line && log::is_log_line(&line) {
+                    state = State::Log;
+                }
+            }
+            // Lines preceding anything recognized are held back, in case the input is not a diff at
+            // all. They are written unaltered as soon as it is clear that the input is not a plain
+            // file.
+            let is_plain_file_line = state == State::Unknown
+                && prefix == LinePrefix::Other
+                && !raw_line_bytes.contains(&ESCAPE);
+            if plain_file_input.handle_line(&mut painter, &line, is_plain_file_line)? {
+                continue;
+            }
+            if state == State::Blame {
+                if blame::handle_blame_line(&mut painter, &line, &mut blame_state, config)? {
+                    continue;
+                }
                 painter.emit()?;
-                handle_file_meta_header_line(
-                    &mut painter,
-                    &minus_file,
-                    &plus_file,
-                    config,
-                    source == Source::DiffUnified,
-                )?;
+                writeln!(painter.writer, "{}", raw_line)?;
+                continue;
             }
-        } else if line.starts_with("@@") {
-            state = State::HunkHeader;
-            painter.set_highlighter();
-            if should_handle(&state, config) {
+            if state == State::Grep {
+                if grep::handle_grep_line(&mut painter, &line, &raw_line, &mut grep_state, config)?
+                {
+                    continue;
+                }
                 painter.emit()?;
-                handle_hunk_header_line(&mut painter, &line, &raw_line, config)?;
+                writeln!(painter.writer, "{}", raw_line)?;
                 continue;
             }
-        } else if source == Source::DiffUnified && line.starts_with("Only in ")
-            || line.starts_with("Submodule ")
-            || line.starts_with("Binary files ")
-        {
-            // Additional FileMeta cases:
-            //
-            // 1. When comparing directories with diff -u, if filenames match between the
-            //    directories, the files themselves will be compared. However, if an equivalent
-            //    filename is not present, diff outputs a single line (Only in...) starting
-            //    indicating that the file is present in only one of the directories.
-            //
-            // 2. Git diff emits lines describing submodule state such as "Submodule x/y/z contains
-            //    untracked content"
-            //
-            // See https://github.com/dandavison/delta/issues/60#issuecomment-557485242 for a
-            // proposal for more robust parsing logic.
-
-            state = State::FileMeta;
-            painter.paint_buffered_minus_and_plus_lines();
-            if should_handle(&State::FileMeta, config) {
+            // Diff lines in a log listing (as in `git log --oneline -p`) end log mode and are handled
+            // as usual.
+            if state == State::Log && prefix == LinePrefix::Other {
+                if log::handle_log_line(&mut painter, &line, config)? {
+                    continue;
+                }
                 painter.emit()?;
-                handle_generic_file_meta_header_line(&mut painter, &line, &raw_line, config)?;
+                writeln!(painter.writer, "{}", raw_line)?;
                 continue;
             }
-        } else if state.is_in_hunk() {
-            // A true hunk line should start with one of: '+', '-', ' '. However, handle_hunk_line
-            // handles all lines until the state machine transitions away from the hunk states.
-            state = handle_hunk_line(&mut painter, &line, &raw_line, state, config);
+            if matches!(
+                prefix,
+                LinePrefix::Commit | LinePrefix::Diff | LinePrefix::HunkHeader
+            ) {
+                rejected_hunks.end_hunk(&mut painter, config)?;
+            }
+            if source == Source::Unknown {
+                source = detect_source(&line);
+            }
+            if prefix == LinePrefix::Commit {
+                painter.paint_buffered_minus_and_plus_lines();
+                state = State::CommitMeta;
+                submodule = None;
+                if should_handle(&state, config) {
+                    painter.emit()?;
+                    handle_commit_meta_header_line(&mut painter, &line, &raw_line, config)?;
+                    continue;
+                }
+            } else if prefix == LinePrefix::Diff {
+                painter.paint_buffered_minus_and_plus_lines();
+                state = State::FileMeta;
+                if let Some(path) = rejected_hunks.start_file(&line) {
+                    // A .rej file written by `git apply --reject`, which has no "---" and "+++" lines
+                    minus_file = parse::rewrite_file_path(path.to_string(), config);
+                    plus_file = minus_file.clone();
+                    progress.diff_stats.start_file(&minus_file, &plus_file);
+                    progress.numbering.set_path(&plus_file);
+                    painter.set_syntax(parse::get_file_extension_from_file_meta_line_file_path(
+                        &plus_file,
+                    ));
+                    painter.set_tab_width(Some(&plus_file));
+                    painter.set_diff_driver(Some(&plus_file));
+                    painter.set_line_length_limit(Some(&plus_file));
+                    if should_handle(&State::FileMeta, config) {
+                        painter.emit()?;
+                        let header = add_number_label(
+                            format!("{} (rejected hunks)", plus_file),
+                            progress.numbering.file_label(),
+                            config,
+                        );
+                        handle_generic_file_meta_header_line(
+                            &mut painter,
+                            &header,
+                            &header,
+                            config,
+                        )?;
+                    }
+                }
+            } else if (state == State::FileMeta || source == Source::DiffUnified)
+                && prefix == LinePrefix::MinusFile
+            {
+                minus_file = parse::rewrite_file_path(
+                    parse::get_file_path_from_file_meta_line(
+                        &line,
+                        source == Source::GitDiff,
+                        config.get_diff_prefixes_to_remove(),
+                    ),
+                    config,
+                );
+                if source == Source::DiffUnified {
+                    state = State::FileMeta;
+                    painter.set_syntax(parse::get_file_extension_from_marker_line(&line));
+                } else {
+                    painter.set_syntax(parse::get_file_extension_from_file_meta_line_file_path(
+                        &minus_file,
+                    ));
+                }
+            } else if (state == State::FileMeta || source == Source::DiffUnified)
+                && prefix == LinePrefix::PlusFile
+            {
+                plus_file = parse::rewrite_file_path(
+                    parse::get_file_path_from_file_meta_line(
+                        &line,
+                        source == Source::GitDiff,
+                        config.get_diff_prefixes_to_remove(),
+                    ),
+                    config,
+                );
+                painter.set_syntax(parse::get_file_extension_from_file_meta_line_file_path(
+                    &plus_file,
+                ));
+                progress.diff_stats.start_file(&minus_file, &plus_file);
+                progress.numbering.set_path(if plus_file == "/dev/null" {
+                    &minus_file
+                } else {
+                    &plus_file
+                });
+                let path = if plus_file == "/dev/null" {
+                    &minus_file
+                } else {
+                    &plus_file
+                };
+                painter.set_tab_width(Some(path));
+                painter.set_diff_driver(Some(path));
+                painter.set_line_length_limit(Some(path));
+                let file_stats_label = annotations.file_stats.take().map(|(n_added, n_removed)| {
+                    make_file_stats_label(n_added, n_removed, painter.get_language_name(), config)
+                });
+                if should_handle(&State::FileMeta, config) {
+                    painter.emit()?;
+                    if let Some(path) = &submodule {
+                        if !is_in_submodule(&plus_file, path) && !is_in_submodule(&minus_file, path)
+                        {
+                            submodule = None;
+                        }
+                    }
+                    handle_file_meta_header_line(
+                        &mut painter,
+                        &get_submodule_file_label(&minus_file, &submodule, config),
+                        &get_submodule_file_label(&plus_file, &submodule, config),
+                        config,
+                        source == Source::DiffUnified,
+                        progress.numbering.file_label(),
+                        file_stats_label,
+                    )?;
+                    file_meta_rows.path = None;
+                    write_file_meta_rows(&mut painter, &mut file_meta_rows, config)?;
+                }
+            } else if prefix == LinePrefix::HunkHeader {
+                // Without context lines (git diff -U0), the changed lines of the previous hunk are
+                // still buffered.
+                painter.paint_buffered_minus_and_plus_lines();
+                state = State::HunkHeader;
+                n_hunk_lines = 0;
+                let (code_fragment, line_numbers) = parse::parse_hunk_header(&line);
+                hunk_line_counter = Some(HunkLineCounter::new(&line_numbers));
+                let hunk_label = make_hunk_header_label(
+                    progress.numbering.handle_hunk_header(
+                        line_numbers[line_numbers.len() - 1].0,
+                        code_fragment.trim(),
+                    ),
+                    annotations.hunk_badges.take(),
+                    config,
+                );
+                rejected_hunks.start_hunk(&line_numbers);
+                if let Some(line_plugin) = line_plugin.as_mut() {
+                    line_plugin.start_hunk(&line_numbers);
+                }
+                if let Some(hunks) =
+                    cached_rendering.start_hunk(&plus_file, painter.available_terminal_width)
+                {
+                    hunks.push_hunk_header(&line, &raw_line, hunk_label);
+                    continue;
+                }
+                painter.hunk_language = annotations.hunk_language.take();
+                painter.set_highlighter();
+                painter.last_zero_line = None;
+                if should_handle(&state, config) {
+                    painter.emit()?;
+                    handle_hunk_header_line(
+                        &mut painter,
+                        &line,
+                        &raw_line,
+                        &plus_file,
+                        hunk_label,
+                        config,
+                    )?;
+                    continue;
+                }
+            } else if source == Source::DiffUnified && prefix == LinePrefix::OnlyIn
+                || prefix == LinePrefix::Submodule
+                || prefix == LinePrefix::BinaryFiles
+            {
+                // Additional FileMeta cases:
+                //
+                // 1. When comparing directories with diff -u, if filenames match between the
+                //    directories, the files themselves will be compared. However, if an equivalent
+                //    filename is not present, diff outputs a single line (Only in...) starting
+                //    indicating that the file is present in only one of the directories.
+                //
+                // 2. Git diff emits lines describing submodule state such as "Submodule x/y/z contains
+                //    untracked content"
+                //
+                // See https://github.com/dandavison/delta/issues/60#issuecomment-557485242 for a
+                // proposal for more robust parsing logic.
+
+                state = State::FileMeta;
+                painter.paint_buffered_minus_and_plus_lines();
+                if prefix == LinePrefix::Submodule {
+                    // Lines following a "Submodule" line, such as the commit list of
+                    // --submodule=log, are displayed as they are.
+                    state = State::Submodule;
+                    if let Some(path) = parse::get_submodule_path(&line) {
+                        submodule = Some(parse::rewrite_file_path(path.to_string(), config));
+                    }
+                }
+                if should_handle(&State::FileMeta, config) {
+                    painter.emit()?;
+                    // Only a binary file within a file section, as in git's output, is numbered.
+                    let file_label = progress
+                        .numbering
+                        .file_label()
+                        .filter(|_| prefix == LinePrefix::BinaryFiles);
+                    handle_generic_file_meta_header_line(
+                        &mut painter,
+                        &add_number_label(line.to_string(), file_label.clone(), config),
+                        &add_number_label(raw_line.to_string(), file_label, config),
+                        config,
+                    )?;
+                    continue;
+                }
+            } else if state.is_in_hunk() {
+                // A true hunk line should start with one of: '+', '-', ' '. However, handle_hunk_line
+                // handles all lines until the state machine transitions away from the hunk states.
+                let line = if config.sanitize_control_chars && !is_input_colored {
+                    Cow::Borrowed(raw_line.as_ref())
+                } else {
+                    line
+                };
+                n_hunk_lines += 1;
+                progress.diff_stats.count_hunk_line(&line);
+                if !painter.degraded {
+                    maybe_degrade(&mut painter, n_bytes, n_hunk_lines, config);
+                }
+                if let Some(hunks) = cached_rendering.buffered_hunks.as_mut() {
+                    hunks.push_hunk_line(&line, &raw_line);
+                    // Don't let the held back lines become arbitrarily large: render them, and the
+                    // remainder of the file, without the cache.
+                    if hunks.lines.len() > config.max_buffered_lines {
+                        state = paint_hunk_lines(
+                            &mut painter,
+                            &cached_rendering.buffered_hunks.take().unwrap().lines,
+                            &plus_file,
+                            config,
+                        )?;
+                    }
+                    continue;
+                }
+                let (line, style_override) = match line_plugin.as_mut() {
+                    Some(plugin) => {
+                        let file = if plus_file == "/dev/null" {
+                            &minus_file
+                        } else {
+                            &plus_file
+                        };
+                        match plugin.transform_line(&line, file, config) {
+                            Some((line, style_override)) => (Cow::Owned(line), style_override),
+                            None => {
+                                line_plugin = None;
+                                (line, None)
+                            }
+                        }
+                    }
+                    None => (line, None),
+                };
+                let style_override = context_style.or(style_override);
+                state = handle_hunk_line(
+                    &mut painter,
+                    &line,
+                    &raw_line,
+                    state,
+                    style_override,
+                    config,
+                );
+                painter.emit()?;
+                continue;
+            }
+
+            if state == State::CommitMeta {
+                if let Some(date_line) = log::format_commit_date_line(&line, config) {
+                    painter.emit()?;
+                    writeln!(painter.writer, "{}", date_line)?;
+                    continue;
+                }
+                if let Some(signature_line) = log::format_signature_line(&line, config) {
+                    painter.emit()?;
+                    writeln!(painter.writer, "{}", signature_line)?;
+                    continue;
+                }
+                if let Some(message_lines) = log::format_commit_message_line(
+                    &line,
+                    &painter.decorations_width,
+                    painter.available_terminal_width,
+                    config,
+                ) {
+                    painter.emit()?;
+                    for message_line in message_lines {
+                        let message_line =
+                            commit_links::linkify(&message_line, &config.commit_link_rules)
+                                .unwrap_or(message_line);
+                        writeln!(painter.writer, "{}", message_line)?;
+                    }
+                    continue;
+                }
+                // Git indents the lines of the message by four spaces.
+                if line.starts_with("    ") {
+                    if let Some(linked_line) =
+                        commit_links::linkify(&line, &config.commit_link_rules)
+                    {
+                        painter.emit()?;
+                        writeln!(painter.writer, "{}", linked_line)?;
+                        continue;
+                    }
+                }
+            }
+            if state == State::FileMeta && source == Source::GitDiff {
+                cached_rendering.handle_file_meta_line(&line);
+            }
+            if state == State::FileMeta && should_handle(&State::FileMeta, config) {
+                // The file metadata section is 4 lines. Skip them under non-plain file-styles,
+                // except that under side-by-side, mode changes and similarity are displayed.
+                if config.side_by_side {
+                    file_meta_rows.push_line(&line, config);
+                }
+                continue;
+            } else if state == State::Unknown && plain_file_input.is_holding() {
+                plain_file_input.hold_line(&mut painter, line, &raw_line, config)?;
+            } else {
+                painter.emit()?;
+                writeln!(painter.writer, "{}", raw_line)?;
+            }
+        }
+
+        plain_file_input.end(&mut painter, config)?;
+        cached_rendering.end(&mut painter, &plus_file, config)?;
+        painter.paint_buffered_minus_and_plus_lines();
+        painter.emit()?;
+        write_file_meta_rows(&mut painter, &mut file_meta_rows, config)?;
+        rejected_hunks.end_hunk(&mut painter, config)
+    };
+    if config.strict {
+        process_lines()?;
+    } else {
+        // Input that delta fails to process is written unaltered, with a warning, rather than
+        // being lost. The warning takes the place of the panic message, and gives the location of
+        // the panic; panics in other threads are reported as usual.
+        install_panic_hook();
+        let outer_location = CAUGHT_PANIC_LOCATION.with(|location| location.replace(Some(None)));
+        let result = panic::catch_unwind(AssertUnwindSafe(process_lines));
+        let location = CAUGHT_PANIC_LOCATION
+            .with(|location| location.replace(outer_location))
+            .flatten();
+        match result {
+            Ok(result) => result?,
+            Err(payload) => {
+                let message = match payload.downcast_ref::<&str>() {
+                    Some(message) => message.to_string(),
+                    None => payload
+                        .downcast_ref::<String>()
+                        .cloned()
+                        .unwrap_or_default(),
+                };
+                let message = match location {
+                    Some(location) => format!("panicked at {}: {}", location, message),
+                    None => message,
+                };
+                warnings::warn(
+                    "input-not-processed",
+                    &format!(
+                        "failed to process the input ({}): displaying the remainder of the \
+                         input unaltered. Use --strict to fail instead.",
+                        message
+                    ),
+                    config,
+                );
+                write_remaining_input_unaltered(&mut painter, &current_line, &mut lines)?;
+            }
+        }
+    }
+    progress.available_terminal_width = painter.available_terminal_width;
+    progress.n_lines_written = n_lines_written.get();
+    progress.n_files = lines.n_files;
+    progress.is_selection_found = lines.is_selection_found;
+    writer.flush()
+}
+
+/// Write the output which follows the whole input: the notices and summaries of the input.
+pub fn end_input(
+    writer: &mut dyn Write,
+    progress: &InputProgress,
+    config: &Config,
+) -> std::io::Result<()> {
+    if let Some(selection) = &config.select {
+        if !progress.is_selection_found {
+            warnings::warn(
+                "no-selection",
+                &format!("--select {}: no such file or hunk in the input", selection),
+                config,
+            );
+        }
+    }
+    if let Some(omitted) = &progress.omitted {
+        writeln!(
+            writer,
+            "{}",
+            config.filter_notice_style.paint(format!(
+                "{} more hunk{} in {} file{} not displayed (--max-output-lines {})",
+                omitted.n_hunks,
+                if omitted.n_hunks == 1 { "" } else { "s" },
+                omitted.n_files,
+                if omitted.n_files == 1 { "" } else { "s" },
+                config.max_output_lines
+            ))
+        )?;
+    }
+    write_end_mark(writer, config)?;
+    if config.stats {
+        stats::write_stats(
+            writer,
+            &progress.diff_stats,
+            progress.available_terminal_width,
+            config,
+        )?;
+    }
+    if config.numbered {
+        numbering::write_index(writer, &progress.numbering, config)?;
+    }
+    if let Some(path) = &config.emit_locations {
+        numbering::write_locations_file(path, &progress.numbering)?;
+    }
+    writer.flush()
+}
+
+thread_local! {
+    // While delta() is catching panics in this thread, the location of the panic, if there has
+    // been one.
+    static CAUGHT_PANIC_LOCATION: RefCell<Option<Option<String>>> = RefCell::new(None);
+}
+
+/// Install, once, a panic hook which records the location of a panic in a thread in which delta()
+/// is catching panics, rather than reporting it. Panics in other threads are passed to the hook
+/// which was installed before.
+fn install_panic_hook() {
+    static INSTALL_PANIC_HOOK: Once = Once::new();
+    INSTALL_PANIC_HOOK.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let is_caught =
+                CAUGHT_PANIC_LOCATION.with(|location| match location.borrow_mut().as_mut() {
+                    Some(location) => {
+                        *location = info.location().map(|l| l.to_string());
+                        true
+                    }
+                    None => false,
+                });
+            if !is_caught {
+                previous_hook(info);
+            }
+        }));
+    });
+}
+
+/// The files and hunks of the input which are not displayed under --max-output-lines.
+#[derive(Default)]
+struct OmittedInput {
+    n_files: usize,
+    n_hunks: usize,
+    is_in_file_meta: bool,
+}
+
+impl OmittedInput {
+    /// Count the file or hunk started by a line which is not a hunk line.
+    fn count(&mut self, prefix: &LinePrefix) {
+        match prefix {
+            LinePrefix::Commit => self.is_in_file_meta = false,
+            LinePrefix::OnlyIn => {
+                self.n_files += 1;
+                self.is_in_file_meta = false;
+            }
+            LinePrefix::Diff => {
+                self.n_files += 1;
+                self.is_in_file_meta = true;
+            }
+            // A diff -u file section, which has no "diff" line
+            LinePrefix::MinusFile if !self.is_in_file_meta => {
+                self.n_files += 1;
+                self.is_in_file_meta = true;
+            }
+            LinePrefix::HunkHeader => {
+                // The output may have stopped within a file.
+                self.n_files = self.n_files.max(1);
+                self.n_hunks += 1;
+                self.is_in_file_meta = false;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The --file-stats, --hunk-badges and --hunk-language annotations of the input filter, which are
+/// held until the file or hunk they annotate starts.
+#[derive(Default)]
+struct PendingAnnotations {
+    // The numbers of lines added to and removed from the next file
+    file_stats: Option<(usize, usize)>,
+    hunk_badges: Option<String>,
+    // The language of the next hunk, if it differs from that of its file
+    hunk_language: Option<String>,
+}
+
+/// Input which is not a diff, which is held back until it is clear whether it is displayed as a
+/// file, as the diff of `diff -y` output, or as it is.
+struct PlainFileInput<'a> {
+    // The lines held back, until a line which is not part of a plain file
+    held_lines: Option<Vec<String>>,
+    // Whether the input is displayed as a file, and the line numbers data to restore if it is
+    // followed by a diff
+    is_plain_file: Option<bool>,
+    is_displaying: bool,
+    saved_line_numbers_data: Option<LineNumbersData<'a>>,
+}
+
+impl<'a> PlainFileInput<'a> {
+    fn new() -> Self {
+        Self {
+            held_lines: Some(Vec::new()),
+            is_plain_file: None,
+            is_displaying: false,
+            saved_line_numbers_data: None,
+        }
+    }
+
+    fn is_holding(&self) -> bool {
+        self.held_lines.is_some()
+    }
+
+    /// Display a line as a line of the file being displayed, returning whether it was. A line
+    /// which is not part of a plain file ends the file, and the lines held back are written.
+    fn handle_line(
+        &mut self,
+        painter: &mut Painter<'a>,
+        line: &str,
+        is_plain_file_line: bool,
+    ) -> std::io::Result<bool> {
+        if self.is_displaying {
+            if is_plain_file_line {
+                show::paint_plain_file_line(painter, line)?;
+                return Ok(true);
+            }
+            self.is_displaying = false;
+            if let Some(line_numbers_data) = self.saved_line_numbers_data.take() {
+                painter.line_numbers_data = line_numbers_data;
+            }
+        }
+        if !is_plain_file_line {
+            self.write_held_lines(painter)?;
+        }
+        Ok(false)
+    }
+
+    /// Hold back a line which precedes anything recognized.
+    fn hold_line(
+        &mut self,
+        painter: &mut Painter<'a>,
+        line: Cow<str>,
+        raw_line: &str,
+        config: &Config,
+    ) -> std::io::Result<()> {
+        // Only input whose language is known is displayed as a file, and only `diff -y` output is
+        // displayed as a diff, so other input is written unaltered as it is read. Input which is
+        // displayed as a file is held back only until MAX_HELD_LINES lines have been read, and the
+        // lines which follow are displayed as they are read.
+        let is_plain_file = *self
+            .is_plain_file
+            .get_or_insert_with(|| show::is_plain_file(&line, config));
+        if !is_plain_file && !sdiff::may_be_sdiff_line(&line) {
+            self.write_held_lines(painter)?;
             painter.emit()?;
-            continue;
+            return writeln!(painter.writer, "{}", raw_line);
+        }
+        let held_lines = self.held_lines.get_or_insert_with(Vec::new);
+        held_lines.push(line.into_owned());
+        if held_lines.len() == MAX_HELD_LINES {
+            if is_plain_file {
+                let held_lines = self.held_lines.take().unwrap_or_default();
+                self.saved_line_numbers_data = show::start_plain_file(painter, &held_lines)?;
+                self.is_displaying = true;
+            } else {
+                self.write_held_lines(painter)?;
+            }
         }
+        Ok(())
+    }
 
-        if state == State::FileMeta && should_handle(&State::FileMeta, config) {
-            // The file metadata section is 4 lines. Skip them under non-plain file-styles.
-            continue;
-        } else {
+    fn write_held_lines(&mut self, painter: &mut Painter) -> std::io::Result<()> {
+        for held_line in self.held_lines.take().unwrap_or_default() {
+            writeln!(painter.writer, "{}", held_line)?;
+        }
+        Ok(())
+    }
+
+    /// Display the lines held back at the end of the input.
+    fn end(&mut self, painter: &mut Painter, config: &Config) -> std::io::Result<()> {
+        let held_lines = match self.held_lines.take() {
+            Some(held_lines) if !held_lines.is_empty() => held_lines,
+            _ => return Ok(()),
+        };
+        // `diff -y` output is displayed as the diff it is, rather than as a file.
+        match sdiff::parse_sdiff_lines(&held_lines) {
+            Some(hunk_lines) => {
+                paint_hunks(painter, hunk_lines.iter().map(String::as_str), "", config)
+            }
+            None if self.is_plain_file == Some(true) => {
+                show::paint_plain_file(painter, &held_lines, None)
+            }
+            None => {
+                for held_line in held_lines {
+                    writeln!(painter.writer, "{}", held_line)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The target file of a .rej file, and the line range of its current rejected hunk, following
+/// which the lines of the target file which the hunk expected to change are displayed.
+#[derive(Default)]
+struct RejectedHunks {
+    file: Option<String>,
+    hunk: Option<(usize, usize)>,
+}
+
+impl RejectedHunks {
+    /// Start a file at its "diff" line, returning the path of its target file if it is a .rej
+    /// file.
+    fn start_file(&mut self, line: &str) -> Option<&str> {
+        self.file = rej::parse_rejected_hunks_line(line).map(String::from);
+        self.file.as_deref()
+    }
+
+    fn start_hunk(&mut self, line_numbers: &[(usize, usize)]) {
+        if self.file.is_some() {
+            self.hunk = Some(line_numbers[0]);
+        }
+    }
+
+    /// Display the context of the target file following the current rejected hunk, if any.
+    fn end_hunk(&mut self, painter: &mut Painter, config: &Config) -> std::io::Result<()> {
+        if let (Some(path), Some(line_range)) = (&self.file, self.hunk.take()) {
+            painter.paint_buffered_minus_and_plus_lines();
             painter.emit()?;
-            writeln!(painter.writer, "{}", raw_line)?;
+            rej::paint_target_file_context(painter, path, line_range, config)?;
+        }
+        Ok(())
+    }
+}
+
+/// The hunks of the current file which are held back, under --render-cache, until the end of the
+/// file.
+struct CachedRendering {
+    cache: Option<RenderCache>,
+    recording: Rc<RefCell<Option<Vec<u8>>>>,
+    // The blob ids of the versions of the current file, from its "index" line, while its hunks
+    // may be rendered from the cache
+    file_blobs: Option<(String, String)>,
+    buffered_hunks: Option<BufferedHunks>,
+}
+
+impl CachedRendering {
+    fn new(config: &Config, recording: Rc<RefCell<Option<Vec<u8>>>>) -> Self {
+        Self {
+            cache: RenderCache::new(config),
+            recording,
+            file_blobs: None,
+            buffered_hunks: None,
+        }
+    }
+
+    fn start_file(&mut self) {
+        self.file_blobs = None;
+    }
+
+    /// Read the blob ids of the file from a line of its metadata in git's output.
+    fn handle_file_meta_line(&mut self, line: &str) {
+        if self.cache.is_some() {
+            if let Some((minus_blob, plus_blob)) = pre_render::parse_index_line(line) {
+                self.file_blobs = Some((minus_blob.to_string(), plus_blob.to_string()));
+            }
+        }
+    }
+
+    /// Start a hunk, returning the hunks of the file held back if its hunks are held back.
+    fn start_hunk(
+        &mut self,
+        plus_file: &str,
+        available_terminal_width: usize,
+    ) -> Option<&mut BufferedHunks> {
+        if let (Some(cache), Some((minus_blob, plus_blob))) = (&self.cache, self.file_blobs.take())
+        {
+            self.buffered_hunks = Some(cache.start_file(
+                plus_file,
+                &minus_blob,
+                &plus_blob,
+                available_terminal_width,
+            ));
+        }
+        self.buffered_hunks.as_mut()
+    }
+
+    fn write_buffered_hunks(
+        &mut self,
+        painter: &mut Painter,
+        plus_file: &str,
+        config: &Config,
+    ) -> std::io::Result<()> {
+        if let (Some(cache), Some(hunks)) = (&self.cache, self.buffered_hunks.take()) {
+            paint_buffered_hunks(painter, cache, hunks, plus_file, &self.recording, config)?;
+        }
+        Ok(())
+    }
+
+    fn end(
+        &mut self,
+        painter: &mut Painter,
+        plus_file: &str,
+        config: &Config,
+    ) -> std::io::Result<()> {
+        self.write_buffered_hunks(painter, plus_file, config)?;
+        if let Some(cache) = &self.cache {
+            cache.limit_size();
+        }
+        Ok(())
+    }
+}
+
+/// Write the hunks of a file which were held back for the render cache: from the cache if it
+/// holds a rendering of them, and otherwise rendered as usual and added to the cache.
+fn paint_buffered_hunks(
+    painter: &mut Painter,
+    cache: &RenderCache,
+    hunks: BufferedHunks,
+    plus_file: &str,
+    recording: &Rc<RefCell<Option<Vec<u8>>>>,
+    config: &Config,
+) -> std::io::Result<()> {
+    painter.emit()?;
+    if let Some(rendered) = cache.get(&hunks) {
+        return painter.writer.write_all(&rendered);
+    }
+    *recording.borrow_mut() = Some(Vec::new());
+    let result = paint_hunk_lines(painter, &hunks.lines, plus_file, config).and_then(|_| {
+        painter.paint_buffered_minus_and_plus_lines();
+        painter.emit()
+    });
+    let rendered = recording.borrow_mut().take().unwrap_or_default();
+    result?;
+    // A rendering made after expensive features were disabled is not kept.
+    if !painter.degraded {
+        cache.put(&hunks, &rendered);
+    }
+    Ok(())
+}
+
+/// Render hunk lines which were held back, returning the state following the last of them.
+fn paint_hunk_lines(
+    painter: &mut Painter,
+    lines: &[BufferedHunkLine],
+    plus_file: &str,
+    config: &Config,
+) -> std::io::Result<State> {
+    let mut state = State::HunkHeader;
+    for hunk_line in lines {
+        match hunk_line {
+            BufferedHunkLine::HunkHeader {
+                line,
+                raw_line,
+                label,
+            } => {
+                painter.paint_buffered_minus_and_plus_lines();
+                painter.set_highlighter();
+                state = State::HunkHeader;
+                painter.emit()?;
+                if should_handle(&state, config) {
+                    handle_hunk_header_line(
+                        painter,
+                        line,
+                        raw_line,
+                        plus_file,
+                        label.clone(),
+                        config,
+                    )?;
+                } else {
+                    writeln!(painter.writer, "{}", raw_line)?;
+                }
+            }
+            BufferedHunkLine::HunkLine { line, raw_line } => {
+                state = handle_hunk_line(painter, line, raw_line, state, None, config);
+                painter.emit()?;
+            }
         }
     }
+    Ok(state)
+}
+
+/// Render a single hunk, such as the hunk under the cursor in an editor, and return the styled
+/// output. `hunk` starts with its "@@" header line. The paths of the old and new versions of the
+/// file determine the language used for syntax highlighting. Unlike `delta`, no file header is
+/// written.
+#[allow(dead_code)] // An entry point for library users: the delta binary renders whole diffs.
+pub fn render_hunk(
+    hunk: &str,
+    minus_file: &str,
+    plus_file: &str,
+    config: &Config,
+) -> std::io::Result<String> {
+    let mut output = Vec::new();
+    {
+        let mut painter = Painter::new(&mut output, config);
+        let path = if plus_file == "/dev/null" {
+            minus_file
+        } else {
+            plus_file
+        };
+        painter.set_syntax(parse::get_file_extension_from_file_meta_line_file_path(
+            path,
+        ));
+        painter.set_tab_width(Some(path));
+        painter.set_diff_driver(Some(path));
+        painter.set_line_length_limit(Some(path));
+        paint_hunks(&mut painter, hunk.lines(), path, config)?;
+    }
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
 
+/// Paint hunks of the file with this path, each starting with its "@@" header line.
+fn paint_hunks<'a>(
+    painter: &mut Painter,
+    lines: impl Iterator<Item = &'a str>,
+    path: &str,
+    config: &Config,
+) -> std::io::Result<()> {
+    let mut state = State::Unknown;
+    for line in lines {
+        if parse::classify_line_prefix(line.as_bytes()) == LinePrefix::HunkHeader {
+            painter.paint_buffered_minus_and_plus_lines();
+            painter.set_highlighter();
+            state = State::HunkHeader;
+            if should_handle(&state, config) {
+                painter.emit()?;
+                handle_hunk_header_line(
+                    painter,
+                    line,
+                    line,
+                    path,
+                    make_hunk_header_label(None, None, config),
+                    config,
+                )?;
+            }
+        } else if state.is_in_hunk() {
+            state = handle_hunk_line(painter, line, line, state, None, config);
+            painter.emit()?;
+        }
+    }
     painter.paint_buffered_minus_and_plus_lines();
+    painter.emit()
+}
+
+/// Write the input from the point at which processing failed, starting with any lines buffered by
+/// the painter and the line being processed.
+fn write_remaining_input_unaltered<I>(
+    painter: &mut Painter,
+    current_line: &str,
+    lines: &mut filter::InputFilter<I>,
+) -> std::io::Result<()>
+where
+    I: BufRead,
+{
     painter.emit()?;
+    let buffered_lines = painter
+        .minus_lines
+        .drain(..)
+        .map(|line| ('-', line))
+        .chain(painter.plus_lines.drain(..).map(|line| ('+', line)));
+    for (marker, line) in buffered_lines {
+        writeln!(
+            painter.writer,
+            "{}{}",
+            marker,
+            line.get(1..).unwrap_or("").trim_end_matches('\n')
+        )?;
+    }
+    writeln!(painter.writer, "{}", current_line)?;
+    while let Some(filtered_line) = lines.next_line() {
+        match filtered_line {
+            FilteredLine::Line(line) => {
+                painter.writer.write_all(&line)?;
+                writeln!(painter.writer)?;
+            }
+            FilteredLine::ExpandedContextLine(line) | FilteredLine::DistantContextLine(line) => {
+                painter.writer.write_all(&line)?;
+                writeln!(painter.writer)?;
+            }
+            FilteredLine::Notice(notice) => writeln!(painter.writer, "{}", notice)?,
+            FilteredLine::HunkBadges(_)
+            | FilteredLine::HunkLanguage(_)
+            | FilteredLine::FileStats(_, _) => {}
+        }
+    }
     Ok(())
 }
 
+/// Disable expensive features if the input has exceeded the size limits.
+fn maybe_degrade(painter: &mut Painter, n_bytes: usize, n_hunk_lines: usize, config: &Config) {
+    if config.auto_degrade_bytes > 0 && n_bytes > config.auto_degrade_bytes {
+        painter.degrade(&format!(
+            "input exceeds {} bytes (see --auto-degrade-bytes)",
+            config.auto_degrade_bytes
+        ));
+    } else if config.auto_degrade_hunk_lines > 0 && n_hunk_lines > config.auto_degrade_hunk_lines {
+        painter.degrade(&format!(
+            "hunk exceeds {} lines (see --auto-degrade-hunk-lines)",
+            config.auto_degrade_hunk_lines
+        ));
+    }
+}
+
 /// Should a handle_* function be called on this element?
 fn should_handle(state: &State, config: &Config) -> bool {
     let style = config.get_style(state);
@@ -244,13 +1339,33 @@ fn handle_commit_meta_header_line(
         painter.writer,
         &format!("{}{}", line, if pad { " " } else { "" }),
         &format!("{}{}", raw_line, if pad { " " } else { "" }),
-        &config.decorations_width,
+        &painter.decorations_width,
         config.commit_style,
         decoration_ansi_term_style,
+        config.ascii,
     )?;
     Ok(())
 }
 
+fn is_in_submodule(path: &str, submodule: &str) -> bool {
+    path.strip_prefix(submodule)
+        .map_or(false, |rest| rest.starts_with('/'))
+}
+
+/// Within the changes in a submodule (`git diff --submodule=diff`), display a file's path relative
+/// to the submodule, under the submodule's path.
+fn get_submodule_file_label(path: &str, submodule: &Option<String>, config: &Config) -> String {
+    match submodule {
+        Some(submodule) if is_in_submodule(path, submodule) => format!(
+            "{} {} {}",
+            submodule,
+            if config.ascii { ">" } else { "▸" },
+            &path[submodule.len() + 1..]
+        ),
+        _ => path.to_string(),
+    }
+}
+
 /// Construct file change line from minus and plus file and write with FileMeta styling.
 fn handle_file_meta_header_line(
     painter: &mut Painter,
@@ -258,21 +1373,119 @@ fn handle_file_meta_header_line(
     plus_file: &str,
     config: &Config,
     comparing: bool,
+    file_label: Option<String>,
+    file_stats_label: Option<String>,
 ) -> std::io::Result<()> {
-    let line = parse::get_file_change_description_from_file_paths(
-        minus_file, plus_file, comparing, config,
+    let mut line = add_number_label(
+        parse::get_file_change_description_from_file_paths(
+            minus_file, plus_file, comparing, config,
+        ),
+        file_label,
+        config,
     );
+    if let Some(file_stats_label) = file_stats_label {
+        line = format!("{} {}", line, file_stats_label);
+    }
     // FIXME: no support for 'raw'
     handle_generic_file_meta_header_line(painter, &line, &line, config)
 }
 
+/// Write the side-by-side rows of a file's metadata, preceded by the file's header if it has not
+/// been displayed, as for a file of which only the mode has changed.
+fn write_file_meta_rows(
+    painter: &mut Painter,
+    rows: &mut side_by_side::FileMetaRows,
+    config: &Config,
+) -> std::io::Result<()> {
+    if rows.is_empty() || config.file_style.is_omitted {
+        return Ok(());
+    }
+    painter.paint_buffered_minus_and_plus_lines();
+    painter.emit()?;
+    if let Some(path) = rows.path.take() {
+        handle_generic_file_meta_header_line(painter, &path, &path, config)?;
+    }
+    rows.write(
+        painter.writer,
+        &painter.line_widths.side_by_side_data,
+        config,
+    )
+}
+
+/// Return the --file-stats label of a file header, e.g. "(Rust, +24 −3)".
+fn make_file_stats_label(
+    n_added: usize,
+    n_removed: usize,
+    language: Option<String>,
+    config: &Config,
+) -> String {
+    let minus = if config.ascii { "-" } else { "−" };
+    match language {
+        Some(language) => format!("({}, +{} {}{})", language, n_added, minus, n_removed),
+        None => format!("(+{} {}{})", n_added, minus, n_removed),
+    }
+}
+
+/// Prefix a file or hunk header with its --numbered label.
+fn add_number_label(header: String, label: Option<String>, config: &Config) -> String {
+    match label {
+        Some(label) if config.numbered => format!("{} {}", label, header),
+        _ => header,
+    }
+}
+
+fn add_hunk_header_label(header: String, label: &Option<String>) -> String {
+    match label {
+        Some(label) => format!("{} {}", label, header),
+        None => header,
+    }
+}
+
+/// Return the label displayed in front of a hunk header: --hunk-label, followed by the hunk's
+/// number under --numbered, and its --hunk-badges.
+fn make_hunk_header_label(
+    number_label: Option<String>,
+    badges: Option<String>,
+    config: &Config,
+) -> Option<String> {
+    let number_label = number_label.filter(|_| config.numbered);
+    let label = match (config.hunk_label.as_str(), number_label) {
+        ("", number_label) => number_label,
+        (hunk_label, Some(number_label)) => Some(format!("{} {}", hunk_label, number_label)),
+        (hunk_label, None) => Some(hunk_label.to_string()),
+    };
+    match (label, badges) {
+        (Some(label), Some(badges)) => Some(format!("{} {}", label, badges)),
+        (label, badges) => label.or(badges),
+    }
+}
+
+/// Write the terminal mark requested by --file-marks for the start of a file.
+fn write_file_start_mark(painter: &mut Painter, config: &Config) -> std::io::Result<()> {
+    match config.file_marks {
+        // The end of the previous file's output, and the start of this file's output
+        FileMarks::Osc133 => write!(painter.writer, "\x1b]133;D\x07\x1b]133;C\x07"),
+        FileMarks::ITerm2 => write!(painter.writer, "\x1b]1337;SetMark\x07"),
+        FileMarks::None => Ok(()),
+    }
+}
+
+/// Write the terminal mark requested by --file-marks for the end of the output.
+fn write_end_mark(writer: &mut dyn Write, config: &Config) -> std::io::Result<()> {
+    match config.file_marks {
+        FileMarks::Osc133 => write!(writer, "\x1b]133;D\x07"),
+        FileMarks::ITerm2 | FileMarks::None => Ok(()),
+    }
+}
+
 /// Write `line` with FileMeta styling.
-fn handle_generic_file_meta_header_line(
+pub fn handle_generic_file_meta_header_line(
     painter: &mut Painter,
     line: &str,
     raw_line: &str,
     config: &Config,
 ) -> std::io::Result<()> {
+    write_file_start_mark(painter, config)?;
     if config.file_style.is_omitted {
         return Ok(());
     }
@@ -321,9 +1534,10 @@ fn handle_generic_file_meta_header_line(
         painter.writer,
         &format!("{}{}", line, if pad { " " } else { "" }),
         &format!("{}{}", raw_line, if pad { " " } else { "" }),
-        &config.decorations_width,
+        &painter.decorations_width,
         config.file_style,
         decoration_ansi_term_style,
+        config.ascii,
     )?;
     Ok(())
 }
@@ -332,11 +1546,16 @@ fn handle_hunk_header_line(
     painter: &mut Painter,
     line: &str,
     raw_line: &str,
+    plus_file: &str,
+    label: Option<String>,
     config: &Config,
 ) -> std::io::Result<()> {
     if config.hunk_header_style.is_omitted {
         return Ok(());
     }
+    if config.inline_hunk_headers {
+        return handle_inline_hunk_header_line(painter, line, plus_file, label, config);
+    }
     let decoration_ansi_term_style;
     let draw_fn = match config.hunk_header_style.decoration_style {
         DecorationStyle::Box(style) => {
@@ -373,24 +1592,67 @@ fn handle_hunk_header_line(
         }
     };
     let (raw_code_fragment, line_numbers) = parse::parse_hunk_header(&line);
+    let plus_line_number = line_numbers[line_numbers.len() - 1].0;
     // Emit the hunk header, with any requested decoration
-    if config.hunk_header_style.is_raw {
+    if !config.hunk_header_format.is_empty() {
+        let funcname = match funcname::get_funcname_range(
+            &raw_code_fragment,
+            painter.diff_driver.as_deref(),
+            config,
+        ) {
+            Some((start, end)) => &raw_code_fragment[start..end],
+            None => raw_code_fragment.trim(),
+        };
+        let text = add_hunk_header_label(
+            format_hunk_header(
+                &config.hunk_header_format,
+                plus_file,
+                plus_line_number,
+                raw_code_fragment.trim(),
+                funcname,
+            ),
+            &label,
+        );
         writeln!(painter.writer)?;
         draw_fn(
             painter.writer,
-            &format!("{} ", line),
-            &format!("{} ", raw_line),
-            &config.decorations_width,
+            &text,
+            &text,
+            &painter.decorations_width,
             config.hunk_header_style,
             decoration_ansi_term_style,
+            config.ascii,
+        )?;
+        if config.line_numbers {
+            painter.line_numbers_data.initialize_hunk(line_numbers);
+        }
+        return Ok(());
+    } else if config.hunk_header_style.is_raw {
+        writeln!(painter.writer)?;
+        draw_fn(
+            painter.writer,
+            &format!("{} ", add_hunk_header_label(line.to_string(), &label)),
+            &format!("{} ", add_hunk_header_label(raw_line.to_string(), &label)),
+            &painter.decorations_width,
+            config.hunk_header_style,
+            decoration_ansi_term_style,
+            config.ascii,
         )?;
     } else {
-        let line = match painter.prepare(&raw_code_fragment, false) {
-            s if s.len() > 0 => format!("{} ", s),
-            s => s,
+        let (line, symbol_ranges) = match painter.prepare(&raw_code_fragment, false) {
+            (s, symbol_ranges) if s.len() > 0 => (format!("{} ", s), symbol_ranges),
+            prepared => prepared,
         };
         writeln!(painter.writer)?;
         if !line.is_empty() {
+            if let Some(label) = &label {
+                painter.output_buffer.push_str(
+                    &config
+                        .hunk_header_style
+                        .paint(format!("{} ", label))
+                        .to_string(),
+                );
+            }
             let lines = vec![line];
             let syntax_style_sections = Painter::get_syntax_style_sections_for_lines(
                 &lines,
@@ -398,36 +1660,73 @@ fn handle_hunk_header_line(
                 &mut painter.highlighter,
                 &painter.config,
             );
+            let diff_style_sections = match funcname::get_funcname_range(
+                &lines[0],
+                painter.diff_driver.as_deref(),
+                config,
+            ) {
+                Some((start, end)) => vec![
+                    (config.hunk_header_style, &lines[0][..start]),
+                    (
+                        highlight_patterns::overlay(
+                            config.hunk_header_style,
+                            config.hunk_header_funcname_style,
+                        ),
+                        &lines[0][start..end],
+                    ),
+                    (config.hunk_header_style, &lines[0][end..]),
+                ],
+                None => vec![(config.hunk_header_style, lines[0].as_str())],
+            };
             Painter::paint_lines(
-                syntax_style_sections,
-                vec![vec![(config.hunk_header_style, &lines[0])]],
+                StyleSections {
+                    syntax: syntax_style_sections,
+                    diff: vec![control_chars::style_control_char_sections(
+                        diff_style_sections,
+                        &symbol_ranges,
+                        config,
+                    )],
+                },
                 &State::HunkHeader,
                 &mut painter.output_buffer,
                 config,
                 &mut None,
                 "",
-                None,
-                Some(false),
+                0,
             );
             painter.output_buffer.pop(); // trim newline
             draw_fn(
                 painter.writer,
                 &painter.output_buffer,
                 &painter.output_buffer,
-                &config.decorations_width,
+                &painter.decorations_width,
                 config.hunk_header_style,
                 decoration_ansi_term_style,
+                config.ascii,
             )?;
             if !config.hunk_header_style.is_raw {
                 painter.output_buffer.clear()
             };
+        } else if let Some(label) = &label {
+            let label = config
+                .hunk_header_style
+                .paint(format!("{} ", label))
+                .to_string();
+            draw_fn(
+                painter.writer,
+                &label,
+                &label,
+                &painter.decorations_width,
+                config.hunk_header_style,
+                decoration_ansi_term_style,
+                config.ascii,
+            )?;
         }
     };
     // Emit a single line number, or prepare for full line-numbering
     if config.line_numbers {
         painter.line_numbers_data.initialize_hunk(line_numbers);
     } else {
-        let plus_line_number = line_numbers[line_numbers.len() - 1].0;
         match config.hunk_header_style.decoration_ansi_term_style() {
             Some(style) => writeln!(
                 painter.writer,
@@ -437,9 +1736,100 @@ fn handle_hunk_header_line(
             None => writeln!(painter.writer, "{}", plus_line_number)?,
         }
     }
+    if config.copy_hunk_location {
+        copy_hunk_location(painter, plus_file, plus_line_number)?;
+    }
     Ok(())
 }
 
+/// Under --inline-hunk-headers, start the output of the hunk with its line number range, so that
+/// it prefixes the first line of the hunk, instead of writing a hunk header.
+fn handle_inline_hunk_header_line(
+    painter: &mut Painter,
+    line: &str,
+    plus_file: &str,
+    label: Option<String>,
+    config: &Config,
+) -> std::io::Result<()> {
+    let (_, line_numbers) = parse::parse_hunk_header(line);
+    let (plus_line_number, plus_length) = line_numbers[line_numbers.len() - 1];
+    let range = match plus_length {
+        0 | 1 => format!("{}", plus_line_number),
+        _ => format!(
+            "{}-{}",
+            plus_line_number,
+            plus_line_number + plus_length - 1
+        ),
+    };
+    let annotation = add_hunk_header_label(format!("{}:", range), &label);
+    painter.output_buffer.push_str(
+        &config
+            .inline_hunk_header_style
+            .paint(annotation)
+            .to_string(),
+    );
+    painter.output_buffer.push(' ');
+    if config.line_numbers {
+        painter.line_numbers_data.initialize_hunk(line_numbers);
+    }
+    if config.copy_hunk_location {
+        copy_hunk_location(painter, plus_file, plus_line_number)?;
+    }
+    Ok(())
+}
+
+/// Write the OSC 52 sequence which sets the clipboard to the location of the hunk, if no location
+/// has been copied yet: the clipboard is written once, with the first hunk displayed.
+fn copy_hunk_location(
+    painter: &mut Painter,
+    plus_file: &str,
+    plus_line_number: usize,
+) -> std::io::Result<()> {
+    if painter.is_hunk_location_copied {
+        return Ok(());
+    }
+    painter.is_hunk_location_copied = true;
+    let location = format!("{}:{}", plus_file, plus_line_number);
+    write!(painter.writer, "\x1b]52;c;{}\x07", base64::encode(location))
+}
+
+/// Substitute the {file}, {line}, {context}, and {funcname} placeholders in a hunk header format
+/// string, in which '{{' and '}}' are literal braces. The line number may be offset, as in
+/// {line+3} or {line-1}.
+fn format_hunk_header(
+    format: &str,
+    file: &str,
+    line_number: usize,
+    context: &str,
+    funcname: &str,
+) -> String {
+    HUNK_HEADER_PLACEHOLDER_REGEX
+        .replace_all(format, |caps: &Captures| {
+            match caps.get(1).map(|m| m.as_str()) {
+                Some("file") => file.to_string(),
+                Some("context") => context.to_string(),
+                Some("funcname") => funcname.to_string(),
+                Some(_) => {
+                    let offset = caps.get(3).map_or(0, |m| m.as_str().parse().unwrap_or(0));
+                    match caps.get(2).map(|m| m.as_str()) {
+                        Some("-") => line_number.saturating_sub(offset),
+                        _ => line_number.saturating_add(offset),
+                    }
+                    .to_string()
+                }
+                // An escaped literal brace
+                None => caps[0][..1].to_string(),
+            }
+        })
+        .trim_end()
+        .to_string()
+}
+
+lazy_static! {
+    static ref HUNK_HEADER_PLACEHOLDER_REGEX: Regex =
+        Regex::new(r"\{\{|\}\}|\{(file|context|funcname|line(?:([+-])(\d+))?)\}").unwrap();
+}
+
 /// Handle a hunk line, i.e. a minus line, a plus line, or an unchanged line.
 // In the case of a minus or plus line, we store the line in a
 // buffer. When we exit the changed region we process the collected
@@ -451,6 +1841,7 @@ fn handle_hunk_line(
     line: &str,
     raw_line: &str,
     state: State,
+    style_override: Option<Style>,
     config: &Config,
 ) -> State {
     // Don't let the line buffers become arbitrarily large -- if we
@@ -466,18 +1857,41 @@ fn handle_hunk_line(
             if state == State::HunkPlus {
                 painter.paint_buffered_minus_and_plus_lines();
             }
-            painter.minus_lines.push(painter.prepare(&line, true));
+            let (line, symbol_ranges) = painter.prepare(&line, true);
+            painter.minus_lines.push(line);
+            painter.minus_line_style_overrides.push(style_override);
+            painter.minus_line_symbol_ranges.push(symbol_ranges);
             State::HunkMinus
         }
         Some('+') => {
-            painter.plus_lines.push(painter.prepare(&line, true));
+            let (line, symbol_ranges) = painter.prepare(&line, true);
+            painter.plus_lines.push(line);
+            painter.plus_line_style_overrides.push(style_override);
+            painter.plus_line_symbol_ranges.push(symbol_ranges);
             State::HunkPlus
         }
         Some(' ') => {
             painter.paint_buffered_minus_and_plus_lines();
-            painter.paint_zero_line(&line);
+            painter.paint_zero_line(&line, style_override);
             State::HunkZero
         }
+        // Under side-by-side, git's "\ No newline at end of file" line is displayed as a badge at
+        // the bottom of the panel of the line that it follows, once that line has been painted.
+        Some('\\') if config.side_by_side && !painter.degraded => {
+            match state {
+                State::HunkMinus => painter.minus_no_newline = true,
+                State::HunkPlus => painter.plus_no_newline = true,
+                _ => side_by_side::paint_no_newline_badges(
+                    true,
+                    true,
+                    &mut painter.output_buffer,
+                    &painter.line_widths.side_by_side_data,
+                    config,
+                    &mut Some(&mut painter.line_numbers_data),
+                ),
+            }
+            state
+        }
         _ => {
             // The first character here could be e.g. '\' from '\ No newline at end of file'. This
             // is not a hunk line, but the parser does not have a more accurate state corresponding
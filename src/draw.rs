@@ -16,6 +16,7 @@ pub fn write_no_decoration(
     _line_width: &Width, // ignored
     text_style: Style,
     _decoration_style: ansi_term::Style,
+    _ascii: bool,
 ) -> std::io::Result<()> {
     if text_style.is_raw {
         writeln!(writer, "{}", raw_text)?;
@@ -34,21 +35,12 @@ pub fn write_boxed(
     _line_width: &Width, // ignored
     text_style: Style,
     decoration_style: ansi_term::Style,
+    ascii: bool,
 ) -> std::io::Result<()> {
-    let up_left = if decoration_style.is_bold {
-        box_drawing::heavy::UP_LEFT
-    } else {
-        box_drawing::light::UP_LEFT
-    };
+    let decoration = Decoration::new(decoration_style, ascii);
+    let up_left = get_box_drawing_characters(decoration).up_left;
     let box_width = UnicodeWidthStr::width(strip_ansi_codes(text).as_ref());
-    write_boxed_partial(
-        writer,
-        text,
-        raw_text,
-        box_width,
-        text_style,
-        decoration_style,
-    )?;
+    write_boxed_partial(writer, text, raw_text, box_width, text_style, decoration)?;
     writeln!(writer, "{}", decoration_style.paint(up_left))?;
     Ok(())
 }
@@ -62,6 +54,7 @@ pub fn write_boxed_with_underline(
     line_width: &Width,
     text_style: Style,
     decoration_style: ansi_term::Style,
+    ascii: bool,
 ) -> std::io::Result<()> {
     let box_width = UnicodeWidthStr::width(strip_ansi_codes(text).as_ref());
     write_boxed_with_horizontal_whisker(
@@ -70,7 +63,7 @@ pub fn write_boxed_with_underline(
         raw_text,
         box_width,
         text_style,
-        decoration_style,
+        Decoration::new(decoration_style, ascii),
     )?;
     let line_width = match *line_width {
         Width::Fixed(n) => n,
@@ -84,7 +77,7 @@ pub fn write_boxed_with_underline(
             0
         },
         text_style,
-        decoration_style,
+        Decoration::new(decoration_style, ascii),
     )?;
     write!(writer, "\n")?;
     Ok(())
@@ -103,6 +96,7 @@ pub fn write_underlined(
     line_width: &Width,
     text_style: Style,
     decoration_style: ansi_term::Style,
+    ascii: bool,
 ) -> std::io::Result<()> {
     _write_under_or_over_lined(
         UnderOverline::Under,
@@ -111,7 +105,7 @@ pub fn write_underlined(
         raw_text,
         line_width,
         text_style,
-        decoration_style,
+        Decoration::new(decoration_style, ascii),
     )
 }
 
@@ -122,6 +116,7 @@ pub fn write_overlined(
     line_width: &Width,
     text_style: Style,
     decoration_style: ansi_term::Style,
+    ascii: bool,
 ) -> std::io::Result<()> {
     _write_under_or_over_lined(
         UnderOverline::Over,
@@ -130,7 +125,7 @@ pub fn write_overlined(
         raw_text,
         line_width,
         text_style,
-        decoration_style,
+        Decoration::new(decoration_style, ascii),
     )
 }
 
@@ -141,6 +136,7 @@ pub fn write_underoverlined(
     line_width: &Width,
     text_style: Style,
     decoration_style: ansi_term::Style,
+    ascii: bool,
 ) -> std::io::Result<()> {
     _write_under_or_over_lined(
         UnderOverline::Underover,
@@ -149,7 +145,7 @@ pub fn write_underoverlined(
         raw_text,
         line_width,
         text_style,
-        decoration_style,
+        Decoration::new(decoration_style, ascii),
     )
 }
 
@@ -160,7 +156,7 @@ fn _write_under_or_over_lined(
     raw_text: &str,
     line_width: &Width,
     text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
     let text_width = UnicodeWidthStr::width(strip_ansi_codes(text).as_ref());
     let line_width = match *line_width {
@@ -169,7 +165,7 @@ fn _write_under_or_over_lined(
     };
     let mut write_line: Box<dyn FnMut(&mut dyn Write) -> std::io::Result<()>> =
         Box::new(|writer| {
-            write_horizontal_line(writer, line_width, text_style, decoration_style)?;
+            write_horizontal_line(writer, line_width, text_style, decoration)?;
             write!(writer, "\n")?;
             Ok(())
         });
@@ -193,17 +189,13 @@ fn write_horizontal_line(
     writer: &mut dyn Write,
     width: usize,
     _text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
-    let horizontal = if decoration_style.is_bold {
-        box_drawing::heavy::HORIZONTAL
-    } else {
-        box_drawing::light::HORIZONTAL
-    };
+    let horizontal = get_box_drawing_characters(decoration).horizontal;
     write!(
         writer,
         "{}",
-        decoration_style.paint(horizontal.repeat(width))
+        decoration.style.paint(horizontal.repeat(width))
     )
 }
 
@@ -213,22 +205,11 @@ fn write_boxed_with_horizontal_whisker(
     raw_text: &str,
     box_width: usize,
     text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
-    let up_horizontal = if decoration_style.is_bold {
-        box_drawing::heavy::UP_HORIZONTAL
-    } else {
-        box_drawing::light::UP_HORIZONTAL
-    };
-    write_boxed_partial(
-        writer,
-        text,
-        raw_text,
-        box_width,
-        text_style,
-        decoration_style,
-    )?;
-    write!(writer, "{}", decoration_style.paint(up_horizontal))?;
+    let up_horizontal = get_box_drawing_characters(decoration).up_horizontal;
+    write_boxed_partial(writer, text, raw_text, box_width, text_style, decoration)?;
+    write!(writer, "{}", decoration.style.paint(up_horizontal))?;
     Ok(())
 }
 
@@ -238,27 +219,20 @@ fn write_boxed_partial(
     raw_text: &str,
     box_width: usize,
     text_style: Style,
-    decoration_style: ansi_term::Style,
+    decoration: Decoration,
 ) -> std::io::Result<()> {
-    let (horizontal, down_left, vertical) = if decoration_style.is_bold {
-        (
-            box_drawing::heavy::HORIZONTAL,
-            box_drawing::heavy::DOWN_LEFT,
-            box_drawing::heavy::VERTICAL,
-        )
-    } else {
-        (
-            box_drawing::light::HORIZONTAL,
-            box_drawing::light::DOWN_LEFT,
-            box_drawing::light::VERTICAL,
-        )
-    };
+    let BoxDrawingCharacters {
+        horizontal,
+        down_left,
+        vertical,
+        ..
+    } = get_box_drawing_characters(decoration);
     let horizontal_edge = horizontal.repeat(box_width);
     write!(
         writer,
         "{}{}\n",
-        decoration_style.paint(&horizontal_edge),
-        decoration_style.paint(down_left),
+        decoration.style.paint(&horizontal_edge),
+        decoration.style.paint(down_left),
     )?;
     if text_style.is_raw {
         write!(writer, "{}", raw_text)?;
@@ -268,7 +242,58 @@ fn write_boxed_partial(
     write!(
         writer,
         "{}\n{}",
-        decoration_style.paint(vertical),
-        decoration_style.paint(&horizontal_edge),
+        decoration.style.paint(vertical),
+        decoration.style.paint(&horizontal_edge),
     )
 }
+
+/// The style of the lines drawn around a text, and whether they are drawn with ASCII characters.
+#[derive(Clone, Copy)]
+struct Decoration {
+    style: ansi_term::Style,
+    ascii: bool,
+}
+
+impl Decoration {
+    fn new(style: ansi_term::Style, ascii: bool) -> Self {
+        Self { style, ascii }
+    }
+}
+
+struct BoxDrawingCharacters {
+    horizontal: &'static str,
+    vertical: &'static str,
+    down_left: &'static str,
+    up_left: &'static str,
+    up_horizontal: &'static str,
+}
+
+/// Return the characters used to draw decorations: heavy box-drawing characters for a bold
+/// decoration style, light ones otherwise, or ASCII characters under the ascii feature.
+fn get_box_drawing_characters(decoration: Decoration) -> BoxDrawingCharacters {
+    if decoration.ascii {
+        BoxDrawingCharacters {
+            horizontal: if decoration.style.is_bold { "=" } else { "-" },
+            vertical: "|",
+            down_left: "+",
+            up_left: "+",
+            up_horizontal: "+",
+        }
+    } else if decoration.style.is_bold {
+        BoxDrawingCharacters {
+            horizontal: box_drawing::heavy::HORIZONTAL,
+            vertical: box_drawing::heavy::VERTICAL,
+            down_left: box_drawing::heavy::DOWN_LEFT,
+            up_left: box_drawing::heavy::UP_LEFT,
+            up_horizontal: box_drawing::heavy::UP_HORIZONTAL,
+        }
+    } else {
+        BoxDrawingCharacters {
+            horizontal: box_drawing::light::HORIZONTAL,
+            vertical: box_drawing::light::VERTICAL,
+            down_left: box_drawing::light::DOWN_LEFT,
+            up_left: box_drawing::light::UP_LEFT,
+            up_horizontal: box_drawing::light::UP_HORIZONTAL,
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::features::OptionValueFunction;
+
+/// Output for screen readers: lines are labeled rather than distinguished by color alone, and
+/// decorations and separators are not drawn with box-drawing characters.
+pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
+    builtin_feature!([
+        (
+            "accessible",
+            bool,
+            None,
+            _opt => true
+        ),
+        (
+            "side-by-side",
+            bool,
+            None,
+            _opt => false
+        ),
+        (
+            "commit-decoration-style",
+            String,
+            None,
+            _opt => "none"
+        ),
+        (
+            "file-decoration-style",
+            String,
+            None,
+            _opt => "none"
+        ),
+        (
+            "hunk-header-decoration-style",
+            String,
+            None,
+            _opt => "none"
+        ),
+        (
+            "hunk-header-format",
+            String,
+            None,
+            _opt => "line {line}: {context}"
+        ),
+        (
+            "file-modified-label",
+            String,
+            None,
+            _opt => "modified:"
+        ),
+        (
+            "line-numbers-left-format",
+            String,
+            None,
+            _opt => "{nm:>4} "
+        ),
+        (
+            "line-numbers-right-format",
+            String,
+            None,
+            _opt => "{np:>4} "
+        )
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_accessible() {
+        let config = make_config_from_args(&["--accessible"]);
+        assert!(!config.side_by_side);
+        let output = run_delta(DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "",
+                "modified: a.py",
+                "",
+                "line 1: def f():",
+                "context: a = 1",
+                "removed: b = 2",
+                "added: b = 3",
+            ]
+        );
+        assert!(!output.contains('─'));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@ def f():
+ a = 1
+-b = 2
++b = 3
+";
+}
@@ -0,0 +1,104 @@
+use crate::features::OptionValueFunction;
+
+/// Replace the Unicode characters in delta's default line-number and blame formats with ASCII
+/// characters. The decorations and other characters that delta draws itself are chosen
+/// according to the `ascii` flag.
+pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
+    builtin_feature!([
+        (
+            "ascii",
+            bool,
+            None,
+            _opt => true
+        ),
+        (
+            "line-numbers-left-format",
+            String,
+            None,
+            opt => if opt.side_by_side {"|{nm:^4}|"} else {"{nm:^4}:"}
+        ),
+        (
+            "line-numbers-right-format",
+            String,
+            None,
+            opt => if opt.side_by_side {"|{np:^4}|"} else {"{np:^4}|"}
+        ),
+        (
+            "blame-format",
+            String,
+            None,
+            _opt => "{timestamp:<16} {author:<15.14} {commit:<8} | "
+        )
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_ascii() {
+        for args in &[
+            vec![
+                "--ascii",
+                "--line-numbers",
+                "--file-decoration-style",
+                "box",
+            ],
+            vec!["--ascii", "--side-by-side", "--width", "40"],
+        ] {
+            let config = make_config_from_args(args);
+            let output = run_delta(DIFF, &config);
+            let output = strip_ansi_codes(&output);
+            assert!(output.is_ascii(), "{}", output);
+            assert!(output.contains("a = 1"));
+        }
+    }
+
+    #[test]
+    fn test_ascii_line_numbers_and_decorations() {
+        let config = make_config_from_args(&[
+            "--ascii",
+            "--line-numbers",
+            "--file-decoration-style",
+            "box",
+            "--hunk-header-decoration-style",
+            "bold box",
+        ]);
+        let output = run_delta(DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        let lines: Vec<&str> = output
+            .lines()
+            .skip_while(|line| !line.starts_with("===="))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "=========+",
+                "def f(): |",
+                "=========+",
+                " 1  : 1  |a = 1",
+                " 2  :    |b = 2",
+                "    : 2  |b = 3",
+            ]
+        );
+        assert!(output.contains("\n----------------------+\nrenamed: a.py -> b.py |\n"));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/b.py
+similarity index 50%
+rename from a.py
+rename to b.py
+--- a/a.py
++++ b/b.py
+@@ -1,2 +1,2 @@ def f():
+ a = 1
+-b = 2
++b = 3
+";
+}
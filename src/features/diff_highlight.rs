@@ -12,7 +12,7 @@ pub fn _make_feature(bold: bool) -> Vec<(String, OptionValueFunction)> {
             "keep-plus-minus-markers",
             bool,
             None,
-            _opt => false
+            _opt => true
         ),
         (
             "minus-style",
@@ -74,6 +74,7 @@ mod test_utils {
         assert_eq!(opt.plus_style, "green");
         assert_eq!(opt.plus_non_emph_style, "green");
         assert_eq!(opt.plus_emph_style, "green reverse");
+        assert!(opt.keep_plus_minus_markers);
     }
 
     #[test]
@@ -45,6 +45,54 @@ pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
             String,
             None,
             _opt => "magenta box"
+        ),
+        (
+            "hunk-header-format",
+            String,
+            None,
+            _opt => "@ {file}:{line} @ {context}"
+        ),
+        (
+            "file-modified-label",
+            String,
+            None,
+            _opt => "modified:"
+        ),
+        (
+            "file-removed-label",
+            String,
+            None,
+            _opt => "deleted:"
+        ),
+        (
+            "file-added-label",
+            String,
+            None,
+            _opt => "added:"
+        ),
+        (
+            "file-renamed-label",
+            String,
+            None,
+            _opt => "renamed:"
+        ),
+        (
+            "keep-plus-minus-markers",
+            bool,
+            None,
+            _opt => false
+        ),
+        (
+            "minus-empty-line-marker-style",
+            String,
+            None,
+            _opt => "normal red"
+        ),
+        (
+            "plus-empty-line-marker-style",
+            String,
+            None,
+            _opt => "normal green"
         )
     ]));
     feature
@@ -54,6 +102,8 @@ pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
 pub mod tests {
     use std::fs::remove_file;
 
+    use console::strip_ansi_codes;
+
     use crate::tests::integration_test_utils::integration_test_utils;
 
     #[test]
@@ -72,6 +122,28 @@ pub mod tests {
 
         assert_eq!(opt.hunk_header_style, "bold syntax");
         assert_eq!(opt.hunk_header_decoration_style, "magenta box");
+        assert_eq!(opt.hunk_header_format, "@ {file}:{line} @ {context}");
+
+        assert_eq!(opt.file_modified_label, "modified:");
+        assert_eq!(opt.file_removed_label, "deleted:");
+        assert!(!opt.keep_plus_minus_markers);
+        assert_eq!(opt.minus_empty_line_marker_style, "normal red");
+    }
+
+    #[test]
+    fn test_diff_so_fancy_hunk_header() {
+        let config = integration_test_utils::make_config_from_args(&[
+            "--features",
+            "diff-so-fancy",
+            "--hunk-header-decoration-style",
+            "none",
+        ]);
+        let output = integration_test_utils::run_delta(
+            crate::features::line_numbers::tests::ONE_MINUS_ONE_PLUS_LINE_DIFF,
+            &config,
+        );
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\n@ a.py:1 @\n"));
     }
 
     #[test]
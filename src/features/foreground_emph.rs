@@ -0,0 +1,71 @@
+use crate::features::OptionValueFunction;
+
+/// Emphasize the changed sections of lines by their foreground attributes only: an emph style's
+/// background defaults to the background of its line (see make_hunk_styles in config.rs), so that
+/// the emphasized sections are not drawn with a second background color.
+pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
+    builtin_feature!([
+        (
+            "foreground-emph",
+            bool,
+            None,
+            _opt => true
+        ),
+        (
+            "minus-emph-style",
+            String,
+            None,
+            _opt => "bold ul red auto"
+        ),
+        (
+            "plus-emph-style",
+            String,
+            None,
+            _opt => "syntax bold ul auto"
+        )
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::integration_test_utils::integration_test_utils::make_config_from_args;
+
+    #[test]
+    fn test_foreground_emph() {
+        let config = make_config_from_args(&["--foreground-emph"]);
+        assert_eq!(
+            config.minus_emph_style.ansi_term_style.background,
+            config.minus_style.ansi_term_style.background
+        );
+        assert_eq!(
+            config.plus_emph_style.ansi_term_style.background,
+            config.plus_style.ansi_term_style.background
+        );
+        assert!(config.minus_emph_style.ansi_term_style.is_bold);
+        assert!(config.plus_emph_style.ansi_term_style.is_underline);
+        assert_eq!(
+            config.minus_emph_style.ansi_term_style.foreground,
+            Some(ansi_term::Color::Fixed(1))
+        );
+        assert!(config.plus_emph_style.is_syntax_highlighted);
+    }
+
+    #[test]
+    fn test_foreground_emph_with_custom_emph_style() {
+        let config = make_config_from_args(&[
+            "--foreground-emph",
+            "--minus-style",
+            "normal black",
+            "--minus-emph-style",
+            "bold yellow auto",
+        ]);
+        assert_eq!(
+            config.minus_emph_style.ansi_term_style.background,
+            Some(ansi_term::Color::Fixed(0))
+        );
+        assert_eq!(
+            config.minus_emph_style.ansi_term_style.foreground,
+            Some(ansi_term::Color::Fixed(3))
+        );
+    }
+}
@@ -8,6 +8,7 @@ use crate::config;
 use crate::delta::State;
 use crate::features::side_by_side;
 use crate::features::OptionValueFunction;
+use crate::highlight_patterns::overlay;
 use crate::style::Style;
 
 pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
@@ -93,14 +94,26 @@ pub fn format_and_paint_line_numbers<'a>(
         }
         _ => return Vec::new(),
     };
+    let (minus_style, plus_style) = match &config.line_numbers_primary_side {
+        Some(side_by_side::PanelSide::Left) => (
+            overlay(minus_style, config.line_numbers_primary_style),
+            overlay(plus_style, config.line_numbers_secondary_style),
+        ),
+        Some(side_by_side::PanelSide::Right) => (
+            overlay(minus_style, config.line_numbers_secondary_style),
+            overlay(plus_style, config.line_numbers_primary_style),
+        ),
+        None => (minus_style, plus_style),
+    };
 
     let mut formatted_numbers = Vec::new();
 
     let (emit_left, emit_right) = match (config.side_by_side, side_by_side_panel) {
-        (false, _) => (true, true),
+        // A side-by-side line without a panel is being painted in unified layout because
+        // side-by-side display has been disabled for the remainder of the input.
+        (false, _) | (true, None) => (true, true),
         (true, Some(side_by_side::PanelSide::Left)) => (true, false),
         (true, Some(side_by_side::PanelSide::Right)) => (false, true),
-        (true, None) => unreachable!(),
     };
 
     if emit_left {
@@ -132,6 +145,8 @@ pub fn format_and_paint_line_numbers<'a>(
 lazy_static! {
     static ref LINE_NUMBERS_PLACEHOLDER_REGEX: Regex = Regex::new(
         r"(?x)
+\{\{ | \}\}       # An escaped literal brace
+|
 \{
 (nm|np)         # 1: Literal nm or np
 (?:             # Start optional format spec (non-capturing)
@@ -140,12 +155,20 @@ lazy_static! {
     ([^<^>])?   #         2: Optional fill character (ignored)
     ([<^>])     #         3: Alignment spec
   )?            #
-  (\d+)         #     4: Width
+  (\d+)?        #     4: Optional width
+  (,)?          #     5: Optional thousands separator
 )?              #
 \}
 "
     )
     .unwrap();
+    static ref THOUSANDS_SEPARATOR: char = get_thousands_separator(
+        &["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+    );
 }
 
 #[derive(Default)]
@@ -167,6 +190,7 @@ pub struct LineNumberPlaceholderData<'a> {
     pub placeholder: Option<&'a str>,
     pub alignment_spec: Option<&'a str>,
     pub width: Option<usize>,
+    pub thousands_separator: bool,
     pub suffix: &'a str,
 }
 
@@ -201,13 +225,19 @@ fn parse_line_number_format<'a>(format_string: &'a str) -> LineNumberFormatData<
         let _match = captures.get(0).unwrap();
         format_data.push(LineNumberPlaceholderData {
             prefix: &format_string[offset.._match.start()],
-            placeholder: captures.get(1).map(|m| m.as_str()),
+            // An escaped brace is represented by a placeholder which is the brace itself.
+            placeholder: Some(
+                captures
+                    .get(1)
+                    .map_or(&_match.as_str()[..1], |m| m.as_str()),
+            ),
             alignment_spec: captures.get(3).map(|m| m.as_str()),
             width: captures.get(4).map(|m| {
                 m.as_str()
                     .parse()
                     .unwrap_or_else(|_| panic!("Invalid width in format string: {}", format_string))
             }),
+            thousands_separator: captures.get(5).is_some(),
             suffix: &format_string[_match.end()..],
         });
         offset = _match.end();
@@ -219,6 +249,7 @@ fn parse_line_number_format<'a>(format_string: &'a str) -> LineNumberFormatData<
             placeholder: None,
             alignment_spec: None,
             width: None,
+            thousands_separator: false,
             suffix: &format_string[0..],
         })
     }
@@ -240,6 +271,16 @@ fn format_and_paint_line_number_field<'a>(
         ansi_strings.push(style.paint(placeholder.prefix));
 
         let alignment_spec = placeholder.alignment_spec.unwrap_or("^");
+        let thousands_separator = if placeholder.thousands_separator {
+            Some(*THOUSANDS_SEPARATOR)
+        } else {
+            None
+        };
+        // The separators of the hunk's largest line number widen the field.
+        let min_field_width = match thousands_separator {
+            Some(_) => min_field_width + min_field_width.saturating_sub(1) / 3,
+            None => min_field_width,
+        };
         let width = if let Some(placeholder_width) = placeholder.width {
             max(placeholder_width, min_field_width)
         } else {
@@ -251,12 +292,15 @@ fn format_and_paint_line_number_field<'a>(
                 minus_number,
                 alignment_spec,
                 width,
+                thousands_separator,
             ))),
             Some("np") => ansi_strings.push(plus_number_style.paint(format_line_number(
                 plus_number,
                 alignment_spec,
                 width,
+                thousands_separator,
             ))),
+            Some(brace @ "{") | Some(brace @ "}") => ansi_strings.push(style.paint(brace)),
             None => {}
             Some(_) => unreachable!(),
         }
@@ -266,11 +310,19 @@ fn format_and_paint_line_number_field<'a>(
     ansi_strings
 }
 
-/// Return line number formatted according to `alignment` and `width`.
-fn format_line_number(line_number: Option<usize>, alignment: &str, width: usize) -> String {
-    let n = line_number
-        .map(|n| format!("{}", n))
-        .unwrap_or_else(|| "".to_string());
+/// Return line number formatted according to `alignment` and `width`, with its digits grouped in
+/// thousands if a separator is given.
+fn format_line_number(
+    line_number: Option<usize>,
+    alignment: &str,
+    width: usize,
+    thousands_separator: Option<char>,
+) -> String {
+    let n = match (line_number, thousands_separator) {
+        (Some(n), Some(separator)) => group_digits(n, separator),
+        (Some(n), None) => format!("{}", n),
+        (None, _) => "".to_string(),
+    };
     match alignment {
         "<" => format!("{0:<1$}", n, width),
         "^" => format!("{0:^1$}", n, width),
@@ -279,6 +331,32 @@ fn format_line_number(line_number: Option<usize>, alignment: &str, width: usize)
     }
 }
 
+/// Return the number with a separator between each group of three digits, e.g. 1,234,567.
+fn group_digits(n: usize, separator: char) -> String {
+    let digits = n.to_string();
+    let first_group_len = match digits.len() % 3 {
+        0 => 3,
+        len => len,
+    };
+    let mut grouped = digits[..first_group_len].to_string();
+    for group in digits.as_bytes()[first_group_len..].chunks(3) {
+        grouped.push(separator);
+        grouped.push_str(std::str::from_utf8(group).unwrap());
+    }
+    grouped
+}
+
+/// Return the thousands separator of the locale's numbers, as given by the LC_ALL, LC_NUMERIC or
+/// LANG environment variables. Locales which are not recognized use a comma.
+fn get_thousands_separator(locale: &str) -> char {
+    let language = locale.split(&['_', '.', '@'][..]).next();
+    match language.unwrap_or("") {
+        "da" | "de" | "el" | "es" | "id" | "it" | "nl" | "pt" | "ro" | "sl" | "tr" | "vi" => '.',
+        "cs" | "fi" | "fr" | "hu" | "nb" | "nn" | "no" | "pl" | "ru" | "sk" | "sv" | "uk" => ' ',
+        _ => ',',
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use console::strip_ansi_codes;
@@ -299,6 +377,7 @@ pub mod tests {
                 placeholder: Some("nm"),
                 alignment_spec: None,
                 width: None,
+                thousands_separator: false,
                 suffix: "",
             }]
         )
@@ -313,6 +392,7 @@ pub mod tests {
                 placeholder: Some("np"),
                 alignment_spec: None,
                 width: Some(4),
+                thousands_separator: false,
                 suffix: "",
             }]
         )
@@ -327,6 +407,7 @@ pub mod tests {
                 placeholder: Some("np"),
                 alignment_spec: Some(">"),
                 width: Some(4),
+                thousands_separator: false,
                 suffix: "",
             }]
         )
@@ -341,6 +422,7 @@ pub mod tests {
                 placeholder: Some("np"),
                 alignment_spec: Some(">"),
                 width: Some(4),
+                thousands_separator: false,
                 suffix: "",
             }]
         )
@@ -355,6 +437,7 @@ pub mod tests {
                 placeholder: Some("np"),
                 alignment_spec: Some(">"),
                 width: Some(4),
+                thousands_separator: false,
                 suffix: "@@",
             }]
         )
@@ -370,6 +453,7 @@ pub mod tests {
                     placeholder: Some("nm"),
                     alignment_spec: Some("<"),
                     width: Some(3),
+                    thousands_separator: false,
                     suffix: "@@---{np:_>4}**",
                 },
                 LineNumberPlaceholderData {
@@ -377,6 +461,7 @@ pub mod tests {
                     placeholder: Some("np"),
                     alignment_spec: Some(">"),
                     width: Some(4),
+                    thousands_separator: false,
                     suffix: "**",
                 }
             ]
@@ -392,11 +477,115 @@ pub mod tests {
                 placeholder: None,
                 alignment_spec: None,
                 width: None,
+                thousands_separator: false,
                 suffix: "__@@---**",
             },]
         )
     }
 
+    #[test]
+    fn test_line_number_format_regex_8() {
+        assert_eq!(
+            parse_line_number_format("{{{nm}}}"),
+            vec![
+                LineNumberPlaceholderData {
+                    prefix: "",
+                    placeholder: Some("{"),
+                    alignment_spec: None,
+                    width: None,
+                    thousands_separator: false,
+                    suffix: "{nm}}}",
+                },
+                LineNumberPlaceholderData {
+                    prefix: "",
+                    placeholder: Some("nm"),
+                    alignment_spec: None,
+                    width: None,
+                    thousands_separator: false,
+                    suffix: "}}",
+                },
+                LineNumberPlaceholderData {
+                    prefix: "",
+                    placeholder: Some("}"),
+                    alignment_spec: None,
+                    width: None,
+                    thousands_separator: false,
+                    suffix: "",
+                }
+            ]
+        )
+    }
+
+    #[test]
+    fn test_line_number_format_regex_9() {
+        assert_eq!(
+            parse_line_number_format("{np:>5,}"),
+            vec![LineNumberPlaceholderData {
+                prefix: "",
+                placeholder: Some("np"),
+                alignment_spec: Some(">"),
+                width: Some(5),
+                thousands_separator: true,
+                suffix: "",
+            }]
+        )
+    }
+
+    #[test]
+    fn test_group_digits() {
+        assert_eq!(group_digits(7, ','), "7");
+        assert_eq!(group_digits(999, ','), "999");
+        assert_eq!(group_digits(1000, ','), "1,000");
+        assert_eq!(group_digits(1234567, '.'), "1.234.567");
+    }
+
+    #[test]
+    fn test_get_thousands_separator() {
+        assert_eq!(get_thousands_separator("en_US.UTF-8"), ',');
+        assert_eq!(get_thousands_separator("de_DE.UTF-8"), '.');
+        assert_eq!(get_thousands_separator("fr_FR"), ' ');
+        assert_eq!(get_thousands_separator("C"), ',');
+        assert_eq!(get_thousands_separator(""), ',');
+    }
+
+    #[test]
+    fn test_thousands_separator() {
+        let config = make_config_from_args(&[
+            "--line-numbers",
+            "--line-numbers-left-format",
+            "{nm:>1,}⋮",
+            "--line-numbers-right-format",
+            "{np:>1,}│",
+        ]);
+        let diff = TWO_MINUS_LINES_DIFF.replace("@@ -1,2 +0,0 @@", "@@ -12344,2 +12343,0 @@");
+        let output = run_delta(&diff, &config);
+        let mut lines = output.lines().skip(4);
+        let separator = *THOUSANDS_SEPARATOR;
+        assert_eq!(
+            strip_ansi_codes(lines.next().unwrap()),
+            format!("12{}344⋮      │a = 1", separator)
+        );
+        assert_eq!(
+            strip_ansi_codes(lines.next().unwrap()),
+            format!("12{}345⋮      │b = 2", separator)
+        );
+    }
+
+    #[test]
+    fn test_escaped_braces() {
+        let config = make_config_from_args(&[
+            "--line-numbers",
+            "--line-numbers-left-format",
+            "{{{nm:^4}}}",
+            "--line-numbers-right-format",
+            "{{np}}│",
+        ]);
+        let output = run_delta(TWO_MINUS_LINES_DIFF, &config);
+        let mut lines = output.lines().skip(4);
+        let line_1 = lines.next().unwrap();
+        assert_eq!(strip_ansi_codes(line_1), "{ 1  }{np}│a = 1");
+    }
+
     fn _get_capture<'a>(i: usize, j: usize, caps: &'a Vec<Captures>) -> &'a str {
         caps[i].get(j).map_or("", |m| m.as_str())
     }
@@ -521,6 +710,40 @@ pub mod tests {
         assert_eq!(lines.next().unwrap(), "     ⋮10000│bb = 2");
     }
 
+    #[test]
+    fn test_primary_side() {
+        let args = [
+            "--line-numbers",
+            "--line-numbers-zero-style",
+            "blue",
+            "--line-numbers-primary-style",
+            "bold",
+            "--line-numbers-secondary-style",
+            "dim",
+        ];
+        let config =
+            make_config_from_args(&[&args[..], &["--line-numbers-primary-side", "old"]].concat());
+        let output = run_delta(ONE_MINUS_ONE_PLUS_LINE_DIFF, &config);
+        let line = output.lines().nth(4).unwrap();
+        let (left, right) = line.split_at(line.find('⋮').unwrap());
+        assert!(left.contains("\x1b[1m 1  "));
+        assert!(right.contains("\x1b[2m 1  "));
+        assert_eq!(strip_ansi_codes(line), " 1  ⋮ 1  │a = 1");
+
+        let config =
+            make_config_from_args(&[&args[..], &["--line-numbers-primary-side", "new"]].concat());
+        let output = run_delta(ONE_MINUS_ONE_PLUS_LINE_DIFF, &config);
+        let line = output.lines().nth(4).unwrap();
+        let (left, right) = line.split_at(line.find('⋮').unwrap());
+        assert!(left.contains("\x1b[2m 1  "));
+        assert!(right.contains("\x1b[1m 1  "));
+
+        let config = make_config_from_args(&args);
+        let output = run_delta(ONE_MINUS_ONE_PLUS_LINE_DIFF, &config);
+        let line = output.lines().nth(4).unwrap();
+        assert!(!line.contains("\x1b[1m") && !line.contains("\x1b[2m"));
+    }
+
     pub const TWO_MINUS_LINES_DIFF: &str = "\
 diff --git i/a.py w/a.py
 index 223ca50..e69de29 100644
@@ -26,6 +26,14 @@ type OptionValueFunction = Box<dyn Fn(&cli::Opt, &Option<GitConfig>) -> Provenan
 // for the option.
 pub fn make_builtin_features() -> HashMap<String, BuiltinFeature> {
     vec![
+        (
+            "accessible".to_string(),
+            accessible::make_feature().into_iter().collect(),
+        ),
+        (
+            "ascii".to_string(),
+            ascii::make_feature().into_iter().collect(),
+        ),
         (
             "color-only".to_string(),
             color_only::make_feature().into_iter().collect(),
@@ -38,6 +46,10 @@ pub fn make_builtin_features() -> HashMap<String, BuiltinFeature> {
             "diff-so-fancy".to_string(),
             diff_so_fancy::make_feature().into_iter().collect(),
         ),
+        (
+            "foreground-emph".to_string(),
+            foreground_emph::make_feature().into_iter().collect(),
+        ),
         (
             "line-numbers".to_string(),
             line_numbers::make_feature().into_iter().collect(),
@@ -78,9 +90,12 @@ macro_rules! builtin_feature {
     }
 }
 
+pub mod accessible;
+pub mod ascii;
 pub mod color_only;
 pub mod diff_highlight;
 pub mod diff_so_fancy;
+pub mod foreground_emph;
 pub mod line_numbers;
 pub mod navigate;
 pub mod raw;
@@ -91,6 +106,7 @@ pub mod tests {
     use std::collections::HashSet;
     use std::fs::remove_file;
 
+    use crate::bat::assets::HighlightingAssets;
     use crate::cli;
     use crate::features::make_builtin_features;
     use crate::tests::integration_test_utils::integration_test_utils::make_options_from_args_and_git_config;
@@ -100,7 +116,7 @@ pub mod tests {
         let builtin_features = make_builtin_features();
         let mut args = vec!["delta".to_string()];
         args.extend(builtin_features.keys().map(|s| format!("--{}", s)));
-        let opt = cli::Opt::from_iter_and_git_config(args, &mut None);
+        let opt = cli::Opt::from_iter_and_git_config(args, &mut None, HighlightingAssets::new());
         let features: HashSet<&str> = opt.features.split_whitespace().collect();
         for feature in builtin_features.keys() {
             assert!(features.contains(feature.as_str()))
@@ -180,6 +196,52 @@ pub mod tests {
         remove_file(git_config_path).unwrap();
     }
 
+    #[test]
+    fn test_features_on_command_line_enable_and_disable_features_in_gitconfig() {
+        let git_config_contents = b"
+[delta]
+    features = my-feature line-numbers
+    navigate = true
+
+[delta \"my-feature\"]
+    minus-style = blue
+";
+        let git_config_path =
+            "delta__test_features_on_command_line_enable_and_disable_features_in_gitconfig.gitconfig";
+
+        let opt = make_options_from_args_and_git_config(
+            &["--features", "+side-by-side -line-numbers"],
+            Some(git_config_contents),
+            Some(git_config_path),
+        );
+        assert_eq!(opt.features, "navigate my-feature side-by-side");
+        assert!(opt.side_by_side);
+        assert!(!opt.line_numbers);
+        assert!(opt.navigate);
+        assert_eq!(opt.minus_style, "blue");
+
+        let opt = make_options_from_args_and_git_config(
+            &["--features", "-my-feature -navigate"],
+            Some(git_config_contents),
+            Some(git_config_path),
+        );
+        assert_eq!(opt.features, "line-numbers");
+        assert!(opt.line_numbers);
+        assert!(!opt.navigate);
+        assert_ne!(opt.minus_style, "blue");
+
+        // A name without a prefix replaces the features in gitconfig.
+        let opt = make_options_from_args_and_git_config(
+            &["--features", "raw -navigate"],
+            Some(git_config_contents),
+            Some(git_config_path),
+        );
+        assert_eq!(opt.features, "raw");
+        assert!(!opt.navigate);
+
+        remove_file(git_config_path).unwrap();
+    }
+
     #[test]
     fn test_recursive_feature_gathering_1() {
         let git_config_contents = b"
@@ -14,6 +14,12 @@ pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
             String,
             None,
             _opt => "Δ"
+        ),
+        (
+            "hunk-label",
+            String,
+            None,
+            _opt => "•"
         )
     ])
 }
@@ -32,8 +38,31 @@ pub fn make_navigate_regexp(config: &Config) -> String {
 mod tests {
     use std::fs::remove_file;
 
+    use console::strip_ansi_codes;
+
     use crate::tests::integration_test_utils::integration_test_utils;
 
+    #[test]
+    fn test_navigate_labels_files_and_hunks() {
+        let config = integration_test_utils::make_config_from_args(&["--navigate"]);
+        let output = integration_test_utils::run_delta(DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\nΔ a.py\n"));
+        assert!(output.contains("\n• def f(): │\n"));
+
+        let config = integration_test_utils::make_config_from_args(&[
+            "--hunk-label",
+            "@@",
+            "--hunk-header-format",
+            "{file}:{line}",
+            "--hunk-header-decoration-style",
+            "none",
+        ]);
+        let output = integration_test_utils::run_delta(DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\n@@ a.py:1\n"));
+    }
+
     #[test]
     fn test_navigate_with_overriden_key_in_main_section() {
         let git_config_contents = b"
@@ -148,4 +177,15 @@ mod tests {
 
         remove_file(git_config_path).unwrap();
     }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@ def f():
+ a = 1
+-b = 2
++b = 3
+";
 }
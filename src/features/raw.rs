@@ -74,6 +74,24 @@ pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
             None,
             _opt => true
         ),
+        (
+            "no-lockfile-summary",
+            bool,
+            None,
+            _opt => true
+        ),
+        (
+            "max-line-length",
+            usize,
+            None,
+            _opt => 0
+        ),
+        (
+            "no-sanitize-control-chars",
+            bool,
+            None,
+            _opt => true
+        ),
         (
             "tabs",
             usize,
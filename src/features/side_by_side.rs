@@ -1,14 +1,17 @@
+use std::io::Write;
+
 use console;
 
 use itertools::Itertools;
 use syntect::highlighting::Style as SyntectStyle;
 
+use crate::ansi;
 use crate::cli;
 use crate::config::Config;
 use crate::delta::State;
 use crate::features::line_numbers;
 use crate::features::OptionValueFunction;
-use crate::paint::Painter;
+use crate::paint::{LineAlignment, LineSections, LineWidths, Painter, StyleSections};
 use crate::style::Style;
 
 pub fn make_feature() -> Vec<(String, OptionValueFunction)> {
@@ -37,7 +40,6 @@ pub struct SideBySideData {
 
 pub struct Panel {
     pub width: usize,
-    pub offset: usize,
 }
 
 impl SideBySideData {
@@ -47,79 +49,254 @@ impl SideBySideData {
             _ => available_terminal_width / 2,
         };
         Self {
-            left_panel: Panel {
-                width: panel_width,
-                offset: 0,
-            },
-            right_panel: Panel {
-                width: panel_width,
-                offset: 0,
-            },
+            left_panel: Panel { width: panel_width },
+            right_panel: Panel { width: panel_width },
+        }
+    }
+}
+
+/// The metadata of a file in a git diff which is not displayed in its header, such as a mode
+/// change, displayed under --side-by-side as rows which span both panels.
+#[derive(Default)]
+pub struct FileMetaRows {
+    // The path of the file, until its header has been displayed
+    pub path: Option<String>,
+    old_mode: Option<String>,
+    rows: Vec<String>,
+}
+
+impl FileMetaRows {
+    pub fn start_file(&mut self, path: Option<String>) {
+        *self = Self {
+            path,
+            ..Self::default()
+        };
+    }
+
+    /// Record a file metadata line, if it is one which is displayed.
+    pub fn push_line(&mut self, line: &str, config: &Config) {
+        let arrow = if config.ascii { "->" } else { "→" };
+        if let Some(mode) = line.strip_prefix("old mode ") {
+            self.old_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("new mode ") {
+            let old_mode = self.old_mode.take().unwrap_or_default();
+            self.rows
+                .push(format!("mode {} {} {}", old_mode, arrow, mode));
+        } else if let Some(index) = line.strip_prefix("similarity index ") {
+            self.rows.push(format!("similarity {}", index));
+        } else if let Some(index) = line.strip_prefix("dissimilarity index ") {
+            self.rows.push(format!("dissimilarity {}", index));
+        } else if let Some(path) = line.strip_prefix("copy from ") {
+            self.rows.push(format!("copied from {}", path));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Write the rows, centered across both panels, in the file style.
+    pub fn write(
+        &mut self,
+        writer: &mut dyn Write,
+        side_by_side_data: &SideBySideData,
+        config: &Config,
+    ) -> std::io::Result<()> {
+        let width = side_by_side_data.left_panel.width + side_by_side_data.right_panel.width;
+        for row in self.rows.drain(..) {
+            let padding = width.saturating_sub(ansi::measure_text_width(&row));
+            writeln!(
+                writer,
+                "{}{}",
+                " ".repeat(padding / 2),
+                config.file_style.paint(row)
+            )?;
         }
+        Ok(())
     }
 }
 
-/// Emit a sequence of minus and plus lines in side-by-side mode.
+/// Emit a sequence of minus and plus lines in side-by-side mode. If a context line is given, it is
+/// displayed, dimmed, in the first empty row of each panel.
 pub fn paint_minus_and_plus_lines_side_by_side(
-    minus_syntax_style_sections: Vec<Vec<(SyntectStyle, &str)>>,
-    minus_diff_style_sections: Vec<Vec<(Style, &str)>>,
-    plus_syntax_style_sections: Vec<Vec<(SyntectStyle, &str)>>,
-    plus_diff_style_sections: Vec<Vec<(Style, &str)>>,
-    line_alignment: Vec<(Option<usize>, Option<usize>)>,
+    (mut minus_style_sections, mut plus_style_sections): (StyleSections, StyleSections),
+    line_alignment: LineAlignment,
+    context_line: Option<&str>,
     output_buffer: &mut String,
+    line_widths: &LineWidths,
     config: &Config,
     line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
-    background_color_extends_to_terminal_width: Option<bool>,
 ) {
+    let (mut left_panel_context_line, mut right_panel_context_line) = (context_line, context_line);
     for (minus_line_index, plus_line_index) in line_alignment {
+        if let (true, Some(i), Some(j)) = (
+            config.side_by_side_align_edits,
+            minus_line_index,
+            plus_line_index,
+        ) {
+            align_edits(
+                &mut minus_style_sections.syntax[i],
+                &mut minus_style_sections.diff[i],
+                &mut plus_style_sections.syntax[j],
+                &mut plus_style_sections.diff[j],
+                config,
+            );
+        }
         output_buffer.push_str(&paint_left_panel_minus_line(
             minus_line_index,
-            &minus_syntax_style_sections,
-            &minus_diff_style_sections,
-            line_numbers_data,
-            if config.keep_plus_minus_markers {
-                "-"
+            &minus_style_sections,
+            if minus_line_index.is_none() {
+                left_panel_context_line.take()
             } else {
-                ""
+                None
             },
-            background_color_extends_to_terminal_width,
+            line_numbers_data,
+            &line_widths.side_by_side_data,
             config,
         ));
         output_buffer.push_str(&paint_right_panel_plus_line(
             plus_line_index,
-            &plus_syntax_style_sections,
-            &plus_diff_style_sections,
-            line_numbers_data,
-            if config.keep_plus_minus_markers {
-                "+"
+            &plus_style_sections,
+            if plus_line_index.is_none() {
+                right_panel_context_line.take()
             } else {
-                ""
+                None
             },
-            background_color_extends_to_terminal_width,
+            line_numbers_data,
+            line_widths,
             config,
         ));
         output_buffer.push_str("\n");
     }
 }
 
+/// Under --side-by-side-align-edits, pad the shorter of each pair of changes in a pair of modified
+/// lines, so that the unchanged text which follows the changes starts at the same column in both
+/// panels. The style sections of a line are those of its syntax highlighting and of its diff.
+fn align_edits(
+    minus_syntax_sections: &mut LineSections<SyntectStyle>,
+    minus_diff_sections: &mut LineSections<Style>,
+    plus_syntax_sections: &mut LineSections<SyntectStyle>,
+    plus_diff_sections: &mut LineSections<Style>,
+    config: &Config,
+) {
+    let minus_runs = get_unchanged_runs(minus_diff_sections, config.minus_emph_style);
+    let plus_runs = get_unchanged_runs(plus_diff_sections, config.plus_emph_style);
+    if minus_runs.len() != plus_runs.len()
+        || minus_runs
+            .iter()
+            .zip(&plus_runs)
+            .any(|(minus_run, plus_run)| minus_run.text != plus_run.text)
+    {
+        return;
+    }
+    // The padding to insert, as (byte offset, width), in each line
+    let (mut minus_padding, mut plus_padding) = (Vec::new(), Vec::new());
+    let (mut minus_padded, mut plus_padded) = (0, 0);
+    for (minus_run, plus_run) in minus_runs.iter().zip(&plus_runs) {
+        let minus_column = minus_run.column + minus_padded;
+        let plus_column = plus_run.column + plus_padded;
+        if minus_column < plus_column {
+            minus_padding.push((minus_run.offset, plus_column - minus_column));
+            minus_padded += plus_column - minus_column;
+        } else if plus_column < minus_column {
+            plus_padding.push((plus_run.offset, minus_column - plus_column));
+            plus_padded += minus_column - plus_column;
+        }
+    }
+    // Padding is inserted from the end of the line, so that the offsets remain valid.
+    for (offset, width) in minus_padding.into_iter().rev() {
+        insert_padding(minus_syntax_sections, offset, width);
+        insert_padding(minus_diff_sections, offset, width);
+    }
+    for (offset, width) in plus_padding.into_iter().rev() {
+        insert_padding(plus_syntax_sections, offset, width);
+        insert_padding(plus_diff_sections, offset, width);
+    }
+}
+
+/// A run of unchanged text in a modified line
+struct UnchangedRun {
+    text: String,
+    // The byte offset and the column at which the run starts
+    offset: usize,
+    column: usize,
+}
+
+/// Return the runs of unchanged text of a line, given its diff style sections, in which the
+/// changes are displayed in `emph_style`.
+fn get_unchanged_runs(diff_sections: &[(Style, &str)], emph_style: Style) -> Vec<UnchangedRun> {
+    let mut runs: Vec<UnchangedRun> = Vec::new();
+    let (mut offset, mut column) = (0, 0);
+    let mut is_in_run = false;
+    for (style, text) in diff_sections {
+        if *style == emph_style {
+            is_in_run = false;
+        } else if is_in_run {
+            runs.last_mut().unwrap().text.push_str(text);
+        } else {
+            runs.push(UnchangedRun {
+                text: text.to_string(),
+                offset,
+                column,
+            });
+            is_in_run = true;
+        }
+        offset += text.len();
+        column += ansi::measure_text_width(text);
+    }
+    runs
+}
+
+/// Insert `width` spaces into the style sections of a line at byte offset `offset`, in the style
+/// of the text which follows them.
+fn insert_padding<T: Copy>(sections: &mut Vec<(T, &str)>, offset: usize, width: usize) {
+    const SPACES: &str = "                                                                ";
+    let mut start = 0;
+    let mut i = 0;
+    while i < sections.len() && start + sections[i].1.len() <= offset {
+        start += sections[i].1.len();
+        i += 1;
+    }
+    let style = match sections.get(i) {
+        Some((style, text)) => {
+            let (before, after) = text.split_at(offset - start);
+            let style = *style;
+            if !before.is_empty() {
+                sections[i].1 = after;
+                sections.insert(i, (style, before));
+                i += 1;
+            }
+            style
+        }
+        None => return,
+    };
+    let mut remaining = width;
+    while remaining > 0 {
+        let n = remaining.min(SPACES.len());
+        sections.insert(i, (style, &SPACES[..n]));
+        i += 1;
+        remaining -= n;
+    }
+}
+
 pub fn paint_zero_lines_side_by_side(
-    syntax_style_sections: Vec<Vec<(SyntectStyle, &str)>>,
-    diff_style_sections: Vec<Vec<(Style, &str)>>,
+    style_sections: StyleSections,
     state: &State,
     output_buffer: &mut String,
+    side_by_side_data: &SideBySideData,
     config: &Config,
     line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
     prefix: &str,
-    background_color_extends_to_terminal_width: Option<bool>,
 ) {
-    for (line_index, (syntax_sections, diff_sections)) in syntax_style_sections
+    for (line_index, sections) in style_sections
+        .syntax
         .iter()
-        .zip_eq(diff_style_sections.iter())
+        .zip_eq(style_sections.diff.iter())
         .enumerate()
     {
         let (mut left_panel_line, left_panel_line_is_empty) = Painter::paint_line(
-            syntax_sections,
-            diff_sections,
+            sections,
             state,
             line_numbers_data,
             Some(PanelSide::Left),
@@ -136,16 +313,15 @@ pub fn paint_zero_lines_side_by_side(
             &mut left_panel_line,
             left_panel_line_is_empty,
             Some(line_index),
-            &diff_style_sections,
+            &style_sections.diff,
             &State::HunkZero,
-            background_color_extends_to_terminal_width,
+            side_by_side_data.left_panel.width,
             config,
         );
         output_buffer.push_str(&left_panel_line);
 
         let (mut right_panel_line, right_panel_line_is_empty) = Painter::paint_line(
-            syntax_sections,
-            diff_sections,
+            sections,
             state,
             line_numbers_data,
             Some(PanelSide::Right),
@@ -156,9 +332,9 @@ pub fn paint_zero_lines_side_by_side(
             &mut right_panel_line,
             right_panel_line_is_empty,
             Some(line_index),
-            &diff_style_sections,
+            &style_sections.diff,
             &State::HunkZero,
-            background_color_extends_to_terminal_width,
+            side_by_side_data.right_panel.width,
             config,
         );
         output_buffer.push_str(&right_panel_line);
@@ -166,32 +342,102 @@ pub fn paint_zero_lines_side_by_side(
     }
 }
 
+/// Paint a row with a badge at the bottom of the panel, or panels, whose file has no newline at
+/// its end. The line number fields of the row are blank.
+pub fn paint_no_newline_badges(
+    minus_no_newline: bool,
+    plus_no_newline: bool,
+    output_buffer: &mut String,
+    side_by_side_data: &SideBySideData,
+    config: &Config,
+    line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
+) {
+    // The first character of a line is taken to be the +/- marker column, and is not displayed.
+    let badge = "  no newline at end of file ";
+    let syntax_sections = |has_badge: bool| match has_badge {
+        true => vec![(config.null_syntect_style, badge)],
+        false => Vec::new(),
+    };
+    let diff_sections = |has_badge: bool| match has_badge {
+        true => vec![(config.no_newline_badge_style, badge)],
+        false => Vec::new(),
+    };
+    // A panel's line number field is blank if the numbers are those of the other panel's state.
+    let (mut left_panel_line, left_panel_line_is_empty) = Painter::paint_line(
+        (
+            &syntax_sections(minus_no_newline),
+            &diff_sections(minus_no_newline),
+        ),
+        &State::HunkPlus,
+        line_numbers_data,
+        Some(PanelSide::Left),
+        "",
+        config,
+    );
+    let (mut right_panel_line, right_panel_line_is_empty) = Painter::paint_line(
+        (
+            &syntax_sections(plus_no_newline),
+            &diff_sections(plus_no_newline),
+        ),
+        &State::HunkMinus,
+        line_numbers_data,
+        Some(PanelSide::Right),
+        "",
+        config,
+    );
+    if config.line_numbers {
+        if let Some(d) = line_numbers_data.as_mut() {
+            d.hunk_minus_line_number -= 1;
+            d.hunk_plus_line_number -= 1;
+        }
+    }
+    right_pad_left_panel_line(
+        &mut left_panel_line,
+        left_panel_line_is_empty,
+        None,
+        &Vec::new(),
+        &State::HunkMinus,
+        side_by_side_data.left_panel.width,
+        config,
+    );
+    right_fill_right_panel_line(
+        &mut right_panel_line,
+        right_panel_line_is_empty,
+        None,
+        &Vec::new(),
+        &State::HunkPlus,
+        side_by_side_data.right_panel.width,
+        config,
+    );
+    output_buffer.push_str(&left_panel_line);
+    output_buffer.push_str(&right_panel_line);
+    output_buffer.push('\n');
+}
+
 fn paint_left_panel_minus_line(
     line_index: Option<usize>,
-    syntax_style_sections: &Vec<Vec<(SyntectStyle, &str)>>,
-    diff_style_sections: &Vec<Vec<(Style, &str)>>,
+    style_sections: &StyleSections,
+    context_line: Option<&str>,
     line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
-    prefix: &str,
-    background_color_extends_to_terminal_width: Option<bool>,
+    side_by_side_data: &SideBySideData,
     config: &Config,
 ) -> String {
     let (mut panel_line, panel_line_is_empty) = paint_minus_or_plus_panel_line(
         line_index,
-        &syntax_style_sections,
-        &diff_style_sections,
+        style_sections,
+        context_line,
         &State::HunkMinus,
         line_numbers_data,
         PanelSide::Left,
-        prefix,
         config,
     );
     right_pad_left_panel_line(
         &mut panel_line,
         panel_line_is_empty,
         line_index,
-        diff_style_sections,
+        &style_sections.diff,
         &State::HunkMinus,
-        background_color_extends_to_terminal_width,
+        side_by_side_data.left_panel.width,
         config,
     );
 
@@ -200,30 +446,36 @@ fn paint_left_panel_minus_line(
 
 fn paint_right_panel_plus_line(
     line_index: Option<usize>,
-    syntax_style_sections: &Vec<Vec<(SyntectStyle, &str)>>,
-    diff_style_sections: &Vec<Vec<(Style, &str)>>,
+    style_sections: &StyleSections,
+    context_line: Option<&str>,
     line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
-    prefix: &str,
-    background_color_extends_to_terminal_width: Option<bool>,
+    line_widths: &LineWidths,
     config: &Config,
 ) -> String {
     let (mut panel_line, panel_line_is_empty) = paint_minus_or_plus_panel_line(
         line_index,
-        &syntax_style_sections,
-        &diff_style_sections,
+        style_sections,
+        context_line,
         &State::HunkPlus,
         line_numbers_data,
         PanelSide::Right,
-        prefix,
         config,
     );
+    if let Some(index) = line_index {
+        Painter::mark_long_line(
+            &mut panel_line,
+            &style_sections.diff[index],
+            line_widths.line_length_limit,
+            config,
+        );
+    }
     right_fill_right_panel_line(
         &mut panel_line,
         panel_line_is_empty,
         line_index,
-        diff_style_sections,
+        &style_sections.diff,
         &State::HunkPlus,
-        background_color_extends_to_terminal_width,
+        line_widths.side_by_side_data.right_panel.width,
         config,
     );
     panel_line
@@ -234,7 +486,6 @@ fn get_right_fill_style_for_left_panel(
     line_index: Option<usize>,
     diff_style_sections: &Vec<Vec<(Style, &str)>>,
     state: &State,
-    background_color_extends_to_terminal_width: Option<bool>,
     config: &Config,
 ) -> Style {
     match (line_is_empty, line_index) {
@@ -245,7 +496,7 @@ fn get_right_fill_style_for_left_panel(
                 Painter::get_should_right_fill_background_color_and_fill_style(
                     &diff_style_sections[index],
                     state,
-                    background_color_extends_to_terminal_width,
+                    None,
                     config,
                 );
             if should_fill {
@@ -258,7 +509,8 @@ fn get_right_fill_style_for_left_panel(
 }
 
 /// Construct half of a minus or plus line under side-by-side mode, i.e. the half line that
-/// goes in one or other panel. Return a tuple `(painted_half_line, is_empty)`.
+/// goes in one or other panel. Return a tuple `(painted_half_line, is_empty)`. If there is no line
+/// for this panel, the context line is displayed in its place, dimmed, if one is given.
 // Suppose the line being displayed is a minus line with a paired plus line. Then both times
 // this function is called, `line_index` will be `Some`. This case proceeds as one would
 // expect: on the first call, we are constructing the left panel line, and we are passed
@@ -280,21 +532,37 @@ fn get_right_fill_style_for_left_panel(
 // increment the minus line number, so we need to knock that back down.
 fn paint_minus_or_plus_panel_line(
     line_index: Option<usize>,
-    syntax_style_sections: &Vec<Vec<(SyntectStyle, &str)>>,
-    diff_style_sections: &Vec<Vec<(Style, &str)>>,
+    style_sections: &StyleSections,
+    context_line: Option<&str>,
     state: &State,
     line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
     panel_side: PanelSide,
-    prefix: &str,
     config: &Config,
 ) -> (String, bool) {
-    let (empty_line_syntax_sections, empty_line_diff_sections) = (Vec::new(), Vec::new());
+    let prefix = match (state, config.keep_plus_minus_markers) {
+        (State::HunkMinus, true) => "-",
+        (State::HunkPlus, true) => "+",
+        _ => "",
+    };
+    let (empty_line_syntax_sections, empty_line_diff_sections) = match context_line {
+        Some(context_line) => (
+            vec![(config.null_syntect_style, context_line)],
+            vec![(
+                Style {
+                    ansi_term_style: ansi_term::Style::new().dimmed(),
+                    ..Style::new()
+                },
+                context_line,
+            )],
+        ),
+        None => (Vec::new(), Vec::new()),
+    };
 
     let (line_syntax_sections, line_diff_sections, state_for_line_numbers_field) =
         if let Some(index) = line_index {
             (
-                &syntax_style_sections[index],
-                &diff_style_sections[index],
+                &style_sections.syntax[index],
+                &style_sections.diff[index],
                 state,
             )
         } else {
@@ -311,8 +579,7 @@ fn paint_minus_or_plus_panel_line(
         };
 
     let (line, line_is_empty) = Painter::paint_line(
-        line_syntax_sections,
-        line_diff_sections,
+        (line_syntax_sections, line_diff_sections),
         &state_for_line_numbers_field,
         line_numbers_data,
         Some(panel_side),
@@ -346,7 +613,7 @@ fn right_pad_left_panel_line(
     line_index: Option<usize>,
     diff_style_sections: &Vec<Vec<(Style, &str)>>,
     state: &State,
-    background_color_extends_to_terminal_width: Option<bool>,
+    panel_width: usize,
     config: &Config,
 ) {
     // The left panel uses spaces to pad to the midpoint. This differs from the right panel,
@@ -367,15 +634,13 @@ fn right_pad_left_panel_line(
         };
     };
     // Pad with (maybe painted) spaces to the panel width.
-    let text_width = console::measure_text_width(&panel_line);
-    let panel_width = config.side_by_side_data.left_panel.width;
+    let text_width = ansi::measure_text_width(&panel_line);
     if text_width < panel_width {
         let fill_style = get_right_fill_style_for_left_panel(
             panel_line_is_empty,
             line_index,
             &diff_style_sections,
             state,
-            background_color_extends_to_terminal_width,
             config,
         );
         panel_line.push_str(
@@ -399,15 +664,11 @@ fn right_fill_right_panel_line(
     line_index: Option<usize>,
     diff_style_sections: &Vec<Vec<(Style, &str)>>,
     state: &State,
-    background_color_extends_to_terminal_width: Option<bool>,
+    panel_width: usize,
     config: &Config,
 ) {
-    *panel_line = console::truncate_str(
-        &panel_line,
-        config.side_by_side_data.right_panel.width,
-        &config.truncation_symbol,
-    )
-    .to_string();
+    *panel_line =
+        console::truncate_str(&panel_line, panel_width, &config.truncation_symbol).to_string();
 
     // Unlike `right_pad_left_panel_line`, the line-end emissions here are basically the same as
     // the non side-by-side implementation in Painter::paint_lines.
@@ -415,7 +676,7 @@ fn right_fill_right_panel_line(
         Painter::get_should_right_fill_background_color_and_fill_style(
             &diff_style_sections[index],
             state,
-            background_color_extends_to_terminal_width,
+            None,
             config,
         )
     } else {
@@ -443,11 +704,43 @@ fn right_fill_right_panel_line(
 pub mod tests {
     use console::strip_ansi_codes;
 
+    use super::SideBySideData;
+    use crate::cli;
     use crate::features::line_numbers::tests::*;
+    use crate::paint::Painter;
+    use crate::style::Style;
     use crate::tests::integration_test_utils::integration_test_utils::{
         make_config_from_args, run_delta,
     };
 
+    #[test]
+    fn test_panel_widths_follow_terminal_width() {
+        let config = make_config_from_args(&["--side-by-side"]);
+        let mut writer = Vec::new();
+        let mut painter = Painter::new(&mut writer, &config);
+        let terminal_width = painter.available_terminal_width;
+        // Simulate a resize of the terminal since the painter was made.
+        painter.available_terminal_width = terminal_width + 10;
+        painter.line_widths.side_by_side_data = SideBySideData::new(&cli::Width::Fixed(2), &2);
+        painter.update_terminal_width();
+        assert_eq!(painter.available_terminal_width, terminal_width);
+        assert_eq!(
+            painter.line_widths.side_by_side_data.left_panel.width,
+            terminal_width / 2
+        );
+        assert_eq!(
+            painter.line_widths.side_by_side_data.right_panel.width,
+            terminal_width / 2
+        );
+
+        let config = make_config_from_args(&["--side-by-side", "--width", "40"]);
+        let mut writer = Vec::new();
+        let mut painter = Painter::new(&mut writer, &config);
+        painter.available_terminal_width = terminal_width + 10;
+        painter.update_terminal_width();
+        assert_eq!(painter.line_widths.side_by_side_data.left_panel.width, 20);
+    }
+
     #[test]
     fn test_two_minus_lines() {
         let config = make_config_from_args(&["--side-by-side", "--width", "40"]);
@@ -468,6 +761,41 @@ pub mod tests {
         assert_eq!("│    │              │ 2  │b = 2", strip_ansi_codes(line_2));
     }
 
+    #[test]
+    fn test_side_by_side_context_fill() {
+        let config = make_config_from_args(&[
+            "--side-by-side",
+            "--side-by-side-context-fill",
+            "--width",
+            "40",
+        ]);
+        let output = run_delta(CONTEXT_FILL_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        let mut lines = output.lines().skip(4);
+        assert_eq!("│ 1  │a = 1         │ 1  │a = 1", lines.next().unwrap());
+        assert_eq!("│    │a = 1         │ 2  │b = 2", lines.next().unwrap());
+        assert_eq!("│    │              │ 3  │c = 3", lines.next().unwrap());
+        assert_eq!("│ 2  │d = 4         │ 4  │d = 4", lines.next().unwrap());
+        assert_eq!("│ 3  │e = 5         │    │d = 4", lines.next().unwrap());
+    }
+
+    #[test]
+    fn test_side_by_side_file_meta_rows() {
+        let config = make_config_from_args(&["--side-by-side", "--width", "40"]);
+        let output = run_delta(FILE_META_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[1], "s.sh");
+        assert_eq!(lines[3], "          mode 100644 → 100755");
+        assert_eq!(lines[5], "renamed: x.txt ⟶   y.txt");
+        assert_eq!(lines[7], "             similarity 83%");
+
+        let config = make_config_from_args(&["--width", "40"]);
+        let output = strip_ansi_codes(&run_delta(FILE_META_DIFF, &config)).to_string();
+        assert!(!output.contains("mode 100644"));
+        assert!(!output.contains("similarity"));
+    }
+
     #[test]
     fn test_one_minus_one_plus_line() {
         let config = make_config_from_args(&["--side-by-side", "--width", "40"]);
@@ -477,4 +805,184 @@ pub mod tests {
         assert_eq!("│ 1  │a = 1         │ 1  │a = 1", lines.next().unwrap());
         assert_eq!("│ 2  │b = 2         │ 2  │bb = 2", lines.next().unwrap());
     }
+
+    #[test]
+    fn test_side_by_side_no_newline_badges() {
+        let config = make_config_from_args(&["--side-by-side", "--width", "80"]);
+        let output = run_delta(NO_NEWLINE_AT_END_OF_FILE_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(!output.contains("No newline at end of file"));
+        let mut lines = output.lines().skip(4);
+        assert_eq!(
+            "│ 1  │x                                 │ 1  │x",
+            lines.next().unwrap()
+        );
+        assert_eq!(
+            "│ 2  │y                                 │ 2  │y",
+            lines.next().unwrap()
+        );
+        assert_eq!(
+            "│    │                                  │ 3  │z",
+            lines.next().unwrap()
+        );
+        assert_eq!(
+            "│    │ no newline at end of file        │    │",
+            lines.next().unwrap()
+        );
+        let mut lines = lines.skip(4);
+        assert_eq!(
+            "│ 2  │q                                 │    │",
+            lines.next().unwrap()
+        );
+        assert_eq!(
+            "│    │                                  │ 3  │r",
+            lines.next().unwrap()
+        );
+        assert_eq!(
+            "│ 3  │s                                 │ 4  │s",
+            lines.next().unwrap()
+        );
+        assert_eq!(
+            "│    │ no newline at end of file        │    │ no newline at end of file ",
+            lines.next().unwrap()
+        );
+    }
+
+    const NO_NEWLINE_AT_END_OF_FILE_DIFF: &str = "\
+diff --git a/a b/a
+index 223ca50..e69de29 100644
+--- a/a
++++ b/a
+@@ -1,2 +1,3 @@
+ x
+-y
+\\ No newline at end of file
++y
++z
+diff --git a/b b/b
+index 223ca50..e69de29 100644
+--- a/b
++++ b/b
+@@ -2,2 +3,2 @@
+-q
++r
+ s
+\\ No newline at end of file
+";
+
+    #[test]
+    fn test_side_by_side_align_edits() {
+        let config = make_config_from_args(&[
+            "--side-by-side",
+            "--side-by-side-align-edits",
+            "--width",
+            "50",
+        ]);
+        let output = run_delta(ONE_MINUS_ONE_PLUS_LINE_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        let mut lines = output.lines().skip(4);
+        assert_eq!(
+            "│ 1  │a = 1              │ 1  │a = 1",
+            lines.next().unwrap()
+        );
+        assert_eq!(
+            "│ 2  │b  = 2             │ 2  │bb = 2",
+            lines.next().unwrap()
+        );
+
+        let diff = ONE_MINUS_ONE_PLUS_LINE_DIFF.replace("+bb = 2", "+f(b, c) = 2");
+        let diff = diff.replace("-b = 2", "-f(alpha, c) = 2");
+        let output = strip_ansi_codes(&run_delta(&diff, &config)).to_string();
+        assert!(output.contains("│ 2  │f(alpha, c) = 2    │ 2  │f(b    , c) = 2"));
+    }
+
+    #[test]
+    fn test_side_by_side_non_emph_styles() {
+        let args = [
+            "--minus-non-emph-style",
+            "red",
+            "--side-by-side-plus-non-emph-style",
+            "dim green",
+        ];
+        let config = make_config_from_args(&args);
+        assert_eq!(
+            config.minus_non_emph_style,
+            Style::from_str("red", None, None, true, false)
+        );
+        assert_eq!(config.plus_non_emph_style, config.plus_style);
+
+        let config = make_config_from_args(&[&args[..], &["--side-by-side"]].concat());
+        assert_eq!(
+            config.minus_non_emph_style,
+            Style::from_str("red", None, None, true, false)
+        );
+        let dim_green = Style::from_str("dim green", None, None, true, false);
+        assert_eq!(config.plus_non_emph_style, dim_green);
+        let output = run_delta(ONE_MINUS_ONE_PLUS_LINE_DIFF, &config);
+        assert!(output.contains(&dim_green.paint(" = 2").to_string()));
+    }
+
+    #[test]
+    fn test_line_number_separator_with_emoji_modifier() {
+        let config = make_config_from_args(&[
+            "--side-by-side",
+            "--width",
+            "40",
+            "--line-numbers-left-format",
+            "{nm:^4}👍🏽",
+            "--line-numbers-right-format",
+            "{np:^4}👍🏽",
+        ]);
+        let output = run_delta(ONE_MINUS_ONE_PLUS_LINE_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        let mut lines = output.lines().skip(4);
+        assert_eq!(" 1  👍🏽a = 1          1  👍🏽a = 1", lines.next().unwrap());
+        assert_eq!(" 2  👍🏽b = 2          2  👍🏽bb = 2", lines.next().unwrap());
+    }
+
+    #[test]
+    fn test_side_by_side_is_disabled_for_huge_hunks() {
+        let config = make_config_from_args(&[
+            "--side-by-side",
+            "--width",
+            "40",
+            "--auto-degrade-hunk-lines",
+            "1",
+        ]);
+        let output = run_delta(ONE_MINUS_ONE_PLUS_LINE_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        let mut lines = output.lines().skip(4);
+        // The limit is exceeded at the second line of the hunk.
+        assert_eq!("│ 1  │a = 1         │ 1  │a = 1", lines.next().unwrap());
+        assert_eq!("│ 2  ││    │b = 2", lines.next().unwrap());
+        assert_eq!("│    ││ 2  │bb = 2", lines.next().unwrap());
+    }
+
+    const FILE_META_DIFF: &str = "\
+diff --git a/s.sh b/s.sh
+old mode 100644
+new mode 100755
+diff --git a/x.txt b/y.txt
+similarity index 83%
+rename from x.txt
+rename to y.txt
+index 0fdf397..e0318ee 100644
+--- a/x.txt
++++ b/y.txt
+@@ -5,2 +5,2 @@ b
+ e
+-f
++F
+";
+
+    const CONTEXT_FILL_DIFF: &str = "\
+--- a/a.py
++++ b/a.py
+@@ -1,3 +1,4 @@
+ a = 1
++b = 2
++c = 3
+ d = 4
+-e = 5
+";
 }
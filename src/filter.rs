@@ -0,0 +1,1399 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::BufRead;
+
+use bytelines::ByteLines;
+use regex::Regex;
+
+use crate::ansi;
+use crate::config::Config;
+use crate::hunk_badges;
+use crate::hunk_language;
+use crate::invert;
+use crate::lockfile;
+use crate::parse::{self, LinePrefix};
+use crate::pre_render::{self, PreRenderFilter};
+use crate::rej;
+use crate::structured_diff;
+
+/// A selection of a single file, or a single hunk of a file, as given to --select: "PATH" or
+/// "PATH:N", where N counts hunks from 1, or "F" or "F.N", where F is the number of the file in
+/// the input, as displayed by --numbered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selection {
+    pub path: String,
+    pub file_number: Option<usize>,
+    pub hunk: Option<usize>,
+}
+
+impl Selection {
+    pub fn from_str(s: &str) -> Self {
+        if let Some((file_number, hunk)) = parse_file_and_hunk_number(s) {
+            return Self {
+                path: "".to_string(),
+                file_number: Some(file_number),
+                hunk,
+            };
+        }
+        let path_selection = |path: &str, hunk| Self {
+            path: path.to_string(),
+            file_number: None,
+            hunk,
+        };
+        match s.rsplit_once(':') {
+            Some((path, hunk)) if !path.is_empty() => match hunk.parse::<usize>() {
+                Ok(hunk) => path_selection(path, Some(hunk)),
+                Err(_) => path_selection(s, None),
+            },
+            _ => path_selection(s, None),
+        }
+    }
+}
+
+/// Parse "F" or "F.N", where F and N are numbers.
+fn parse_file_and_hunk_number(s: &str) -> Option<(usize, Option<usize>)> {
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    match s.split_once('.') {
+        Some((file, hunk)) if is_number(file) && is_number(hunk) => {
+            Some((file.parse().ok()?, Some(hunk.parse().ok()?)))
+        }
+        None if is_number(s) => Some((s.parse().ok()?, None)),
+        _ => None,
+    }
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.file_number, self.hunk) {
+            (Some(file_number), Some(hunk)) => write!(f, "{}.{}", file_number, hunk),
+            (Some(file_number), None) => write!(f, "{}", file_number),
+            (None, Some(hunk)) => write!(f, "{}:{}", self.path, hunk),
+            (None, None) => write!(f, "{}", self.path),
+        }
+    }
+}
+
+/// A glob pattern matched against file paths, as given to --include-path and --exclude-path.
+#[derive(Clone, Debug)]
+pub struct PathGlob {
+    regex: Regex,
+}
+
+impl PathGlob {
+    pub fn new(glob: &str) -> Self {
+        // As in .gitignore, a pattern without a slash (other than a trailing slash) matches at any
+        // depth, and a pattern which matches a directory matches everything beneath it.
+        let (glob, is_directory) = match glob.strip_suffix('/') {
+            Some(glob) => (glob, true),
+            None => (glob, false),
+        };
+        let mut pattern = "^".to_string();
+        if !glob.contains('/') {
+            pattern.push_str("(?:.*/)?");
+        }
+        let mut chars = glob.trim_start_matches('/').chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        pattern.push_str("(?:.*/)?");
+                    } else {
+                        pattern.push_str(".*");
+                    }
+                }
+                '*' => pattern.push_str("[^/]*"),
+                '?' => pattern.push_str("[^/]"),
+                c => pattern.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        pattern.push_str(if is_directory { "/.*$" } else { "(?:/.*)?$" });
+        Self {
+            regex: Regex::new(&pattern).unwrap(),
+        }
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        self.regex.is_match(path)
+    }
+}
+
+/// A line of input to be rendered, a notice to be written in place of input which is not
+/// displayed, or the badges of the next hunk.
+pub enum FilteredLine<'a> {
+    Line(Cow<'a, [u8]>),
+    Notice(String),
+    // The --hunk-badges of the hunk whose header follows
+    HunkBadges(String),
+    // The language in which the hunk whose header follows is to be highlighted
+    HunkLanguage(String),
+    // A context line added to a hunk by --expand-context
+    ExpandedContextLine(Vec<u8>),
+    // A context line beyond --distant-context
+    DistantContextLine(Vec<u8>),
+    // The numbers of lines added to and removed from the file whose metadata follows, under
+    // --file-stats
+    FileStats(usize, usize),
+}
+
+/// Filters the input, file by file and hunk by hunk, before it is rendered. When no filtering has
+/// been requested, lines are passed through without being copied or examined.
+pub struct InputFilter<'a, I>
+where
+    I: BufRead,
+{
+    lines: ByteLines<I>,
+    config: &'a Config,
+    is_active: bool,
+    // Lines which have been filtered and are ready to be rendered
+    queue: VecDeque<FilteredLine<'static>>,
+    at_eof: bool,
+    // The file metadata lines of the current file, held back until it is known whether any part of
+    // the file will be rendered.
+    file_meta: Vec<Vec<u8>>,
+    file_meta_released: bool,
+    in_file: bool,
+    is_git_diff: bool,
+    minus_file: String,
+    plus_file: String,
+    // The number of files in the input so far, counting from 1
+    pub n_files: usize,
+    n_hunks: usize,
+    hunk: Vec<Vec<u8>>,
+    // The hunk lines with ANSI escape sequences removed
+    hunk_text: Vec<String>,
+    hunk_line_counter: Option<HunkLineCounter>,
+    // The output for the current file, held back until it is known whether the file is hidden by
+    // --hide-files-matching
+    file_output: Vec<FilteredLine<'static>>,
+    // The --hide-files-matching regex which matched a line of the current file
+    file_hidden_by: Option<&'a Regex>,
+    // Whether any part of the input was chosen by --select
+    pub is_selection_found: bool,
+    // The files not displayed due to --include-path and --exclude-path
+    skipped_paths: Vec<String>,
+    pre_render_filter: PreRenderFilter,
+    // The hunks of the current file, if it is to be passed through a --pre-render-filter command
+    // or compared as structured data
+    pre_render_hunks: Vec<(Vec<u8>, String)>,
+    // The output for each hunk of the current file under --reverse-hunks, released in reverse
+    // order when the file ends
+    reversed_hunks: Vec<Vec<FilteredLine<'static>>>,
+    // The numbers of lines added to and removed from the current file, released with its
+    // metadata under --file-stats
+    file_stats: Option<(usize, usize)>,
+}
+
+impl<'a, I> InputFilter<'a, I>
+where
+    I: BufRead,
+{
+    pub fn new(lines: ByteLines<I>, config: &'a Config) -> Self {
+        Self {
+            lines,
+            config,
+            is_active: config.select.is_some()
+                || !config.include_path.is_empty()
+                || !config.exclude_path.is_empty()
+                || !config.hide_hunks_matching.is_empty()
+                || !config.hide_files_matching.is_empty()
+                || config.max_hunk_lines > 0
+                || config.reverse_hunks
+                || config.invert
+                || !config.hunk_badge_rules.is_empty()
+                || config.hunk_lang_directives
+                || config.collapse_noise_hunks
+                || config.expand_context > 0
+                || config.distant_context > 0
+                || !config.hunk_language_rules.is_empty()
+                || !config.pre_render_filters.is_empty()
+                || config.structured_diff
+                || config.notebook_diff
+                || config.lockfile_summary
+                || config.file_stats,
+            queue: VecDeque::new(),
+            at_eof: false,
+            file_meta: Vec::new(),
+            file_meta_released: false,
+            in_file: false,
+            is_git_diff: false,
+            minus_file: "".to_string(),
+            plus_file: "".to_string(),
+            n_files: 0,
+            n_hunks: 0,
+            hunk: Vec::new(),
+            hunk_text: Vec::new(),
+            hunk_line_counter: None,
+            file_output: Vec::new(),
+            file_hidden_by: None,
+            is_selection_found: false,
+            skipped_paths: Vec::new(),
+            pre_render_filter: PreRenderFilter::default(),
+            pre_render_hunks: Vec::new(),
+            reversed_hunks: Vec::new(),
+            file_stats: None,
+        }
+    }
+
+    /// Return the next line to be rendered.
+    pub fn next_line(&mut self) -> Option<FilteredLine<'_>> {
+        if !self.is_active {
+            if let Some(line) = self.queue.pop_front() {
+                return Some(line);
+            }
+            return match self.lines.next() {
+                Some(Ok(line)) => Some(FilteredLine::Line(Cow::Borrowed(line))),
+                _ => None,
+            };
+        }
+        while self.queue.is_empty() && !self.at_eof {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line.to_vec(),
+                _ => {
+                    self.at_eof = true;
+                    self.end_hunk();
+                    self.end_file();
+                    self.push_skipped_paths_notice();
+                    break;
+                }
+            };
+            self.process_line(line);
+        }
+        self.queue.pop_front()
+    }
+
+    fn process_line(&mut self, raw_line: Vec<u8>) {
+        let line = if raw_line.contains(&0x1b) {
+            ansi::strip_ansi_codes(&String::from_utf8_lossy(&raw_line)).to_string()
+        } else {
+            String::from_utf8_lossy(&raw_line).to_string()
+        };
+        if let Some(counter) = self.hunk_line_counter.as_mut() {
+            if counter.accepts(&line) {
+                self.hunk.push(raw_line);
+                self.hunk_text.push(line);
+                return;
+            }
+            self.end_hunk();
+        }
+        match parse::classify_line_prefix(line.as_bytes()) {
+            LinePrefix::Commit => {
+                self.end_file();
+                self.release_other_line(raw_line);
+            }
+            LinePrefix::Diff => {
+                self.end_file();
+                self.start_file(line.starts_with("diff --git "));
+                if let Some(path) = rej::parse_rejected_hunks_line(&line) {
+                    self.minus_file = parse::rewrite_file_path(path.to_string(), self.config);
+                    self.plus_file = self.minus_file.clone();
+                }
+                self.file_meta.push(raw_line);
+            }
+            prefix @ LinePrefix::MinusFile | prefix @ LinePrefix::PlusFile => {
+                if !self.in_file || self.n_hunks > 0 {
+                    // A diff -u file section, which has no "diff" line
+                    self.end_file();
+                    self.start_file(false);
+                }
+                let path = parse::rewrite_file_path(
+                    parse::get_file_path_from_file_meta_line(
+                        &line,
+                        self.is_git_diff,
+                        self.config.get_diff_prefixes_to_remove(),
+                    ),
+                    self.config,
+                );
+                if prefix == LinePrefix::MinusFile {
+                    self.minus_file = path;
+                } else {
+                    self.plus_file = path;
+                }
+                self.file_meta.push(raw_line);
+            }
+            LinePrefix::HunkHeader if self.in_file => {
+                let line_numbers = match parse::try_parse_hunk_header(&line) {
+                    Some((_, line_numbers)) => line_numbers,
+                    None => {
+                        // The input is corrupt: pass it on as it is, so that it is displayed
+                        // unaltered when processing fails.
+                        self.end_file();
+                        self.release_lines(vec![raw_line]);
+                        self.is_active = false;
+                        return;
+                    }
+                };
+                self.n_hunks += 1;
+                self.hunk.push(raw_line);
+                self.hunk_text.push(line);
+                self.hunk_line_counter = Some(HunkLineCounter::new(&line_numbers));
+            }
+            _ if self.in_file && self.n_hunks == 0 => self.file_meta.push(raw_line),
+            _ => self.release_other_line(raw_line),
+        }
+    }
+
+    fn start_file(&mut self, is_git_diff: bool) {
+        self.in_file = true;
+        self.is_git_diff = is_git_diff;
+        self.file_meta_released = false;
+        self.minus_file.clear();
+        self.plus_file.clear();
+        self.n_files += 1;
+        self.n_hunks = 0;
+        self.file_stats = None;
+    }
+
+    /// Decide the fate of a completed hunk.
+    fn end_hunk(&mut self) {
+        if self.hunk_line_counter.take().is_none() {
+            return;
+        }
+        let hunk = std::mem::take(&mut self.hunk);
+        let hunk_text = std::mem::take(&mut self.hunk_text);
+        if PreRenderFilter::get_command(self.file_path(), self.config).is_some()
+            || structured_diff::get_format(self.file_path(), self.config).is_some()
+            || lockfile::get_format(self.file_path(), self.config).is_some()
+            || self.config.expand_context > 0
+            || self.config.file_stats
+        {
+            // The hunks are replaced, or counted, when the file ends.
+            self.pre_render_hunks
+                .extend(hunk.into_iter().zip(hunk_text));
+            return;
+        }
+        self.handle_hunk(hunk, hunk_text, (0, 0));
+    }
+
+    /// Replace the hunks of the current file by a diff of the two versions of the file after they
+    /// have been passed through its --pre-render-filter command, or, under --structured-diff,
+    /// flattened as data. If the versions of the file cannot be read, or the command or parsing
+    /// fails, the original hunks are used. The hunks of a lockfile are replaced by a summary of its
+    /// package version changes, if any packages can be found in them. The context of the hunks is
+    /// then extended under --expand-context.
+    fn apply_pre_render_filter(&mut self) {
+        let lines = std::mem::take(&mut self.pre_render_hunks);
+        if lines.is_empty() {
+            return;
+        }
+        if self.config.file_stats {
+            self.file_stats = Some(count_added_and_removed_lines(
+                lines.iter().map(|(_, line)| line.as_str()),
+            ));
+        }
+        let file_meta: Vec<String> = self
+            .file_meta
+            .iter()
+            .map(|line| ansi::strip_ansi_codes(&String::from_utf8_lossy(line)).to_string())
+            .collect();
+        let (filtered_lines, no_differences_notice, notice) = if let Some(command) =
+            PreRenderFilter::get_command(self.file_path(), self.config)
+        {
+            (
+                self.pre_render_filter.make_hunks(
+                    command,
+                    &self.minus_file,
+                    &self.plus_file,
+                    &file_meta,
+                    self.is_git_diff,
+                    self.config.diff_context_lines,
+                ),
+                format!("no differences after filtering through '{}'", command),
+                format!("compared after filtering through '{}'", command),
+            )
+        } else if let Some(format) = structured_diff::get_format(self.file_path(), self.config) {
+            (
+                structured_diff::make_hunks(
+                    format,
+                    &self.minus_file,
+                    &self.plus_file,
+                    &file_meta,
+                    self.is_git_diff,
+                    self.config.diff_context_lines,
+                ),
+                format!("no differences in the {}", format.description()),
+                format!("compared as {}", format.description()),
+            )
+        } else if let Some(format) = lockfile::get_format(self.file_path(), self.config) {
+            let hunk_text: Vec<String> = lines.iter().map(|(_, line)| line.clone()).collect();
+            if let Some(summary) =
+                lockfile::summarize(format, self.file_path(), &hunk_text, self.config)
+            {
+                if self.is_selected(None) {
+                    self.release_file_meta();
+                    for line in summary {
+                        self.release(FilteredLine::Notice(line));
+                    }
+                }
+                return;
+            }
+            (None, String::new(), String::new())
+        } else {
+            (None, String::new(), String::new())
+        };
+        let lines = match filtered_lines {
+            Some(filtered_lines) => {
+                if self.is_selected(None) {
+                    self.release_file_meta();
+                    self.release(FilteredLine::Notice(format!(
+                        "{}: {}",
+                        self.file_path(),
+                        if filtered_lines.is_empty() {
+                            no_differences_notice
+                        } else {
+                            notice
+                        }
+                    )));
+                }
+                filtered_lines
+                    .into_iter()
+                    .map(|line| (line.clone().into_bytes(), line))
+                    .collect()
+            }
+            None => lines,
+        };
+        let mut hunks: Vec<(Vec<Vec<u8>>, Vec<String>)> = Vec::new();
+        for (raw_line, line) in lines {
+            if line.starts_with("@@") || hunks.is_empty() {
+                hunks.push((Vec::new(), Vec::new()));
+            }
+            let (hunk, hunk_text) = hunks.last_mut().unwrap();
+            hunk.push(raw_line);
+            hunk_text.push(line);
+        }
+        let expansions = if self.config.expand_context > 0 {
+            self.expand_context(&mut hunks)
+        } else {
+            vec![(0, 0); hunks.len()]
+        };
+        self.n_hunks = 0;
+        for ((hunk, hunk_text), expansion) in hunks.into_iter().zip(expansions) {
+            self.n_hunks += 1;
+            self.handle_hunk(hunk, hunk_text, expansion);
+        }
+    }
+
+    /// Extend the context of the hunks of the current file to --expand-context lines before and
+    /// after their changes, with lines of the new version of the file, without overlapping the
+    /// neighboring hunks. A hunk whose lines do not match the file is left as it is. Return the
+    /// numbers of lines added before and after each hunk.
+    fn expand_context(&self, hunks: &mut [(Vec<Vec<u8>>, Vec<String>)]) -> Vec<(usize, usize)> {
+        let mut expansions = vec![(0, 0); hunks.len()];
+        let plus_blob = self
+            .file_meta
+            .iter()
+            .map(|line| ansi::strip_ansi_codes(&String::from_utf8_lossy(line)).to_string())
+            .find_map(|line| pre_render::parse_index_line(&line).map(|(_, blob)| blob.to_string()));
+        let contents = match pre_render::read_file_version(
+            &self.plus_file,
+            plus_blob.as_deref(),
+            self.is_git_diff,
+        ) {
+            Some(contents) => contents,
+            None => return expansions,
+        };
+        let contents = String::from_utf8_lossy(&contents);
+        let file_lines: Vec<&str> = contents.lines().collect();
+        // The first and last lines of the new version of the file in each hunk
+        let get_plus_range = |hunk_text: &[String]| -> Option<(usize, usize, usize, usize)> {
+            match parse::try_parse_hunk_header(&hunk_text[0])?.1[..] {
+                [(minus_start, minus_count), (plus_start, plus_count)] => {
+                    let first = |start, count| if count == 0 { start + 1 } else { start };
+                    Some((
+                        first(minus_start, minus_count),
+                        minus_count,
+                        first(plus_start, plus_count),
+                        plus_count,
+                    ))
+                }
+                _ => None,
+            }
+        };
+        let n_context = self.config.expand_context;
+        let mut previous_end = 0;
+        for i in 0..hunks.len() {
+            let next_start = hunks
+                .get(i + 1)
+                .and_then(|(_, hunk_text)| get_plus_range(hunk_text))
+                .map_or(file_lines.len() + 1, |(_, _, plus_first, _)| plus_first);
+            let (hunk, hunk_text) = &mut hunks[i];
+            let (minus_first, minus_count, plus_first, plus_count) = match get_plus_range(hunk_text)
+            {
+                Some(range) if range.0 > 0 && range.2 > 0 => range,
+                _ => continue,
+            };
+            let plus_last = plus_first + plus_count - 1;
+            let plus_lines = hunk_text[1..]
+                .iter()
+                .filter(|line| line.starts_with(' ') || line.starts_with('+'))
+                .map(|line| &line[1..]);
+            if plus_last > file_lines.len()
+                || !plus_lines
+                    .zip(&file_lines[plus_first - 1..plus_last])
+                    .all(|(line, file_line)| line == *file_line)
+            {
+                previous_end = plus_last;
+                continue;
+            }
+            let count_context = |lines: &mut dyn Iterator<Item = &String>| {
+                lines.take_while(|line| line.starts_with(' ')).count()
+            };
+            let n_before = n_context
+                .saturating_sub(count_context(&mut hunk_text[1..].iter()))
+                .min((plus_first - 1).saturating_sub(previous_end))
+                .min(minus_first - 1);
+            let n_after = n_context
+                .saturating_sub(count_context(&mut hunk_text[1..].iter().rev()))
+                .min(next_start.saturating_sub(plus_last + 1));
+            previous_end = plus_last + n_after;
+            if n_before == 0 && n_after == 0 {
+                continue;
+            }
+            let code_fragment = hunk_text[0]
+                .splitn(3, "@@")
+                .nth(2)
+                .unwrap_or("")
+                .to_string();
+            let header = format!(
+                "@@ -{},{} +{},{} @@{}",
+                minus_first - n_before,
+                minus_count + n_before + n_after,
+                plus_first - n_before,
+                plus_count + n_before + n_after,
+                code_fragment
+            );
+            let context_line = |line: &&str| format!(" {}", line);
+            let before = file_lines[plus_first - 1 - n_before..plus_first - 1]
+                .iter()
+                .map(context_line);
+            let after = file_lines[plus_last..plus_last + n_after]
+                .iter()
+                .map(context_line);
+            let lines: Vec<String> = std::iter::once(header)
+                .chain(before)
+                .chain(hunk_text.drain(1..))
+                .chain(after)
+                .collect();
+            let raw_lines: Vec<Vec<u8>> = std::iter::once(lines[0].clone().into_bytes())
+                .chain(
+                    lines[1..=n_before]
+                        .iter()
+                        .map(|line| line.clone().into_bytes()),
+                )
+                .chain(hunk.drain(1..))
+                .chain(
+                    lines[lines.len() - n_after..]
+                        .iter()
+                        .map(|line| line.clone().into_bytes()),
+                )
+                .collect();
+            *hunk = raw_lines;
+            *hunk_text = lines;
+            expansions[i] = (n_before, n_after);
+        }
+        expansions
+    }
+
+    /// Decide the fate of a hunk, whose first `expansion.0` and last `expansion.1` lines (after
+    /// the hunk header) were added by --expand-context.
+    fn handle_hunk(
+        &mut self,
+        hunk: Vec<Vec<u8>>,
+        hunk_text: Vec<String>,
+        expansion: (usize, usize),
+    ) {
+        let (hunk, hunk_text) = if self.config.invert {
+            let hunk_text = invert::invert_hunk(&hunk_text);
+            let hunk = hunk_text
+                .iter()
+                .map(|line| line.clone().into_bytes())
+                .collect();
+            (hunk, hunk_text)
+        } else {
+            (hunk, hunk_text)
+        };
+        if self.file_hidden_by.is_none() {
+            let config = self.config;
+            self.file_hidden_by = config.hide_files_matching.iter().find(|regex| {
+                hunk_text[1..]
+                    .iter()
+                    .any(|line| regex.is_match(line.get(1..).unwrap_or("")))
+            });
+        }
+        if !self.is_selected(Some(self.n_hunks)) {
+            return;
+        }
+        self.release_file_meta();
+        let hunk_lines = &hunk_text[1..];
+        let output = if let Some(regex) = self.get_hunk_hidden_by(hunk_lines) {
+            vec![FilteredLine::Notice(format!(
+                "hunk {} hidden (--hide-hunks-matching '{}')",
+                self.n_hunks, regex
+            ))]
+        } else if let Some(badge) = self.get_collapsing_noise_badge(hunk_lines) {
+            vec![FilteredLine::Notice(format!(
+                "hunk {} collapsed [{}] (--select {}:{} to display it)",
+                self.n_hunks,
+                badge,
+                self.file_path(),
+                self.n_hunks
+            ))]
+        } else if let Some((n_added, n_removed)) = self.get_hunk_summary_counts(hunk_lines) {
+            vec![FilteredLine::Notice(format!(
+                "+{} {}{} lines in {} {}",
+                n_added,
+                if self.config.ascii { "-" } else { "−" },
+                n_removed,
+                self.file_path(),
+                get_hunk_header_ranges(&hunk_text[0])
+            ))]
+        } else {
+            let hunk_output = self.make_hunk_output(hunk, &hunk_text, expansion);
+            hunk_badges::get_hunk_badges(hunk_lines, &self.config.hunk_badge_rules)
+                .map(FilteredLine::HunkBadges)
+                .into_iter()
+                .chain(
+                    hunk_language::get_hunk_language(hunk_lines, self.config)
+                        .map(FilteredLine::HunkLanguage),
+                )
+                .chain(hunk_output)
+                .collect()
+        };
+        if self.config.reverse_hunks {
+            self.reversed_hunks.push(output);
+        } else {
+            output.into_iter().for_each(|line| self.release(line));
+        }
+    }
+
+    /// Return the numbers of added and removed lines of a hunk, if there are more than
+    /// --max-hunk-lines of them, so that it is displayed as a summary.
+    fn get_hunk_summary_counts(&self, hunk_lines: &[String]) -> Option<(usize, usize)> {
+        if self.config.max_hunk_lines == 0 {
+            return None;
+        }
+        let (n_added, n_removed) =
+            count_added_and_removed_lines(hunk_lines.iter().map(String::as_str));
+        if n_added + n_removed > self.config.max_hunk_lines {
+            Some((n_added, n_removed))
+        } else {
+            None
+        }
+    }
+
+    /// Return the lines of a hunk to be rendered, in which the context lines added by
+    /// --expand-context are marked, and those beyond --distant-context are marked or collapsed.
+    fn make_hunk_output(
+        &self,
+        hunk: Vec<Vec<u8>>,
+        hunk_text: &[String],
+        expansion: (usize, usize),
+    ) -> Vec<FilteredLine<'static>> {
+        let n_lines = hunk.len();
+        let is_at_ends = |i: usize, (n_before, n_after): (usize, usize)| {
+            i > 0 && (i <= n_before || i + n_after >= n_lines)
+        };
+        let distant = self.get_distant_context(hunk_text);
+        let (n_distant_before, n_distant_after) = distant;
+        let mark = |(i, line)| {
+            if is_at_ends(i, expansion) {
+                FilteredLine::ExpandedContextLine(line)
+            } else {
+                FilteredLine::Line(Cow::Owned(line))
+            }
+        };
+        if distant == (0, 0) {
+            return hunk.into_iter().enumerate().map(mark).collect();
+        }
+        if !self.config.collapse_distant_context {
+            return hunk
+                .into_iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    if is_at_ends(i, distant) {
+                        FilteredLine::DistantContextLine(line)
+                    } else {
+                        mark((i, line))
+                    }
+                })
+                .collect();
+        }
+        let notice = |n: usize| {
+            FilteredLine::Notice(format!(
+                "{} context line{} collapsed (--distant-context {})",
+                n,
+                if n == 1 { "" } else { "s" },
+                self.config.distant_context
+            ))
+        };
+        let header = make_trimmed_hunk_header(&hunk_text[0], distant);
+        let mut output = vec![FilteredLine::Line(Cow::Owned(header.into_bytes()))];
+        if n_distant_before > 0 {
+            output.push(notice(n_distant_before));
+        }
+        output.extend(
+            hunk.into_iter()
+                .enumerate()
+                .skip(1 + n_distant_before)
+                .take(n_lines - 1 - n_distant_before - n_distant_after)
+                .map(mark),
+        );
+        if n_distant_after > 0 {
+            output.push(notice(n_distant_after));
+        }
+        output
+    }
+
+    /// Return the numbers of context lines at the start and at the end of a hunk which are more
+    /// than --distant-context lines from its nearest added or removed line. The hunks of merge
+    /// commits, and hunks without added or removed lines, have none.
+    fn get_distant_context(&self, hunk_text: &[String]) -> (usize, usize) {
+        let max_context = self.config.distant_context;
+        if max_context == 0
+            || !matches!(
+                parse::try_parse_hunk_header(&hunk_text[0]),
+                Some((_, line_numbers)) if line_numbers.len() == 2
+            )
+        {
+            return (0, 0);
+        }
+        let hunk_lines = &hunk_text[1..];
+        let is_context = |line: &&String| line.is_empty() || line.starts_with(' ');
+        let n_before = hunk_lines.iter().take_while(is_context).count();
+        if n_before == hunk_lines.len() {
+            return (0, 0);
+        }
+        let n_after = hunk_lines.iter().rev().take_while(is_context).count();
+        (
+            n_before.saturating_sub(max_context),
+            n_after.saturating_sub(max_context),
+        )
+    }
+
+    /// Return the --hide-hunks-matching regex which every added and removed line of the hunk
+    /// matches, if any. Hunks without added or removed lines are never hidden.
+    fn get_hunk_hidden_by(&self, hunk_lines: &[String]) -> Option<&'a Regex> {
+        let changed_lines: Vec<&str> = hunk_lines
+            .iter()
+            .filter(|line| line.starts_with('-') || line.starts_with('+'))
+            .map(|line| &line[1..])
+            .collect();
+        if changed_lines.is_empty() {
+            return None;
+        }
+        self.config
+            .hide_hunks_matching
+            .iter()
+            .find(|regex| changed_lines.iter().all(|line| regex.is_match(line)))
+    }
+
+    /// Return the badge of a hunk which is collapsed under --collapse-noise-hunks, if any. A hunk
+    /// chosen by --select is not collapsed.
+    fn get_collapsing_noise_badge(&self, hunk_lines: &[String]) -> Option<&'static str> {
+        if !self.config.collapse_noise_hunks
+            || self
+                .config
+                .select
+                .as_ref()
+                .map_or(false, |selection| selection.hunk.is_some())
+        {
+            return None;
+        }
+        hunk_badges::get_noise_badge(hunk_lines, self.file_path())
+    }
+
+    /// Decide the fate of a file which has no hunks, e.g. a binary file or a pure rename.
+    fn end_file(&mut self) {
+        self.apply_pre_render_filter();
+        let reversed_hunks = std::mem::take(&mut self.reversed_hunks);
+        for line in reversed_hunks.into_iter().rev().flatten() {
+            self.release(line);
+        }
+        if self.in_file && !self.is_path_included() {
+            self.skipped_paths.push(self.file_path().to_string());
+        } else if self.in_file && self.n_hunks == 0 && self.is_selected(None) {
+            self.release_file_meta();
+        }
+        let file_output = std::mem::take(&mut self.file_output);
+        match self.file_hidden_by.take() {
+            Some(regex) if !file_output.is_empty() => {
+                self.queue.push_back(FilteredLine::Notice(format!(
+                    "{} hidden (--hide-files-matching '{}')",
+                    self.file_path(),
+                    regex
+                )))
+            }
+            _ => self.queue.extend(file_output),
+        }
+        self.file_meta.clear();
+        self.in_file = false;
+    }
+
+    fn release_file_meta(&mut self) {
+        if !self.file_meta_released {
+            if let Some((n_added, n_removed)) = self.file_stats.take() {
+                self.release(FilteredLine::FileStats(n_added, n_removed));
+            }
+            let mut file_meta = std::mem::take(&mut self.file_meta);
+            if self.config.invert {
+                let file_meta_text: Vec<String> = file_meta
+                    .iter()
+                    .map(|line| ansi::strip_ansi_codes(&String::from_utf8_lossy(line)).to_string())
+                    .collect();
+                file_meta = invert::invert_file_meta(&file_meta_text)
+                    .into_iter()
+                    .map(String::into_bytes)
+                    .collect();
+            }
+            self.release_lines(file_meta);
+            self.file_meta_released = true;
+            self.is_selection_found = true;
+        }
+    }
+
+    /// Pass on a line to be rendered. Within a file, it is held back if the file might yet be
+    /// hidden by --hide-files-matching.
+    fn release(&mut self, line: FilteredLine<'static>) {
+        if self.in_file && !self.config.hide_files_matching.is_empty() {
+            self.file_output.push(line);
+        } else {
+            self.queue.push_back(line);
+        }
+    }
+
+    fn release_lines(&mut self, lines: Vec<Vec<u8>>) {
+        for line in lines {
+            self.release(FilteredLine::Line(Cow::Owned(line)));
+        }
+    }
+
+    /// Handle a line which is not part of a file section, such as commit metadata.
+    fn release_other_line(&mut self, line: Vec<u8>) {
+        if self.config.select.is_none() {
+            self.release_lines(vec![line]);
+        }
+    }
+
+    fn push_skipped_paths_notice(&mut self) {
+        if !self.skipped_paths.is_empty() {
+            self.queue.push_back(FilteredLine::Notice(format!(
+                "{} {} not displayed (--include-path, --exclude-path): {}",
+                self.skipped_paths.len(),
+                if self.skipped_paths.len() == 1 {
+                    "file"
+                } else {
+                    "files"
+                },
+                self.skipped_paths.join(", ")
+            )));
+        }
+    }
+
+    /// Is the current file, or hunk number `hunk` of the current file, to be rendered?
+    fn is_selected(&self, hunk: Option<usize>) -> bool {
+        self.is_path_included()
+            && match &self.config.select {
+                Some(selection) => {
+                    let is_file_selected = match selection.file_number {
+                        Some(file_number) => file_number == self.n_files,
+                        None => selection.path == self.file_path(),
+                    };
+                    is_file_selected && (selection.hunk.is_none() || selection.hunk == hunk)
+                }
+                None => true,
+            }
+    }
+
+    /// Is the current file's path allowed by --include-path and --exclude-path?
+    fn is_path_included(&self) -> bool {
+        let path = self.file_path();
+        (self.config.include_path.is_empty()
+            || self
+                .config
+                .include_path
+                .iter()
+                .any(|glob| glob.is_match(path)))
+            && !self
+                .config
+                .exclude_path
+                .iter()
+                .any(|glob| glob.is_match(path))
+    }
+
+    fn file_path(&self) -> &str {
+        if self.plus_file.is_empty() || self.plus_file == "/dev/null" {
+            &self.minus_file
+        } else {
+            &self.plus_file
+        }
+    }
+}
+
+/// Return the numbers of added and removed lines among the lines of hunks.
+fn count_added_and_removed_lines<'b>(lines: impl Iterator<Item = &'b str>) -> (usize, usize) {
+    lines
+        .filter(|line| !line.starts_with("@@"))
+        .fold((0, 0), |(n_added, n_removed), line| {
+            match line.chars().next() {
+                Some('+') => (n_added + 1, n_removed),
+                Some('-') => (n_added, n_removed + 1),
+                _ => (n_added, n_removed),
+            }
+        })
+}
+
+/// Return the header of a hunk from which the first `n_before` and last `n_after` lines, all
+/// context lines, have been removed.
+fn make_trimmed_hunk_header(header: &str, (n_before, n_after): (usize, usize)) -> String {
+    let (code_fragment, line_numbers) = parse::try_parse_hunk_header(header).unwrap();
+    let (minus_start, minus_length) = line_numbers[0];
+    let (plus_start, plus_length) = line_numbers[1];
+    format!(
+        "@@ -{},{} +{},{} @@{}",
+        minus_start + n_before,
+        minus_length - n_before - n_after,
+        plus_start + n_before,
+        plus_length - n_before - n_after,
+        code_fragment
+    )
+}
+
+/// Return the line ranges of a hunk header, such as "@@ -1,2 +1,3 @@", without the code fragment
+/// which follows them.
+fn get_hunk_header_ranges(header: &str) -> &str {
+    let marker = &header[..header.len() - header.trim_start_matches('@').len()];
+    match header[marker.len()..].find(marker) {
+        Some(i) => &header[..2 * marker.len() + i],
+        None => header,
+    }
+}
+
+/// Determines where a hunk ends, using the line counts in the hunk header. The hunk header of a
+/// merge commit's combined diff has more than two line counts; for these, a hunk ends at the first
+/// line that does not look like a hunk line.
+pub struct HunkLineCounter {
+    n_minus: usize,
+    n_plus: usize,
+    is_combined: bool,
+}
+
+impl HunkLineCounter {
+    pub fn new(line_numbers_and_hunk_lengths: &[(usize, usize)]) -> Self {
+        let n = line_numbers_and_hunk_lengths.len();
+        Self {
+            n_minus: line_numbers_and_hunk_lengths[0].1,
+            n_plus: line_numbers_and_hunk_lengths[n - 1].1,
+            is_combined: n > 2,
+        }
+    }
+
+    /// Is `line` part of the hunk? If so, update the counts of lines remaining.
+    pub fn accepts(&mut self, line: &str) -> bool {
+        let first_char = line.chars().next();
+        if first_char == Some('\\') {
+            // "\ No newline at end of file"
+            return true;
+        }
+        if self.is_combined {
+            return matches!(first_char, Some(' ') | Some('-') | Some('+'));
+        }
+        if self.n_minus == 0 && self.n_plus == 0 {
+            return false;
+        }
+        match first_char {
+            // An empty line is a context line whose trailing whitespace has been removed.
+            Some(' ') | None => {
+                self.n_minus = self.n_minus.saturating_sub(1);
+                self.n_plus = self.n_plus.saturating_sub(1);
+            }
+            Some('-') => self.n_minus = self.n_minus.saturating_sub(1),
+            Some('+') => self.n_plus = self.n_plus.saturating_sub(1),
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    const TWO_FILES_DIFF: &str = "\
+commit 94907c0f136f46dc46ffae2dc92dca9af7eb7c2e
+Author: Dan Davison <dandavison7@gmail.com>
+
+    Change two files
+
+diff --git a/src/a.rs b/src/a.rs
+index 223ca50..367a6f6 100644
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1,2 +1,2 @@
+ fn a() {}
+-fn b() {}
++fn bb() {}
+@@ -10,2 +10,2 @@
+ fn c() {}
+-fn d() {}
++fn dd() {}
+diff --git a/src/b.rs b/src/b.rs
+index 223ca50..367a6f6 100644
+--- a/src/b.rs
++++ b/src/b.rs
+@@ -1 +1 @@
+-fn e() {}
++fn ee() {}
+";
+
+    #[test]
+    fn test_selection_from_str() {
+        assert_eq!(
+            Selection::from_str("src/foo.rs:2"),
+            Selection {
+                path: "src/foo.rs".to_string(),
+                file_number: None,
+                hunk: Some(2)
+            }
+        );
+        assert_eq!(
+            Selection::from_str("src/foo.rs"),
+            Selection {
+                path: "src/foo.rs".to_string(),
+                file_number: None,
+                hunk: None
+            }
+        );
+        assert_eq!(
+            Selection::from_str("C:x"),
+            Selection {
+                path: "C:x".to_string(),
+                file_number: None,
+                hunk: None
+            }
+        );
+        assert_eq!(
+            Selection::from_str("3.2"),
+            Selection {
+                path: "".to_string(),
+                file_number: Some(3),
+                hunk: Some(2)
+            }
+        );
+        assert_eq!(Selection::from_str("3").file_number, Some(3));
+        assert_eq!(Selection::from_str("3.2").to_string(), "3.2");
+        assert_eq!(Selection::from_str("a.2").file_number, None);
+    }
+
+    #[test]
+    fn test_path_glob() {
+        let glob = PathGlob::new("*.lock");
+        assert!(glob.is_match("Cargo.lock"));
+        assert!(glob.is_match("a/b/yarn.lock"));
+        assert!(!glob.is_match("Cargo.lock.rs"));
+        let glob = PathGlob::new("dist/");
+        assert!(glob.is_match("dist/a.js"));
+        assert!(glob.is_match("web/dist/a/b.js"));
+        assert!(!glob.is_match("dist"));
+        let glob = PathGlob::new("src/*.rs");
+        assert!(glob.is_match("src/a.rs"));
+        assert!(!glob.is_match("src/a/b.rs"));
+        assert!(!glob.is_match("x/src/a.rs"));
+        let glob = PathGlob::new("src/**/*.snap");
+        assert!(glob.is_match("src/a.snap"));
+        assert!(glob.is_match("src/a/b/c.snap"));
+        let glob = PathGlob::new("snapshots");
+        assert!(glob.is_match("tests/snapshots/a.snap"));
+    }
+
+    #[test]
+    fn test_exclude_path() {
+        let config = make_config_from_args(&["--exclude-path", "a.rs", "--exclude-path", "c/"]);
+        let output = run_delta(TWO_FILES_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("commit 94907c0f"));
+        assert!(!output.contains("fn dd() {}"));
+        assert!(output.contains("fn ee() {}"));
+        assert!(
+            output.ends_with("1 file not displayed (--include-path, --exclude-path): src/a.rs\n")
+        );
+    }
+
+    #[test]
+    fn test_include_path() {
+        let config = make_config_from_args(&["--include-path", "src/a.rs"]);
+        let output = run_delta(TWO_FILES_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("fn dd() {}"));
+        assert!(!output.contains("fn ee() {}"));
+        assert!(output.ends_with("src/b.rs\n"));
+    }
+
+    #[test]
+    fn test_hide_hunks_matching() {
+        let config = make_config_from_args(&["--hide-hunks-matching", "^fn [bd]+\\("]);
+        let output = run_delta(TWO_FILES_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("hunk 1 hidden (--hide-hunks-matching '^fn [bd]+\\(')"));
+        assert!(output.contains("hunk 2 hidden"));
+        assert!(!output.contains("fn dd() {}"));
+        assert!(output.contains("fn ee() {}"));
+    }
+
+    #[test]
+    fn test_hide_files_matching() {
+        let config = make_config_from_args(&["--hide-files-matching", "fn c"]);
+        let output = run_delta(TWO_FILES_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("src/a.rs hidden (--hide-files-matching 'fn c')"));
+        assert!(!output.contains("fn bb() {}"));
+        assert!(output.contains("fn ee() {}"));
+    }
+
+    #[test]
+    fn test_max_hunk_lines() {
+        let config = make_config_from_args(&["--max-hunk-lines", "1"]);
+        let output = run_delta(TWO_FILES_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("+1 −1 lines in src/a.rs @@ -1,2 +1,2 @@"));
+        assert!(output.contains("+1 −1 lines in src/a.rs @@ -10,2 +10,2 @@"));
+        assert!(output.contains("+1 −1 lines in src/b.rs @@ -1 +1 @@"));
+        assert!(!output.contains("fn dd() {}"));
+
+        // Context lines are not counted.
+        let config = make_config_from_args(&["--max-hunk-lines", "2"]);
+        let output = run_delta(TWO_FILES_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(!output.contains("lines in src/a.rs"));
+        assert!(output.contains("fn dd() {}"));
+    }
+
+    #[test]
+    fn test_get_hunk_header_ranges() {
+        assert_eq!(
+            get_hunk_header_ranges("@@ -10,2 +10,2 @@ fn c() {}"),
+            "@@ -10,2 +10,2 @@"
+        );
+        assert_eq!(
+            get_hunk_header_ranges("@@@ -1 -1 +1,2 @@@"),
+            "@@@ -1 -1 +1,2 @@@"
+        );
+    }
+
+    #[test]
+    fn test_reverse_hunks() {
+        let config = make_config_from_args(&["--reverse-hunks"]);
+        let output = run_delta(TWO_FILES_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        let position = |s| output.find(s).unwrap();
+        assert!(position("src/a.rs") < position("fn dd() {}"));
+        assert!(position("fn dd() {}") < position("fn bb() {}"));
+        assert!(position("fn bb() {}") < position("src/b.rs"));
+        assert!(position("src/b.rs") < position("fn ee() {}"));
+    }
+
+    #[test]
+    fn test_pre_render_filter() {
+        let dir = std::env::temp_dir().join(format!(
+            "delta-test-pre-render-filter-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b, c) = (dir.join("a.txt"), dir.join("b.txt"), dir.join("c.txt"));
+        std::fs::write(&a, "x\ny\n").unwrap();
+        std::fs::write(&b, "y\nx\n").unwrap();
+        std::fs::write(&c, "y\nz\n").unwrap();
+        let make_diff = |minus: &std::path::Path, plus: &std::path::Path| {
+            format!(
+                "--- {}\n+++ {}\n@@ -1,2 +1,2 @@\n-x\n y\n+x\n",
+                minus.display(),
+                plus.display()
+            )
+        };
+        let config = make_config_from_args(&["--pre-render-filter", "*.txt=sort"]);
+
+        let output = run_delta(&make_diff(&a, &b), &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("no differences after filtering through 'sort'"));
+        assert!(!output.contains("x\n"));
+
+        let output = run_delta(&make_diff(&a, &c), &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("compared after filtering through 'sort'"));
+        assert!(output.contains("\nx\n"));
+        assert!(output.contains("\nz\n"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_expand_context() {
+        let dir =
+            std::env::temp_dir().join(format!("delta-test-expand-context-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.txt");
+        let lines: Vec<String> = (1..=10).map(|i| format!("line {}", i)).collect();
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        let diff = format!(
+            "--- {0}\n+++ {0}\n@@ -3 +3 @@\n-old 3\n+line 3\n@@ -5 +5 @@\n-old 5\n+line 5\n",
+            path.display()
+        );
+        let config =
+            make_config_from_args(&["--expand-context", "3", "--hunk-header-style", "omit"]);
+        let output = run_delta(&diff, &config);
+        // The added context lines are dim.
+        assert!(output.contains("\x1b[2mline 1\x1b[0m"));
+        let output = strip_ansi_codes(&output);
+        let lines: Vec<&str> = output
+            .lines()
+            .skip_while(|line| *line != "line 1")
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "line 1", "line 2", "old 3", "line 3", "line 4", "old 5", "line 5", "line 6",
+                "line 7", "line 8"
+            ]
+        );
+
+        // Lines are not added under --color-only, or when delta is a diff filter.
+        for args in [&["--color-only"], &["--diff-filter-mode"]] {
+            let config = make_config_from_args(&[&["--expand-context", "3"], &args[..]].concat());
+            let output = run_delta(&diff, &config);
+            assert_eq!(output.lines().count(), diff.lines().count());
+        }
+
+        // A hunk which does not match the file is not expanded.
+        std::fs::write(&path, "").unwrap();
+        let output = strip_ansi_codes(&run_delta(&diff, &config)).to_string();
+        assert!(!output.contains("line 1"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_stats() {
+        let diff = "\
+diff --git a/a.rs b/a.rs
+index 223ca50..e69de29 100644
+--- a/a.rs
++++ b/a.rs
+@@ -1,2 +1,3 @@
+-fn f() {}
++fn f() {
++}
+ fn g() {}
+@@ -10,2 +11,2 @@ fn h() {
+--- a
++++ b
+diff --git a/notes b/notes
+index 223ca50..e69de29 100644
+--- a/notes
++++ b/notes
+@@ -1 +0,0 @@
+-a
+";
+        let config = make_config_from_args(&["--file-stats"]);
+        let output = strip_ansi_codes(&run_delta(diff, &config)).to_string();
+        assert!(output.contains("\na.rs (Rust, +3 −2)\n"));
+        assert!(output.contains("\nnotes (+0 −1)\n"));
+
+        let config = make_config_from_args(&["--file-stats", "--ascii"]);
+        let output = strip_ansi_codes(&run_delta(diff, &config)).to_string();
+        assert!(output.contains("\na.rs (Rust, +3 -2)\n"));
+
+        let config = make_config_from_args(&[]);
+        let output = strip_ansi_codes(&run_delta(diff, &config)).to_string();
+        assert!(output.contains("\na.rs\n"));
+    }
+
+    #[test]
+    fn test_distant_context() {
+        let diff = "\
+--- a/a.rs
++++ b/a.rs
+@@ -10,8 +10,8 @@ mod tests
+ fn f() {
+     let a = 1;
+     let b = 2;
+-    let c = 3;
++    let c = 4;
+     let d = 5;
+     let e = 6;
+ }
+";
+        let config = make_config_from_args(&["--distant-context", "1"]);
+        let output = run_delta(diff, &config);
+        assert!(output.contains("\x1b[2mfn f() {\x1b[0m"));
+        assert!(output.contains("\x1b[2m    let e = 6;\x1b[0m"));
+        assert!(!output.contains("\x1b[2m    let b = 2;"));
+
+        let config = make_config_from_args(&[
+            "--distant-context",
+            "1",
+            "--collapse-distant-context",
+            "--line-numbers",
+        ]);
+        let output = strip_ansi_codes(&run_delta(diff, &config)).to_string();
+        assert_eq!(
+            output
+                .matches("\n2 context lines collapsed (--distant-context 1)\n")
+                .count(),
+            2
+        );
+        assert!(!output.contains("let a = 1;"));
+        assert!(output.contains(" 12 ⋮ 12 │    let b = 2;"));
+        assert!(output.contains(" 14 ⋮ 14 │    let d = 5;"));
+        assert!(!output.contains("let e = 6;"));
+
+        assert_eq!(
+            make_trimmed_hunk_header("@@ -10,8 +10,8 @@ mod tests", (2, 2)),
+            "@@ -12,4 +12,4 @@ mod tests"
+        );
+    }
+
+    #[test]
+    fn test_hunk_line_counter() {
+        let mut counter = HunkLineCounter::new(&[(1, 2), (1, 1)]);
+        assert!(counter.accepts(" a"));
+        assert!(counter.accepts("-b"));
+        assert!(counter.accepts("\\ No newline at end of file"));
+        assert!(!counter.accepts("-c"));
+    }
+
+    #[test]
+    fn test_select_hunk() {
+        let config = make_config_from_args(&["--select", "src/a.rs:2", "--emit-patch"]);
+        let output = run_delta(TWO_FILES_DIFF, &config);
+        assert_eq!(
+            output,
+            "\
+diff --git a/src/a.rs b/src/a.rs
+index 223ca50..367a6f6 100644
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -10,2 +10,2 @@
+ fn c() {}
+-fn d() {}
++fn dd() {}
+"
+        );
+    }
+
+    #[test]
+    fn test_select_file_is_rendered() {
+        let config = make_config_from_args(&["--select", "src/b.rs"]);
+        let output = run_delta(TWO_FILES_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(!output.contains("commit"));
+        assert!(!output.contains("src/a.rs"));
+        assert!(output.contains("src/b.rs"));
+        assert!(output.contains("fn ee() {}"));
+    }
+}
@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::config::Config;
+
+// The code fragment which git places after the line numbers of a hunk header is the "funcname" of
+// the hunk: the nearest preceding line which matches the funcname patterns of the file's diff
+// driver. A repository selects a driver for a file with a `diff=DRIVER` attribute in
+// .gitattributes, and the driver's patterns are given by the diff.DRIVER.xfuncname git config
+// setting. If a pattern contains a group, git uses the text matched by the first group rather than
+// the whole line. Delta applies the same patterns to the code fragment in order to display the
+// function name in --hunk-header-funcname-style, and to substitute it for {funcname} in
+// --hunk-header-format.
+
+/// The xfuncname patterns of a diff driver. Each newline-separated line of diff.DRIVER.xfuncname
+/// is a pattern; a pattern prefixed with '!' is negated, so that lines which match it are not
+/// funcnames.
+pub struct FuncnameRule {
+    driver: String,
+    patterns: Vec<(bool, Regex)>,
+}
+
+/// Make the funcname rules from the (driver, xfuncname) pairs of the git config. Patterns that are
+/// not valid regular expressions are ignored.
+pub fn make_funcname_rules(xfuncname_patterns: &[(String, String)]) -> Vec<FuncnameRule> {
+    xfuncname_patterns
+        .iter()
+        .map(|(driver, xfuncname)| FuncnameRule {
+            driver: driver.clone(),
+            patterns: xfuncname
+                .lines()
+                .filter(|pattern| !pattern.is_empty())
+                .filter_map(|pattern| match pattern.strip_prefix('!') {
+                    Some(pattern) => Regex::new(pattern).ok().map(|regex| (true, regex)),
+                    None => Regex::new(pattern).ok().map(|regex| (false, regex)),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Return the diff driver of the file with this path, relative to the top of the repository, if it
+/// has funcname rules.
+pub fn get_diff_driver(path: &str, config: &Config) -> Option<String> {
+    if config.funcname_rules.is_empty() || path == "/dev/null" {
+        return None;
+    }
+    let repo = git2::Repository::discover(".").ok()?;
+    let driver = repo
+        .get_attr(
+            Path::new(path),
+            "diff",
+            git2::AttrCheckFlags::FILE_THEN_INDEX,
+        )
+        .ok()??;
+    config
+        .funcname_rules
+        .iter()
+        .find(|rule| rule.driver == driver)
+        .map(|rule| rule.driver.clone())
+}
+
+/// Return the byte range of the funcname in the code fragment of a hunk header, under the rule of
+/// the diff driver. When no pattern matches, git has already reduced the fragment to the text of a
+/// group, and the whole fragment, without surrounding whitespace, is the funcname.
+pub fn get_funcname_range(
+    code_fragment: &str,
+    driver: Option<&str>,
+    config: &Config,
+) -> Option<(usize, usize)> {
+    let rule = config
+        .funcname_rules
+        .iter()
+        .find(|rule| Some(rule.driver.as_str()) == driver)?;
+    let start = code_fragment.len() - code_fragment.trim_start().len();
+    let text = code_fragment.trim();
+    if text.is_empty() {
+        return None;
+    }
+    match rule
+        .patterns
+        .iter()
+        .find_map(|(is_negated, regex)| regex.captures(text).map(|caps| (is_negated, caps)))
+    {
+        Some((true, _)) => None,
+        Some((false, caps)) => caps
+            .get(1)
+            .or_else(|| caps.get(0))
+            .filter(|m| !m.as_str().is_empty())
+            .map(|m| (start + m.start(), start + m.end())),
+        None => Some((start, start + text.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::make_config_from_args;
+
+    fn make_config(xfuncname_patterns: &[(&str, &str)]) -> Config {
+        let mut config = make_config_from_args(&[]);
+        config.funcname_rules = make_funcname_rules(
+            &xfuncname_patterns
+                .iter()
+                .map(|(driver, xfuncname)| (driver.to_string(), xfuncname.to_string()))
+                .collect::<Vec<_>>(),
+        );
+        config
+    }
+
+    #[test]
+    fn test_get_funcname_range() {
+        let config = make_config(&[("pas", "!^begin\n^(procedure|function) ([a-z]+)\n^sub")]);
+        let driver = Some("pas");
+        assert_eq!(
+            get_funcname_range(" function foo(x)", driver, &config),
+            Some((1, 9))
+        );
+        assert_eq!(get_funcname_range(" begin", driver, &config), None);
+        assert_eq!(
+            get_funcname_range(" submit()", driver, &config),
+            Some((1, 4))
+        );
+        // git has already extracted the group
+        assert_eq!(get_funcname_range(" bar ", driver, &config), Some((1, 4)));
+        assert_eq!(get_funcname_range(" foo", None, &config), None);
+        assert_eq!(get_funcname_range(" foo", Some("tex"), &config), None);
+    }
+}
@@ -0,0 +1,127 @@
+use std::ffi::OsString;
+use std::io::{BufReader, ErrorKind};
+use std::process;
+
+use bytelines::ByteLinesReader;
+use structopt::StructOpt;
+
+use crate::bat::output::OutputType;
+use crate::cli;
+use crate::config::Config;
+use crate::delta::delta;
+
+// `delta diff [args...]`, `delta log [args...]` and `delta show [args...]` run the git command
+// themselves and display its output, so that delta can be used where git's core.pager cannot be
+// configured. Delta's own options are given before the command, e.g. `delta -s diff HEAD~`.
+
+/// The git commands which delta can run.
+const GIT_COMMANDS: &[&str] = &["diff", "log", "show"];
+
+/// Return the index of the git command among the command-line arguments of delta, if delta was
+/// invoked as `delta [delta-options...] diff|log|show [git-args...]`. A command word given where
+/// delta expects a file name, e.g. `delta a.txt diff`, is not a command.
+pub fn get_git_command_index(args: &[OsString]) -> Option<usize> {
+    args.iter().enumerate().skip(1).find_map(|(i, arg)| {
+        if !GIT_COMMANDS.iter().any(|command| arg == command) {
+            return None;
+        }
+        match cli::Opt::clap().get_matches_from_safe(&args[..i]) {
+            Ok(arg_matches) if arg_matches.value_of_os("minus-file").is_none() => Some(i),
+            _ => None,
+        }
+    })
+}
+
+/// Return the arguments with which git is run for the command and arguments given to delta: git
+/// is told not to use a pager or color, and `git log` is told to display the diffs of commits.
+pub fn make_git_args(command: &OsString, args: &[OsString]) -> Vec<OsString> {
+    let mut git_args: Vec<OsString> = vec!["--no-pager".into(), command.clone()];
+    git_args.push("--color=never".into());
+    if command == "log" {
+        git_args.push("-p".into());
+    }
+    git_args.extend(args.iter().cloned());
+    git_args
+}
+
+/// Run git with the arguments and display its output. Return the exit code of git.
+pub fn run_git_command(command: &OsString, args: &[OsString], config: &Config) -> i32 {
+    let mut git_process = process::Command::new("git")
+        .args(make_git_args(command, args))
+        .stdout(process::Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|error| {
+            eprintln!("Failed to run git: {}", error);
+            process::exit(2);
+        });
+
+    {
+        let mut output_type = OutputType::from_mode(config.paging_mode, None, config).unwrap();
+        let mut writer = output_type.handle().unwrap();
+        if let Err(error) = delta(
+            BufReader::new(git_process.stdout.take().unwrap()).byte_lines(),
+            &mut writer,
+            config,
+        ) {
+            match error.kind() {
+                ErrorKind::BrokenPipe => {
+                    git_process.kill().ok();
+                    git_process.wait().ok();
+                    return 0;
+                }
+                _ => eprintln!("{}", error),
+            }
+        };
+        // The pager, if any, is waited for here.
+    }
+    match git_process.wait() {
+        Ok(status) => status.code().unwrap_or(2),
+        Err(_) => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_args(args: &[&str]) -> Vec<OsString> {
+        std::iter::once("delta")
+            .chain(args.iter().cloned())
+            .map(OsString::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_get_git_command_index() {
+        assert_eq!(get_git_command_index(&make_args(&["diff"])), Some(1));
+        assert_eq!(
+            get_git_command_index(&make_args(&["log", "--stat", "-3"])),
+            Some(1)
+        );
+        assert_eq!(
+            get_git_command_index(&make_args(&["-s", "--width", "80", "show", "HEAD"])),
+            Some(4)
+        );
+        assert_eq!(
+            get_git_command_index(&make_args(&["--syntax-theme", "diff", "diff"])),
+            Some(3)
+        );
+        assert_eq!(get_git_command_index(&make_args(&["a.txt", "diff"])), None);
+        assert_eq!(get_git_command_index(&make_args(&["--side-by-side"])), None);
+        assert_eq!(get_git_command_index(&make_args(&["blame"])), None);
+    }
+
+    #[test]
+    fn test_make_git_args() {
+        let args = make_args(&["log", "--stat", "-3"]);
+        assert_eq!(
+            make_git_args(&args[1], &args[2..]),
+            make_args(&["--no-pager", "log", "--color=never", "-p", "--stat", "-3"])[1..]
+        );
+        let args = make_args(&["diff", "--cached"]);
+        assert_eq!(
+            make_git_args(&args[1], &args[2..]),
+            make_args(&["--no-pager", "diff", "--color=never", "--cached"])[1..]
+        );
+    }
+}
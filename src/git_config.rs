@@ -52,6 +52,22 @@ impl GitConfig {
             None
         }
     }
+
+    /// Return the names and values of the entries whose names match the regex.
+    pub fn get_entries(&self, regex: &str) -> Vec<(String, String)> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        match self.config.entries(Some(regex)) {
+            Ok(entries) => (&entries)
+                .filter_map(|entry| {
+                    let entry = entry.ok()?;
+                    Some((entry.name()?.to_string(), entry.value()?.to_string()))
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
 }
 
 pub trait GitConfigGet {
@@ -75,6 +91,24 @@ impl GitConfigGet for Option<String> {
     }
 }
 
+/// A multi-valued git config entry, i.e. one that may be given more than once.
+impl GitConfigGet for Vec<String> {
+    fn git_config_get(key: &str, git_config: &GitConfig) -> Option<Self> {
+        let entries = git_config
+            .config
+            .entries(Some(&format!("^{}$", key.replace('.', "\\."))))
+            .ok()?;
+        let values: Vec<String> = (&entries)
+            .filter_map(|entry| entry.ok().and_then(|entry| entry.value().map(String::from)))
+            .collect();
+        if values.is_empty() {
+            None
+        } else {
+            Some(values)
+        }
+    }
+}
+
 impl GitConfigGet for bool {
     fn git_config_get(key: &str, git_config: &GitConfig) -> Option<Self> {
         git_config.config.get_bool(key).ok()
@@ -0,0 +1,351 @@
+use std::fmt::Write as FmtWrite;
+use std::path::Path;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::ansi;
+use crate::config::Config;
+use crate::delta::{self, State};
+use crate::paint::{Painter, StyleSections};
+use crate::parse;
+
+// Output of `git grep -n` and `rg -n`. Matching lines are of the form "path:line_number:line";
+// context lines (when -A, -B, or -C is used) are of the form "path-line_number-line".
+lazy_static! {
+    static ref GREP_LINE_REGEX: Regex = Regex::new(
+        r"(?x)
+^
+([^\s:][^:]*?)          # 1. Path
+(?:
+  :([0-9]+):            # 2. Line number of a matching line
+  |
+  -([0-9]+)-            # 3. Line number of a context line
+)
+(.*)                    # 4. Line
+$
+"
+    )
+    .unwrap();
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GrepLine<'a> {
+    pub path: &'a str,
+    pub line_number: usize,
+    pub is_context: bool,
+    pub code: &'a str,
+    // Byte offset of the code within the line
+    pub code_offset: usize,
+}
+
+/// State carried from one line of grep output to the next.
+#[derive(Default)]
+pub struct GrepState {
+    path: Option<String>,
+}
+
+/// Is this line the first line of grep output? To avoid misidentifying other input, such as
+/// compiler errors or log lines of the form "server:8080:listening", the path must be that of a
+/// file, relative to the current directory or to the top of the repository. Paths containing
+/// whitespace are not recognized here, nor are lines that look like diff metadata.
+pub fn is_grep_line(line: &str) -> bool {
+    parse::classify_line_prefix(line.as_bytes()) == parse::LinePrefix::Other
+        && match parse_grep_line(line) {
+            Some(grep_line) => {
+                !grep_line.path.contains(char::is_whitespace) && is_file(grep_line.path)
+            }
+            None => false,
+        }
+}
+
+fn is_file(path: &str) -> bool {
+    Path::new(path).is_file()
+        || git2::Repository::discover(".")
+            .ok()
+            .and_then(|repo| repo.workdir().map(|dir| dir.join(path).is_file()))
+            .unwrap_or(false)
+}
+
+/// Handle a line of grep output. Results are grouped under a file header each time the path
+/// changes. `raw_line` is used to locate the matched text, if the grep tool emitted color. Return
+/// false if the line was not recognized.
+pub fn handle_grep_line(
+    painter: &mut Painter,
+    line: &str,
+    raw_line: &str,
+    grep: &mut GrepState,
+    config: &Config,
+) -> std::io::Result<bool> {
+    if line == "--" {
+        // Separator between non-adjacent groups of context lines
+        painter.emit()?;
+        writeln!(
+            painter.writer,
+            "{}",
+            config.grep_line_number_style.paint("--")
+        )?;
+        return Ok(true);
+    }
+    let grep_line = match parse_grep_line(line) {
+        Some(grep_line) => grep_line,
+        None => return Ok(false),
+    };
+    if grep.path.as_deref() != Some(grep_line.path) {
+        painter.emit()?;
+        delta::handle_generic_file_meta_header_line(
+            painter,
+            grep_line.path,
+            grep_line.path,
+            config,
+        )?;
+        painter.set_syntax(parse::get_file_extension_from_file_meta_line_file_path(
+            grep_line.path,
+        ));
+        painter.set_tab_width(Some(grep_line.path));
+        painter.set_highlighter();
+        grep.path = Some(grep_line.path.to_string());
+    }
+    let match_ranges: Vec<(usize, usize)> = if grep_line.is_context {
+        Vec::new()
+    } else {
+        get_styled_ranges(raw_line)
+            .into_iter()
+            .filter(|(_, end)| *end > grep_line.code_offset)
+            .map(|(start, end)| {
+                (
+                    start.max(grep_line.code_offset) - grep_line.code_offset,
+                    end - grep_line.code_offset,
+                )
+            })
+            .collect()
+    };
+    paint_grep_line(painter, &grep_line, &match_ranges, config);
+    painter.emit()?;
+    Ok(true)
+}
+
+fn parse_grep_line(line: &str) -> Option<GrepLine<'_>> {
+    let caps = GREP_LINE_REGEX.captures(line)?;
+    let code = caps.get(4).unwrap();
+    Some(GrepLine {
+        path: caps.get(1).unwrap().as_str(),
+        line_number: caps.get(2).or_else(|| caps.get(3))?.as_str().parse().ok()?,
+        is_context: caps.get(3).is_some(),
+        code: code.as_str(),
+        code_offset: code.start(),
+    })
+}
+
+/// Write the line number followed by the syntax-highlighted line, with matched text in
+/// grep-match-style.
+fn paint_grep_line(
+    painter: &mut Painter,
+    grep_line: &GrepLine,
+    match_ranges: &[(usize, usize)],
+    config: &Config,
+) {
+    write!(
+        painter.output_buffer,
+        "{}{}",
+        config
+            .grep_line_number_style
+            .paint(format!("{:>4}", grep_line.line_number)),
+        if grep_line.is_context { "-" } else { ":" }
+    )
+    .unwrap();
+
+    // Split the line into sections at the match boundaries and expand tabs in each section
+    // separately, so that the boundaries can be located in the prepared line.
+    let code = grep_line.code;
+    let mut sections = Vec::new();
+    let mut offset = 0;
+    for &(start, end) in match_ranges {
+        let end = end.min(code.len());
+        if start < offset
+            || start >= end
+            || !code.is_char_boundary(start)
+            || !code.is_char_boundary(end)
+        {
+            continue;
+        }
+        sections.push((false, &code[offset..start]));
+        sections.push((true, &code[start..end]));
+        offset = end;
+    }
+    sections.push((false, &code[offset..]));
+
+    let mut line = " ".to_string();
+    let mut section_boundaries = Vec::new();
+    for (is_match, text) in sections {
+        let start = line.len();
+        line.push_str(&painter.expand_tabs(text.graphemes(true)));
+        section_boundaries.push((is_match, start, line.len()));
+    }
+    line.push('\n');
+    let lines = vec![line];
+    let syntax_style_sections = Painter::get_syntax_style_sections_for_lines(
+        &lines,
+        &State::HunkZero,
+        &mut painter.highlighter,
+        config,
+    );
+    let line = &lines[0];
+    let mut diff_style_sections = Vec::new();
+    let mut offset = 0;
+    for (is_match, start, end) in section_boundaries {
+        if is_match {
+            diff_style_sections.push((config.zero_style, &line[offset..start]));
+            diff_style_sections.push((config.grep_match_style, &line[start..end]));
+            offset = end;
+        }
+    }
+    diff_style_sections.push((config.zero_style, &line[offset..]));
+    diff_style_sections.retain(|(_, text)| !text.is_empty());
+    Painter::paint_lines(
+        StyleSections {
+            syntax: syntax_style_sections,
+            diff: vec![diff_style_sections],
+        },
+        &State::HunkZero,
+        &mut painter.output_buffer,
+        config,
+        &mut None,
+        "",
+        0,
+    );
+}
+
+/// Return the byte ranges of the styled text in a string containing ANSI escape sequences. The
+/// ranges are offsets into the string with escape sequences removed. Text is considered styled
+/// from any SGR sequence other than a reset, up to the next reset.
+fn get_styled_ranges(s: &str) -> Vec<(usize, usize)> {
+    let bytes = s.as_bytes();
+    let mut ranges = Vec::new();
+    let mut start = None;
+    let (mut i, mut offset) = (0, 0);
+    while i < bytes.len() {
+        if bytes[i] == 0x1b {
+            let j = i + ansi::escape_sequence_length(&bytes[i..]) - 1;
+            if bytes.get(i + 1) == Some(&b'[') && bytes.get(j) == Some(&b'm') {
+                let is_reset = s[i + 2..j]
+                    .split(';')
+                    .all(|param| param.is_empty() || param == "0");
+                if is_reset {
+                    if let Some(start) = start.take() {
+                        if start < offset {
+                            ranges.push((start, offset));
+                        }
+                    }
+                } else if start.is_none() {
+                    start = Some(offset);
+                }
+            }
+            i = j + 1;
+        } else {
+            i += 1;
+            offset += 1;
+        }
+    }
+    if let Some(start) = start {
+        if start < offset {
+            ranges.push((start, offset));
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    const GIT_GREP_COLOR: &str = "\
+\x1b[35msrc/align.rs\x1b[m\x1b[36m:\x1b[m\x1b[32m1\x1b[m\x1b[36m:\x1b[m\x1b[1;31muse\x1b[m std::cmp::max;
+\x1b[35msrc/align.rs\x1b[m\x1b[36m-\x1b[m\x1b[32m2\x1b[m\x1b[36m-\x1b[m
+\x1b[36m--\x1b[m
+\x1b[35msrc/bat/mod.rs\x1b[m\x1b[36m:\x1b[m\x1b[32m10\x1b[m\x1b[36m:\x1b[m\tpub \x1b[1;31muse\x1b[m x::y;
+";
+
+    #[test]
+    fn test_parse_grep_line() {
+        assert_eq!(
+            parse_grep_line("src/a-b.rs:12:    let x = a:1:b;"),
+            Some(GrepLine {
+                path: "src/a-b.rs",
+                line_number: 12,
+                is_context: false,
+                code: "    let x = a:1:b;",
+                code_offset: 14,
+            })
+        );
+        assert_eq!(
+            parse_grep_line("src/a-b.rs-13-}"),
+            Some(GrepLine {
+                path: "src/a-b.rs",
+                line_number: 13,
+                is_context: true,
+                code: "}",
+                code_offset: 14,
+            })
+        );
+        assert!(parse_grep_line("@@ -1,2 +1,2 @@").is_none());
+        assert!(parse_grep_line("diff --git a/a.rs b/a.rs").is_none());
+    }
+
+    #[test]
+    fn test_is_grep_line() {
+        assert!(is_grep_line("src/main.rs:1:fn main() {"));
+        assert!(!is_grep_line(
+            "--- one.rs\t2019-11-20 06:16:08.000000000 +0100"
+        ));
+        assert!(!is_grep_line("Date:   Mon Jun 1 12:34:56 2020 -0400"));
+        assert!(!is_grep_line("server:8080:listening"));
+        assert!(!is_grep_line("src/no_such_file.rs:1:fn main() {"));
+    }
+
+    #[test]
+    fn test_get_styled_ranges() {
+        assert_eq!(
+            get_styled_ranges("a\x1b[1;31mbc\x1b[m d \x1b[0m\x1b[1m\x1b[31mef\x1b[0m"),
+            vec![(1, 3), (6, 8)]
+        );
+        assert_eq!(get_styled_ranges("abc"), vec![]);
+    }
+
+    #[test]
+    fn test_grep() {
+        let config = make_config_from_args(&[
+            "--file-style",
+            "yellow",
+            "--file-decoration-style",
+            "none",
+            "--tabs",
+            "2",
+        ]);
+        let output = run_delta(GIT_GREP_COLOR, &config);
+        let output = strip_ansi_codes(&output);
+        let mut lines = output.lines();
+        assert_eq!(lines.next().unwrap(), "");
+        assert_eq!(lines.next().unwrap(), "src/align.rs");
+        assert_eq!(lines.next().unwrap(), "   1:use std::cmp::max;");
+        assert_eq!(lines.next().unwrap(), "   2-");
+        assert_eq!(lines.next().unwrap(), "--");
+        assert_eq!(lines.next().unwrap(), "");
+        assert_eq!(lines.next().unwrap(), "src/bat/mod.rs");
+        assert_eq!(lines.next().unwrap(), "  10:  pub use x::y;");
+    }
+
+    #[test]
+    fn test_grep_match_is_highlighted() {
+        let config = make_config_from_args(&["--grep-match-style", "bold red"]);
+        let output = run_delta(GIT_GREP_COLOR, &config);
+        let line = output.lines().find(|line| line.contains("max")).unwrap();
+        assert!(line.contains(&config.grep_match_style.paint("use").to_string()));
+    }
+}
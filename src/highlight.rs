@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use syntect::highlighting::{
+    HighlightIterator, HighlightState, Highlighter, Style as SyntectStyle, Theme,
+};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
+
+/// The maximum number of distinct lines held in the cache. When this is exceeded the cache is
+/// cleared, so that memory use stays bounded on huge diffs consisting of mostly unique lines.
+const MAX_CACHED_LINES: usize = 8192;
+
+/// A syntax highlighter which remembers the result of highlighting each line. Highlighting is
+/// stateful (a line may be inside a multi-line string or comment, for example), so a cached result
+/// is only reused if the highlighter is in exactly the same state as it was when the result was
+/// computed. Since the parse state determines the language, the cache is effectively keyed by
+/// (language, line content). This makes repeated identical lines, which are very common in
+/// lockfiles, generated code, and moved blocks, cheap to highlight.
+pub struct CachingHighlighter<'a> {
+    highlighter: Highlighter<'a>,
+    parse_state: ParseState,
+    highlight_state: HighlightState,
+    pub cache: HighlightCache,
+}
+
+#[derive(Default)]
+pub struct HighlightCache {
+    entries: HashMap<String, Vec<CacheEntry>>,
+    n_entries: usize,
+}
+
+struct CacheEntry {
+    parse_state: ParseState,
+    highlight_state: HighlightState,
+    // Section styles and their lengths in bytes
+    sections: Vec<(SyntectStyle, usize)>,
+    next_parse_state: ParseState,
+    next_highlight_state: HighlightState,
+}
+
+impl<'a> CachingHighlighter<'a> {
+    pub fn new(syntax: &SyntaxReference, theme: &'a Theme, cache: HighlightCache) -> Self {
+        let highlighter = Highlighter::new(theme);
+        let highlight_state = HighlightState::new(&highlighter, ScopeStack::new());
+        Self {
+            highlighter,
+            parse_state: ParseState::new(syntax),
+            highlight_state,
+            cache,
+        }
+    }
+
+    /// Highlight a line, advancing the highlighter state.
+    pub fn highlight<'b>(
+        &mut self,
+        line: &'b str,
+        syntax_set: &SyntaxSet,
+    ) -> Vec<(SyntectStyle, &'b str)> {
+        if let Some(entry) = self
+            .cache
+            .get(line, &self.parse_state, &self.highlight_state)
+        {
+            self.parse_state = entry.next_parse_state.clone();
+            self.highlight_state = entry.next_highlight_state.clone();
+            return split_line(line, &entry.sections);
+        }
+        let (parse_state, highlight_state) =
+            (self.parse_state.clone(), self.highlight_state.clone());
+        let ops = self.parse_state.parse_line(line, syntax_set);
+        let sections: Vec<(SyntectStyle, &str)> =
+            HighlightIterator::new(&mut self.highlight_state, &ops[..], line, &self.highlighter)
+                .collect();
+        self.cache.insert(
+            line,
+            CacheEntry {
+                parse_state,
+                highlight_state,
+                sections: sections
+                    .iter()
+                    .map(|(style, s)| (*style, s.len()))
+                    .collect(),
+                next_parse_state: self.parse_state.clone(),
+                next_highlight_state: self.highlight_state.clone(),
+            },
+        );
+        sections
+    }
+}
+
+impl HighlightCache {
+    fn get(
+        &self,
+        line: &str,
+        parse_state: &ParseState,
+        highlight_state: &HighlightState,
+    ) -> Option<&CacheEntry> {
+        self.entries.get(line).and_then(|entries| {
+            entries.iter().find(|entry| {
+                entry.parse_state == *parse_state && entry.highlight_state == *highlight_state
+            })
+        })
+    }
+
+    fn insert(&mut self, line: &str, entry: CacheEntry) {
+        if self.n_entries >= MAX_CACHED_LINES {
+            self.entries.clear();
+            self.n_entries = 0;
+        }
+        self.entries
+            .entry(line.to_string())
+            .or_default()
+            .push(entry);
+        self.n_entries += 1;
+    }
+}
+
+fn split_line<'b>(
+    line: &'b str,
+    sections: &[(SyntectStyle, usize)],
+) -> Vec<(SyntectStyle, &'b str)> {
+    let mut offset = 0;
+    sections
+        .iter()
+        .map(|(style, len)| {
+            let section = (*style, &line[offset..offset + len]);
+            offset += len;
+            section
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use syntect::easy::HighlightLines;
+
+    use super::*;
+    use crate::bat::assets::HighlightingAssets;
+
+    const LINES: [&str; 6] = [
+        "fn f() {\n",
+        "    let x = 1;\n",
+        "    /*\n",
+        "    let x = 1;\n",
+        "    */\n",
+        "    let x = 1;\n",
+    ];
+
+    #[test]
+    fn test_caching_highlighter_agrees_with_syntect() {
+        let assets = HighlightingAssets::new();
+        let syntax_set = assets.syntax_set.get();
+        let syntax = syntax_set.find_syntax_by_extension("rs").unwrap();
+        let theme = &assets.theme_set.themes["Monokai Extended"];
+
+        let mut expected = HighlightLines::new(syntax, theme);
+        let mut highlighter = CachingHighlighter::new(syntax, theme, HighlightCache::default());
+        for line in LINES.iter() {
+            assert_eq!(
+                highlighter.highlight(line, syntax_set),
+                expected.highlight(line, syntax_set)
+            );
+        }
+        // The repeated line inside the comment is highlighted differently, so it must not share a
+        // cache entry with the repeated line outside the comment. The final line is a cache hit.
+        assert_eq!(highlighter.cache.entries["    let x = 1;\n"].len(), 2);
+        assert_eq!(highlighter.cache.n_entries, 5);
+    }
+}
@@ -0,0 +1,133 @@
+use std::process;
+
+use regex::Regex;
+
+use crate::config::Config;
+use crate::paint;
+use crate::style::Style;
+
+/// A --highlight rule: text matching the regex is displayed in the rule's style, on top of the
+/// syntax highlighting and diff styling of the line.
+#[derive(Clone, Debug)]
+pub struct HighlightPattern {
+    regex: Regex,
+    style: Style,
+}
+
+impl HighlightPattern {
+    /// Parse a --highlight value, 'REGEX' or 'REGEX=>STYLE'. A rule without a style uses
+    /// --highlight-style.
+    pub fn from_str(s: &str, default_style: Style, true_color: bool) -> Self {
+        let (regex, style) = match s.rsplit_once("=>") {
+            Some((regex, style)) => (regex, Style::from_str(style, None, None, true_color, false)),
+            None => (s, default_style),
+        };
+        let regex = Regex::new(regex).unwrap_or_else(|_| {
+            eprintln!(
+                "Invalid highlight: {}. \
+                 The value must be a valid Rust regular expression. \
+                 See https://docs.rs/regex.",
+                regex
+            );
+            process::exit(1);
+        });
+        Self { regex, style }
+    }
+}
+
+/// Split the style sections of a painted line so that the text matched by the --highlight rules is
+/// displayed in their styles. Where the matches of several rules overlap, the last rule wins.
+pub fn style_highlighted_sections(
+    sections: Vec<(Style, String)>,
+    config: &Config,
+) -> Vec<(Style, String)> {
+    let line: String = sections.iter().map(|(_, text)| text.as_str()).collect();
+    // The first character of the line is the placeholder for the +/- marker, which is not matched.
+    let marker_len = line.chars().next().map_or(0, char::len_utf8);
+    let mut ranges = Vec::new();
+    let mut styles = Vec::new();
+    for pattern in &config.highlight_patterns {
+        for m in pattern.regex.find_iter(&line[marker_len..]) {
+            if m.start() < m.end() {
+                ranges.push((marker_len + m.start(), marker_len + m.end()));
+                styles.push(pattern.style);
+            }
+        }
+    }
+    paint::restyle_ranges(sections, &ranges, |style, range| match range {
+        Some(i) => overlay(style, styles[i]),
+        None => style,
+    })
+}
+
+/// Return the style with the colors and attributes of the highlight style applied on top of it.
+pub fn overlay(style: Style, highlight_style: Style) -> Style {
+    let (base, top) = (style.ansi_term_style, highlight_style.ansi_term_style);
+    Style {
+        ansi_term_style: ansi_term::Style {
+            foreground: top.foreground.or(base.foreground),
+            background: top.background.or(base.background),
+            is_bold: base.is_bold || top.is_bold,
+            is_dimmed: base.is_dimmed || top.is_dimmed,
+            is_italic: base.is_italic || top.is_italic,
+            is_underline: base.is_underline || top.is_underline,
+            is_blink: base.is_blink || top.is_blink,
+            is_reverse: base.is_reverse || top.is_reverse,
+            is_hidden: base.is_hidden || top.is_hidden,
+            is_strikethrough: base.is_strikethrough || top.is_strikethrough,
+        },
+        ..style
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_highlight() {
+        let config = make_config_from_args(&[
+            "--highlight",
+            "TODO",
+            "--highlight",
+            "FIXME=>bold yellow",
+            "--highlight-style",
+            "bold red",
+            "--minus-style",
+            "normal",
+            "--plus-style",
+            "normal",
+            "--zero-style",
+            "normal",
+            "--syntax-theme",
+            "none",
+        ]);
+        let output = run_delta(DIFF, &config);
+        let bold_red = crate::style::Style::from_str("bold red", None, None, true, false);
+        let bold_yellow = crate::style::Style::from_str("bold yellow", None, None, true, false);
+        assert_eq!(
+            output.matches(&bold_red.paint("TODO").to_string()).count(),
+            2
+        );
+        assert!(output.contains(&bold_yellow.paint("FIXME").to_string()));
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\n# TODO: b\n"));
+        assert!(output.contains("\n# FIXME: c\n"));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,3 @@
+ # TODO: a
+-# TODO: b
++# FIXME: c
++d = 4
+";
+}
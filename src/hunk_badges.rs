@@ -0,0 +1,441 @@
+use std::process;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+// Under --hunk-badges, each hunk header is labeled with badges such as "[comment-only]", which
+// classify the hunk's changes, so that hunks which need less attention can be recognized when
+// skimming a diff. The classification uses simple heuristics, applied to the hunk's removed and
+// added lines only.
+
+lazy_static! {
+    static ref COMMENT_REGEX: Regex = Regex::new(r"^\s*(//|/\*|\*|--|;|<!--|%|#)").unwrap();
+    static ref PREPROCESSOR_DIRECTIVE_REGEX: Regex =
+        Regex::new(r"^\s*#\s*(include|import|define|undef|if|ifdef|ifndef|elif|else|endif|pragma)\b")
+            .unwrap();
+    static ref IMPORT_REGEX: Regex = Regex::new(
+        r"^\s*(import\b|from\s+\S+\s+import\b|(pub\s+)?use\s|extern\s+crate\b|#\s*include\b|#\s*import\b|@import\b|using\s|require\b|library\()|\brequire\("
+    )
+    .unwrap();
+    static ref TOKEN_REGEX: Regex = Regex::new(r"\w+|\S").unwrap();
+    static ref IDENTIFIER_REGEX: Regex = Regex::new(r"^[A-Za-z_]\w*$").unwrap();
+    static ref TRAILING_COMMA_REGEX: Regex = Regex::new(r",([)\]}>]|$)").unwrap();
+}
+
+// The extensions of the files of languages in which a trailing comma in a list is optional.
+const TRAILING_COMMA_EXTENSIONS: &[&str] = &[
+    "cjs", "dart", "go", "js", "jsx", "kt", "kts", "mjs", "php", "py", "rs", "swift", "ts", "tsx",
+];
+
+const BUILTIN_BADGE_NAMES: &[&str] = &[
+    "whitespace-only",
+    "comment-only",
+    "import-changes",
+    "rename-var",
+];
+
+/// A rule which gives a hunk a badge.
+pub struct HunkBadgeRule {
+    pub name: String,
+    classifier: Classifier,
+}
+
+enum Classifier {
+    // The removed and added lines differ only in whitespace.
+    WhitespaceOnly,
+    // Every removed and added line is a comment, or blank.
+    CommentOnly,
+    // Every removed and added line is an import statement, or blank.
+    ImportChanges,
+    // The removed and added lines differ only in that one identifier is replaced by another.
+    RenameVar,
+    // Every removed and added line matches the --hunk-badge regex.
+    Matching(Regex),
+}
+
+/// Return the rules of --hunk-badges and --hunk-badge. A --hunk-badge value is of the form
+/// 'NAME=REGEX'; a rule with the name of a built-in badge replaces the built-in rule.
+pub fn make_hunk_badge_rules(
+    use_builtin_rules: bool,
+    hunk_badges: &[String],
+) -> Vec<HunkBadgeRule> {
+    let rules: Vec<HunkBadgeRule> = hunk_badges
+        .iter()
+        .map(|s| {
+            let (name, regex) = match s.split_once('=') {
+                Some((name, regex)) if !name.is_empty() => (name, regex),
+                _ => {
+                    eprintln!(
+                        "Invalid value for --hunk-badge: '{}'. It should be of the form \
+                         'NAME=REGEX'.",
+                        s
+                    );
+                    process::exit(1);
+                }
+            };
+            let regex = Regex::new(regex).unwrap_or_else(|_| {
+                eprintln!(
+                    "Invalid hunk-badge: {}. \
+                     The value must be a valid Rust regular expression. \
+                     See https://docs.rs/regex.",
+                    regex
+                );
+                process::exit(1);
+            });
+            HunkBadgeRule {
+                name: name.to_string(),
+                classifier: Classifier::Matching(regex),
+            }
+        })
+        .collect();
+    if !use_builtin_rules {
+        return rules;
+    }
+    let mut builtin_rules: Vec<HunkBadgeRule> = BUILTIN_BADGE_NAMES
+        .iter()
+        .filter(|name| !rules.iter().any(|rule| rule.name == **name))
+        .map(|name| HunkBadgeRule {
+            name: name.to_string(),
+            classifier: match *name {
+                "whitespace-only" => Classifier::WhitespaceOnly,
+                "comment-only" => Classifier::CommentOnly,
+                "import-changes" => Classifier::ImportChanges,
+                _ => Classifier::RenameVar,
+            },
+        })
+        .collect();
+    builtin_rules.extend(rules);
+    builtin_rules
+}
+
+/// Return the badges of a hunk, e.g. "[comment-only]", given its lines (without the hunk header).
+/// Hunks without removed or added lines have no badges.
+pub fn get_hunk_badges(hunk_lines: &[String], rules: &[HunkBadgeRule]) -> Option<String> {
+    let changed_lines = |marker| -> Vec<&str> {
+        hunk_lines
+            .iter()
+            .filter_map(|line| line.strip_prefix(marker))
+            .map(|line| line.trim_end_matches('\n'))
+            .collect()
+    };
+    let (minus_lines, plus_lines) = (changed_lines('-'), changed_lines('+'));
+    if minus_lines.is_empty() && plus_lines.is_empty() {
+        return None;
+    }
+    let badges: Vec<String> = rules
+        .iter()
+        .filter(|rule| rule.classifier.matches(&minus_lines, &plus_lines))
+        .map(|rule| format!("[{}]", rule.name))
+        .collect();
+    if badges.is_empty() {
+        None
+    } else {
+        Some(badges.join(" "))
+    }
+}
+
+/// Return the badge of a hunk which only reorders imports or reformats code, and which is collapsed
+/// under --collapse-noise-hunks, given its lines (without the hunk header) and its file's path.
+/// Imports are reordered when the changed lines are all imports, and the removed and added lines
+/// have the same tokens. Code is reformatted when the removed and added lines differ only in
+/// whitespace and, in languages in which they are optional, trailing commas.
+pub fn get_noise_badge(hunk_lines: &[String], path: &str) -> Option<&'static str> {
+    let changed_lines = |marker| -> Vec<&str> {
+        hunk_lines
+            .iter()
+            .filter_map(|line| line.strip_prefix(marker))
+            .map(|line| line.trim_end_matches('\n'))
+            .collect()
+    };
+    let (minus_lines, plus_lines) = (changed_lines('-'), changed_lines('+'));
+    if minus_lines.is_empty() && plus_lines.is_empty() {
+        return None;
+    }
+    let sorted_tokens = |lines: &[&str]| -> Vec<String> {
+        let mut tokens: Vec<String> = lines
+            .iter()
+            .flat_map(|line| TOKEN_REGEX.find_iter(line))
+            .map(|m| m.as_str().to_string())
+            .filter(|token| token != ",")
+            .collect();
+        tokens.sort();
+        tokens
+    };
+    if Classifier::ImportChanges.matches(&minus_lines, &plus_lines)
+        && sorted_tokens(&minus_lines) == sorted_tokens(&plus_lines)
+    {
+        return Some("import-reordering");
+    }
+    let has_optional_trailing_commas = path.rsplit_once('.').map_or(false, |(_, extension)| {
+        TRAILING_COMMA_EXTENSIONS.contains(&extension)
+    });
+    let normalize = |lines: &[&str]| -> String {
+        let code: String = lines
+            .iter()
+            .flat_map(|line| line.chars())
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        if has_optional_trailing_commas {
+            TRAILING_COMMA_REGEX.replace_all(&code, "$1").into_owned()
+        } else {
+            code
+        }
+    };
+    if normalize(&minus_lines) == normalize(&plus_lines) {
+        return Some("formatting");
+    }
+    None
+}
+
+impl Classifier {
+    fn matches(&self, minus_lines: &[&str], plus_lines: &[&str]) -> bool {
+        let all_lines = || minus_lines.iter().chain(plus_lines.iter());
+        let is_blank = |line: &&&str| line.trim().is_empty();
+        match self {
+            Self::WhitespaceOnly => {
+                let non_whitespace = |lines: &[&str]| -> String {
+                    lines
+                        .iter()
+                        .flat_map(|line| line.chars())
+                        .filter(|c| !c.is_whitespace())
+                        .collect()
+                };
+                non_whitespace(minus_lines) == non_whitespace(plus_lines)
+            }
+            Self::CommentOnly => {
+                all_lines().any(|line| !is_blank(&line))
+                    && all_lines().all(|line| {
+                        is_blank(&line)
+                            || COMMENT_REGEX.is_match(line)
+                                && !PREPROCESSOR_DIRECTIVE_REGEX.is_match(line)
+                    })
+            }
+            Self::ImportChanges => {
+                all_lines().any(|line| !is_blank(&line))
+                    && all_lines().all(|line| is_blank(&line) || IMPORT_REGEX.is_match(line))
+            }
+            // A changed word in a comment is not a renaming.
+            Self::RenameVar => {
+                is_rename(minus_lines, plus_lines)
+                    && !Self::CommentOnly.matches(minus_lines, plus_lines)
+            }
+            Self::Matching(regex) => all_lines().all(|line| regex.is_match(line)),
+        }
+    }
+}
+
+/// Is each added line the corresponding removed line with one identifier consistently replaced by
+/// another?
+fn is_rename(minus_lines: &[&str], plus_lines: &[&str]) -> bool {
+    if minus_lines.len() != plus_lines.len() {
+        return false;
+    }
+    let mut renaming: Option<(&str, &str)> = None;
+    for (minus_line, plus_line) in minus_lines.iter().zip(plus_lines) {
+        let minus_tokens: Vec<&str> = TOKEN_REGEX
+            .find_iter(minus_line)
+            .map(|m| m.as_str())
+            .collect();
+        let plus_tokens: Vec<&str> = TOKEN_REGEX
+            .find_iter(plus_line)
+            .map(|m| m.as_str())
+            .collect();
+        if minus_tokens.len() != plus_tokens.len() {
+            return false;
+        }
+        for (minus_token, plus_token) in minus_tokens.into_iter().zip(plus_tokens) {
+            if minus_token == plus_token {
+                continue;
+            }
+            if !IDENTIFIER_REGEX.is_match(minus_token) || !IDENTIFIER_REGEX.is_match(plus_token) {
+                return false;
+            }
+            match renaming {
+                None => renaming = Some((minus_token, plus_token)),
+                Some(renaming) if renaming == (minus_token, plus_token) => {}
+                Some(_) => return false,
+            }
+        }
+    }
+    renaming.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    fn get_badges(hunk: &str) -> Option<String> {
+        let hunk_lines: Vec<String> = hunk.lines().map(|line| line.to_string()).collect();
+        get_hunk_badges(&hunk_lines, &make_hunk_badge_rules(true, &[]))
+    }
+
+    #[test]
+    fn test_hunk_badges() {
+        assert_eq!(
+            get_badges("-if x:\n-    y()\n+if x: y()"),
+            Some("[whitespace-only]".to_string())
+        );
+        assert_eq!(
+            get_badges("-# a comment\n+# another comment\n+"),
+            Some("[comment-only]".to_string())
+        );
+        assert_eq!(
+            get_badges("-#include <a.h>\n+#include <b.h>\n+#include <c.h>"),
+            Some("[import-changes]".to_string())
+        );
+        assert_eq!(
+            get_badges("-import os\n+import os, sys"),
+            Some("[import-changes]".to_string())
+        );
+        assert_eq!(
+            get_badges(" def f(x):\n-    y = x\n-    return y\n+    z = x\n+    return z"),
+            Some("[rename-var]".to_string())
+        );
+        assert_eq!(get_badges("-    y = x\n+    z = w"), None);
+        assert_eq!(get_badges("-a = 1\n+a = 2"), None);
+        assert_eq!(get_badges(" a = 1"), None);
+    }
+
+    #[test]
+    fn test_get_noise_badge() {
+        let get_badge = |hunk: &str, path: &str| {
+            let hunk_lines: Vec<String> = hunk.lines().map(|line| line.to_string()).collect();
+            get_noise_badge(&hunk_lines, path)
+        };
+        assert_eq!(
+            get_badge("-import sys\n import re\n+import sys", "a.py"),
+            Some("import-reordering")
+        );
+        assert_eq!(
+            get_badge("-import sys\n-import os\n+import os\n+import sys", "a.py"),
+            Some("import-reordering")
+        );
+        assert_eq!(
+            get_badge("-use std::{io, fs};\n+use std::{fs, io};", "a.rs"),
+            Some("import-reordering")
+        );
+        assert_eq!(get_badge("-import sys\n+import os", "a.py"), None);
+        assert_eq!(
+            get_badge("-f(a, b)\n+f(\n+    a,\n+    b,\n+)", "a.rs"),
+            Some("formatting")
+        );
+        assert_eq!(get_badge("-f(a, b)\n+f(a, b,)", "a.c"), None);
+        assert_eq!(get_badge("-f(a, b)\n+f(a, c)", "a.rs"), None);
+        assert_eq!(get_badge(" f(a, b)", "a.rs"), None);
+    }
+
+    #[test]
+    fn test_collapse_noise_hunks() {
+        let config = make_config_from_args(&["--collapse-noise-hunks"]);
+        let output = strip_ansi_codes(&run_delta(NOISE_DIFF, &config)).to_string();
+        assert!(output
+            .contains("\nhunk 1 collapsed [import-reordering] (--select a.py:1 to display it)\n"));
+        assert!(
+            output.contains("\nhunk 2 collapsed [formatting] (--select a.py:2 to display it)\n")
+        );
+        assert!(output.contains("\n    return 2\n"));
+
+        let config = make_config_from_args(&["--collapse-noise-hunks", "--select", "a.py:2"]);
+        let output = strip_ansi_codes(&run_delta(NOISE_DIFF, &config)).to_string();
+        assert!(!output.contains("collapsed"));
+        assert!(output.contains("\n        a,\n"));
+    }
+
+    #[test]
+    fn test_collapse_noise_hunks_diff_filter_mode() {
+        let config = make_config_from_args(&["--collapse-noise-hunks", "--diff-filter-mode"]);
+        let output = run_delta(NOISE_DIFF, &config);
+        assert_eq!(output.lines().count(), NOISE_DIFF.lines().count());
+    }
+
+    #[test]
+    fn test_custom_hunk_badge_rules() {
+        let rules = make_hunk_badge_rules(
+            true,
+            &[
+                "version=^version = ".to_string(),
+                "comment-only=^\\s*//".to_string(),
+            ],
+        );
+        let names: Vec<&str> = rules.iter().map(|rule| rule.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "whitespace-only",
+                "import-changes",
+                "rename-var",
+                "version",
+                "comment-only"
+            ]
+        );
+        let hunk_lines = vec!["-# a".to_string(), "+# b".to_string()];
+        assert_eq!(get_hunk_badges(&hunk_lines, &rules), None);
+        let hunk_lines = vec![
+            "-version = \"0.1\"".to_string(),
+            "+version = \"0.2\"".to_string(),
+        ];
+        assert_eq!(
+            get_hunk_badges(&hunk_lines, &rules),
+            Some("[version]".to_string())
+        );
+    }
+
+    #[test]
+    fn test_hunk_badges_in_hunk_headers() {
+        let config = make_config_from_args(&[
+            "--hunk-badges",
+            "--hunk-header-style",
+            "plain",
+            "--hunk-header-decoration-style",
+            "none",
+        ]);
+        let output = strip_ansi_codes(&run_delta(DIFF, &config)).to_string();
+        assert!(output.contains("\n[comment-only] def f(): \n"));
+        assert!(output.contains("\n[rename-var] def g(): \n"));
+        assert!(output.contains("\ndef h(): \n"));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@ def f():
+ a = 1
+-# b
++# c d
+@@ -10,2 +10,2 @@ def g():
+-    x = 1
+-    return x
++    y = 1
++    return y
+@@ -20,1 +20,1 @@ def h():
+-    return 1
++    return 2
+";
+
+    const NOISE_DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@
+-import sys
+ import re
++import sys
+@@ -10,2 +10,5 @@ def f():
+-    f(a, b)
++    f(
++        a,
++        b,
++    )
+@@ -20,1 +20,1 @@ def h():
+-    return 1
++    return 2
+";
+}
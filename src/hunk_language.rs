@@ -0,0 +1,147 @@
+use std::process;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::config::Config;
+
+// The language used to highlight a hunk may differ from that of its file, for hunks which lie in
+// an embedded region such as a SQL query in a Python string, or a shell script in a YAML file.
+// Such a hunk's language is given either by a `delta: lang=LANGUAGE` directive in one of its
+// lines, e.g. in a comment, under --hunk-lang-directives, or by a --lang-for-hunks-matching rule.
+
+lazy_static! {
+    static ref LANG_DIRECTIVE_REGEX: Regex = Regex::new(r"\bdelta:\s*lang=([\w+#.-]+)").unwrap();
+}
+
+/// A --lang-for-hunks-matching rule: hunks with a line matching the regex are highlighted as the
+/// language.
+pub struct HunkLanguageRule {
+    regex: Regex,
+    language: String,
+}
+
+/// Parse the --lang-for-hunks-matching values, which are of the form 'REGEX=LANGUAGE'.
+pub fn make_hunk_language_rules(values: &[String]) -> Vec<HunkLanguageRule> {
+    values
+        .iter()
+        .map(|s| {
+            let (regex, language) = match s.rsplit_once('=') {
+                Some((regex, language)) if !language.is_empty() => (regex, language),
+                _ => {
+                    eprintln!(
+                        "Invalid value for --lang-for-hunks-matching: '{}'. It should be of the \
+                         form 'REGEX=LANGUAGE'.",
+                        s
+                    );
+                    process::exit(1);
+                }
+            };
+            let regex = Regex::new(regex).unwrap_or_else(|_| {
+                eprintln!(
+                    "Invalid lang-for-hunks-matching: {}. \
+                     The value must be a valid Rust regular expression. \
+                     See https://docs.rs/regex.",
+                    regex
+                );
+                process::exit(1);
+            });
+            HunkLanguageRule {
+                regex,
+                language: language.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Return the language in which a hunk is to be highlighted, if it differs from that of its file,
+/// given its lines (without the hunk header). A directive takes precedence over the rules, and the
+/// first matching rule applies.
+pub fn get_hunk_language(hunk_lines: &[String], config: &Config) -> Option<String> {
+    let lines = || hunk_lines.iter().map(|line| line.get(1..).unwrap_or(""));
+    if config.hunk_lang_directives {
+        if let Some(captures) = lines().find_map(|line| LANG_DIRECTIVE_REGEX.captures(line)) {
+            return Some(captures[1].to_string());
+        }
+    }
+    config
+        .hunk_language_rules
+        .iter()
+        .find(|rule| lines().any(|line| rule.regex.is_match(line)))
+        .map(|rule| rule.language.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    fn get_language(hunk: &str, args: &[&str]) -> Option<String> {
+        let hunk_lines: Vec<String> = hunk.lines().map(|line| line.to_string()).collect();
+        get_hunk_language(&hunk_lines, &make_config_from_args(args))
+    }
+
+    #[test]
+    fn test_get_hunk_language() {
+        let hunk = " query = '''  # delta: lang=sql\n-SELECT a\n+SELECT b\n";
+        assert_eq!(
+            get_language(hunk, &["--hunk-lang-directives"]),
+            Some("sql".to_string())
+        );
+        assert_eq!(get_language(hunk, &[]), None);
+        assert_eq!(
+            get_language(
+                hunk,
+                &[
+                    "--hunk-lang-directives",
+                    "--lang-for-hunks-matching",
+                    "SELECT=bash"
+                ]
+            ),
+            Some("sql".to_string())
+        );
+        assert_eq!(
+            get_language(
+                "-SELECT a\n+SELECT b\n",
+                &[
+                    "--lang-for-hunks-matching",
+                    "^import=py",
+                    "--lang-for-hunks-matching",
+                    "^SELECT\\b=sql"
+                ]
+            ),
+            Some("sql".to_string())
+        );
+    }
+
+    #[test]
+    fn test_hunk_language_is_used_for_highlighting() {
+        let config = make_config_from_args(&["--lang-for-hunks-matching", "^SELECT=sql"]);
+        let output = run_delta(DIFF, &config);
+        let sql_config = make_config_from_args(&["--default-language", "sql"]);
+        let sql_output = run_delta(&DIFF.replace("a.txt", "a"), &sql_config);
+        let hunk_lines = |output: &str| -> Vec<String> {
+            output
+                .lines()
+                .skip_while(|line| !line.contains("SELECT"))
+                .map(|line| line.to_string())
+                .collect()
+        };
+        assert_eq!(hunk_lines(&output), hunk_lines(&sql_output));
+        let txt_output = run_delta(DIFF, &make_config_from_args(&[]));
+        assert_ne!(hunk_lines(&output), hunk_lines(&txt_output));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.txt b/a.txt
+index 223ca50..e69de29 100644
+--- a/a.txt
++++ b/a.txt
+@@ -1,2 +1,2 @@
+ SELECT name FROM users
+-WHERE id = 1;
++WHERE id = 2;
+";
+}
@@ -0,0 +1,264 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+// Under --invert, the input is read as the reverse diff: what was removed is displayed as added,
+// and vice versa, so that e.g. `git diff HEAD upstream | delta --invert` shows what upstream would
+// change in the working copy. The file metadata and hunks of each file are rewritten before they
+// are rendered, so that styles, side-by-side panels and line numbers all follow.
+
+lazy_static! {
+    static ref DIFF_GIT_LINE_REGEX: Regex = Regex::new(r"^diff --git a/(.+?) b/(.+)$").unwrap();
+    static ref INDEX_LINE_REGEX: Regex = Regex::new(r"^index ([0-9a-f]+)\.\.([0-9a-f]+)").unwrap();
+    static ref HUNK_HEADER_COORDINATES_REGEX: Regex =
+        Regex::new(r"^@@ -([0-9,]+) \+([0-9,]+) @@").unwrap();
+}
+
+/// Return the file metadata lines of the reverse diff.
+pub fn invert_file_meta(lines: &[String]) -> Vec<String> {
+    let minus_path = lines.iter().find_map(|line| line.strip_prefix("--- "));
+    let plus_path = lines.iter().find_map(|line| line.strip_prefix("+++ "));
+    let rename_from = lines
+        .iter()
+        .find_map(|line| line.strip_prefix("rename from "));
+    let rename_to = lines
+        .iter()
+        .find_map(|line| line.strip_prefix("rename to "));
+    lines
+        .iter()
+        .map(|line| {
+            if let Some(caps) = DIFF_GIT_LINE_REGEX.captures(line) {
+                format!("diff --git a/{} b/{}", &caps[2], &caps[1])
+            } else if let Some(caps) = INDEX_LINE_REGEX.captures(line) {
+                format!(
+                    "index {}..{}{}",
+                    &caps[2],
+                    &caps[1],
+                    &line[caps.get(0).unwrap().end()..]
+                )
+            } else if line.starts_with("--- ") {
+                format!(
+                    "--- {}",
+                    swap_path_prefix(plus_path.unwrap_or(""), "b/", "a/")
+                )
+            } else if line.starts_with("+++ ") {
+                format!(
+                    "+++ {}",
+                    swap_path_prefix(minus_path.unwrap_or(""), "a/", "b/")
+                )
+            } else if line.starts_with("rename from ") {
+                format!("rename from {}", rename_to.unwrap_or(""))
+            } else if line.starts_with("rename to ") {
+                format!("rename to {}", rename_from.unwrap_or(""))
+            } else if let Some(mode) = line.strip_prefix("new file mode ") {
+                format!("deleted file mode {}", mode)
+            } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+                format!("new file mode {}", mode)
+            } else if let Some(mode) = line.strip_prefix("old mode ") {
+                format!("new mode {}", mode)
+            } else if let Some(mode) = line.strip_prefix("new mode ") {
+                format!("old mode {}", mode)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
+fn swap_path_prefix(path: &str, prefix: &str, new_prefix: &str) -> String {
+    match path.strip_prefix(prefix) {
+        Some(path) => format!("{}{}", new_prefix, path),
+        None => path.to_string(),
+    }
+}
+
+/// Return the lines of the reverse of a hunk: the line ranges of the hunk header are swapped, and
+/// in each run of changed lines, the added lines become removed lines which precede the removed
+/// lines, which become added lines. The hunks of merge commits' combined diffs are not inverted.
+pub fn invert_hunk(lines: &[String]) -> Vec<String> {
+    let header = match lines.first() {
+        Some(header) => header,
+        None => return Vec::new(),
+    };
+    let caps = match HUNK_HEADER_COORDINATES_REGEX.captures(header) {
+        Some(caps) => caps,
+        None => return lines.to_vec(),
+    };
+    let mut inverted = vec![format!(
+        "@@ -{} +{} @@{}",
+        &caps[2],
+        &caps[1],
+        &header[caps.get(0).unwrap().end()..]
+    )];
+    // The changed lines of the current run, each with any "\ No newline at end of file" line
+    // which follows it
+    let mut minus_lines: Vec<Vec<String>> = Vec::new();
+    let mut plus_lines: Vec<Vec<String>> = Vec::new();
+    let mut last_is_plus = false;
+    for line in &lines[1..] {
+        if let Some(content) = line.strip_prefix('-') {
+            minus_lines.push(vec![format!("+{}", content)]);
+            last_is_plus = false;
+        } else if let Some(content) = line.strip_prefix('+') {
+            plus_lines.push(vec![format!("-{}", content)]);
+            last_is_plus = true;
+        } else if line.starts_with('\\') && (!minus_lines.is_empty() || !plus_lines.is_empty()) {
+            let run = if last_is_plus {
+                &mut plus_lines
+            } else {
+                &mut minus_lines
+            };
+            run.last_mut().unwrap().push(line.to_string());
+        } else {
+            inverted.extend(plus_lines.drain(..).flatten());
+            inverted.extend(minus_lines.drain(..).flatten());
+            inverted.push(line.to_string());
+        }
+    }
+    inverted.extend(plus_lines.drain(..).flatten());
+    inverted.extend(minus_lines.drain(..).flatten());
+    inverted
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    fn to_strings(s: &str) -> Vec<String> {
+        s.lines().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_invert_file_meta() {
+        let file_meta = to_strings(
+            "\
+diff --git a/a.py b/b.py
+similarity index 90%
+rename from a.py
+rename to b.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/b.py",
+        );
+        assert_eq!(
+            invert_file_meta(&file_meta),
+            to_strings(
+                "\
+diff --git a/b.py b/a.py
+similarity index 90%
+rename from b.py
+rename to a.py
+index e69de29..223ca50 100644
+--- a/b.py
++++ b/a.py"
+            )
+        );
+    }
+
+    #[test]
+    fn test_invert_file_meta_of_added_file() {
+        let file_meta = to_strings(
+            "\
+diff --git a/a.py b/a.py
+new file mode 100644
+index 0000000..e69de29
+--- /dev/null
++++ b/a.py",
+        );
+        assert_eq!(
+            invert_file_meta(&file_meta),
+            to_strings(
+                "\
+diff --git a/a.py b/a.py
+deleted file mode 100644
+index e69de29..0000000
+--- a/a.py
++++ /dev/null"
+            )
+        );
+    }
+
+    #[test]
+    fn test_invert_hunk() {
+        let hunk = to_strings(
+            "\
+@@ -1,4 +1,3 @@ def f():
+ a = 1
+-b = 2
+-c = 3
++b = 4
+ d = 5
+-e = 6
+\\ No newline at end of file",
+        );
+        assert_eq!(
+            invert_hunk(&hunk),
+            to_strings(
+                "\
+@@ -1,3 +1,4 @@ def f():
+ a = 1
+-b = 4
++b = 2
++c = 3
+ d = 5
++e = 6
+\\ No newline at end of file"
+            )
+        );
+    }
+
+    #[test]
+    fn test_invert() {
+        let output = run_delta(
+            DIFF,
+            &make_config_from_args(&["--invert", "--line-numbers"]),
+        );
+        let inverted_output = run_delta(INVERTED_DIFF, &make_config_from_args(&["--line-numbers"]));
+        assert_eq!(output, inverted_output);
+        assert!(strip_ansi_codes(&output).contains("removed: a.py"));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+new file mode 100644
+index 0000000..e69de29
+--- /dev/null
++++ b/a.py
+@@ -0,0 +1,2 @@
++a = 1
++b = 2
+diff --git a/b.py b/b.py
+index 223ca50..e69de29 100644
+--- a/b.py
++++ b/b.py
+@@ -1,3 +1,3 @@
+ a = 1
+-b = 2
++b = 3
+ c = 4
+";
+
+    const INVERTED_DIFF: &str = "\
+diff --git a/a.py b/a.py
+deleted file mode 100644
+index e69de29..0000000
+--- a/a.py
++++ /dev/null
+@@ -1,2 +0,0 @@
+-a = 1
+-b = 2
+diff --git a/b.py b/b.py
+index e69de29..223ca50 100644
+--- a/b.py
++++ b/b.py
+@@ -1,3 +1,3 @@
+ a = 1
+-b = 3
++b = 2
+ c = 4
+";
+}
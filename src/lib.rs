@@ -0,0 +1,102 @@
+//! Delta's rendering of diffs, for programs such as editor plugins which display diffs without
+//! running delta as a subprocess.
+//!
+//! ```
+//! let config = git_delta::make_config(&["--no-gitconfig", "--line-numbers"]).unwrap();
+//! let hunk = "@@ -1 +1 @@\n-a = 1\n+a = 2\n";
+//! let output = git_delta::render_hunk(hunk, "a.py", "a.py", &config).unwrap();
+//! assert!(console::strip_ansi_codes(&output).contains("a = 2"));
+//! assert!(git_delta::make_config(&["--no-such-option"]).is_err());
+//! ```
+
+// The modules are those of the delta binary, of which the library uses only a part.
+#![allow(dead_code)]
+
+extern crate bitflags;
+
+#[macro_use]
+extern crate error_chain;
+
+mod align;
+mod ansi;
+mod bat;
+mod benchmark;
+mod blame;
+mod cli;
+mod color;
+mod commit_links;
+mod config;
+mod control_chars;
+mod delta;
+mod draw;
+mod edits;
+mod env;
+mod features;
+mod filter;
+mod funcname;
+mod git_command;
+mod git_config;
+mod grep;
+mod highlight;
+mod highlight_patterns;
+mod hunk_badges;
+mod hunk_language;
+mod invert;
+mod line_plugin;
+mod lockfile;
+mod log;
+mod mono;
+mod notebook;
+mod numbering;
+mod options;
+mod paint;
+mod parse;
+mod parse_style;
+mod pre_render;
+mod redact;
+mod rej;
+mod reload;
+mod render_cache;
+mod sdiff;
+mod show;
+mod show_styles;
+mod split_output;
+mod stats;
+mod structured_diff;
+mod style;
+mod syntect_color;
+mod terminal_profile;
+mod tests;
+mod validate;
+mod verify_emulation;
+mod warnings;
+mod word_diff;
+
+use structopt::clap;
+
+pub use crate::config::Config;
+pub use crate::delta::render_hunk;
+
+mod errors {
+    error_chain! {
+        foreign_links {
+            Io(::std::io::Error);
+            SyntectError(::syntect::LoadingError);
+            ParseIntError(::std::num::ParseIntError);
+        }
+    }
+}
+
+/// Make the config for these delta options, e.g. `&["--side-by-side"]`, given as they would be on
+/// the command line, combined with the [delta] section of the user's git config as the delta
+/// binary combines them. As in the delta binary, an invalid option value, such as an invalid
+/// style, is reported on stderr and ends the process.
+pub fn make_config(args: &[&str]) -> Result<Config, clap::Error> {
+    let args = std::iter::once("delta").chain(args.iter().copied());
+    let opt = cli::Opt::from_iter_safe_and_git_config(
+        args,
+        &mut git_config::GitConfig::try_create(),
+        bat::assets::HighlightingAssets::new(),
+    )?;
+    Ok(Config::from(opt))
+}
@@ -0,0 +1,222 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::style::Style;
+use crate::warnings;
+
+/// A --line-plugin program, which is sent a record of each hunk line and may replace the line's
+/// content or style. The program is started once, and communicates in JSON lines: for each hunk
+/// line, delta writes a record such as
+///
+///   {"content":"b = 2","file":"a.py","minus_line_number":2,"plus_line_number":null,"state":"minus"}
+///
+/// to its standard input, and then reads a single line from its standard output, which must be a
+/// JSON object with optional "content" and "style" fields, e.g. {} to leave the line unchanged, or
+/// {"content": "b = 3", "style": "bold red"}. The style is a delta style string, which replaces
+/// the line's minus-style, plus-style or zero-style. The program must flush its output after each
+/// response.
+pub struct LinePlugin {
+    command: String,
+    process: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+    minus_line_number: usize,
+    plus_line_number: usize,
+}
+
+impl LinePlugin {
+    /// Start the --line-plugin program, if any. If it cannot be started, a warning is written and
+    /// hunk lines are displayed as usual.
+    pub fn new(config: &Config) -> Option<Self> {
+        let command = config.line_plugin.as_deref()?;
+        match Self::spawn(command) {
+            Some(plugin) => Some(plugin),
+            None => {
+                warnings::warn(
+                    "line-plugin",
+                    &format!("failed to start --line-plugin command: {}", command),
+                    config,
+                );
+                None
+            }
+        }
+    }
+
+    fn spawn(command: &str) -> Option<Self> {
+        let words = shell_words::split(command).ok()?;
+        let (program, args) = words.split_first()?;
+        let mut process = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+        let stdin = process.stdin.take()?;
+        let stdout = BufReader::new(process.stdout.take()?);
+        Some(Self {
+            command: command.to_string(),
+            process,
+            stdin: Some(stdin),
+            stdout,
+            minus_line_number: 0,
+            plus_line_number: 0,
+        })
+    }
+
+    /// Set the line numbers of the first lines of a hunk, from its hunk header.
+    pub fn start_hunk(&mut self, line_numbers: &[(usize, usize)]) {
+        self.minus_line_number = line_numbers[0].0;
+        self.plus_line_number = line_numbers[line_numbers.len() - 1].0;
+    }
+
+    /// Send a hunk line to the program, and return the line with the program's changes, and the
+    /// style it gave the line, if any. Return None if the program has failed; the failure is
+    /// reported, and the plugin should not be used again.
+    pub fn transform_line(
+        &mut self,
+        line: &str,
+        file: &str,
+        config: &Config,
+    ) -> Option<(String, Option<Style>)> {
+        let (state, prefix, content) = match line.chars().next() {
+            Some('-') => ("minus", "-", &line[1..]),
+            Some('+') => ("plus", "+", &line[1..]),
+            Some(' ') => ("zero", " ", &line[1..]),
+            _ => return Some((line.to_string(), None)),
+        };
+        let (minus_line_number, plus_line_number) = match state {
+            "minus" => (Some(self.minus_line_number), None),
+            "plus" => (None, Some(self.plus_line_number)),
+            _ => (Some(self.minus_line_number), Some(self.plus_line_number)),
+        };
+        if minus_line_number.is_some() {
+            self.minus_line_number += 1;
+        }
+        if plus_line_number.is_some() {
+            self.plus_line_number += 1;
+        }
+        let record = json!({
+            "state": state,
+            "content": content,
+            "file": file,
+            "minus_line_number": minus_line_number,
+            "plus_line_number": plus_line_number,
+        });
+        let response = self.exchange(&record).and_then(|response| {
+            let style = response
+                .get("style")
+                .and_then(Value::as_str)
+                .map(|style| Style::try_from_str(style, config.true_color))
+                .transpose()
+                .map_err(|error| format!("invalid style: {}", error))?;
+            Ok((response, style))
+        });
+        let (response, style) = match response {
+            Ok(response) => response,
+            Err(reason) => {
+                warnings::warn(
+                    "line-plugin",
+                    &format!(
+                        "--line-plugin command {}: {}: \
+                         displaying the remainder of the input without it.",
+                        self.command, reason
+                    ),
+                    config,
+                );
+                return None;
+            }
+        };
+        let content = match response.get("content").and_then(Value::as_str) {
+            Some(content) => format!("{}{}", prefix, content),
+            None => line.to_string(),
+        };
+        Some((content, style))
+    }
+
+    fn exchange(&mut self, record: &Value) -> Result<Value, String> {
+        let stdin = self.stdin.as_mut().ok_or("no input")?;
+        writeln!(stdin, "{}", record)
+            .and_then(|_| stdin.flush())
+            .map_err(|err| format!("failed to write: {}", err))?;
+        let mut response = String::new();
+        match self.stdout.read_line(&mut response) {
+            Ok(0) => return Err("exited".to_string()),
+            Err(err) => return Err(format!("failed to read: {}", err)),
+            Ok(_) => {}
+        }
+        match serde_json::from_str(&response) {
+            Ok(Value::Object(response)) => Ok(Value::Object(response)),
+            _ => Err(format!("invalid response: {}", response.trim_end())),
+        }
+    }
+}
+
+impl Drop for LinePlugin {
+    fn drop(&mut self) {
+        // Closing the program's input tells it that there are no more lines.
+        self.stdin = None;
+        let _ = self.process.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    // A plugin which makes added lines bold red, and replaces the content of removed lines. The
+    // fields of the records are in alphabetical order.
+    const PLUGIN: &str = "sed -u \
+        -e 's/.*\"state\":\"plus\".*/{\"style\":\"bold red\"}/' \
+        -e 's/.*\"minus_line_number\":\\([0-9]*\\).*\"state\":\"minus\".*/{\"content\":\"line \\1\"}/' \
+        -e 's/.*\"state\":\"zero\".*/{}/'";
+
+    #[test]
+    fn test_line_plugin() {
+        let config = make_config_from_args(&["--line-plugin", PLUGIN]);
+        let output = run_delta(DIFF, &config);
+        let bold_red = crate::style::Style::from_str("bold red", None, None, true, false);
+        assert!(output.contains(&bold_red.paint("b = 3").to_string()));
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\nline 2\n"));
+        assert!(!output.contains("b = 2"));
+        assert!(output.contains("\na = 1\n"));
+    }
+
+    #[test]
+    fn test_failing_line_plugin() {
+        let config = make_config_from_args(&["--line-plugin", "echo not-json"]);
+        let output = run_delta(DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\nb = 2\n"));
+        assert!(output.contains("\nb = 3\n"));
+    }
+
+    #[test]
+    fn test_line_plugin_with_invalid_style() {
+        let plugin = "sed -u -e 's/.*/{\"style\":\"nocolor\"}/'";
+        let config = make_config_from_args(&["--line-plugin", plugin]);
+        let output = run_delta(DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\na = 1\n"));
+        assert!(output.contains("\nb = 2\n"));
+        assert!(output.contains("\nb = 3\n"));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@
+ a = 1
+-b = 2
++b = 3
+";
+}
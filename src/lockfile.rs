@@ -0,0 +1,296 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::config::Config;
+
+// The diff of a lockfile, such as Cargo.lock, can run to thousands of lines which say little more
+// than which package versions changed. Unless --no-lockfile-summary is given, the hunks of a
+// recognized lockfile are replaced by a summary of the packages which were added, removed,
+// upgraded or downgraded. The packages are read from the hunks themselves: the removed and
+// context lines of a hunk give the old versions of the packages which appear in it, and the added
+// and context lines give the new versions.
+
+lazy_static! {
+    static ref TOML_NAME_REGEX: Regex = Regex::new(r#"^name = "([^"]+)"$"#).unwrap();
+    static ref TOML_VERSION_REGEX: Regex = Regex::new(r#"^version = "([^"]+)"$"#).unwrap();
+    static ref NPM_PACKAGE_REGEX: Regex = Regex::new(r#"^\s*"([^"]*)": \{$"#).unwrap();
+    static ref NPM_VERSION_REGEX: Regex = Regex::new(r#"^\s*"version": "([^"]+)",?$"#).unwrap();
+    static ref YARN_VERSION_REGEX: Regex = Regex::new(r#"^\s+version:? "?([^"]+)"?$"#).unwrap();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    // Cargo.lock and poetry.lock: TOML [[package]] tables with name and version keys
+    Toml,
+    // package-lock.json: objects keyed by package name, or by "node_modules/" path
+    Npm,
+    // yarn.lock: entries keyed by package specifiers, with an indented version field
+    Yarn,
+}
+
+/// Return the format of the file at `path`, if it is a lockfile to be summarized.
+pub fn get_format(path: &str, config: &Config) -> Option<Format> {
+    if !config.lockfile_summary {
+        return None;
+    }
+    match path.rsplit('/').next()? {
+        "Cargo.lock" | "poetry.lock" => Some(Format::Toml),
+        "package-lock.json" => Some(Format::Npm),
+        "yarn.lock" => Some(Format::Yarn),
+        _ => None,
+    }
+}
+
+/// Return the lines of the summary of the package version changes made by the hunks of a lockfile,
+/// or None if no packages can be found in the hunks.
+pub fn summarize(
+    format: Format,
+    path: &str,
+    hunk_lines: &[String],
+    config: &Config,
+) -> Option<Vec<String>> {
+    let mut minus_packages = BTreeMap::new();
+    let mut plus_packages = BTreeMap::new();
+    for hunk in hunk_lines.split(|line| line.starts_with("@@")) {
+        let side = |markers: &[char]| -> Vec<&str> {
+            hunk.iter()
+                .filter(|line| line.starts_with(markers))
+                .map(|line| line[1..].trim_end_matches('\n'))
+                .collect()
+        };
+        read_packages(format, &side(&[' ', '-']), &mut minus_packages);
+        read_packages(format, &side(&[' ', '+']), &mut plus_packages);
+    }
+    if minus_packages.is_empty() && plus_packages.is_empty() {
+        return None;
+    }
+    let empty = BTreeSet::new();
+    let names: BTreeSet<&String> = minus_packages.keys().chain(plus_packages.keys()).collect();
+    let mut changes = Vec::new();
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for name in names {
+        let minus_versions = minus_packages.get(name).unwrap_or(&empty);
+        let plus_versions = plus_packages.get(name).unwrap_or(&empty);
+        let removed: Vec<&str> = minus_versions
+            .difference(plus_versions)
+            .map(String::as_str)
+            .collect();
+        let added: Vec<&str> = plus_versions
+            .difference(minus_versions)
+            .map(String::as_str)
+            .collect();
+        let (change, versions) = match (removed.is_empty(), added.is_empty()) {
+            (true, true) => continue,
+            (true, false) => ("added", added.join(", ")),
+            (false, true) => ("removed", removed.join(", ")),
+            (false, false) => (
+                if compare_versions(removed[0], added[added.len() - 1]) == Ordering::Greater {
+                    "downgraded"
+                } else {
+                    "upgraded"
+                },
+                format!(
+                    "{} {} {}",
+                    removed.join(", "),
+                    if config.ascii { "->" } else { "→" },
+                    added.join(", ")
+                ),
+            ),
+        };
+        *counts.entry(change).or_insert(0) += 1;
+        changes.push(format!("  {:<10} {} {}", change, name, versions));
+    }
+    let summary = if changes.is_empty() {
+        "no package version changes".to_string()
+    } else {
+        ["added", "removed", "upgraded", "downgraded"]
+            .iter()
+            .filter_map(|change| counts.get(change).map(|n| format!("{} {}", n, change)))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+    let mut lines = vec![format!(
+        "{}: {} (--no-lockfile-summary to display the diff)",
+        path, summary
+    )];
+    lines.extend(changes);
+    Some(lines)
+}
+
+/// Add the packages found in the lines of a lockfile to a map from package name to versions.
+fn read_packages(
+    format: Format,
+    lines: &[&str],
+    packages: &mut BTreeMap<String, BTreeSet<String>>,
+) {
+    let mut name: Option<String> = None;
+    for line in lines {
+        let version = match format {
+            Format::Toml => {
+                if line.starts_with('[') {
+                    name = None;
+                } else if let Some(captures) = TOML_NAME_REGEX.captures(line) {
+                    name = Some(captures[1].to_string());
+                }
+                TOML_VERSION_REGEX.captures(line)
+            }
+            Format::Npm => {
+                if let Some(captures) = NPM_PACKAGE_REGEX.captures(line) {
+                    // In lockfileVersion 2 and later, packages are keyed by their path.
+                    let key = captures[1].rsplit("node_modules/").next().unwrap_or("");
+                    name = Some(key.to_string()).filter(|key| !key.is_empty());
+                }
+                NPM_VERSION_REGEX.captures(line)
+            }
+            Format::Yarn => {
+                if !line.starts_with(char::is_whitespace) && line.ends_with(':') {
+                    name = get_yarn_package_name(line);
+                }
+                YARN_VERSION_REGEX.captures(line)
+            }
+        };
+        if let (Some(package), Some(version)) = (&name, version) {
+            packages
+                .entry(package.to_string())
+                .or_default()
+                .insert(version[1].to_string());
+            name = None;
+        }
+    }
+}
+
+/// Return the package name of a yarn.lock entry header such as `"@babel/core@^7.0.0", ...:` or
+/// `lodash@npm:^4.17.21:`.
+fn get_yarn_package_name(line: &str) -> Option<String> {
+    if line.starts_with('#') || line.starts_with("__metadata") {
+        return None;
+    }
+    let specifier = line
+        .trim_end_matches(':')
+        .split(", ")
+        .next()?
+        .trim_matches('"');
+    // The name of a scoped package starts with '@'.
+    let at = specifier.get(1..)?.find('@')? + 1;
+    Some(specifier[..at].to_string())
+}
+
+/// Compare two versions by their numeric components, e.g. "1.10.0" > "1.9.2".
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let components = |version: &str| -> Vec<u64> {
+        version
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|component| component.parse().ok())
+            .collect()
+    };
+    components(a).cmp(&components(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    fn get_summary(format: Format, hunk: &str) -> Option<Vec<String>> {
+        let hunk_lines: Vec<String> = hunk.lines().map(|line| line.to_string()).collect();
+        summarize(format, "lockfile", &hunk_lines, &make_config_from_args(&[]))
+    }
+
+    #[test]
+    fn test_summarize_npm_lockfile() {
+        let summary = get_summary(
+            Format::Npm,
+            r#"@@ -10,12 +10,7 @@
+     "node_modules/a": {
+-      "version": "1.10.0",
++      "version": "1.9.2",
+       "dev": true
+     },
+-    "node_modules/b/node_modules/c": {
+-      "version": "2.0.0"
+-    },
+     "node_modules/d": {
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            summary,
+            vec![
+                "lockfile: 1 removed, 1 downgraded (--no-lockfile-summary to display the diff)",
+                "  downgraded a 1.10.0 → 1.9.2",
+                "  removed    c 2.0.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_summarize_yarn_lockfile() {
+        let summary = get_summary(
+            Format::Yarn,
+            r#"@@ -1,4 +1,8 @@
+ "@babel/core@^7.0.0", "@babel/core@^7.1.0":
+-  version "7.1.0"
++  version "7.1.2"
+   resolved "https://registry.yarnpkg.com/@babel/core/-/core-7.1.2.tgz"
++
++lodash@^4.17.21:
++  version "4.17.21"
++  resolved "https://registry.yarnpkg.com/lodash/-/lodash-4.17.21.tgz"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            summary,
+            vec![
+                "lockfile: 1 added, 1 upgraded (--no-lockfile-summary to display the diff)",
+                "  upgraded   @babel/core 7.1.0 → 7.1.2",
+                "  added      lodash 4.17.21",
+            ]
+        );
+        assert_eq!(get_summary(Format::Yarn, "@@ -1 +1 @@\n-a\n+b\n"), None);
+    }
+
+    #[test]
+    fn test_lockfile_summary() {
+        let output = strip_ansi_codes(&run_delta(DIFF, &make_config_from_args(&[]))).to_string();
+        assert!(output.contains(
+            "Cargo.lock: 1 added, 1 upgraded (--no-lockfile-summary to display the diff)\n"
+        ));
+        assert!(output.contains("\n  upgraded   serde 1.0.1 → 1.0.2\n"));
+        assert!(output.contains("\n  added      yaml-rust 0.4.3\n"));
+        assert!(!output.contains("version = "));
+
+        let config = make_config_from_args(&["--no-lockfile-summary"]);
+        let output = strip_ansi_codes(&run_delta(DIFF, &config)).to_string();
+        assert!(!output.contains("upgraded"));
+        assert!(output.contains("\nversion = \"1.0.2\"\n"));
+    }
+
+    const DIFF: &str = r#"diff --git a/Cargo.lock b/Cargo.lock
+index 223ca50..e69de29 100644
+--- a/Cargo.lock
++++ b/Cargo.lock
+@@ -10,4 +10,4 @@
+ [[package]]
+ name = "serde"
+-version = "1.0.1"
++version = "1.0.2"
+ source = "registry+https://github.com/rust-lang/crates.io-index"
+@@ -40,3 +40,8 @@
+ [[package]]
+ name = "yaml"
+ version = "0.1.0"
++
++[[package]]
++name = "yaml-rust"
++version = "0.4.3"
++source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+}
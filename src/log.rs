@@ -0,0 +1,521 @@
+use chrono::{DateTime, FixedOffset, Local};
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use unicode_width::UnicodeWidthStr;
+
+use crate::cli;
+use crate::config::Config;
+use crate::paint::Painter;
+use crate::parse;
+
+// Output of `git log --oneline` and `git reflog`, optionally with --graph. For example:
+//
+// * 8c1d2f3 (HEAD -> master, tag: 0.3.1, origin/master) Fix typo
+// 8c1d2f3 (HEAD -> master) HEAD@{0}: commit: Fix typo
+lazy_static! {
+    static ref LOG_LINE_REGEX: Regex = Regex::new(
+        r"(?x)
+^
+([*|/\\_.\ ]*?)                 # 1. Graph
+([0-9a-f]{7,40})                # 2. Abbreviated commit hash
+(?:\ \(([^()]+)\))?             # 3. Ref decorations
+(?:\ ([^\s@]+@\{[^{}]+\}):)?    # 4. Reflog selector
+(.*)                            # 5. Subject, or reflog message
+$
+"
+    )
+    .unwrap();
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogLine<'a> {
+    pub graph: &'a str,
+    pub commit: &'a str,
+    pub decorations: Option<&'a str>,
+    pub reflog_selector: Option<&'a str>,
+    pub subject: &'a str,
+}
+
+/// Is this line the first line of `git log --oneline` or `git reflog` output? Only lines whose
+/// hash is followed by ref decorations or a reflog selector are recognized here, since a line
+/// starting with a hexadecimal word is otherwise not distinctive enough.
+pub fn is_log_line(line: &str) -> bool {
+    parse::classify_line_prefix(line.as_bytes()) == parse::LinePrefix::Other
+        && match parse_log_line(line) {
+            Some(log_line) => log_line.decorations.is_some() || log_line.reflog_selector.is_some(),
+            None => false,
+        }
+}
+
+/// Handle a line of `git log --oneline` or `git reflog` output. Return false if the line was not
+/// recognized.
+pub fn handle_log_line(
+    painter: &mut Painter,
+    line: &str,
+    config: &Config,
+) -> std::io::Result<bool> {
+    let log_line = match parse_log_line(line) {
+        Some(log_line) => log_line,
+        None => return Ok(false),
+    };
+    painter.emit()?;
+    writeln!(painter.writer, "{}", format_log_line(&log_line, config))?;
+    Ok(true)
+}
+
+fn parse_log_line(line: &str) -> Option<LogLine<'_>> {
+    let caps = LOG_LINE_REGEX.captures(line)?;
+    Some(LogLine {
+        graph: caps.get(1).unwrap().as_str(),
+        commit: caps.get(2).unwrap().as_str(),
+        decorations: caps.get(3).map(|m| m.as_str()),
+        reflog_selector: caps.get(4).map(|m| m.as_str()),
+        subject: caps.get(5).unwrap().as_str(),
+    })
+}
+
+fn format_log_line(log_line: &LogLine, config: &Config) -> String {
+    let mut s = String::new();
+    s.push_str(log_line.graph);
+    s.push_str(&config.log_hash_style.paint(log_line.commit).to_string());
+    if let Some(decorations) = log_line.decorations {
+        s.push(' ');
+        s.push_str(&config.log_decoration_style.paint("(").to_string());
+        for (i, decoration) in decorations.split(", ").enumerate() {
+            if i > 0 {
+                s.push_str(&config.log_decoration_style.paint(", ").to_string());
+            }
+            s.push_str(&format_decoration(decoration, config));
+        }
+        s.push_str(&config.log_decoration_style.paint(")").to_string());
+    }
+    if let Some(reflog_selector) = log_line.reflog_selector {
+        s.push(' ');
+        s.push_str(
+            &config
+                .log_reflog_selector_style
+                .paint(format!("{}:", reflog_selector))
+                .to_string(),
+        );
+    }
+    s.push_str(log_line.subject);
+    s
+}
+
+/// Style a single ref decoration, e.g. "HEAD -> master", "tag: 0.3.1", or "origin/master".
+fn format_decoration(decoration: &str, config: &Config) -> String {
+    let style = config.log_decoration_style;
+    if let Some(tag) = decoration.strip_prefix("tag: ") {
+        format!(
+            "{}{}",
+            style.paint("tag: "),
+            config.log_tag_style.paint(tag)
+        )
+    } else if let Some(branch) = decoration.strip_prefix("HEAD -> ") {
+        format!(
+            "{}{}{}",
+            config.log_head_style.paint("HEAD"),
+            style.paint(" -> "),
+            config.log_branch_style.paint(branch)
+        )
+    } else if decoration == "HEAD" {
+        config.log_head_style.paint(decoration).to_string()
+    } else {
+        config.log_branch_style.paint(decoration).to_string()
+    }
+}
+
+// The formats of the "Date:" line of `git log` under --date=default, iso, iso-strict and rfc.
+const COMMIT_DATE_FORMATS: &[&str] = &[
+    "%a %b %e %H:%M:%S %Y %z",
+    "%Y-%m-%d %H:%M:%S %z",
+    "%Y-%m-%dT%H:%M:%S%:z",
+    "%a, %e %b %Y %H:%M:%S %z",
+];
+
+lazy_static! {
+    static ref RELATIVE_DATE_PLACEHOLDER_REGEX: Regex =
+        Regex::new(r"\{\{|\}\}|\{relative\}").unwrap();
+}
+
+/// Reformat the "Date:" line of a commit according to --commit-date-format. Return None if the
+/// option is not in effect, or the line is not a date line in a format that is recognized.
+pub fn format_commit_date_line(line: &str, config: &Config) -> Option<String> {
+    let format = config.commit_date_format.as_deref()?;
+    let date = line.strip_prefix("Date:")?;
+    let (indent, date) = date.split_at(date.len() - date.trim_start().len());
+    let date = COMMIT_DATE_FORMATS
+        .iter()
+        .find_map(|format| DateTime::parse_from_str(date.trim_end(), format).ok())?;
+    Some(format!(
+        "Date:{}{}",
+        indent,
+        format_commit_date(&date, format, Local::now().timestamp())
+    ))
+}
+
+/// Format the date using strftime-style specifiers, with {relative} replaced by the time elapsed
+/// since the date, relative to `now` (seconds since the epoch), and '{{' and '}}' by literal
+/// braces.
+fn format_commit_date(date: &DateTime<FixedOffset>, format: &str, now: i64) -> String {
+    let relative = humanize_elapsed_time(now - date.timestamp());
+    RELATIVE_DATE_PLACEHOLDER_REGEX
+        .replace_all(&date.format(format).to_string(), |caps: &Captures| {
+            if &caps[0] == "{relative}" {
+                relative.clone()
+            } else {
+                // An escaped literal brace
+                caps[0][..1].to_string()
+            }
+        })
+        .into_owned()
+}
+
+// The lines of `git log --show-signature` output which report the status of a signature, from gpg
+// or, for SSH signatures, from ssh-keygen. A good signature from a key which is not fully trusted
+// is reported with a trust level such as "[unknown]".
+lazy_static! {
+    static ref GOOD_SIGNATURE_REGEX: Regex =
+        Regex::new(r#"^(gpg: Good signature from |Good "[^"]*" signature for )"#).unwrap();
+    static ref UNTRUSTED_GOOD_SIGNATURE_REGEX: Regex =
+        Regex::new(r"\[(unknown|undefined|never|marginal|expired|revoked)\]$").unwrap();
+    static ref SIGNATURE_WARNING_REGEX: Regex = Regex::new(
+        r"^(gpg: WARNING: |gpg: +There is no indication|gpg: Note: |gpg: +aka .*\[(unknown|undefined|never|marginal|expired|revoked)\]$|Primary key fingerprint: )"
+    )
+    .unwrap();
+    static ref BAD_SIGNATURE_REGEX: Regex = Regex::new(
+        r"^(gpg: BAD signature from |gpg: Can't check signature|gpg: no signature found|gpg: Signature made .*expired|BAD signature|Could not verify signature)"
+    )
+    .unwrap();
+}
+
+/// Style a line of `git log --show-signature` output which reports the status of a signature.
+/// Return None if the line is not such a line. Other lines, such as "gpg: Signature made ...",
+/// are left as they are.
+pub fn format_signature_line(line: &str, config: &Config) -> Option<String> {
+    let style = if BAD_SIGNATURE_REGEX.is_match(line) {
+        config.signature_bad_style
+    } else if GOOD_SIGNATURE_REGEX.is_match(line) {
+        if UNTRUSTED_GOOD_SIGNATURE_REGEX.is_match(line.trim_end()) {
+            config.signature_warning_style
+        } else {
+            config.signature_good_style
+        }
+    } else if SIGNATURE_WARNING_REGEX.is_match(line.trim_end()) {
+        config.signature_warning_style
+    } else {
+        return None;
+    };
+    Some(style.paint(line).to_string())
+}
+
+lazy_static! {
+    static ref MARKDOWN_HEADER_REGEX: Regex = Regex::new(r"^#{1,6} +(.+)$").unwrap();
+    static ref MARKDOWN_LIST_ITEM_REGEX: Regex =
+        Regex::new(r"^( {0,3})([-*+]|\d+[.)]) +(.*)$").unwrap();
+}
+
+/// Render a line of a commit message in `git log` or `git show` output with light markdown
+/// styling under --commit-message-markdown: headers are displayed in bold, bullet list markers
+/// are replaced by bullets, and lines which are too wide for the terminal are wrapped, with list
+/// items given a hanging indent. Indented lines, such as code, are left as they are. Return None
+/// if the line is not to be restyled.
+pub fn format_commit_message_line(
+    line: &str,
+    decorations_width: &cli::Width,
+    available_terminal_width: usize,
+    config: &Config,
+) -> Option<Vec<String>> {
+    if !config.commit_message_markdown {
+        return None;
+    }
+    // Git indents the lines of the message by four spaces.
+    let indent = "    ";
+    let text = line.strip_prefix(indent)?.trim_end();
+    if text.is_empty() || text.starts_with("    ") || text.starts_with('\t') {
+        return None;
+    }
+    if let Some(caps) = MARKDOWN_HEADER_REGEX.captures(text) {
+        return Some(vec![format!(
+            "{}{}",
+            indent,
+            ansi_term::Style::new().bold().paint(&caps[1])
+        )]);
+    }
+    let (first_prefix, prefix, text) = match MARKDOWN_LIST_ITEM_REGEX.captures(text) {
+        Some(caps) => {
+            let marker = match &caps[2] {
+                "-" | "*" | "+" if config.ascii => "*",
+                "-" | "*" | "+" => "•",
+                number => number,
+            };
+            let first_prefix = format!("{}{}{} ", indent, &caps[1], marker);
+            let prefix = " ".repeat(first_prefix.width());
+            (first_prefix, prefix, caps.get(3).unwrap().as_str())
+        }
+        None => (indent.to_string(), indent.to_string(), text),
+    };
+    let width = match decorations_width {
+        cli::Width::Fixed(width) => *width,
+        cli::Width::Variable => available_terminal_width,
+    };
+    let mut lines = Vec::new();
+    let mut line = first_prefix;
+    for (i, word) in text.split_whitespace().enumerate() {
+        if i > 0 {
+            if line.width() + 1 + word.width() > width {
+                lines.push(std::mem::replace(&mut line, prefix.clone()));
+            } else {
+                line.push(' ');
+            }
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    Some(lines)
+}
+
+/// Describe a number of elapsed seconds in the manner of `git log --date=relative`, e.g.
+/// "3 hours ago" or "1 year, 2 months ago".
+fn humanize_elapsed_time(seconds: i64) -> String {
+    let plural = |n: i64, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    if seconds < 0 {
+        return "in the future".to_string();
+    }
+    if seconds < 90 {
+        return format!("{} ago", plural(seconds, "second"));
+    }
+    let minutes = (seconds + 30) / 60;
+    if minutes < 90 {
+        return format!("{} ago", plural(minutes, "minute"));
+    }
+    let hours = (minutes + 30) / 60;
+    if hours < 36 {
+        return format!("{} ago", plural(hours, "hour"));
+    }
+    let days = (hours + 12) / 24;
+    if days < 14 {
+        return format!("{} ago", plural(days, "day"));
+    }
+    if days < 70 {
+        return format!("{} ago", plural((days + 3) / 7, "week"));
+    }
+    if days < 365 {
+        return format!("{} ago", plural((days + 15) / 30, "month"));
+    }
+    if days < 1825 {
+        let total_months = (days * 12 * 2 + 365) / (365 * 2);
+        let (years, months) = (total_months / 12, total_months % 12);
+        return if months > 0 {
+            format!("{}, {} ago", plural(years, "year"), plural(months, "month"))
+        } else {
+            format!("{} ago", plural(years, "year"))
+        };
+    }
+    format!("{} ago", plural((days + 183) / 365, "year"))
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::style::Style;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    const GIT_LOG_ONELINE: &str = "\
+\x1b[33m8c1d2f3\x1b[m\x1b[33m (\x1b[m\x1b[1;36mHEAD -> \x1b[m\x1b[1;32mmaster\x1b[m\x1b[33m, \x1b[m\x1b[1;33mtag: 0.3.1\x1b[m\x1b[33m)\x1b[m Fix typo (again)
+\x1b[33m1a2b3c4\x1b[m Add feature
+";
+
+    #[test]
+    fn test_parse_log_line() {
+        assert_eq!(
+            parse_log_line("* 8c1d2f3 (HEAD -> master, origin/master) Fix typo"),
+            Some(LogLine {
+                graph: "* ",
+                commit: "8c1d2f3",
+                decorations: Some("HEAD -> master, origin/master"),
+                reflog_selector: None,
+                subject: " Fix typo",
+            })
+        );
+        assert_eq!(
+            parse_log_line("8c1d2f3 HEAD@{12}: checkout: moving from a to b"),
+            Some(LogLine {
+                graph: "",
+                commit: "8c1d2f3",
+                decorations: None,
+                reflog_selector: Some("HEAD@{12}"),
+                subject: " checkout: moving from a to b",
+            })
+        );
+        assert!(parse_log_line("commit 8c1d2f3").is_none());
+    }
+
+    #[test]
+    fn test_is_log_line() {
+        assert!(is_log_line("8c1d2f3 (HEAD -> master) Fix typo"));
+        assert!(is_log_line("8c1d2f3 master@{1}: commit: Fix typo"));
+        assert!(!is_log_line("8c1d2f3 Fix typo"));
+        assert!(!is_log_line("deadbeef is not a commit"));
+    }
+
+    #[test]
+    fn test_log_oneline() {
+        let config = make_config_from_args(&["--log-tag-style", "bold red"]);
+        let output = run_delta(GIT_LOG_ONELINE, &config);
+        assert!(output.contains(&config.log_tag_style.paint("0.3.1").to_string()));
+        assert!(output.contains(&config.log_hash_style.paint("1a2b3c4").to_string()));
+        let output = strip_ansi_codes(&output);
+        let mut lines = output.lines();
+        assert_eq!(
+            lines.next().unwrap(),
+            "8c1d2f3 (HEAD -> master, tag: 0.3.1) Fix typo (again)"
+        );
+        assert_eq!(lines.next().unwrap(), "1a2b3c4 Add feature");
+    }
+
+    #[test]
+    fn test_humanize_elapsed_time() {
+        assert_eq!(humanize_elapsed_time(-5), "in the future");
+        assert_eq!(humanize_elapsed_time(1), "1 second ago");
+        assert_eq!(humanize_elapsed_time(89), "89 seconds ago");
+        assert_eq!(humanize_elapsed_time(3600), "60 minutes ago");
+        assert_eq!(humanize_elapsed_time(3 * 3600), "3 hours ago");
+        assert_eq!(humanize_elapsed_time(2 * 86400), "2 days ago");
+        assert_eq!(humanize_elapsed_time(15 * 86400), "2 weeks ago");
+        assert_eq!(humanize_elapsed_time(100 * 86400), "3 months ago");
+        assert_eq!(humanize_elapsed_time(365 * 86400), "1 year ago");
+        assert_eq!(humanize_elapsed_time(430 * 86400), "1 year, 2 months ago");
+        assert_eq!(humanize_elapsed_time(3650 * 86400), "10 years ago");
+    }
+
+    #[test]
+    fn test_format_commit_date() {
+        let date =
+            DateTime::parse_from_str("Thu Jan 1 00:00:00 1970 +0000", COMMIT_DATE_FORMATS[0])
+                .unwrap();
+        let now = 14 * 86400;
+        assert_eq!(format_commit_date(&date, "{relative}", now), "2 weeks ago");
+        assert_eq!(
+            format_commit_date(&date, "%Y-%m-%d ({relative})", now),
+            "1970-01-01 (2 weeks ago)"
+        );
+        assert_eq!(
+            format_commit_date(&date, "{{relative}} {{{relative}}}", now),
+            "{relative} {2 weeks ago}"
+        );
+    }
+
+    #[test]
+    fn test_commit_date_format() {
+        let input = "\
+commit 8c1d2f3
+Author: Delta <delta@example.com>
+Date:   Thu Jan 1 00:00:00 1970 +0000
+
+    Fix typo
+";
+        let config = make_config_from_args(&["--commit-date-format", "%Y-%m-%d ({relative})"]);
+        let output = strip_ansi_codes(&run_delta(input, &config)).to_string();
+        assert!(output.contains("\nDate:   1970-01-01 ("));
+        assert!(output.contains(" years ago)\n"));
+
+        let config = make_config_from_args(&[]);
+        let output = strip_ansi_codes(&run_delta(input, &config)).to_string();
+        assert!(output.contains("\nDate:   Thu Jan 1 00:00:00 1970 +0000\n"));
+    }
+
+    #[test]
+    fn test_signature_lines() {
+        let input = "\
+commit 8c1d2f3
+gpg: Signature made Thu Jan  1 00:00:00 1970 UTC
+gpg:                using RSA key 0123456789ABCDEF
+gpg: Good signature from \"Delta <delta@example.com>\" [unknown]
+gpg: WARNING: This key is not certified with a trusted signature!
+Primary key fingerprint: 0123 4567 89AB CDEF
+Author: Delta <delta@example.com>
+Date:   Thu Jan 1 00:00:00 1970 +0000
+
+    gpg: Good signature from a commit message
+
+commit 1a2b3c4
+gpg: Good signature from \"Delta <delta@example.com>\" [ultimate]
+gpg: BAD signature from \"Delta <delta@example.com>\" [ultimate]
+";
+        let config = make_config_from_args(&[
+            "--signature-good-style",
+            "green",
+            "--signature-warning-style",
+            "yellow",
+            "--signature-bad-style",
+            "red",
+        ]);
+        let output = run_delta(input, &config);
+        let painted = |style: &str, line: &str| {
+            Style::from_str(style, None, None, true, false)
+                .paint(line)
+                .to_string()
+        };
+        assert!(output.contains(&painted(
+            "yellow",
+            "gpg: Good signature from \"Delta <delta@example.com>\" [unknown]"
+        )));
+        assert!(output.contains(&painted(
+            "yellow",
+            "gpg: WARNING: This key is not certified with a trusted signature!"
+        )));
+        assert!(output.contains(&painted(
+            "green",
+            "gpg: Good signature from \"Delta <delta@example.com>\" [ultimate]"
+        )));
+        assert!(output.contains(&painted(
+            "red",
+            "gpg: BAD signature from \"Delta <delta@example.com>\" [ultimate]"
+        )));
+        assert!(output.contains("\ngpg: Signature made Thu Jan  1 00:00:00 1970 UTC\n"));
+        assert!(output.contains("\n    gpg: Good signature from a commit message\n"));
+    }
+
+    #[test]
+    fn test_commit_message_markdown() {
+        let input = "\
+commit 8c1d2f3
+Author: Delta <delta@example.com>
+Date:   Thu Jan 1 00:00:00 1970 +0000
+
+    Add feature
+
+    # Details
+    - first item, which is long enough to be wrapped
+    2. second item
+
+        code  block
+
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1 +1 @@
+-# Details
++- item
+";
+        let config = make_config_from_args(&["--commit-message-markdown", "--width", "40"]);
+        let output = run_delta(input, &config);
+        assert!(output.contains(&format!(
+            "\n    {}\n",
+            ansi_term::Style::new().bold().paint("Details")
+        )));
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains(
+            "\n    • first item, which is long enough\n      to be wrapped\n    2. second item\n"
+        ));
+        assert!(output.contains("\n        code  block\n"));
+        assert!(output.contains("\n# Details\n"));
+        assert!(output.contains("\n- item\n"));
+    }
+}
@@ -4,24 +4,61 @@ extern crate bitflags;
 extern crate error_chain;
 
 mod align;
+mod ansi;
 mod bat;
+mod benchmark;
+mod blame;
 mod cli;
 mod color;
+mod commit_links;
 mod config;
+mod control_chars;
 mod delta;
 mod draw;
 mod edits;
 mod env;
 mod features;
+mod filter;
+mod funcname;
+mod git_command;
 mod git_config;
+mod grep;
+mod highlight;
+mod highlight_patterns;
+mod hunk_badges;
+mod hunk_language;
+mod invert;
+mod line_plugin;
+mod lockfile;
+mod log;
+mod mono;
+mod notebook;
+mod numbering;
 mod options;
 mod paint;
 mod parse;
 mod parse_style;
+mod pre_render;
+mod redact;
+mod rej;
+mod reload;
+mod render_cache;
+mod sdiff;
+mod show;
+mod show_styles;
+mod split_output;
+mod stats;
+mod structured_diff;
 mod style;
 mod syntect_color;
+mod terminal_profile;
 mod tests;
+mod validate;
+mod verify_emulation;
+mod warnings;
+mod word_diff;
 
+use std::ffi::OsString;
 use std::io::{self, ErrorKind, Read, Write};
 use std::path::PathBuf;
 use std::process;
@@ -34,7 +71,7 @@ use structopt::StructOpt;
 
 use crate::bat::assets::{list_languages, HighlightingAssets};
 use crate::bat::output::{OutputType, PagingMode};
-use crate::delta::delta;
+use crate::delta::{delta, delta_part, end_input, InputProgress};
 use crate::options::theme::is_light_syntax_theme;
 
 mod errors {
@@ -49,11 +86,25 @@ mod errors {
 
 fn main() -> std::io::Result<()> {
     let assets = HighlightingAssets::new();
-    let opt = cli::Opt::from_args_and_git_config(&mut git_config::GitConfig::try_create(), assets);
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let git_command_index = git_command::get_git_command_index(&args);
+    let opt = match git_command_index {
+        Some(i) => cli::Opt::from_iter_and_git_config(
+            &args[..i],
+            &mut git_config::GitConfig::try_create(),
+            assets,
+        ),
+        None => {
+            cli::Opt::from_args_and_git_config(&mut git_config::GitConfig::try_create(), assets)
+        }
+    };
 
     if opt.list_languages {
         list_languages()?;
         process::exit(0);
+    } else if opt.list_options {
+        options::list::list_options(&opt, &mut io::stdout())?;
+        process::exit(0);
     } else if opt.list_syntax_themes {
         list_syntax_themes()?;
         process::exit(0);
@@ -63,64 +114,234 @@ fn main() -> std::io::Result<()> {
     }
 
     let _show_config = opt.show_config;
-    let config = config::Config::from(opt);
+    let _show_styles = opt.show_styles;
+    let _benchmark = opt.benchmark.clone();
+    let _benchmark_iterations = opt.benchmark_iterations;
+    let _verify_emulation = opt.verify_emulation.clone();
+    let _split_output = opt.split_output.clone();
+    let _validate = opt.validate;
+    let _features = opt.features.clone();
+    let _more_files = opt.more_files.clone();
+    let _patches = opt.patches;
+    let _inputs = opt.input.clone();
+    let _unknown_git_config_options = opt.computed.unknown_git_config_options.clone();
+    let mut is_light_mode = opt.computed.is_light_mode;
+    let mut config = config::Config::from(opt);
+    for key in &_unknown_git_config_options {
+        warnings::warn(
+            "unknown-option",
+            &format!("delta.{} in the git config is not a delta option", key),
+            &config,
+        );
+    }
 
-    if _show_config {
+    if let Some(path) = _benchmark {
+        benchmark::benchmark(&path, _benchmark_iterations, &config)?;
+        process::exit(0);
+    } else if let Some(path) = _verify_emulation {
+        let agreed = verify_emulation::verify_emulation(&path, &_features, &config)?;
+        process::exit(if agreed { 0 } else { 1 });
+    } else if _show_config {
         show_config(&config);
         process::exit(0);
-    } else if atty::is(atty::Stream::Stdin) {
-        return diff(
-            config.minus_file.as_ref(),
-            config.plus_file.as_ref(),
+    } else if _show_styles {
+        let mut output_type = OutputType::from_mode(config.paging_mode, None, &config).unwrap();
+        let mut writer = output_type.handle().unwrap();
+        if let Err(error) = show_styles::show_styles(&mut writer, &config) {
+            match error.kind() {
+                ErrorKind::BrokenPipe => process::exit(0),
+                _ => eprintln!("{}", error),
+            }
+        }
+        process::exit(0);
+    }
+
+    if let Some(i) = git_command_index {
+        process::exit(git_command::run_git_command(
+            &args[i],
+            &args[i + 1..],
             &config,
-        );
+        ));
+    }
+
+    if !_inputs.is_empty() {
+        if let Err(error) = show::show_inputs(&_inputs, &config) {
+            match error.kind() {
+                ErrorKind::BrokenPipe => process::exit(0),
+                _ => {
+                    eprintln!("{}", error);
+                    process::exit(1);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let positional_files: Vec<PathBuf> = config
+        .minus_file
+        .iter()
+        .chain(config.plus_file.iter())
+        .chain(_more_files.iter())
+        .cloned()
+        .collect();
+    if show::is_patches_display(&positional_files, _patches) {
+        if let Err(error) = show::show_patch_files(&positional_files, &config) {
+            match error.kind() {
+                ErrorKind::BrokenPipe => process::exit(0),
+                _ => {
+                    eprintln!("{}", error);
+                    process::exit(1);
+                }
+            }
+        }
+        return Ok(());
+    } else if !_more_files.is_empty() {
+        eprintln!("Usage: delta minus_file plus_file, or delta --patches patch_file...");
+        process::exit(2);
+    } else if atty::is(atty::Stream::Stdin) {
+        return match (config.minus_file.as_ref(), config.plus_file.as_ref()) {
+            (Some(path), None) => {
+                let result = match path.extension().and_then(|extension| extension.to_str()) {
+                    Some("rej") => rej::show_rej_file(path, &config),
+                    // A backup made by GNU patch: show the changes made since
+                    Some("orig") if path.with_extension("").is_file() => {
+                        process::exit(diff(Some(path), Some(&path.with_extension("")), &config))
+                    }
+                    _ => show::show_file(path, &config),
+                };
+                if let Err(error) = result {
+                    match error.kind() {
+                        ErrorKind::BrokenPipe => process::exit(0),
+                        _ => {
+                            eprintln!("{}: {}", path.display(), error);
+                            process::exit(1);
+                        }
+                    }
+                }
+                Ok(())
+            }
+            (minus_file, plus_file) => process::exit(diff(minus_file, plus_file, &config)),
+        };
+    }
+
+    if _validate {
+        let mut input = Vec::new();
+        io::stdin().lock().read_to_end(&mut input)?;
+        let violations = {
+            let mut output_type = OutputType::from_mode(config.paging_mode, None, &config).unwrap();
+            let mut writer = output_type.handle().unwrap();
+            match validate::render_and_validate(&input, &mut writer, &_features, &config) {
+                Ok(violations) => violations,
+                Err(error) if error.kind() == ErrorKind::BrokenPipe => process::exit(0),
+                Err(error) => {
+                    eprintln!("{}", error);
+                    process::exit(1);
+                }
+            }
+        };
+        for violation in &violations {
+            eprintln!("delta --validate: {}", violation);
+        }
+        process::exit(if violations.is_empty() { 0 } else { 1 });
     }
 
     let mut output_type = OutputType::from_mode(config.paging_mode, None, &config).unwrap();
     let mut writer = output_type.handle().unwrap();
 
-    if let Err(error) = delta(io::stdin().lock().byte_lines(), &mut writer, &config) {
-        match error.kind() {
-            ErrorKind::BrokenPipe => process::exit(0),
-            _ => eprintln!("{}", error),
+    if let Some(dir) = _split_output {
+        if let Err(error) =
+            split_output::split_output(io::stdin().lock().byte_lines(), &dir, &mut writer, &config)
+        {
+            eprintln!("{}: {}", dir.display(), error);
+            process::exit(1);
         }
-    };
+        return Ok(());
+    }
+
+    reload::install_signal_handler();
+    let stdin = io::stdin();
+    let mut stdin = stdin.lock();
+    // The input is displayed in parts, one for each reload of the configuration, which are
+    // continued by the next part, so that the input is ended once.
+    let mut progress = InputProgress::default();
+    loop {
+        let mut input = reload::StopAtReload::new(&mut stdin);
+        let result = delta_part(
+            (&mut input).byte_lines(),
+            &mut writer,
+            &config,
+            &mut progress,
+        )
+        .and_then(|_| {
+            if input.is_stopped() {
+                Ok(())
+            } else {
+                end_input(&mut writer, &progress, &config)
+            }
+        });
+        if let Err(error) = result {
+            match error.kind() {
+                ErrorKind::BrokenPipe => process::exit(0),
+                _ => eprintln!("{}", error),
+            }
+            break;
+        };
+        if !input.is_stopped() {
+            break;
+        }
+        let opt = reload::reload_options(&args, is_light_mode);
+        is_light_mode = opt.computed.is_light_mode;
+        config = config::Config::from(opt);
+    }
     Ok(())
 }
 
-/// Run `diff -u` on the files provided on the command line and display the output.
-fn diff(
-    minus_file: Option<&PathBuf>,
-    plus_file: Option<&PathBuf>,
-    config: &config::Config,
-) -> std::io::Result<()> {
+/// Run `diff -u` on the files provided on the command line and display the output. Return the exit
+/// code of `diff`: 0 if the files are the same, 1 if they differ, and 2 if there was a problem.
+fn diff(minus_file: Option<&PathBuf>, plus_file: Option<&PathBuf>, config: &config::Config) -> i32 {
     use std::io::BufReader;
     let die = || {
         eprintln!("Usage: delta minus_file plus_file");
-        process::exit(1);
+        process::exit(2);
     };
-    let diff_process = process::Command::new(PathBuf::from("diff"))
+    let mut diff_process = process::Command::new(PathBuf::from("diff"))
         .arg("-u")
         .args(&[
             minus_file.unwrap_or_else(die),
             plus_file.unwrap_or_else(die),
         ])
         .stdout(process::Stdio::piped())
-        .spawn();
+        .spawn()
+        .unwrap_or_else(|error| {
+            eprintln!("Failed to run diff: {}", error);
+            process::exit(2);
+        });
 
-    let mut output_type = OutputType::from_mode(config.paging_mode, None, &config).unwrap();
-    let mut writer = output_type.handle().unwrap();
-    if let Err(error) = delta(
-        BufReader::new(diff_process.unwrap().stdout.unwrap()).byte_lines(),
-        &mut writer,
-        &config,
-    ) {
-        match error.kind() {
-            ErrorKind::BrokenPipe => process::exit(0),
-            _ => eprintln!("{}", error),
-        }
-    };
-    Ok(())
+    {
+        let mut output_type = OutputType::from_mode(config.paging_mode, None, &config).unwrap();
+        let mut writer = output_type.handle().unwrap();
+        if let Err(error) = delta(
+            BufReader::new(diff_process.stdout.take().unwrap()).byte_lines(),
+            &mut writer,
+            &config,
+        ) {
+            match error.kind() {
+                ErrorKind::BrokenPipe => {
+                    // The pager was closed before all of the output was read, so there were
+                    // differences.
+                    diff_process.kill().ok();
+                    diff_process.wait().ok();
+                    return 1;
+                }
+                _ => eprintln!("{}", error),
+            }
+        };
+        // The pager, if any, is waited for here.
+    }
+    match diff_process.wait() {
+        Ok(status) => status.code().unwrap_or(2),
+        Err(_) => 2,
+    }
 }
 
 fn show_config(config: &config::Config) {
@@ -174,22 +395,29 @@ fn show_config(config: &config::Config) {
     }
     println!(
         "    24-bit-color                  = {true_color}
+    accessible                    = {accessible}
+    ascii                         = {ascii}
     file-added-label              = {file_added_label}
     file-modified-label           = {file_modified_label}
     file-removed-label            = {file_removed_label}
     file-renamed-label            = {file_renamed_label}
+    hunk-label                    = {hunk_label}
     keep-plus-minus-markers       = {keep_plus_minus_markers}
     max-line-distance             = {max_line_distance}
     navigate                      = {navigate}
     paging                        = {paging_mode}
     syntax-theme                  = {syntax_theme}
     tabs                          = {tab_width}
+    terminal-profile              = {terminal_profile}
     word-diff-regex               = {tokenization_regex}",
         true_color = config.true_color,
+        accessible = config.accessible,
+        ascii = config.ascii,
         file_added_label = format_option_value(&config.file_added_label),
         file_modified_label = format_option_value(&config.file_modified_label),
         file_removed_label = format_option_value(&config.file_removed_label),
         file_renamed_label = format_option_value(&config.file_renamed_label),
+        hunk_label = format_option_value(&config.hunk_label),
         keep_plus_minus_markers = config.keep_plus_minus_markers,
         max_line_distance = config.max_line_distance,
         navigate = config.navigate,
@@ -204,10 +432,30 @@ fn show_config(config: &config::Config) {
             .map(|t| t.name.unwrap_or("none".to_string()))
             .unwrap_or("none".to_string()),
         tab_width = config.tab_width,
+        terminal_profile = describe_terminal_profile(&config.terminal_profile),
         tokenization_regex = format_option_value(&config.tokenization_regex.to_string()),
     );
 }
 
+fn describe_terminal_profile(profile: &terminal_profile::TerminalProfile) -> String {
+    let mut capabilities = Vec::new();
+    if profile.true_color {
+        capabilities.push("24-bit color".to_string());
+    }
+    if profile.hyperlinks {
+        capabilities.push("hyperlinks".to_string());
+    }
+    match profile.marks {
+        cli::FileMarks::Osc133 => capabilities.push("osc133 marks".to_string()),
+        cli::FileMarks::ITerm2 => capabilities.push("iterm2 marks".to_string()),
+        cli::FileMarks::None => {}
+    }
+    if capabilities.is_empty() {
+        capabilities.push("no capabilities".to_string());
+    }
+    format!("{} ({})", profile.name, capabilities.join(", "))
+}
+
 // Heuristics determining whether to quote string option values when printing values intended for
 // git config.
 fn format_option_value<S>(s: S) -> String
@@ -0,0 +1,306 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use crate::cli;
+use crate::env;
+
+// Under the mono profile, for printing and for monochrome terminals, delta's output carries no
+// color. The colors are removed from every style, a background color becoming reverse video, and
+// syntax highlighting is disabled. Changed lines keep their +/- markers, the emphasized sections
+// of removed and added lines are displayed in reverse video and bold underline respectively, and
+// decorations are drawn with ASCII characters. With --profile auto, the mono profile is used when
+// standard output is a terminal whose terminfo entry reports at most 2 colors.
+
+// The index of the max_colors capability among the numeric capabilities of a terminfo entry.
+const MAX_COLORS: usize = 13;
+
+const STYLE_ATTRIBUTES: &[&str] = &[
+    "blink",
+    "bold",
+    "box",
+    "dim",
+    "hidden",
+    "italic",
+    "none",
+    "ol",
+    "omit",
+    "overline",
+    "plain",
+    "reverse",
+    "strike",
+    "ul",
+    "underline",
+];
+
+/// Return whether the output is to use the mono profile, according to --profile.
+pub fn is_mono_profile(profile: &str) -> bool {
+    match profile {
+        "mono" => true,
+        "color" => false,
+        "auto" => {
+            atty::is(atty::Stream::Stdout)
+                && env::get_env_var("TERM")
+                    .and_then(|term| get_terminfo_max_colors(&term))
+                    .map_or(false, |max_colors| max_colors <= 2)
+        }
+        _ => {
+            eprintln!(
+                "Invalid value for --profile option: {} (valid values are \"auto\", \"color\", and \"mono\")",
+                profile
+            );
+            process::exit(1);
+        }
+    }
+}
+
+/// Remove the colors from the styles, and set the options of the mono profile.
+pub fn set_mono_options(opt: &mut cli::Opt) {
+    for style in &mut [
+        &mut opt.commit_decoration_style,
+        &mut opt.commit_style,
+        &mut opt.file_decoration_style,
+        &mut opt.file_style,
+        &mut opt.filter_notice_style,
+        &mut opt.grep_line_number_style,
+        &mut opt.grep_match_style,
+        &mut opt.highlight_style,
+        &mut opt.hunk_header_decoration_style,
+        &mut opt.hunk_header_style,
+        &mut opt.line_numbers_left_style,
+        &mut opt.line_numbers_minus_style,
+        &mut opt.line_numbers_plus_style,
+        &mut opt.line_numbers_right_style,
+        &mut opt.line_numbers_zero_style,
+        &mut opt.log_branch_style,
+        &mut opt.log_decoration_style,
+        &mut opt.log_hash_style,
+        &mut opt.log_head_style,
+        &mut opt.log_reflog_selector_style,
+        &mut opt.log_tag_style,
+        &mut opt.minus_emph_style,
+        &mut opt.minus_empty_line_marker_style,
+        &mut opt.minus_non_emph_style,
+        &mut opt.minus_style,
+        &mut opt.plus_emph_style,
+        &mut opt.plus_empty_line_marker_style,
+        &mut opt.plus_non_emph_style,
+        &mut opt.plus_style,
+        &mut opt.redacted_style,
+        &mut opt.side_by_side_minus_non_emph_style,
+        &mut opt.side_by_side_plus_non_emph_style,
+        &mut opt.signature_bad_style,
+        &mut opt.signature_good_style,
+        &mut opt.signature_warning_style,
+        &mut opt.whitespace_error_style,
+        &mut opt.zero_style,
+    ] {
+        **style = remove_colors(style);
+    }
+    // Styles which are distinguished from their neighbors only by color.
+    for (style, mono_style) in &mut [
+        (&mut opt.minus_emph_style, "reverse"),
+        (&mut opt.plus_emph_style, "bold ul"),
+        (&mut opt.minus_empty_line_marker_style, "reverse"),
+        (&mut opt.plus_empty_line_marker_style, "reverse"),
+    ] {
+        if style.as_str() == "normal" {
+            **style = mono_style.to_string();
+        }
+    }
+    opt.keep_plus_minus_markers = true;
+    opt.computed.syntax_theme = None;
+}
+
+/// Remove the colors from a style string, replacing a background color with reverse video.
+pub fn remove_colors(style_string: &str) -> String {
+    let mut words = Vec::new();
+    let mut n_colors = 0;
+    for word in style_string
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c| c == '"' || c == '\''))
+    {
+        let word = word.to_lowercase();
+        if STYLE_ATTRIBUTES.contains(&word.as_str()) {
+            if !words.contains(&word) {
+                words.push(word);
+            }
+            continue;
+        }
+        n_colors += 1;
+        let is_background_color = n_colors == 2 && word != "normal" && word != "auto";
+        if is_background_color && !words.iter().any(|word| word == "reverse") {
+            words.push("reverse".to_string());
+        }
+    }
+    if words.is_empty() && n_colors > 0 {
+        "normal".to_string()
+    } else {
+        words.join(" ")
+    }
+}
+
+/// Return the number of colors reported by the terminfo entry of the terminal `term`: None if there
+/// is no entry, and 0 if the entry does not have the max_colors capability.
+fn get_terminfo_max_colors(term: &str) -> Option<i32> {
+    let first_char = term.chars().next()?;
+    get_terminfo_dirs().into_iter().find_map(|dir| {
+        [
+            first_char.to_string(),
+            format!("{:x}", first_char as u32), // macOS
+        ]
+        .iter()
+        .find_map(|subdir| fs::read(dir.join(subdir).join(term)).ok())
+        .and_then(|entry| parse_terminfo_max_colors(&entry))
+    })
+}
+
+/// The directories searched for terminfo entries, in the order used by ncurses.
+fn get_terminfo_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = env::get_env_var("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = env::get_env_var("HOME") {
+        dirs.push(PathBuf::from(home).join(".terminfo"));
+    }
+    let default_dirs = ["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"];
+    match env::get_env_var("TERMINFO_DIRS") {
+        Some(terminfo_dirs) => {
+            for dir in terminfo_dirs.split(':') {
+                if dir.is_empty() {
+                    dirs.extend(default_dirs.iter().map(PathBuf::from));
+                } else {
+                    dirs.push(PathBuf::from(dir));
+                }
+            }
+        }
+        None => dirs.extend(default_dirs.iter().map(PathBuf::from)),
+    }
+    dirs
+}
+
+/// Read the max_colors capability of a compiled terminfo entry (see term(5)).
+fn parse_terminfo_max_colors(entry: &[u8]) -> Option<i32> {
+    let read_i16 = |offset: usize| -> Option<i16> {
+        Some(i16::from_le_bytes([
+            *entry.get(offset)?,
+            *entry.get(offset + 1)?,
+        ]))
+    };
+    let number_size = match read_i16(0)? {
+        0o432 => 2,
+        0o1036 => 4, // The extended number format of ncurses 6.1
+        _ => return None,
+    };
+    let names_size = read_i16(2)? as usize;
+    let n_booleans = read_i16(4)? as usize;
+    let n_numbers = read_i16(6)? as usize;
+    if n_numbers <= MAX_COLORS {
+        return Some(0);
+    }
+    let mut offset = 12 + names_size + n_booleans;
+    // The numbers are aligned on an even byte boundary.
+    offset += offset % 2;
+    offset += MAX_COLORS * number_size;
+    let max_colors = if number_size == 2 {
+        read_i16(offset)? as i32
+    } else {
+        i32::from_le_bytes([
+            *entry.get(offset)?,
+            *entry.get(offset + 1)?,
+            *entry.get(offset + 2)?,
+            *entry.get(offset + 3)?,
+        ])
+    };
+    // An absent or cancelled capability is negative.
+    Some(max_colors.max(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+    use regex::Regex;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, make_options_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_remove_colors() {
+        assert_eq!(remove_colors("bold red"), "bold");
+        assert_eq!(remove_colors("syntax #3f0001"), "reverse");
+        assert_eq!(remove_colors("normal auto"), "normal");
+        assert_eq!(remove_colors("magenta reverse"), "reverse");
+        assert_eq!(remove_colors("blue ul"), "ul");
+        assert_eq!(remove_colors("bold yellow box ul"), "bold box ul");
+        assert_eq!(remove_colors("raw"), "normal");
+        assert_eq!(remove_colors(""), "");
+        assert_eq!(remove_colors("none"), "none");
+    }
+
+    #[test]
+    fn test_parse_terminfo_max_colors() {
+        let make_entry = |magic: i16, max_colors: i32| {
+            let mut entry = Vec::new();
+            let number_size = if magic == 0o432 { 2 } else { 4 };
+            for value in &[magic, 5, 1, 15, 0, 0] {
+                entry.extend(&value.to_le_bytes());
+            }
+            entry.extend(b"vt52\0");
+            entry.push(1);
+            for i in 0..15 {
+                let value = if i == MAX_COLORS { max_colors } else { -1 };
+                entry.extend(&value.to_le_bytes()[..number_size]);
+            }
+            entry
+        };
+        assert_eq!(
+            parse_terminfo_max_colors(&make_entry(0o432, 256)),
+            Some(256)
+        );
+        assert_eq!(
+            parse_terminfo_max_colors(&make_entry(0o1036, 1 << 24)),
+            Some(1 << 24)
+        );
+        assert_eq!(parse_terminfo_max_colors(&make_entry(0o432, -1)), Some(0));
+        assert_eq!(parse_terminfo_max_colors(b"not terminfo"), None);
+    }
+
+    #[test]
+    fn test_mono_profile() {
+        let opt = make_options_from_args(&["--profile", "mono"]);
+        assert!(opt.ascii);
+        assert!(opt.keep_plus_minus_markers);
+        assert_eq!(opt.minus_emph_style, "reverse");
+        assert_eq!(opt.plus_emph_style, "bold ul");
+        assert!(opt.computed.syntax_theme.is_none());
+
+        let config = make_config_from_args(&["--profile", "mono", "--file-style", "bold red"]);
+        let output = run_delta(DIFF, &config);
+        let sgr_regex = Regex::new(r"\x1b\[([0-9;]*)m").unwrap();
+        for captures in sgr_regex.captures_iter(&output) {
+            for parameter in captures[1].split(';').filter_map(|p| p.parse::<u32>().ok()) {
+                assert!(
+                    !(30..=49).contains(&parameter) && !(90..=107).contains(&parameter),
+                    "{:?}",
+                    output
+                );
+            }
+        }
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\n-b = 2\n+b = 3\n"));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@ def f():
+ a = 1
+-b = 2
++b = 3
+";
+}
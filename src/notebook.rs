@@ -0,0 +1,140 @@
+use serde_json::Value;
+
+// Under --notebook-diff, Jupyter notebooks (.ipynb files) are compared cell by cell rather than as
+// JSON. Each version of a notebook is rendered in the "percent" format used by e.g. Jupytext and
+// VS Code: each cell starts with a "# %%" line labeling its type, the source of code cells
+// follows as it is, and the source of markdown and raw cells is commented out, so that the
+// rendering can be highlighted as Python. The outputs and execution counts of cells are not
+// rendered, so that re-running a notebook makes no difference.
+
+/// Return the cells of a notebook in the percent format, or None if the notebook cannot be parsed.
+/// Only nbformat 4 notebooks, which have a top-level list of cells, are supported.
+pub fn render_cells(text: &str) -> Option<String> {
+    let notebook: Value = serde_json::from_str(text).ok()?;
+    let cells = notebook.get("cells")?.as_array()?;
+    let mut rendered = String::new();
+    for (i, cell) in cells.iter().enumerate() {
+        if i > 0 {
+            rendered.push('\n');
+        }
+        let cell_type = cell.get("cell_type").and_then(Value::as_str).unwrap_or("");
+        let source = get_source(cell);
+        if cell_type == "code" {
+            rendered.push_str("# %%\n");
+            for line in source.lines() {
+                rendered.push_str(line);
+                rendered.push('\n');
+            }
+        } else {
+            rendered.push_str(&format!("# %% [{}]\n", cell_type));
+            for line in source.lines() {
+                if line.is_empty() {
+                    rendered.push_str("#\n");
+                } else {
+                    rendered.push_str(&format!("# {}\n", line));
+                }
+            }
+        }
+    }
+    Some(rendered)
+}
+
+/// Return the source of a cell, which is stored either as a string or as a list of lines.
+fn get_source(cell: &Value) -> String {
+    match cell.get("source") {
+        Some(Value::String(source)) => source.to_string(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_render_cells() {
+        assert_eq!(
+            render_cells(&make_notebook("print(1)", 1)).unwrap(),
+            "\
+# %% [markdown]
+# # Title
+#
+# Text
+
+# %%
+import os
+print(1)
+"
+        );
+        assert_eq!(render_cells("{\"worksheets\": []}"), None);
+    }
+
+    #[test]
+    fn test_notebook_diff() {
+        let dir =
+            std::env::temp_dir().join(format!("delta-test-notebook-diff-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b, c) = (
+            dir.join("a.ipynb"),
+            dir.join("b.ipynb"),
+            dir.join("c.ipynb"),
+        );
+        std::fs::write(&a, make_notebook("print(1)", 1)).unwrap();
+        std::fs::write(&b, make_notebook("print(1)", 2)).unwrap();
+        std::fs::write(&c, make_notebook("print(2)", 2)).unwrap();
+        let make_diff = |minus: &std::path::Path, plus: &std::path::Path| {
+            format!(
+                "--- {}\n+++ {}\n@@ -1 +1 @@\n-   \"execution_count\": 1,\n+   \"execution_count\": 2,\n",
+                minus.display(),
+                plus.display()
+            )
+        };
+        let config = make_config_from_args(&["--notebook-diff"]);
+
+        let output = run_delta(&make_diff(&a, &b), &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("no differences in the notebook cells"));
+        assert!(!output.contains("execution_count"));
+
+        let output = run_delta(&make_diff(&a, &c), &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("compared as notebook cells"));
+        assert!(output.contains("\nprint(1)\n"));
+        assert!(output.contains("\nprint(2)\n"));
+        assert!(output.contains("\nimport os\n"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn make_notebook(code: &str, execution_count: usize) -> String {
+        format!(
+            r##"{{
+ "cells": [
+  {{
+   "cell_type": "markdown",
+   "metadata": {{}},
+   "source": ["# Title\n", "\n", "Text"]
+  }},
+  {{
+   "cell_type": "code",
+   "execution_count": {},
+   "metadata": {{}},
+   "outputs": [{{"output_type": "stream", "name": "stdout", "text": ["{}\n"]}}],
+   "source": "import os\n{}"
+  }}
+ ],
+ "metadata": {{}},
+ "nbformat": 4,
+ "nbformat_minor": 4
+}}
+"##,
+            execution_count, execution_count, code
+        )
+    }
+}
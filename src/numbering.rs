@@ -0,0 +1,238 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::config::Config;
+
+/// The numbers of the files and hunks of the input, as displayed by --numbered. Files are counted
+/// as they are by --select, so that a hunk can be displayed alone with e.g. `--select 3.2`.
+#[derive(Default)]
+pub struct Numbering {
+    files: Vec<NumberedFile>,
+    in_file: bool,
+}
+
+struct NumberedFile {
+    path: String,
+    // The line number and code fragment of each hunk header
+    hunks: Vec<(usize, String)>,
+}
+
+impl Numbering {
+    /// Handle a "diff" line, which starts a file.
+    pub fn handle_diff_line(&mut self) {
+        self.start_file();
+    }
+
+    /// Handle a "---" or "+++" line, which starts a file if it follows a file's hunks, as in the
+    /// output of `diff -u`, which has no "diff" lines.
+    pub fn handle_file_line(&mut self) {
+        match self.files.last() {
+            Some(file) if self.in_file && file.hunks.is_empty() => {}
+            _ => self.start_file(),
+        }
+    }
+
+    /// Handle a line which ends any file, such as a commit line.
+    pub fn handle_end_of_file(&mut self) {
+        self.in_file = false;
+    }
+
+    pub fn set_path(&mut self, path: &str) {
+        if let Some(file) = self.files.last_mut() {
+            file.path = path.to_string();
+        }
+    }
+
+    /// Return the label of the current file, e.g. "[3]", unless the current line is not part of a
+    /// file.
+    pub fn file_label(&self) -> Option<String> {
+        if self.in_file {
+            Some(format!("[{}]", self.files.len()))
+        } else {
+            None
+        }
+    }
+
+    /// Count a hunk of the current file, returning its label, e.g. "[3.2]".
+    pub fn handle_hunk_header(&mut self, line_number: usize, context: &str) -> Option<String> {
+        if !self.in_file {
+            return None;
+        }
+        let n_files = self.files.len();
+        let file = self.files.last_mut()?;
+        file.hunks.push((line_number, context.to_string()));
+        Some(format!("[{}.{}]", n_files, file.hunks.len()))
+    }
+
+    fn start_file(&mut self) {
+        self.files.push(NumberedFile {
+            path: "".to_string(),
+            hunks: Vec::new(),
+        });
+        self.in_file = true;
+    }
+}
+
+/// Write the table of the numbered files and hunks which ends the output under --numbered.
+pub fn write_index(
+    writer: &mut dyn Write,
+    numbering: &Numbering,
+    config: &Config,
+) -> io::Result<()> {
+    let files = numbering
+        .files
+        .iter()
+        .enumerate()
+        .filter(|(_, file)| !file.path.is_empty() || !file.hunks.is_empty());
+    let mut is_first = true;
+    for (i, file) in files {
+        if is_first {
+            writeln!(writer)?;
+            is_first = false;
+        }
+        writeln!(
+            writer,
+            "{} {}",
+            config.filter_notice_style.paint(format!("[{}]", i + 1)),
+            config.file_style.paint(file.path.as_str())
+        )?;
+        for (j, (line_number, context)) in file.hunks.iter().enumerate() {
+            writeln!(
+                writer,
+                "    {} line {}{}{}",
+                config
+                    .filter_notice_style
+                    .paint(format!("[{}.{}]", i + 1, j + 1)),
+                line_number,
+                if context.is_empty() { "" } else { ": " },
+                context
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the location of each hunk to the file at this path, under --emit-locations, in the layout
+/// of compiler error messages, so that editors can jump to them.
+pub fn write_locations_file(path: &str, numbering: &Numbering) -> io::Result<()> {
+    let file = File::create(path).map_err(|error| {
+        io::Error::new(
+            error.kind(),
+            format!("Failed to write --emit-locations file {}: {}", path, error),
+        )
+    })?;
+    let mut writer = BufWriter::new(file);
+    write_locations(&mut writer, numbering)?;
+    writer.flush()
+}
+
+fn write_locations(writer: &mut dyn Write, numbering: &Numbering) -> io::Result<()> {
+    for (i, file) in numbering.files.iter().enumerate() {
+        for (j, (line_number, context)) in file.hunks.iter().enumerate() {
+            writeln!(
+                writer,
+                "{}:{}:1: hunk {}.{}{}{}",
+                file.path,
+                // A hunk of a deleted file starts at line 0.
+                (*line_number).max(1),
+                i + 1,
+                j + 1,
+                if context.is_empty() { "" } else { " " },
+                context
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_numbered() {
+        let config = make_config_from_args(&[
+            "--numbered",
+            "--hunk-header-style",
+            "plain",
+            "--hunk-header-decoration-style",
+            "none",
+        ]);
+        let output = run_delta(DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\n[1] a.py\n"));
+        assert!(output.contains("\n[2] b.py\n"));
+        assert!(output.contains("\n[2.1] \n1\n"));
+        assert!(output.contains("\n[2.2] def g(): \n11\n"));
+        assert!(output.ends_with(
+            "
+[1] a.py
+    [1.1] line 1: def f():
+[2] b.py
+    [2.1] line 1
+    [2.2] line 11: def g():
+"
+        ));
+    }
+
+    #[test]
+    fn test_emit_locations() {
+        let path =
+            std::env::temp_dir().join(format!("delta-test-emit-locations-{}", std::process::id()));
+        let config = make_config_from_args(&["--emit-locations", path.to_str().unwrap()]);
+        run_delta(DIFF, &config);
+        let locations = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            locations,
+            "\
+a.py:1:1: hunk 1.1 def f():
+b.py:1:1: hunk 2.1
+b.py:11:1: hunk 2.2 def g():
+"
+        );
+    }
+
+    #[test]
+    fn test_select_numbered_hunk() {
+        let config = make_config_from_args(&["--select", "2.2", "--emit-patch"]);
+        let output = run_delta(DIFF, &config);
+        assert_eq!(
+            output,
+            "\
+diff --git a/b.py b/b.py
+index 223ca50..e69de29 100644
+--- a/b.py
++++ b/b.py
+@@ -10,1 +11,1 @@ def g():
+-d = 4
++d = 5
+"
+        );
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@ def f():
+ a = 1
+-b = 2
++b = 3
+diff --git a/b.py b/b.py
+index 223ca50..e69de29 100644
+--- a/b.py
++++ b/b.py
+@@ -1,1 +1,2 @@
+ c = 1
++c = 2
+@@ -10,1 +11,1 @@ def g():
+-d = 4
++d = 5
+";
+}
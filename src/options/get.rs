@@ -109,3 +109,4 @@ impl GetOptionValue for String {}
 impl GetOptionValue for bool {}
 impl GetOptionValue for f64 {}
 impl GetOptionValue for usize {}
+impl GetOptionValue for Vec<String> {}
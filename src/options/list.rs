@@ -0,0 +1,185 @@
+use std::io::{self, Write};
+use std::process;
+
+use serde_json::{json, Map, Number, Value};
+use structopt::StructOpt;
+
+use crate::cli;
+use crate::features;
+use crate::options::option_value::{OptionValue, ProvenancedOptionValue};
+
+/// Write the names, types, default values and descriptions of all delta options, and the option
+/// values set by the builtin features, in the format given by --list-options-format.
+pub fn list_options(opt: &cli::Opt, writer: &mut dyn Write) -> io::Result<()> {
+    let listing = make_listing(opt);
+    match opt.list_options_format.as_str() {
+        "json" => {
+            serde_json::to_writer_pretty(&mut *writer, &listing)?;
+            writeln!(writer)
+        }
+        "text" => write_text_listing(&listing, writer),
+        _ => {
+            eprintln!(
+                "Invalid value for --list-options-format: '{}'. It should be 'text' or 'json'.",
+                opt.list_options_format
+            );
+            process::exit(1);
+        }
+    }
+}
+
+fn make_listing(opt: &cli::Opt) -> Value {
+    let option_names = cli::Opt::get_option_names();
+    let app = cli::Opt::clap();
+    let make_option = |long: &str, option_type: &str, default: Option<&str>, help: &str| {
+        json!({
+            "name": long,
+            "type": option_type,
+            "default": parse_default_value(option_type, default),
+            "description": help.trim(),
+        })
+    };
+    let mut options: Vec<Value> = itertools::chain(
+        app.p
+            .opts
+            .iter()
+            .filter(|arg| option_names.contains_key(arg.b.name))
+            .map(|arg| {
+                let long = arg.s.long.unwrap();
+                let option_type = opt
+                    .computed
+                    .option_types
+                    .get(long)
+                    .copied()
+                    .unwrap_or("string");
+                let default = arg.v.default_val.map(|value| value.to_string_lossy());
+                let help = arg.b.long_help.or(arg.b.help).unwrap_or("");
+                make_option(long, option_type, default.as_deref(), help)
+            }),
+        app.p
+            .flags
+            .iter()
+            .filter(|arg| option_names.contains_key(arg.b.name))
+            .map(|arg| {
+                let help = arg.b.long_help.or(arg.b.help).unwrap_or("");
+                make_option(arg.s.long.unwrap(), "boolean", Some("false"), help)
+            }),
+    )
+    .collect();
+    options.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+
+    let mut builtin_features: Vec<(String, features::BuiltinFeature)> =
+        features::make_builtin_features().into_iter().collect();
+    builtin_features.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let features: Vec<Value> = builtin_features
+        .into_iter()
+        .map(|(name, feature)| {
+            let values: Map<String, Value> = feature
+                .iter()
+                .map(|(option_name, value_function)| {
+                    let value = match value_function(opt, &None) {
+                        ProvenancedOptionValue::GitConfigValue(value) => value,
+                        ProvenancedOptionValue::DefaultValue(value) => value,
+                    };
+                    (option_name.to_string(), option_value_to_json(value))
+                })
+                .collect();
+            json!({"name": name, "options": values})
+        })
+        .collect();
+
+    json!({"options": options, "features": features})
+}
+
+/// Convert the default value of an option, as given to clap, to a JSON value of the option's type.
+fn parse_default_value(option_type: &str, default: Option<&str>) -> Value {
+    match (option_type, default) {
+        ("boolean", Some(value)) => value.parse().map(Value::Bool).unwrap_or(Value::Null),
+        ("integer", Some(value)) => value.parse::<u64>().map(Value::from).unwrap_or(Value::Null),
+        ("float", Some(value)) => value
+            .parse()
+            .ok()
+            .and_then(Number::from_f64)
+            .map_or(Value::Null, Value::Number),
+        ("list", _) => Value::Array(default.map(Value::from).into_iter().collect()),
+        (_, Some(value)) => Value::from(value),
+        (_, None) => Value::Null,
+    }
+}
+
+fn option_value_to_json(value: OptionValue) -> Value {
+    match value {
+        OptionValue::Boolean(value) => Value::Bool(value),
+        OptionValue::Float(value) => Number::from_f64(value).map_or(Value::Null, Value::Number),
+        OptionValue::OptionString(value) => value.map_or(Value::Null, Value::String),
+        OptionValue::String(value) => Value::String(value),
+        OptionValue::StringList(values) => Value::from(values),
+        OptionValue::Int(value) => Value::from(value),
+    }
+}
+
+fn write_text_listing(listing: &Value, writer: &mut dyn Write) -> io::Result<()> {
+    writeln!(writer, "OPTIONS")?;
+    for option in listing["options"].as_array().unwrap() {
+        write!(
+            writer,
+            "\n--{} <{}>",
+            option["name"].as_str().unwrap(),
+            option["type"].as_str().unwrap()
+        )?;
+        if !option["default"].is_null() {
+            write!(writer, " [default: {}]", option["default"])?;
+        }
+        writeln!(writer, "\n    {}", option["description"].as_str().unwrap())?;
+    }
+    writeln!(writer, "\nFEATURES")?;
+    for feature in listing["features"].as_array().unwrap() {
+        writeln!(writer, "\n{}", feature["name"].as_str().unwrap())?;
+        for (name, value) in feature["options"].as_object().unwrap() {
+            writeln!(writer, "    {} = {}", name, value)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::make_options_from_args;
+
+    #[test]
+    fn test_list_options_json() {
+        let opt = make_options_from_args(&["--list-options", "--list-options-format", "json"]);
+        let mut output = Vec::new();
+        list_options(&opt, &mut output).unwrap();
+        let listing: Value = serde_json::from_slice(&output).unwrap();
+        let options = listing["options"].as_array().unwrap();
+        let get_option = |name: &str| {
+            options
+                .iter()
+                .find(|option| option["name"] == name)
+                .unwrap_or_else(|| panic!("option {} is not listed", name))
+        };
+        assert_eq!(get_option("side-by-side")["type"], "boolean");
+        assert_eq!(get_option("side-by-side")["default"], false);
+        assert_eq!(get_option("tabs")["type"], "integer");
+        assert_eq!(get_option("tabs")["default"], 4);
+        assert_eq!(get_option("max-line-distance")["type"], "float");
+        assert_eq!(get_option("hunk-badge")["type"], "list");
+        assert_eq!(get_option("syntax-theme")["type"], "string");
+        assert!(get_option("line-numbers")["description"]
+            .as_str()
+            .unwrap()
+            .starts_with("Display line numbers"));
+        assert!(!options
+            .iter()
+            .any(|option| option["name"] == "list-options"));
+
+        let features = listing["features"].as_array().unwrap();
+        let side_by_side = features
+            .iter()
+            .find(|feature| feature["name"] == "side-by-side")
+            .unwrap();
+        assert_eq!(side_by_side["options"]["side-by-side"], true);
+    }
+}
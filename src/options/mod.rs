@@ -1,4 +1,5 @@
 pub mod get;
+pub mod list;
 pub mod option_value;
 pub mod rewrite;
 pub mod set;
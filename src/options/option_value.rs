@@ -6,9 +6,23 @@ pub enum OptionValue {
     Float(f64),
     OptionString(Option<String>),
     String(String),
+    StringList(Vec<String>),
     Int(usize),
 }
 
+impl OptionValue {
+    /// The name of the type of the value, as displayed by --list-options.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            OptionValue::Boolean(_) => "boolean",
+            OptionValue::Float(_) => "float",
+            OptionValue::OptionString(_) | OptionValue::String(_) => "string",
+            OptionValue::StringList(_) => "list",
+            OptionValue::Int(_) => "integer",
+        }
+    }
+}
+
 /// An OptionValue, tagged according to its provenance/semantics.
 pub enum ProvenancedOptionValue {
     GitConfigValue(OptionValue),
@@ -81,6 +95,21 @@ impl From<OptionValue> for String {
     }
 }
 
+impl From<Vec<String>> for OptionValue {
+    fn from(value: Vec<String>) -> Self {
+        OptionValue::StringList(value)
+    }
+}
+
+impl From<OptionValue> for Vec<String> {
+    fn from(value: OptionValue) -> Self {
+        match value {
+            OptionValue::StringList(value) => value,
+            _ => delta_unreachable("Error converting OptionValue to Vec<String>."),
+        }
+    }
+}
+
 impl From<usize> for OptionValue {
     fn from(value: usize) -> Self {
         OptionValue::Int(value)
@@ -1,7 +1,9 @@
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
 use std::process;
 
 use console::Term;
+use regex::Regex;
 use structopt::clap;
 
 use crate::bat::assets::HighlightingAssets;
@@ -11,8 +13,11 @@ use crate::config;
 use crate::env;
 use crate::features;
 use crate::git_config;
+use crate::mono;
 use crate::options::option_value::{OptionValue, ProvenancedOptionValue};
 use crate::options::theme;
+use crate::parse;
+use crate::terminal_profile;
 
 macro_rules! set_options {
 	([$( $field_ident:ident ),* ],
@@ -33,6 +38,10 @@ macro_rules! set_options {
             }
             if $check_names {
                 option_names.insert(option_name);
+                if $opt.list_options {
+                    let option_type = OptionValue::from($opt.$field_ident.clone()).type_name();
+                    $opt.computed.option_types.insert(option_name.to_string(), option_type);
+                }
             }
         )*
         if $check_names {
@@ -41,6 +50,8 @@ macro_rules! set_options {
                 "diff-so-fancy", // Does not exist as a flag on config
                 "features",  // Processed differently
                 // Set prior to the rest
+                "diff-filter-mode",
+                "profile",
                 "no-gitconfig",
                 "dark",
                 "light",
@@ -72,15 +83,29 @@ pub fn set_options(
 
     let option_names = cli::Opt::get_option_names();
 
+    opt.computed.is_diff_filter = opt.diff_filter_mode || is_running_as_diff_filter();
+    if !config::user_supplied_option("profile", arg_matches) {
+        if let Some(profile) = git_config
+            .as_ref()
+            .and_then(|git_config| git_config.get::<String>("delta.profile"))
+        {
+            opt.profile = profile;
+        }
+    }
+    opt.computed.is_mono = mono::is_mono_profile(&opt.profile);
+
     // Set features
     let builtin_features = features::make_builtin_features();
+    opt.computed.disabled_features = split_feature_string(&opt.features.to_lowercase())
+        .filter_map(|feature| feature.strip_prefix('-'))
+        .map(|feature| feature.to_string())
+        .collect();
     let features = gather_features(opt, &builtin_features, git_config);
     opt.features = features.join(" ");
 
     set_widths(opt);
 
     // Set light, dark, and syntax-theme.
-    set_true_color(opt);
     set__light__dark__syntax_theme__options(opt, git_config, arg_matches, &option_names);
     theme::set__is_light_mode__syntax_theme__syntax_set(opt, assets);
 
@@ -109,21 +134,100 @@ pub fn set_options(
         }
         .unwrap_or_else(|| "magenta reverse".to_string())
     }
+    if !config::user_supplied_option("blame-ignore-revs-file", arg_matches) {
+        opt.blame_ignore_revs_file = git_config
+            .as_ref()
+            .and_then(|git_config| git_config.get::<String>("blame.ignoreRevsFile"));
+    }
+    // git's word regex is a POSIX extended regular expression, which is ignored in the rare case
+    // that it is not also a valid Rust regular expression.
+    if !config::user_supplied_option("tokenization-regex", arg_matches) {
+        if let Some(word_regex) = git_config
+            .as_ref()
+            .and_then(|git_config| git_config.get::<String>("diff.wordRegex"))
+            .filter(|word_regex| Regex::new(word_regex).is_ok())
+        {
+            opt.tokenization_regex = word_regex;
+        }
+    }
+    // The --pre-render-filter diff of a file has the same number of context lines as git's.
+    opt.computed.diff_context_lines = git_config
+        .as_ref()
+        .and_then(|git_config| git_config.get::<usize>("diff.context"))
+        .map(|context_lines| context_lines as u32);
+    opt.computed.diff_prefixes = get_diff_prefixes(git_config);
+    opt.computed.xfuncname_patterns = get_xfuncname_patterns(git_config);
+    opt.computed.unknown_git_config_options =
+        get_unknown_git_config_options(git_config, &option_names, &builtin_features);
 
     set_options!(
         [
+            accessible,
+            ascii,
+            auto_degrade_bytes,
+            auto_degrade_hunk_lines,
+            blame,
+            blame_format,
+            blame_ignore_revs_file,
+            blame_palette,
+            blame_timestamp_format,
+            collapse_distant_context,
+            collapse_noise_hunks,
             color_only,
+            commit_date_format,
             commit_decoration_style,
+            commit_link_remote,
+            commit_link_rule,
+            commit_link_service,
+            commit_links,
+            commit_message_markdown,
             commit_style,
+            control_char_style,
+            copy_hunk_location,
+            default_language,
+            distant_context,
+            distant_context_style,
+            emit_locations,
+            emit_patch,
+            exclude_path,
+            expand_context,
+            expanded_context_style,
             file_added_label,
             file_decoration_style,
+            file_labels,
+            file_marks,
             file_modified_label,
             file_removed_label,
             file_renamed_label,
+            file_stats,
             file_style,
+            file_line_length_limits,
+            file_tabs,
+            filter_notice_style,
+            flush_per_line,
+            foreground_emph,
+            grep,
+            grep_line_number_style,
+            grep_match_style,
+            hide_files_matching,
+            hide_hunks_matching,
+            highlight,
+            highlight_style,
+            hunk_badge,
+            hunk_lang_directives,
+            hunk_badges,
             hunk_header_decoration_style,
+            hunk_header_format,
+            hunk_header_funcname_style,
             hunk_header_style,
+            hunk_label,
+            include_path,
+            inline_hunk_header_style,
+            inline_hunk_headers,
+            invert,
+            keep_diff_prefixes,
             keep_plus_minus_markers,
+            lang_for_hunks_matching,
             max_line_distance,
             // Hack: minus-style must come before minus-*emph-style because the latter default
             // dynamically to the value of the former.
@@ -133,14 +237,34 @@ pub fn set_options(
             minus_non_emph_style,
             minus_non_emph_style,
             navigate,
+            no_lockfile_summary,
+            no_newline_badge_style,
+            no_sanitize_control_chars,
+            notebook_diff,
+            numbered,
+            line_length_limit,
+            line_length_marker_style,
             line_numbers,
             line_numbers_left_format,
             line_numbers_left_style,
             line_numbers_minus_style,
             line_numbers_plus_style,
+            line_numbers_primary_side,
+            line_numbers_primary_style,
             line_numbers_right_format,
             line_numbers_right_style,
+            line_numbers_secondary_style,
             line_numbers_zero_style,
+            line_plugin,
+            log_branch_style,
+            log_decoration_style,
+            log_hash_style,
+            log_head_style,
+            log_reflog_selector_style,
+            log_tag_style,
+            max_hunk_lines,
+            max_output_lines,
+            max_line_length,
             paging_mode,
             // Hack: plus-style must come before plus-*emph-style because the latter default
             // dynamically to the value of the former.
@@ -148,13 +272,34 @@ pub fn set_options(
             plus_emph_style,
             plus_empty_line_marker_style,
             plus_non_emph_style,
+            pre_render_filter,
             raw,
+            redact,
+            redacted_style,
+            relative_to,
+            render_cache,
+            reverse_hunks,
+            select,
             side_by_side,
+            side_by_side_align_edits,
+            side_by_side_context_fill,
+            side_by_side_minus_non_emph_style,
+            side_by_side_plus_non_emph_style,
+            signature_bad_style,
+            signature_good_style,
+            signature_warning_style,
+            stats,
+            strict,
+            strip_path_prefix,
+            structured_diff,
             tab_width,
+            terminal_profile,
             tokenization_regex,
             true_color,
             whitespace_error_style,
+            warning_format,
             width,
+            word_diff_mode,
             zero_style
         ],
         opt,
@@ -165,7 +310,18 @@ pub fn set_options(
         true
     );
 
+    unset_disabled_builtin_feature_flags(opt);
     opt.computed.paging_mode = parse_paging_mode(&opt.paging_mode);
+    if opt.computed.is_diff_filter {
+        disable_line_altering_options(opt);
+    }
+    opt.computed.terminal_profile =
+        terminal_profile::get_terminal_profile(&opt.terminal_profile, git_config);
+    set_true_color(opt);
+    set_commit_link_options(opt, git_config);
+    if opt.computed.is_mono {
+        mono::set_mono_options(opt);
+    }
 }
 
 #[allow(non_snake_case)]
@@ -255,6 +411,10 @@ fn set__light__dark__syntax_theme__options(
 ///
 /// [delta "d"]
 ///     features = f e
+///
+/// If every name in `--features` is prefixed with '+' or '-', e.g. `--features "+a -d"`, then the
+/// features of the main [delta] section are gathered as well: `a` is gathered with the priority of
+/// the command line, and `d` is not gathered, wherever it occurs in the feature tree.
 fn gather_features<'a>(
     opt: &cli::Opt,
     builtin_features: &HashMap<String, features::BuiltinFeature>,
@@ -263,22 +423,34 @@ fn gather_features<'a>(
     let mut features = VecDeque::new();
 
     // Gather features from command line.
+    let command_line_features = opt.features.to_lowercase();
+    let enabled_command_line_features = split_feature_string(&command_line_features)
+        .filter(|feature| !feature.starts_with('-'))
+        .map(|feature| feature.trim_start_matches('+'));
     if let Some(git_config) = git_config {
-        for feature in split_feature_string(&opt.features.to_lowercase()) {
+        for feature in enabled_command_line_features {
             gather_features_recursively(feature, &mut features, &builtin_features, opt, git_config);
         }
     } else {
-        for feature in split_feature_string(&opt.features.to_lowercase()) {
+        for feature in enabled_command_line_features {
             features.push_front(feature.to_string());
         }
     }
+    let is_relative_to_git_config = split_feature_string(&command_line_features)
+        .all(|feature| feature.starts_with('+') || feature.starts_with('-'));
 
     // Gather builtin feature flags supplied on command line.
     // TODO: Iterate over programatically-obtained names of builtin features.
     if opt.raw {
         gather_builtin_features_recursively("raw", &mut features, &builtin_features, opt);
     }
-    if opt.color_only {
+    if opt.accessible {
+        gather_builtin_features_recursively("accessible", &mut features, &builtin_features, opt);
+    }
+    if opt.ascii || opt.computed.is_mono {
+        gather_builtin_features_recursively("ascii", &mut features, &builtin_features, opt);
+    }
+    if opt.color_only || opt.computed.is_diff_filter {
         gather_builtin_features_recursively("color-only", &mut features, &builtin_features, opt);
     }
     if opt.diff_highlight {
@@ -292,6 +464,14 @@ fn gather_features<'a>(
     if opt.diff_so_fancy {
         gather_builtin_features_recursively("diff-so-fancy", &mut features, &builtin_features, opt);
     }
+    if opt.foreground_emph {
+        gather_builtin_features_recursively(
+            "foreground-emph",
+            &mut features,
+            &builtin_features,
+            opt,
+        );
+    }
     if opt.line_numbers {
         gather_builtin_features_recursively("line-numbers", &mut features, &builtin_features, opt);
     }
@@ -303,8 +483,9 @@ fn gather_features<'a>(
     }
 
     if let Some(git_config) = git_config {
-        // Gather features from [delta] section if --features was not passed.
-        if opt.features.is_empty() {
+        // Gather features from [delta] section if --features was not passed, or only enables or
+        // disables features in addition to them.
+        if is_relative_to_git_config {
             if let Some(feature_string) = git_config.get::<String>(&format!("delta.features")) {
                 for feature in split_feature_string(&feature_string.to_lowercase()) {
                     gather_features_recursively(
@@ -338,6 +519,9 @@ fn gather_features_recursively<'a>(
     opt: &cli::Opt,
     git_config: &git_config::GitConfig,
 ) {
+    if opt.computed.disabled_features.iter().any(|f| f == feature) {
+        return;
+    }
     if builtin_features.contains_key(feature) {
         gather_builtin_features_recursively(feature, features, builtin_features, opt);
     } else {
@@ -398,7 +582,9 @@ fn gather_builtin_features_recursively<'a>(
     opt: &cli::Opt,
 ) {
     let feature_string = feature.to_string();
-    if features.contains(&feature_string) {
+    if features.contains(&feature_string)
+        || opt.computed.disabled_features.contains(&feature_string)
+    {
         return;
     }
     features.push_front(feature_string);
@@ -430,6 +616,26 @@ fn gather_builtin_features_recursively<'a>(
     }
 }
 
+/// Turn off the boolean options of the builtin features which were disabled in --features, since
+/// they may have been set in git config or on the command line, e.g. as `line-numbers = true`.
+fn unset_disabled_builtin_feature_flags(opt: &mut cli::Opt) {
+    for feature in opt.computed.disabled_features.clone() {
+        match feature.as_str() {
+            "accessible" => opt.accessible = false,
+            "ascii" => opt.ascii = false,
+            "color-only" => opt.color_only = false,
+            "diff-highlight" => opt.diff_highlight = false,
+            "diff-so-fancy" => opt.diff_so_fancy = false,
+            "foreground-emph" => opt.foreground_emph = false,
+            "line-numbers" => opt.line_numbers = false,
+            "navigate" => opt.navigate = false,
+            "raw" => opt.raw = false,
+            "side-by-side" => opt.side_by_side = false,
+            _ => {}
+        }
+    }
+}
+
 fn split_feature_string(features: &str) -> impl Iterator<Item = &str> {
     features.split_whitespace().rev()
 }
@@ -438,7 +644,7 @@ fn set_true_color(opt: &mut cli::Opt) {
     opt.computed.true_color = match opt.true_color.as_ref() {
         "always" => true,
         "never" => false,
-        "auto" => is_truecolor_terminal(),
+        "auto" => is_truecolor_terminal() || opt.computed.terminal_profile.true_color,
         _ => {
             eprintln!(
                 "Invalid value for --24-bit-color option: {} (valid values are \"always\", \"never\", and \"auto\")",
@@ -449,12 +655,145 @@ fn set_true_color(opt: &mut cli::Opt) {
     };
 }
 
+/// Add the commit link rules and hosting service of the remote named by --commit-link-remote, and
+/// look up its URL.
+fn set_commit_link_options(opt: &mut cli::Opt, git_config: &Option<git_config::GitConfig>) {
+    if let Some(git_config) = git_config {
+        let remote = &opt.commit_link_remote;
+        if let Some(rules) =
+            git_config.get::<Vec<String>>(&format!("delta.remote.{}.commit-link-rule", remote))
+        {
+            opt.commit_link_rule.extend(rules);
+        }
+        if let Some(service) =
+            git_config.get::<String>(&format!("delta.remote.{}.commit-link-service", remote))
+        {
+            opt.commit_link_service = service;
+        }
+        opt.computed.commit_link_remote_url =
+            git_config.get::<String>(&format!("remote.{}.url", remote));
+    }
+}
+
 fn is_truecolor_terminal() -> bool {
     env::get_env_var("COLORTERM")
         .map(|colorterm| colorterm == "truecolor" || colorterm == "24bit")
         .unwrap_or(false)
 }
 
+/// Is delta being run by git as its interactive.diffFilter? git sets GIT_EXEC_PATH in the
+/// environment of the commands that it runs, GIT_PAGER_IN_USE in that of its pager, and GIT_PREFIX
+/// in that of shell aliases. A diff filter reads from and writes to pipes. The same holds for e.g.
+/// `git diff | delta > file` in a git hook, so the git process which runs delta must also be an
+/// interactive patch command, such as `git add -p`.
+fn is_running_as_diff_filter() -> bool {
+    env::get_env_var("GIT_EXEC_PATH").is_some()
+        && env::get_env_var("GIT_PAGER_IN_USE").is_none()
+        && env::get_env_var("GIT_PREFIX").is_none()
+        && !atty::is(atty::Stream::Stdin)
+        && !atty::is(atty::Stream::Stdout)
+        && is_run_by_interactive_git_command()
+}
+
+/// Is delta run, directly or through a shell, by a git command which runs interactive.diffFilter?
+/// The command lines of processes are read from /proc, so this is false on systems without it.
+fn is_run_by_interactive_git_command() -> bool {
+    let mut pid = process::id();
+    // The diff filter is run by `sh -c` if it has arguments.
+    for _ in 0..2 {
+        pid = match fs::read_to_string(format!("/proc/{}/stat", pid))
+            .ok()
+            .and_then(|stat| parse_parent_pid(&stat))
+        {
+            Some(ppid) if ppid > 1 => ppid,
+            _ => return false,
+        };
+        let args = match fs::read(format!("/proc/{}/cmdline", pid)) {
+            Ok(cmdline) => cmdline
+                .split(|byte| *byte == 0)
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect::<Vec<_>>(),
+            Err(_) => return false,
+        };
+        if is_interactive_git_command(&args) {
+            return true;
+        }
+    }
+    false
+}
+
+/// Return the parent process id from the contents of /proc/PID/stat: "PID (COMMAND) STATE PPID
+/// ...", in which COMMAND may contain spaces and parentheses.
+fn parse_parent_pid(stat: &str) -> Option<u32> {
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Is this the command line of a git command which runs interactive.diffFilter: `git add -p` or
+/// `git add -i`, the --patch modes of checkout, commit, reset, restore and stash, or the
+/// git-add--interactive script of older versions of git?
+fn is_interactive_git_command(args: &[String]) -> bool {
+    let is_git = |arg: &String| arg == "git" || arg.ends_with("/git");
+    match args.first() {
+        Some(arg) if is_git(arg) => args
+            .iter()
+            .any(|arg| matches!(arg.as_str(), "-p" | "--patch" | "-i" | "--interactive")),
+        Some(_) => args.iter().any(|arg| arg.ends_with("git-add--interactive")),
+        None => false,
+    }
+}
+
+/// Disable the options under which the output does not have one line for each line of the input,
+/// as git requires of a diff filter, or under which the lines are not those of the input. The
+/// decoration options set by the color-only feature are set again, since other features, such as
+/// diff-so-fancy, take precedence over it.
+fn disable_line_altering_options(opt: &mut cli::Opt) {
+    opt.accessible = false;
+    opt.blame = false;
+    opt.collapse_distant_context = false;
+    opt.collapse_noise_hunks = false;
+    opt.color_only = true;
+    opt.commit_date_format = None;
+    opt.commit_decoration_style = "none".to_string();
+    opt.commit_message_markdown = false;
+    opt.commit_style = "raw".to_string();
+    opt.exclude_path = Vec::new();
+    opt.expand_context = 0;
+    opt.file_line_length_limits = Vec::new();
+    opt.file_decoration_style = "none".to_string();
+    opt.file_marks = "none".to_string();
+    opt.file_style = "raw".to_string();
+    opt.file_tabs = Vec::new();
+    opt.grep = false;
+    opt.hide_files_matching = Vec::new();
+    opt.hide_hunks_matching = Vec::new();
+    opt.hunk_header_decoration_style = "none".to_string();
+    opt.hunk_header_style = "raw".to_string();
+    opt.include_path = Vec::new();
+    opt.invert = false;
+    opt.keep_plus_minus_markers = true;
+    opt.line_length_limit = 0;
+    opt.line_numbers = false;
+    opt.line_plugin = None;
+    opt.max_hunk_lines = 0;
+    opt.max_line_length = 0;
+    opt.max_output_lines = 0;
+    opt.no_lockfile_summary = true;
+    opt.no_sanitize_control_chars = true;
+    opt.notebook_diff = false;
+    opt.numbered = false;
+    opt.pre_render_filter = Vec::new();
+    opt.redact = Vec::new();
+    opt.render_cache = false;
+    opt.reverse_hunks = false;
+    opt.select = None;
+    opt.side_by_side = false;
+    opt.stats = false;
+    opt.structured_diff = false;
+    opt.tab_width = 0;
+    opt.word_diff_mode = false;
+}
+
 fn parse_paging_mode(paging_mode_string: &str) -> PagingMode {
     match paging_mode_string {
         "always" => PagingMode::Always,
@@ -471,8 +810,7 @@ fn parse_paging_mode(paging_mode_string: &str) -> PagingMode {
 }
 
 fn set_widths(opt: &mut cli::Opt) {
-    // Allow one character in case e.g. `less --status-column` is in effect. See #41 and #10.
-    opt.computed.available_terminal_width = (Term::stdout().size().1 - 1) as usize;
+    opt.computed.available_terminal_width = get_available_terminal_width();
     let (decorations_width, background_color_extends_to_terminal_width) = match opt.width.as_deref()
     {
         Some("variable") => (cli::Width::Variable, false),
@@ -493,10 +831,75 @@ fn set_widths(opt: &mut cli::Opt) {
         background_color_extends_to_terminal_width;
 }
 
+/// Return the prefixes which git may have added to the file paths of a diff: the default and
+/// mnemonic prefixes, and any configured by diff.srcPrefix and diff.dstPrefix, unless
+/// diff.noprefix is set.
+fn get_diff_prefixes(git_config: &Option<git_config::GitConfig>) -> Vec<String> {
+    let git_config = match git_config {
+        Some(git_config) => git_config,
+        None => return parse::DIFF_PREFIXES.iter().map(|s| s.to_string()).collect(),
+    };
+    if git_config.get::<bool>("diff.noprefix") == Some(true) {
+        return Vec::new();
+    }
+    let mut prefixes: Vec<String> = ["diff.dstPrefix", "diff.srcPrefix"]
+        .iter()
+        .filter_map(|key| git_config.get::<String>(key))
+        .filter(|prefix| !prefix.is_empty())
+        .collect();
+    prefixes.extend(parse::DIFF_PREFIXES.iter().map(|s| s.to_string()));
+    prefixes.dedup();
+    prefixes
+}
+
+/// Return the keys of the settings in the [delta] section of the git config which are neither
+/// options nor the names of builtin features, e.g. misspelled options.
+fn get_unknown_git_config_options(
+    git_config: &Option<git_config::GitConfig>,
+    option_names: &HashMap<&str, &str>,
+    builtin_features: &HashMap<String, features::BuiltinFeature>,
+) -> Vec<String> {
+    let git_config = match git_config {
+        Some(git_config) => git_config,
+        None => return Vec::new(),
+    };
+    let option_names: HashSet<&str> = option_names.values().cloned().collect();
+    git_config
+        .get_entries(r"^delta\.[^.]+$")
+        .into_iter()
+        .filter_map(|(name, _)| name.strip_prefix("delta.").map(String::from))
+        .filter(|key| {
+            !option_names.contains(key.as_str()) && !builtin_features.contains_key(key.as_str())
+        })
+        .collect()
+}
+
+/// Return the (driver, pattern) pairs of the diff.<driver>.xfuncname settings.
+fn get_xfuncname_patterns(git_config: &Option<git_config::GitConfig>) -> Vec<(String, String)> {
+    let git_config = match git_config {
+        Some(git_config) => git_config,
+        None => return Vec::new(),
+    };
+    git_config
+        .get_entries(r"^diff\..+\.xfuncname$")
+        .into_iter()
+        .filter_map(|(name, pattern)| {
+            let driver = name.strip_prefix("diff.")?.strip_suffix(".xfuncname")?;
+            Some((driver.to_string(), pattern))
+        })
+        .collect()
+}
+
+pub fn get_available_terminal_width() -> usize {
+    // Allow one character in case e.g. `less --status-column` is in effect. See #41 and #10.
+    (Term::stdout().size().1 - 1) as usize
+}
+
 #[cfg(test)]
 pub mod tests {
     use std::fs::remove_file;
 
+    use super::{is_interactive_git_command, parse_parent_pid};
     use crate::bat::output::PagingMode;
     use crate::tests::integration_test_utils::integration_test_utils;
 
@@ -505,48 +908,143 @@ pub mod tests {
         let git_config_contents = b"
 [delta]
     24-bit-color = never
+    accessible = true
+    ascii = true
+    auto-degrade-bytes = 77
+    auto-degrade-hunk-lines = 77
+    blame = true
+    blame-format = xxxyyyzzz
+    blame-ignore-revs-file = xxxyyyzzz
+    blame-palette = black white
+    blame-timestamp-format = xxxyyyzzz
+    collapse-distant-context = true
+    collapse-noise-hunks = true
     color-only = true
+    commit-date-format = xxxyyyzzz
     commit-decoration-style = black black
+    commit-link-remote = xxxyyyzzz
+    commit-link-rule = xxxyyyzzz=>zzz
+    commit-link-service = xxxyyyzzz
+    commit-links = true
+    commit-message-markdown = true
     commit-style = black black
+    control-char-style = black black
+    copy-hunk-location = true
+    default-language = xxxyyyzzz
     dark = false
     diff-highlight = true
     diff-so-fancy = true
+    distant-context = 7
+    distant-context-style = black black
+    emit-locations = xxxyyyzzz
+    emit-patch = true
+    exclude-path = xxxyyyzzz
+    expand-context = 7
+    expanded-context-style = black black
     features = xxxyyyzzz
     file-added-label = xxxyyyzzz
     file-decoration-style = black black
+    file-labels = xxxyyyzzz
+    file-marks = xxxyyyzzz
     file-modified-label = xxxyyyzzz
     file-removed-label = xxxyyyzzz
     file-renamed-label = xxxyyyzzz
+    file-stats = true
     file-style = black black
+    file-line-length-limits = xxxyyyzzz
+    file-tabs = xxxyyyzzz
+    filter-notice-style = black black
+    flush-per-line = true
+    foreground-emph = true
+    grep = true
+    grep-line-number-style = black black
+    grep-match-style = black black
+    hide-files-matching = xxxyyyzzz
+    hide-hunks-matching = xxxyyyzzz
+    highlight = xxxyyyzzz
+    highlight-style = black black
+    hunk-badge = xxxyyyzzz=yyy
+    hunk-lang-directives = true
+    hunk-badges = true
     hunk-header-decoration-style = black black
+    hunk-header-format = xxxyyyzzz
+    hunk-header-funcname-style = black black
     hunk-header-style = black black
+    hunk-label = xxxyyyzzz
+    include-path = xxxyyyzzz
+    inline-hunk-header-style = black black
+    inline-hunk-headers = true
+    invert = true
+    keep-diff-prefixes = true
     keep-plus-minus-markers = true
+    lang-for-hunks-matching = xxxyyyzzz=sql
     light = true
+    line-length-limit = 77
+    line-length-marker-style = black black
     line-numbers = true
     line-numbers-left-format = xxxyyyzzz
     line-numbers-left-style = black black
     line-numbers-minus-style = black black
     line-numbers-plus-style = black black
+    line-numbers-primary-side = xxxyyyzzz
+    line-numbers-primary-style = black black
     line-numbers-right-format = xxxyyyzzz
     line-numbers-right-style = black black
+    line-numbers-secondary-style = black black
     line-numbers-zero-style = black black
+    line-plugin = xxxyyyzzz
+    log-branch-style = black black
+    log-decoration-style = black black
+    log-hash-style = black black
+    log-head-style = black black
+    log-reflog-selector-style = black black
+    log-tag-style = black black
+    max-hunk-lines = 77
+    max-output-lines = 77
+    max-line-length = 77
     max-line-distance = 77
     minus-emph-style = black black
     minus-empty-line-marker-style = black black
     minus-non-emph-style = black black
     minus-style = black black
     navigate = true
+    no-lockfile-summary = true
+    no-newline-badge-style = black black
+    no-sanitize-control-chars = true
+    notebook-diff = true
+    numbered = true
     paging = never
     plus-emph-style = black black
     plus-empty-line-marker-style = black black
     plus-non-emph-style = black black
     plus-style = black black
+    pre-render-filter = xxxyyyzzz
     raw = true
+    redact = xxxyyyzzz
+    redacted-style = black black
+    relative-to = xxxyyyzzz
+    render-cache = true
+    reverse-hunks = true
+    select = xxxyyyzzz
     side-by-side = true
+    side-by-side-align-edits = true
+    side-by-side-context-fill = true
+    side-by-side-minus-non-emph-style = black black
+    side-by-side-plus-non-emph-style = black black
+    signature-bad-style = black black
+    signature-good-style = black black
+    signature-warning-style = black black
+    stats = true
+    strict = true
+    strip-path-prefix = xxxyyyzzz
+    structured-diff = true
     syntax-theme = xxxyyyzzz
     tabs = 77
+    terminal-profile = xxxyyyzzz
     whitespace-error-style = black black
+    warning-format = xxxyyyzzz
     width = 77
+    word-diff-mode = true
     word-diff-regex = xxxyyyzzz
     zero-style = black black
     # no-gitconfig
@@ -560,54 +1058,250 @@ pub mod tests {
         );
 
         assert_eq!(opt.true_color, "never");
+        assert_eq!(opt.accessible, true);
+        assert_eq!(opt.ascii, true);
+        assert_eq!(opt.auto_degrade_bytes, 77);
+        assert_eq!(opt.auto_degrade_hunk_lines, 77);
+        assert_eq!(opt.blame, true);
+        assert_eq!(opt.blame_format, "xxxyyyzzz");
+        assert_eq!(opt.blame_ignore_revs_file, Some("xxxyyyzzz".to_string()));
+        assert_eq!(opt.blame_palette, Some("black white".to_string()));
+        assert_eq!(opt.blame_timestamp_format, "xxxyyyzzz");
+        assert!(opt.collapse_distant_context);
+        assert!(opt.collapse_noise_hunks);
         assert_eq!(opt.color_only, true);
+        assert_eq!(opt.commit_date_format, Some("xxxyyyzzz".to_string()));
+        assert_eq!(opt.commit_link_remote, "xxxyyyzzz");
+        assert_eq!(opt.commit_link_rule, vec!["xxxyyyzzz=>zzz".to_string()]);
+        assert_eq!(opt.commit_link_service, "xxxyyyzzz");
+        assert!(opt.commit_links);
+        assert_eq!(opt.commit_message_markdown, true);
         assert_eq!(opt.commit_decoration_style, "black black");
         assert_eq!(opt.commit_style, "black black");
+        assert_eq!(opt.control_char_style, "black black");
+        assert!(opt.copy_hunk_location);
+        assert_eq!(opt.default_language, Some("xxxyyyzzz".to_string()));
         assert_eq!(opt.dark, false);
         // TODO: should set_options not be called on any feature flags?
         // assert_eq!(opt.diff_highlight, true);
         // assert_eq!(opt.diff_so_fancy, true);
+        assert_eq!(opt.distant_context, 7);
+        assert_eq!(opt.distant_context_style, "black black");
+        assert_eq!(opt.emit_locations, Some("xxxyyyzzz".to_string()));
+        assert_eq!(opt.emit_patch, true);
+        assert_eq!(opt.exclude_path, vec!["xxxyyyzzz".to_string()]);
+        assert_eq!(opt.expand_context, 7);
+        assert_eq!(opt.expanded_context_style, "black black");
         assert!(opt.features.split_whitespace().any(|s| s == "xxxyyyzzz"));
         assert_eq!(opt.file_added_label, "xxxyyyzzz");
         assert_eq!(opt.file_decoration_style, "black black");
+        assert_eq!(opt.file_labels, Some("xxxyyyzzz".to_string()));
+        assert_eq!(opt.file_marks, "xxxyyyzzz");
         assert_eq!(opt.file_modified_label, "xxxyyyzzz");
         assert_eq!(opt.file_removed_label, "xxxyyyzzz");
         assert_eq!(opt.file_renamed_label, "xxxyyyzzz");
+        assert!(opt.file_stats);
         assert_eq!(opt.file_style, "black black");
+        assert_eq!(opt.file_line_length_limits, vec!["xxxyyyzzz".to_string()]);
+        assert_eq!(opt.file_tabs, vec!["xxxyyyzzz".to_string()]);
+        assert_eq!(opt.filter_notice_style, "black black");
+        assert!(opt.flush_per_line);
+        assert_eq!(opt.foreground_emph, true);
+        assert_eq!(opt.grep, true);
+        assert_eq!(opt.grep_line_number_style, "black black");
+        assert_eq!(opt.grep_match_style, "black black");
+        assert_eq!(opt.hide_files_matching, vec!["xxxyyyzzz".to_string()]);
+        assert_eq!(opt.hide_hunks_matching, vec!["xxxyyyzzz".to_string()]);
+        assert_eq!(opt.highlight, vec!["xxxyyyzzz".to_string()]);
+        assert_eq!(opt.highlight_style, "black black");
+        assert_eq!(opt.hunk_badge, vec!["xxxyyyzzz=yyy".to_string()]);
+        assert_eq!(opt.hunk_lang_directives, true);
+        assert_eq!(opt.hunk_badges, true);
         assert_eq!(opt.hunk_header_decoration_style, "black black");
+        assert_eq!(opt.hunk_header_format, "xxxyyyzzz");
+        assert_eq!(opt.hunk_header_funcname_style, "black black");
         assert_eq!(opt.hunk_header_style, "black black");
+        assert_eq!(opt.hunk_label, "xxxyyyzzz");
+        assert_eq!(opt.include_path, vec!["xxxyyyzzz".to_string()]);
+        assert_eq!(opt.inline_hunk_header_style, "black black");
+        assert!(opt.inline_hunk_headers);
+        assert_eq!(opt.invert, true);
+        assert_eq!(opt.keep_diff_prefixes, true);
         assert_eq!(opt.keep_plus_minus_markers, true);
+        assert_eq!(
+            opt.lang_for_hunks_matching,
+            vec!["xxxyyyzzz=sql".to_string()]
+        );
         assert_eq!(opt.light, true);
+        assert_eq!(opt.line_length_limit, 77);
+        assert_eq!(opt.line_length_marker_style, "black black");
         assert_eq!(opt.line_numbers, true);
         assert_eq!(opt.line_numbers_left_format, "xxxyyyzzz");
         assert_eq!(opt.line_numbers_left_style, "black black");
         assert_eq!(opt.line_numbers_minus_style, "black black");
         assert_eq!(opt.line_numbers_plus_style, "black black");
+        assert_eq!(opt.line_numbers_primary_side, "xxxyyyzzz");
+        assert_eq!(opt.line_numbers_primary_style, "black black");
         assert_eq!(opt.line_numbers_right_format, "xxxyyyzzz");
         assert_eq!(opt.line_numbers_right_style, "black black");
+        assert_eq!(opt.line_numbers_secondary_style, "black black");
         assert_eq!(opt.line_numbers_zero_style, "black black");
+        assert_eq!(opt.line_plugin, Some("xxxyyyzzz".to_string()));
+        assert_eq!(opt.log_branch_style, "black black");
+        assert_eq!(opt.log_decoration_style, "black black");
+        assert_eq!(opt.log_hash_style, "black black");
+        assert_eq!(opt.log_head_style, "black black");
+        assert_eq!(opt.log_reflog_selector_style, "black black");
+        assert_eq!(opt.log_tag_style, "black black");
+        assert_eq!(opt.max_hunk_lines, 77);
+        assert_eq!(opt.max_output_lines, 77);
+        assert_eq!(opt.max_line_length, 77);
         assert_eq!(opt.max_line_distance, 77 as f64);
         assert_eq!(opt.minus_emph_style, "black black");
         assert_eq!(opt.minus_empty_line_marker_style, "black black");
         assert_eq!(opt.minus_non_emph_style, "black black");
         assert_eq!(opt.minus_style, "black black");
         assert_eq!(opt.navigate, true);
+        assert_eq!(opt.no_lockfile_summary, true);
+        assert_eq!(opt.no_newline_badge_style, "black black");
+        assert!(opt.no_sanitize_control_chars);
+        assert_eq!(opt.notebook_diff, true);
+        assert_eq!(opt.numbered, true);
         assert_eq!(opt.paging_mode, "never");
         assert_eq!(opt.plus_emph_style, "black black");
         assert_eq!(opt.plus_empty_line_marker_style, "black black");
         assert_eq!(opt.plus_non_emph_style, "black black");
         assert_eq!(opt.plus_style, "black black");
+        assert_eq!(opt.pre_render_filter, vec!["xxxyyyzzz".to_string()]);
         assert_eq!(opt.raw, true);
+        assert_eq!(opt.redact, vec!["xxxyyyzzz".to_string()]);
+        assert_eq!(opt.redacted_style, "black black");
+        assert_eq!(opt.relative_to, Some("xxxyyyzzz".to_string()));
+        assert_eq!(opt.render_cache, true);
+        assert_eq!(opt.reverse_hunks, true);
+        assert_eq!(opt.select, Some("xxxyyyzzz".to_string()));
         assert_eq!(opt.side_by_side, true);
+        assert!(opt.side_by_side_align_edits);
+        assert_eq!(opt.side_by_side_context_fill, true);
+        assert_eq!(opt.side_by_side_minus_non_emph_style, "black black");
+        assert_eq!(opt.side_by_side_plus_non_emph_style, "black black");
+        assert_eq!(opt.signature_bad_style, "black black");
+        assert_eq!(opt.signature_good_style, "black black");
+        assert_eq!(opt.signature_warning_style, "black black");
+        assert_eq!(opt.stats, true);
+        assert_eq!(opt.strict, true);
+        assert_eq!(opt.strip_path_prefix, vec!["xxxyyyzzz".to_string()]);
+        assert_eq!(opt.structured_diff, true);
         assert_eq!(opt.syntax_theme, Some("xxxyyyzzz".to_string()));
         assert_eq!(opt.tab_width, 77);
+        assert_eq!(opt.terminal_profile, "xxxyyyzzz");
         assert_eq!(opt.whitespace_error_style, "black black");
+        assert_eq!(opt.warning_format, "xxxyyyzzz");
         assert_eq!(opt.width, Some("77".to_string()));
+        assert_eq!(opt.word_diff_mode, true);
         assert_eq!(opt.tokenization_regex, "xxxyyyzzz");
         assert_eq!(opt.zero_style, "black black");
 
         assert_eq!(opt.computed.paging_mode, PagingMode::Never);
+        assert!(opt.computed.unknown_git_config_options.is_empty());
 
         remove_file(git_config_path).unwrap();
     }
+
+    #[test]
+    fn test_unknown_git_config_options() {
+        let git_config_contents = b"
+[delta]
+    side-by-side = true
+    navigate = true
+    line-numbrs = true
+[delta \"my-feature\"]
+    not-an-option = true
+";
+        let git_config_path = "delta__test_unknown_git_config_options.gitconfig";
+
+        let opt = integration_test_utils::make_options_from_args_and_git_config(
+            &[],
+            Some(git_config_contents),
+            Some(git_config_path),
+        );
+        assert_eq!(
+            opt.computed.unknown_git_config_options,
+            vec!["line-numbrs".to_string()]
+        );
+
+        remove_file(git_config_path).unwrap();
+    }
+
+    #[test]
+    fn test_git_diff_settings_are_used() {
+        let git_config_contents = b"
+[diff]
+    wordRegex = [^[:space:]]+
+    context = 5
+    dstPrefix = new/
+";
+        let git_config_path = "delta__test_git_diff_settings_are_used.gitconfig";
+
+        let opt = integration_test_utils::make_options_from_args_and_git_config(
+            &[],
+            Some(git_config_contents),
+            Some(git_config_path),
+        );
+        assert_eq!(opt.tokenization_regex, "[^[:space:]]+");
+        assert_eq!(opt.computed.diff_context_lines, Some(5));
+        assert_eq!(opt.computed.diff_prefixes[0], "new/");
+        assert!(opt.computed.diff_prefixes.contains(&"a/".to_string()));
+
+        let opt = integration_test_utils::make_options_from_args_and_git_config(
+            &["--word-diff-regex", "\\w+"],
+            Some(git_config_contents),
+            Some(git_config_path),
+        );
+        assert_eq!(opt.tokenization_regex, "\\w+");
+
+        remove_file(git_config_path).unwrap();
+    }
+
+    #[test]
+    fn test_diff_filter_mode() {
+        let opt = integration_test_utils::make_options_from_args(&[
+            "--diff-filter-mode",
+            "--side-by-side",
+            "--line-numbers",
+            "--select",
+            "1",
+        ]);
+        assert!(opt.computed.is_diff_filter);
+        assert!(opt.features.split_whitespace().any(|s| s == "color-only"));
+        assert!(!opt.side_by_side);
+        assert!(!opt.line_numbers);
+        assert_eq!(opt.select, None);
+        assert!(opt.no_lockfile_summary);
+    }
+
+    #[test]
+    fn test_is_interactive_git_command() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert!(is_interactive_git_command(&args(&["git", "add", "-p"])));
+        assert!(is_interactive_git_command(&args(&[
+            "/usr/bin/git",
+            "stash",
+            "--patch"
+        ])));
+        assert!(is_interactive_git_command(&args(&[
+            "/usr/bin/perl",
+            "/usr/lib/git-core/git-add--interactive",
+            "--patch"
+        ])));
+        assert!(!is_interactive_git_command(&args(&["git", "commit"])));
+        assert!(!is_interactive_git_command(&args(&[
+            "sh",
+            "-c",
+            "git diff -p"
+        ])));
+        assert!(!is_interactive_git_command(&[]));
+        assert_eq!(parse_parent_pid("12 (sh) S 34 12 12 0"), Some(34));
+        assert_eq!(parse_parent_pid("12 (a (b) c) S 34 12"), Some(34));
+    }
 }
@@ -1,43 +1,102 @@
 use lazy_static::lazy_static;
 use regex::Regex;
+use std::borrow::Cow;
+use std::fmt::Write as FmtWrite;
 use std::io::Write;
 
 use ansi_term;
 use itertools::Itertools;
-use syntect::easy::HighlightLines;
 use syntect::highlighting::Style as SyntectStyle;
 use syntect::parsing::{SyntaxReference, SyntaxSet};
 use unicode_segmentation::UnicodeSegmentation;
 
+use crate::ansi;
+use crate::benchmark::{self, Phase};
+use crate::cli;
 use crate::config::{self, delta_unreachable};
+use crate::control_chars::{self, SymbolRanges};
 use crate::delta::State;
 use crate::edits;
 use crate::features::line_numbers;
 use crate::features::side_by_side;
+use crate::funcname;
+use crate::highlight::CachingHighlighter;
+use crate::highlight_patterns;
+use crate::options::set;
 use crate::paint::superimpose_style_sections::superimpose_style_sections;
+use crate::redact;
 use crate::style::Style;
+use crate::warnings;
+use crate::word_diff;
 
 pub const ANSI_CSI_CLEAR_TO_EOL: &str = "\x1b[0K";
 pub const ANSI_CSI_CLEAR_TO_BOL: &str = "\x1b[1K";
 pub const ANSI_SGR_RESET: &str = "\x1b[0m";
 
+const TRUNCATION_SYMBOL: &str = "…";
+
+/// A line split into sections of text, each with the style to paint it in.
+pub type LineSections<'a, S> = Vec<(S, &'a str)>;
+
+/// The pairing of minus and plus lines, as (minus line index, plus line index). A line which is
+/// not paired with a line of the other kind is paired with None.
+pub type LineAlignment = Vec<(Option<usize>, Option<usize>)>;
+
+/// The style sections of a sequence of lines: those given by syntax highlighting, and those given
+/// by the diff, which are superimposed when the lines are painted.
+pub struct StyleSections<'a> {
+    pub syntax: Vec<LineSections<'a, SyntectStyle>>,
+    pub diff: Vec<LineSections<'a, Style>>,
+}
+
+/// The widths which the lines of a hunk are painted to: those of the side-by-side panels, and the
+/// width over which an added line is marked, by --line-length-limit.
+pub struct LineWidths {
+    pub side_by_side_data: side_by_side::SideBySideData,
+    // The --line-length-limit of the current file
+    pub line_length_limit: usize,
+}
+
 pub struct Painter<'a> {
     pub minus_lines: Vec<String>,
     pub plus_lines: Vec<String>,
+    // The styles given to buffered lines by --line-plugin, replacing their diff styles
+    pub minus_line_style_overrides: Vec<Option<Style>>,
+    pub plus_line_style_overrides: Vec<Option<Style>>,
+    // The ranges of the symbols which replaced control characters in the buffered lines
+    pub minus_line_symbol_ranges: Vec<SymbolRanges>,
+    pub plus_line_symbol_ranges: Vec<SymbolRanges>,
     pub writer: &'a mut dyn Write,
-    pub syntax: &'a SyntaxReference,
-    pub highlighter: HighlightLines<'a>,
+    pub syntax_extension: Option<String>,
+    // The language of the current hunk, if it differs from that of its file
+    pub hunk_language: Option<String>,
+    // The tab width of the current file
+    pub tab_width: usize,
+    // The diff driver of the current file, if it has funcname rules
+    pub diff_driver: Option<String>,
+    pub highlighter: Option<CachingHighlighter<'a>>,
     pub config: &'a config::Config,
     pub output_buffer: String,
     pub line_numbers_data: line_numbers::LineNumbersData<'a>,
+    // The widths which depend on the terminal width, which change if the terminal is resized: see
+    // update_terminal_width
+    pub available_terminal_width: usize,
+    pub decorations_width: cli::Width,
+    pub line_widths: LineWidths,
+    // Whether expensive features have been disabled for the remainder of the input.
+    pub degraded: bool,
+    // The last context line of the current hunk, under --side-by-side-context-fill
+    pub last_zero_line: Option<String>,
+    // Whether the buffered minus, or plus, lines end a file which has no newline at its end, under
+    // --side-by-side
+    pub minus_no_newline: bool,
+    pub plus_no_newline: bool,
+    // Whether the location of a hunk has been copied to the clipboard, under --copy-hunk-location
+    pub is_hunk_location_copied: bool,
 }
 
 impl<'a> Painter<'a> {
     pub fn new(writer: &'a mut dyn Write, config: &'a config::Config) -> Self {
-        let default_syntax = Self::get_syntax(&config.syntax_set, None);
-        // TODO: Avoid doing this.
-        let dummy_highlighter = HighlightLines::new(default_syntax, &config.syntax_dummy_theme);
-
         let line_numbers_data = if config.line_numbers {
             line_numbers::LineNumbersData::from_format_strings(
                 &config.line_numbers_left_format,
@@ -49,25 +108,116 @@ impl<'a> Painter<'a> {
         Self {
             minus_lines: Vec::new(),
             plus_lines: Vec::new(),
+            minus_line_style_overrides: Vec::new(),
+            plus_line_style_overrides: Vec::new(),
+            minus_line_symbol_ranges: Vec::new(),
+            plus_line_symbol_ranges: Vec::new(),
             output_buffer: String::new(),
-            syntax: default_syntax,
-            highlighter: dummy_highlighter,
+            syntax_extension: None,
+            hunk_language: None,
+            tab_width: config.tab_width,
+            diff_driver: None,
+            highlighter: None,
             writer,
             config,
             line_numbers_data,
+            available_terminal_width: config.available_terminal_width,
+            decorations_width: config.decorations_width,
+            line_widths: LineWidths {
+                side_by_side_data: side_by_side::SideBySideData::new(
+                    &config.decorations_width,
+                    &config.available_terminal_width,
+                ),
+                line_length_limit: config.line_length_limit,
+            },
+            degraded: false,
+            last_zero_line: None,
+            minus_no_newline: false,
+            plus_no_newline: false,
+            is_hunk_location_copied: false,
+        }
+    }
+
+    /// Update the widths which were computed from the terminal width, if the terminal has been
+    /// resized since, so that a long-running stream such as `git log -p` is displayed at the
+    /// current width.
+    pub fn update_terminal_width(&mut self) {
+        if !self.config.track_terminal_width {
+            return;
+        }
+        let available_terminal_width = set::get_available_terminal_width();
+        if available_terminal_width == self.available_terminal_width {
+            return;
+        }
+        self.available_terminal_width = available_terminal_width;
+        if let cli::Width::Fixed(_) = self.decorations_width {
+            self.decorations_width = cli::Width::Fixed(available_terminal_width);
         }
+        self.line_widths.side_by_side_data =
+            side_by_side::SideBySideData::new(&self.decorations_width, &available_terminal_width);
+    }
+
+    /// Disable syntax highlighting, within-line highlighting, and side-by-side display for the
+    /// remainder of the input, and inform the user why.
+    pub fn degrade(&mut self, reason: &str) {
+        self.degraded = true;
+        self.highlighter = None;
+        warnings::warn(
+            "degraded",
+            &format!(
+                "{}: disabling syntax highlighting, within-line highlighting, \
+                 and side-by-side display for the remainder of the input.",
+                reason
+            ),
+            self.config,
+        );
     }
 
     pub fn set_syntax(&mut self, extension: Option<&str>) {
-        self.syntax = Painter::get_syntax(&self.config.syntax_set, extension);
+        // Under --notebook-diff, notebooks are displayed as their cells' Python source.
+        let extension = match extension {
+            Some("ipynb") if self.config.notebook_diff => Some("py"),
+            extension => extension,
+        };
+        self.syntax_extension = extension.map(|s| s.to_string());
+    }
+
+    /// Use the tab width of the file with this path, or the default tab width if None.
+    pub fn set_tab_width(&mut self, path: Option<&str>) {
+        self.tab_width = match path {
+            Some(path) => self.config.get_tab_width(path),
+            None => self.config.tab_width,
+        };
+    }
+
+    /// Use the line length limit of the file with this path, or the default limit if None.
+    pub fn set_line_length_limit(&mut self, path: Option<&str>) {
+        self.line_widths.line_length_limit = match path {
+            Some(path) => self.config.get_line_length_limit(path),
+            None => self.config.line_length_limit,
+        };
     }
 
-    fn get_syntax(syntax_set: &'a SyntaxSet, extension: Option<&str>) -> &'a SyntaxReference {
+    /// Use the diff driver of the file with this path, or none if None.
+    pub fn set_diff_driver(&mut self, path: Option<&str>) {
+        self.diff_driver = path.and_then(|path| funcname::get_diff_driver(path, self.config));
+    }
+
+    fn get_syntax(
+        syntax_set: &'a SyntaxSet,
+        extension: Option<&str>,
+        default_language: Option<&str>,
+    ) -> &'a SyntaxReference {
         if let Some(extension) = extension {
             if let Some(syntax) = syntax_set.find_syntax_by_extension(extension) {
                 return syntax;
             }
         }
+        if let Some(language) = default_language {
+            if let Some(syntax) = syntax_set.find_syntax_by_token(language) {
+                return syntax;
+            }
+        }
         return syntax_set
             .find_syntax_by_extension("txt")
             .unwrap_or_else(|| {
@@ -75,18 +225,53 @@ impl<'a> Painter<'a> {
             });
     }
 
+    /// Return the name of the language of the current file, or None if it is plain text.
+    pub fn get_language_name(&self) -> Option<String> {
+        let syntax = Painter::get_syntax(
+            self.config.syntax_set.get(),
+            self.syntax_extension.as_deref(),
+            self.config.default_language.as_deref(),
+        );
+        Some(syntax.name.clone()).filter(|name| name != "Plain Text")
+    }
+
+    /// Create a highlighter for the current syntax. This is called at each hunk header; the first
+    /// call is what causes the syntax set to be loaded.
     pub fn set_highlighter(&mut self) {
-        if let Some(ref syntax_theme) = self.config.syntax_theme {
-            self.highlighter = HighlightLines::new(self.syntax, &syntax_theme)
+        let config = self.config;
+        if self.degraded {
+            return;
+        }
+        if let Some(ref syntax_theme) = config.syntax_theme {
+            let syntax_set = config.syntax_set.get();
+            let syntax = match self
+                .hunk_language
+                .as_deref()
+                .and_then(|language| syntax_set.find_syntax_by_token(language))
+            {
+                Some(syntax) => syntax,
+                None => Painter::get_syntax(
+                    syntax_set,
+                    self.syntax_extension.as_deref(),
+                    config.default_language.as_deref(),
+                ),
+            };
+            let cache = self
+                .highlighter
+                .take()
+                .map(|highlighter| highlighter.cache)
+                .unwrap_or_default();
+            self.highlighter = Some(CachingHighlighter::new(syntax, &syntax_theme, cache))
         };
     }
 
     /// Replace initial -/+ character with ' ', expand tabs as spaces, and optionally terminate with
-    /// newline.
+    /// newline. Return the line and the byte ranges in it of the symbols which replaced control
+    /// characters.
     // Terminating with newline character is necessary for many of the sublime syntax definitions to
     // highlight correctly.
     // See https://docs.rs/syntect/3.2.0/syntect/parsing/struct.SyntaxSetBuilder.html#method.add_from_folder
-    pub fn prepare(&self, line: &str, append_newline: bool) -> String {
+    pub fn prepare(&self, line: &str, append_newline: bool) -> (String, SymbolRanges) {
         let terminator = if append_newline { "\n" } else { "" };
         if !line.is_empty() {
             let mut line = line.graphemes(true);
@@ -98,20 +283,49 @@ impl<'a> Painter<'a> {
             // TODO: Things should, but do not, work if this leading space is omitted at this stage.
             // See comment in align::Alignment::new.
             line.next();
-            format!(" {}{}", self.expand_tabs(line), terminator)
+            let line = self.expand_tabs(line);
+            let line = self.truncate(redact::redact(&line, self.config).into_owned());
+            let (line, symbol_ranges) = if self.config.sanitize_control_chars {
+                control_chars::sanitize(&line)
+            } else {
+                (Cow::Borrowed(line.as_str()), Vec::new())
+            };
+            let symbol_ranges = symbol_ranges
+                .into_iter()
+                .map(|(start, end)| (start + 1, end + 1))
+                .collect();
+            (format!(" {}{}", line, terminator), symbol_ranges)
         } else {
-            terminator.to_string()
+            (terminator.to_string(), Vec::new())
         }
     }
 
+    /// Truncate a line longer than --max-line-length, marking the truncation. This keeps very
+    /// long lines, such as those of minified files, from freezing syntax highlighting and
+    /// within-line highlighting.
+    pub fn truncate(&self, mut line: String) -> String {
+        let max_line_length = self.config.max_line_length;
+        if max_line_length > 0 && line.len() > max_line_length {
+            if let Some((i, _)) = line.grapheme_indices(true).nth(max_line_length) {
+                line.truncate(i);
+                line.push_str(if self.config.ascii {
+                    "..."
+                } else {
+                    TRUNCATION_SYMBOL
+                });
+            }
+        }
+        line
+    }
+
     /// Expand tabs as spaces.
     /// tab_width = 0 is documented to mean do not replace tabs.
     pub fn expand_tabs<'b, I>(&self, line: I) -> String
     where
         I: Iterator<Item = &'b str>,
     {
-        if self.config.tab_width > 0 {
-            let tab_replacement = " ".repeat(self.config.tab_width);
+        if self.tab_width > 0 {
+            let tab_replacement = " ".repeat(self.tab_width);
             line.map(|s| if s == "\t" { &tab_replacement } else { s })
                 .collect::<String>()
         } else {
@@ -120,127 +334,289 @@ impl<'a> Painter<'a> {
     }
 
     pub fn paint_buffered_minus_and_plus_lines(&mut self) {
-        let minus_line_syntax_style_sections = Self::get_syntax_style_sections_for_lines(
+        // Borrow fields individually, since the timed closures cannot each capture all of self.
+        let (minus_lines, plus_lines, highlighter, config) = (
             &self.minus_lines,
-            &State::HunkMinus,
-            &mut self.highlighter,
-            self.config,
-        );
-        let plus_line_syntax_style_sections = Self::get_syntax_style_sections_for_lines(
             &self.plus_lines,
-            &State::HunkPlus,
             &mut self.highlighter,
             self.config,
         );
-        let (minus_line_diff_style_sections, plus_line_diff_style_sections, line_alignment) =
-            Self::get_diff_style_sections(&self.minus_lines, &self.plus_lines, self.config);
-
-        if self.config.side_by_side {
-            side_by_side::paint_minus_and_plus_lines_side_by_side(
-                minus_line_syntax_style_sections,
-                minus_line_diff_style_sections,
-                plus_line_syntax_style_sections,
-                plus_line_diff_style_sections,
-                line_alignment,
-                &mut self.output_buffer,
-                self.config,
-                &mut Some(&mut self.line_numbers_data),
-                None,
-            );
-        } else {
-            if !self.minus_lines.is_empty() {
-                Painter::paint_lines(
-                    minus_line_syntax_style_sections,
-                    minus_line_diff_style_sections,
-                    &State::HunkMinus,
-                    &mut self.output_buffer,
-                    self.config,
-                    &mut Some(&mut self.line_numbers_data),
-                    if self.config.keep_plus_minus_markers {
-                        "-"
-                    } else {
-                        ""
-                    },
-                    Some(self.config.minus_empty_line_marker_style),
-                    None,
-                );
-            }
-            if !self.plus_lines.is_empty() {
-                Painter::paint_lines(
-                    plus_line_syntax_style_sections,
-                    plus_line_diff_style_sections,
-                    &State::HunkPlus,
-                    &mut self.output_buffer,
-                    self.config,
-                    &mut Some(&mut self.line_numbers_data),
-                    if self.config.keep_plus_minus_markers {
-                        "+"
+        let (minus_line_symbol_ranges, plus_line_symbol_ranges) = (
+            &self.minus_line_symbol_ranges,
+            &self.plus_line_symbol_ranges,
+        );
+        let (output_buffer, line_numbers_data) =
+            (&mut self.output_buffer, &mut self.line_numbers_data);
+        let line_widths = &self.line_widths;
+        let degraded = self.degraded;
+        let last_zero_line = &self.last_zero_line;
+        let (minus_no_newline, plus_no_newline) = (self.minus_no_newline, self.plus_no_newline);
+        let (minus_line_syntax_style_sections, plus_line_syntax_style_sections) =
+            benchmark::timed(Phase::Highlight, || {
+                (
+                    Self::get_syntax_style_sections_for_lines(
+                        minus_lines,
+                        &State::HunkMinus,
+                        highlighter,
+                        config,
+                    ),
+                    Self::get_syntax_style_sections_for_lines(
+                        plus_lines,
+                        &State::HunkPlus,
+                        highlighter,
+                        config,
+                    ),
+                )
+            });
+        // Under --accessible, changes are conveyed by the line labels, which a merged line lacks.
+        if config.word_diff_mode && !config.side_by_side && !config.accessible && !degraded {
+            benchmark::timed(Phase::Paint, || {
+                word_diff::paint_minus_and_plus_lines_merged(
+                    (minus_lines, plus_lines),
+                    (minus_line_symbol_ranges, plus_line_symbol_ranges),
+                    (
+                        minus_line_syntax_style_sections,
+                        plus_line_syntax_style_sections,
+                    ),
+                    output_buffer,
+                    config,
+                    &mut Some(line_numbers_data),
+                    line_widths.line_length_limit,
+                )
+            });
+            self.clear_buffered_minus_and_plus_lines();
+            return;
+        }
+        let (mut minus_line_diff_style_sections, mut plus_line_diff_style_sections, line_alignment) =
+            benchmark::timed(Phase::Edits, || {
+                if degraded {
+                    Self::get_plain_diff_style_sections(minus_lines, plus_lines, config)
+                } else {
+                    Self::get_diff_style_sections(minus_lines, plus_lines, config)
+                }
+            });
+        Self::override_styles(
+            &mut minus_line_diff_style_sections,
+            &self.minus_line_style_overrides,
+        );
+        Self::override_styles(
+            &mut plus_line_diff_style_sections,
+            &self.plus_line_style_overrides,
+        );
+        Self::style_control_char_symbols(
+            &mut minus_line_diff_style_sections,
+            minus_line_symbol_ranges,
+            config,
+        );
+        Self::style_control_char_symbols(
+            &mut plus_line_diff_style_sections,
+            plus_line_symbol_ranges,
+            config,
+        );
+
+        benchmark::timed(Phase::Paint, || {
+            if config.side_by_side && !degraded {
+                side_by_side::paint_minus_and_plus_lines_side_by_side(
+                    (
+                        StyleSections {
+                            syntax: minus_line_syntax_style_sections,
+                            diff: minus_line_diff_style_sections,
+                        },
+                        StyleSections {
+                            syntax: plus_line_syntax_style_sections,
+                            diff: plus_line_diff_style_sections,
+                        },
+                    ),
+                    line_alignment,
+                    // Only the empty panel of a run of added, or removed, lines is filled.
+                    if minus_lines.is_empty() || plus_lines.is_empty() {
+                        last_zero_line.as_deref()
                     } else {
-                        ""
+                        None
                     },
-                    Some(self.config.plus_empty_line_marker_style),
-                    None,
+                    output_buffer,
+                    line_widths,
+                    config,
+                    &mut Some(line_numbers_data),
                 );
+                if minus_no_newline || plus_no_newline {
+                    side_by_side::paint_no_newline_badges(
+                        minus_no_newline,
+                        plus_no_newline,
+                        output_buffer,
+                        &line_widths.side_by_side_data,
+                        config,
+                        &mut Some(line_numbers_data),
+                    );
+                }
+            } else {
+                if !minus_lines.is_empty() {
+                    Painter::paint_lines(
+                        StyleSections {
+                            syntax: minus_line_syntax_style_sections,
+                            diff: minus_line_diff_style_sections,
+                        },
+                        &State::HunkMinus,
+                        output_buffer,
+                        config,
+                        &mut Some(line_numbers_data),
+                        Painter::get_line_prefix(&State::HunkMinus, "-", config),
+                        0,
+                    );
+                }
+                if !plus_lines.is_empty() {
+                    Painter::paint_lines(
+                        StyleSections {
+                            syntax: plus_line_syntax_style_sections,
+                            diff: plus_line_diff_style_sections,
+                        },
+                        &State::HunkPlus,
+                        output_buffer,
+                        config,
+                        &mut Some(line_numbers_data),
+                        Painter::get_line_prefix(&State::HunkPlus, "+", config),
+                        line_widths.line_length_limit,
+                    );
+                }
             }
-        }
+        });
+        self.clear_buffered_minus_and_plus_lines();
+    }
+
+    fn clear_buffered_minus_and_plus_lines(&mut self) {
         self.minus_lines.clear();
         self.plus_lines.clear();
+        self.minus_line_style_overrides.clear();
+        self.plus_line_style_overrides.clear();
+        self.minus_line_symbol_ranges.clear();
+        self.plus_line_symbol_ranges.clear();
+        self.minus_no_newline = false;
+        self.plus_no_newline = false;
     }
 
-    pub fn paint_zero_line(&mut self, line: &str) {
-        let prefix = if self.config.keep_plus_minus_markers && !line.is_empty() {
-            &line[..1]
-        } else {
-            ""
-        };
-        let lines = vec![self.prepare(line, true)];
-        let syntax_style_sections = Painter::get_syntax_style_sections_for_lines(
-            &lines,
-            &State::HunkZero,
-            &mut self.highlighter,
-            &self.config,
-        );
-        let diff_style_sections = vec![(self.config.zero_style, lines[0].as_str())];
+    /// Replace the diff styles of lines with the styles given to them by --line-plugin.
+    fn override_styles(
+        diff_style_sections: &mut Vec<Vec<(Style, &str)>>,
+        style_overrides: &[Option<Style>],
+    ) {
+        for (sections, style) in diff_style_sections.iter_mut().zip(style_overrides) {
+            if let Some(style) = style {
+                for section in sections.iter_mut() {
+                    section.0 = *style;
+                }
+            }
+        }
+    }
 
-        if self.config.side_by_side {
-            side_by_side::paint_zero_lines_side_by_side(
-                syntax_style_sections,
-                vec![diff_style_sections],
-                &State::HunkZero,
-                &mut self.output_buffer,
-                self.config,
-                &mut Some(&mut self.line_numbers_data),
-                prefix,
-                None,
+    /// Display the symbols which replaced control characters in lines in control-char-style.
+    fn style_control_char_symbols(
+        diff_style_sections: &mut [LineSections<Style>],
+        symbol_ranges: &[SymbolRanges],
+        config: &config::Config,
+    ) {
+        for (sections, ranges) in diff_style_sections.iter_mut().zip(symbol_ranges) {
+            *sections = control_chars::style_control_char_sections(
+                std::mem::take(sections),
+                ranges,
+                config,
             );
-        } else {
-            Painter::paint_lines(
-                syntax_style_sections,
-                vec![diff_style_sections],
+        }
+    }
+
+    /// Paint an unchanged line, in zero-style unless `style_override` is given.
+    pub fn paint_zero_line(&mut self, line: &str, style_override: Option<Style>) {
+        let prefix =
+            Painter::get_line_prefix(&State::HunkZero, line.get(..1).unwrap_or(""), self.config);
+        let (line, symbol_ranges) = self.prepare(line, true);
+        let lines = vec![line];
+        if self.config.side_by_side_context_fill {
+            self.last_zero_line = Some(lines[0].clone());
+        }
+        let (highlighter, config) = (&mut self.highlighter, self.config);
+        let (output_buffer, line_numbers_data) =
+            (&mut self.output_buffer, &mut self.line_numbers_data);
+        let side_by_side_data = &self.line_widths.side_by_side_data;
+        let syntax_style_sections = benchmark::timed(Phase::Highlight, || {
+            Painter::get_syntax_style_sections_for_lines(
+                &lines,
                 &State::HunkZero,
-                &mut self.output_buffer,
-                self.config,
-                &mut Some(&mut self.line_numbers_data),
-                prefix,
-                None,
-                None,
-            );
+                highlighter,
+                config,
+            )
+        });
+        let style_sections = StyleSections {
+            syntax: syntax_style_sections,
+            diff: vec![control_chars::style_control_char_sections(
+                vec![(
+                    style_override.unwrap_or(config.zero_style),
+                    lines[0].as_str(),
+                )],
+                &symbol_ranges,
+                config,
+            )],
+        };
+        let degraded = self.degraded;
+
+        benchmark::timed(Phase::Paint, || {
+            if config.side_by_side && !degraded {
+                side_by_side::paint_zero_lines_side_by_side(
+                    style_sections,
+                    &State::HunkZero,
+                    output_buffer,
+                    side_by_side_data,
+                    config,
+                    &mut Some(line_numbers_data),
+                    prefix,
+                );
+            } else {
+                Painter::paint_lines(
+                    style_sections,
+                    &State::HunkZero,
+                    output_buffer,
+                    config,
+                    &mut Some(line_numbers_data),
+                    prefix,
+                    0,
+                );
+            }
+        });
+    }
+
+    /// Return the prefix to paint in place of the marker ("-", "+" or " ") of a hunk line: a label
+    /// for the line under --accessible, otherwise the marker itself if --keep-plus-minus-markers
+    /// is in effect.
+    pub fn get_line_prefix<'m>(state: &State, marker: &'m str, config: &config::Config) -> &'m str {
+        if config.accessible {
+            match state {
+                State::HunkMinus => "removed: ",
+                State::HunkPlus => "added: ",
+                _ => "context: ",
+            }
+        } else if config.keep_plus_minus_markers {
+            marker
+        } else {
+            ""
         }
     }
 
     /// Superimpose background styles and foreground syntax
-    /// highlighting styles, and write colored lines to output buffer.
+    /// highlighting styles, and write colored lines to output buffer. Added lines which are wider
+    /// than `line_length_limit`, unless it is 0, are marked with their width.
     pub fn paint_lines(
-        syntax_style_sections: Vec<Vec<(SyntectStyle, &str)>>,
-        diff_style_sections: Vec<Vec<(Style, &str)>>,
+        style_sections: StyleSections,
         state: &State,
         output_buffer: &mut String,
         config: &config::Config,
         line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
         prefix: &str,
-        empty_line_style: Option<Style>, // a style with background color to highlight an empty line
-        background_color_extends_to_terminal_width: Option<bool>,
+        line_length_limit: usize,
     ) {
+        // A style with background color to highlight an empty line
+        let empty_line_style = match state {
+            State::HunkMinus => Some(config.minus_empty_line_marker_style),
+            State::HunkPlus => Some(config.plus_empty_line_marker_style),
+            _ => None,
+        };
         // There's some unfortunate hackery going on here for two reasons:
         //
         // 1. The prefix needs to be injected into the output stream. We paint
@@ -249,39 +625,44 @@ impl<'a> Painter<'a> {
         // 2. We must ensure that we fill rightwards with the appropriate
         //    non-emph background color. In that case we don't use the last
         //    style of the line, because this might be emph.
-        for (syntax_sections, diff_sections) in syntax_style_sections
+        for (syntax_sections, diff_sections) in style_sections
+            .syntax
             .iter()
-            .zip_eq(diff_style_sections.iter())
+            .zip_eq(style_sections.diff.iter())
         {
-            let (mut line, line_is_empty) = Painter::paint_line(
-                syntax_sections,
-                diff_sections,
+            // Paint directly into the output buffer, so that no per-line allocation is needed.
+            let line = &mut *output_buffer;
+            let line_is_empty = Painter::paint_line_into(
+                line,
+                (syntax_sections, diff_sections),
                 state,
                 line_numbers_data,
                 None,
                 prefix,
                 config,
             );
+            if *state == State::HunkPlus {
+                Painter::mark_long_line(line, diff_sections, line_length_limit, config);
+            }
             let (should_right_fill_background_color, fill_style) =
                 Painter::get_should_right_fill_background_color_and_fill_style(
                     diff_sections,
                     state,
-                    background_color_extends_to_terminal_width,
+                    None,
                     config,
                 );
             if should_right_fill_background_color {
-                Painter::right_fill_background_color(&mut line, fill_style);
+                Painter::right_fill_background_color(line, fill_style);
             } else if line_is_empty {
                 if let Some(empty_line_style) = empty_line_style {
                     Painter::mark_empty_line(
                         &empty_line_style,
-                        &mut line,
+                        line,
                         if config.line_numbers { Some(" ") } else { None },
                     );
                 }
             };
-            output_buffer.push_str(&line);
-            output_buffer.push_str("\n");
+            line.push('\n');
         }
     }
 
@@ -299,6 +680,11 @@ impl<'a> Painter<'a> {
             State::HunkMinus => (config.minus_style, config.minus_non_emph_style),
             State::HunkZero => (config.zero_style, config.zero_style),
             State::HunkPlus => (config.plus_style, config.plus_non_emph_style),
+            // Blame lines are styled according to their commit.
+            State::Blame => diff_sections
+                .first()
+                .map(|(style, _)| (*style, *style))
+                .unwrap_or((config.null_style, config.null_style)),
             _ => (config.null_style, config.null_style),
         };
         let fill_style = if style_sections_contain_more_than_one_style(diff_sections) {
@@ -315,11 +701,10 @@ impl<'a> Painter<'a> {
     /// Emit line with ANSI sequences that extend the background color to the terminal width.
     pub fn right_fill_background_color(line: &mut String, fill_style: Style) {
         // HACK: How to properly incorporate the ANSI_CSI_CLEAR_TO_EOL into ansi_strings?
-        line.push_str(&ansi_term::ANSIStrings(&[fill_style.paint("")]).to_string());
-        if line
-            .to_lowercase()
-            .ends_with(&ANSI_SGR_RESET.to_lowercase())
-        {
+        let mut ansi_buffer = AnsiBuffer::new(line);
+        ansi_buffer.push(fill_style.ansi_term_style, "");
+        ansi_buffer.finish();
+        if line.ends_with(ANSI_SGR_RESET) {
             line.truncate(line.len() - ANSI_SGR_RESET.len());
         }
         line.push_str(ANSI_CSI_CLEAR_TO_EOL);
@@ -332,44 +717,77 @@ impl<'a> Painter<'a> {
     /// otherwise the ANSI_CSI_CLEAR_TO_BOL instruction would overwrite the text to the left of the
     /// current buffer position.
     pub fn mark_empty_line(empty_line_style: &Style, line: &mut String, marker: Option<&str>) {
-        line.push_str(
-            &empty_line_style
-                .paint(marker.unwrap_or(ANSI_CSI_CLEAR_TO_BOL))
-                .to_string(),
+        let mut ansi_buffer = AnsiBuffer::new(line);
+        ansi_buffer.push(
+            empty_line_style.ansi_term_style,
+            marker.unwrap_or(ANSI_CSI_CLEAR_TO_BOL),
         );
+        ansi_buffer.finish();
     }
 
     /// Return painted line (maybe prefixed with line numbers field) and an is_empty? boolean.
     pub fn paint_line(
-        syntax_sections: &Vec<(SyntectStyle, &str)>,
-        diff_sections: &Vec<(Style, &str)>,
+        sections: (&LineSections<SyntectStyle>, &LineSections<Style>),
         state: &State,
         line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
         side_by_side_panel: Option<side_by_side::PanelSide>,
         prefix: &str,
         config: &config::Config,
     ) -> (String, bool) {
+        let mut line = String::new();
+        let is_empty = Painter::paint_line_into(
+            &mut line,
+            sections,
+            state,
+            line_numbers_data,
+            side_by_side_panel,
+            prefix,
+            config,
+        );
+        (line, is_empty)
+    }
+
+    /// Append painted line (maybe prefixed with line numbers field) to `buffer` and return an
+    /// is_empty? boolean.
+    pub fn paint_line_into(
+        buffer: &mut String,
+        (syntax_sections, diff_sections): (&LineSections<SyntectStyle>, &LineSections<Style>),
+        state: &State,
+        line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
+        side_by_side_panel: Option<side_by_side::PanelSide>,
+        prefix: &str,
+        config: &config::Config,
+    ) -> bool {
         let output_line_numbers = config.line_numbers && line_numbers_data.is_some();
         let mut handled_prefix = false;
-        let mut ansi_strings = Vec::new();
+        let mut ansi_buffer = AnsiBuffer::new(buffer);
         if output_line_numbers {
-            ansi_strings.extend(line_numbers::format_and_paint_line_numbers(
+            for ansi_string in line_numbers::format_and_paint_line_numbers(
                 line_numbers_data.as_mut().unwrap(),
                 state,
                 side_by_side_panel,
                 config,
-            ))
+            ) {
+                ansi_buffer.push(*ansi_string.style_ref(), &ansi_string);
+            }
         }
         let mut is_empty = true;
-        for (section_style, mut text) in superimpose_style_sections(
+        let mut sections = superimpose_style_sections(
             syntax_sections,
             diff_sections,
             config.true_color,
             config.null_syntect_style,
-        ) {
+        );
+        if !config.redactions.is_empty() {
+            sections = redact::style_redacted_sections(sections, config);
+        }
+        if !config.highlight_patterns.is_empty() {
+            sections = highlight_patterns::style_highlighted_sections(sections, config);
+        }
+        for (section_style, mut text) in sections {
             if !handled_prefix {
                 if prefix != "" {
-                    ansi_strings.push(section_style.paint(prefix));
+                    ansi_buffer.push(section_style.ansi_term_style, prefix);
                 }
                 if text.len() > 0 {
                     text.remove(0);
@@ -377,18 +795,45 @@ impl<'a> Painter<'a> {
                 handled_prefix = true;
             }
             if !text.is_empty() {
-                ansi_strings.push(section_style.paint(text));
+                ansi_buffer.push(section_style.ansi_term_style, &text);
                 is_empty = false;
             }
         }
-        (ansi_term::ANSIStrings(&ansi_strings).to_string(), is_empty)
+        ansi_buffer.finish();
+        is_empty
+    }
+
+    /// Append to a painted line a marker giving its width, if it is wider than
+    /// `line_length_limit`, unless that is 0.
+    pub fn mark_long_line(
+        line: &mut String,
+        diff_sections: &LineSections<Style>,
+        line_length_limit: usize,
+        config: &config::Config,
+    ) {
+        if line_length_limit == 0 {
+            return;
+        }
+        // The first character of the line is its +/- marker, which is not part of its text.
+        let text: String = diff_sections.iter().map(|(_, text)| *text).collect();
+        let line_width =
+            ansi::measure_text_width(text.get(1..).unwrap_or("").trim_end_matches('\n'));
+        if line_width > line_length_limit {
+            line.push_str(&config.line_length_marker_style.paint(format!(
+                " {} {}",
+                if config.ascii { "<-" } else { "←" },
+                line_width
+            )));
+        }
     }
 
     /// Write output buffer to output stream, and clear the buffer.
     pub fn emit(&mut self) -> std::io::Result<()> {
-        write!(self.writer, "{}", self.output_buffer)?;
-        self.output_buffer.clear();
-        Ok(())
+        benchmark::timed(Phase::Emit, || {
+            self.writer.write_all(self.output_buffer.as_bytes())?;
+            self.output_buffer.clear();
+            Ok(())
+        })
     }
 
     pub fn should_compute_syntax_highlighting(state: &State, config: &config::Config) -> bool {
@@ -405,7 +850,7 @@ impl<'a> Painter<'a> {
                 config.plus_style.is_syntax_highlighted
                     || config.plus_emph_style.is_syntax_highlighted
             }
-            State::HunkHeader => true,
+            State::HunkHeader | State::Blame => true,
             _ => panic!(
                 "should_compute_syntax_highlighting is undefined for state {:?}",
                 state
@@ -416,16 +861,17 @@ impl<'a> Painter<'a> {
     pub fn get_syntax_style_sections_for_lines<'s>(
         lines: &'s Vec<String>,
         state: &State,
-        highlighter: &mut HighlightLines,
+        highlighter: &mut Option<CachingHighlighter>,
         config: &config::Config,
     ) -> Vec<Vec<(SyntectStyle, &'s str)>> {
         let fake = !Painter::should_compute_syntax_highlighting(state, config);
         let mut line_sections = Vec::new();
         for line in lines.iter() {
-            if fake {
-                line_sections.push(vec![(config.null_syntect_style, line.as_str())])
-            } else {
-                line_sections.push(highlighter.highlight(line, &config.syntax_set))
+            match highlighter.as_mut() {
+                Some(highlighter) if !fake => {
+                    line_sections.push(highlighter.highlight(line, config.syntax_set.get()))
+                }
+                _ => line_sections.push(vec![(config.null_syntect_style, line.as_str())]),
             }
         }
         line_sections
@@ -437,9 +883,9 @@ impl<'a> Painter<'a> {
         plus_lines: &'b Vec<String>,
         config: &config::Config,
     ) -> (
-        Vec<Vec<(Style, &'b str)>>,
-        Vec<Vec<(Style, &'b str)>>,
-        Vec<(Option<usize>, Option<usize>)>,
+        Vec<LineSections<'b, Style>>,
+        Vec<LineSections<'b, Style>>,
+        LineAlignment,
     ) {
         let mut diff_sections = edits::infer_edits(
             minus_lines,
@@ -472,6 +918,29 @@ impl<'a> Painter<'a> {
         diff_sections
     }
 
+    /// Style minus and plus lines with their non-emph styles only, without inferring edits.
+    fn get_plain_diff_style_sections<'b>(
+        minus_lines: &'b [String],
+        plus_lines: &'b [String],
+        config: &config::Config,
+    ) -> (
+        Vec<LineSections<'b, Style>>,
+        Vec<LineSections<'b, Style>>,
+        LineAlignment,
+    ) {
+        let plain_sections = |lines: &'b [String], style| {
+            lines
+                .iter()
+                .map(|line| vec![(style, line.as_str())])
+                .collect()
+        };
+        (
+            plain_sections(minus_lines, config.minus_style),
+            plain_sections(plus_lines, config.plus_style),
+            Vec::new(),
+        )
+    }
+
     /// There are some rules according to which we update line section styles that were computed
     /// during the initial edit inference pass. This function applies those rules. The rules are
     /// 1. If there are multiple diff styles in the line, then the line must have some
@@ -539,12 +1008,92 @@ fn is_whitespace_error(sections: &Vec<(Style, &str)>) -> bool {
         .any(|(_, s)| NON_WHITESPACE_REGEX.is_match(s))
 }
 
+/// Writes a sequence of styled strings into a reusable buffer. Only the escape sequences needed to
+/// move from one style to the next are written, so the output is identical to that of
+/// ansi_term::ANSIStrings, but without allocating an ANSIGenericString for every section.
+struct AnsiBuffer<'b> {
+    buffer: &'b mut String,
+    style: Option<ansi_term::Style>,
+}
+
+impl<'b> AnsiBuffer<'b> {
+    fn new(buffer: &'b mut String) -> Self {
+        Self {
+            buffer,
+            style: None,
+        }
+    }
+
+    fn push(&mut self, style: ansi_term::Style, text: &str) {
+        // Writing to a String cannot fail.
+        match self.style {
+            None => write!(self.buffer, "{}", style.prefix()).unwrap(),
+            Some(previous_style) => write!(self.buffer, "{}", previous_style.infix(style)).unwrap(),
+        }
+        self.buffer.push_str(text);
+        self.style = Some(style);
+    }
+
+    fn finish(self) {
+        if let Some(style) = self.style {
+            write!(self.buffer, "{}", style.suffix()).unwrap();
+        }
+    }
+}
+
+/// Split the style sections of a painted line at the boundaries of the byte ranges of the line,
+/// and restyle each piece, given its style and the index of the last range which contains it, if
+/// any.
+pub fn restyle_ranges<F>(
+    sections: Vec<(Style, String)>,
+    ranges: &[(usize, usize)],
+    restyle: F,
+) -> Vec<(Style, String)>
+where
+    F: Fn(Style, Option<usize>) -> Style,
+{
+    if ranges.is_empty() {
+        return sections;
+    }
+    let mut styled_sections = Vec::new();
+    let mut offset = 0;
+    for (style, text) in sections {
+        let (start, end) = (offset, offset + text.len());
+        offset = end;
+        // The boundaries within this section at which it enters or leaves a range
+        let mut boundaries = vec![start];
+        for (range_start, range_end) in ranges {
+            for boundary in &[*range_start, *range_end] {
+                if start < *boundary && *boundary < end {
+                    boundaries.push(*boundary);
+                }
+            }
+        }
+        boundaries.sort_unstable();
+        boundaries.dedup();
+        boundaries.push(end);
+        for window in boundaries.windows(2) {
+            let (i, j) = (window[0], window[1]);
+            let range = ranges
+                .iter()
+                .rposition(|(range_start, range_end)| *range_start <= i && j <= *range_end);
+            styled_sections.push((
+                restyle(style, range),
+                text[i - start..j - start].to_string(),
+            ));
+        }
+    }
+    styled_sections
+}
+
 mod superimpose_style_sections {
     use syntect::highlighting::Style as SyntectStyle;
 
     use crate::bat::terminal::to_ansi_color;
     use crate::style::Style;
 
+    type StyledChar<S> = (S, char);
+
     pub fn superimpose_style_sections(
         sections_1: &[(SyntectStyle, &str)],
         sections_2: &[(Style, &str)],
@@ -556,18 +1105,18 @@ mod superimpose_style_sections {
                 explode(sections_1)
                     .iter()
                     .zip(explode(sections_2))
-                    .collect::<Vec<(&(SyntectStyle, char), (Style, char))>>(),
+                    .collect::<Vec<(&StyledChar<SyntectStyle>, StyledChar<Style>)>>(),
             ),
             true_color,
             null_syntect_style,
         )
     }
 
-    fn explode<T>(style_sections: &[(T, &str)]) -> Vec<(T, char)>
+    fn explode<T>(style_sections: &[(T, &str)]) -> Vec<StyledChar<T>>
     where
         T: Copy,
     {
-        let mut exploded: Vec<(T, char)> = Vec::new();
+        let mut exploded: Vec<StyledChar<T>> = Vec::new();
         for (style, s) in style_sections {
             for c in s.chars() {
                 exploded.push((*style, c));
@@ -577,9 +1126,9 @@ mod superimpose_style_sections {
     }
 
     fn superimpose(
-        style_section_pairs: Vec<(&(SyntectStyle, char), (Style, char))>,
-    ) -> Vec<((SyntectStyle, Style), char)> {
-        let mut superimposed: Vec<((SyntectStyle, Style), char)> = Vec::new();
+        style_section_pairs: Vec<(&StyledChar<SyntectStyle>, StyledChar<Style>)>,
+    ) -> Vec<StyledChar<(SyntectStyle, Style)>> {
+        let mut superimposed: Vec<StyledChar<(SyntectStyle, Style)>> = Vec::new();
         for ((syntax_style, char_1), (style, char_2)) in style_section_pairs {
             if *char_1 != char_2 {
                 panic!(
@@ -758,3 +1307,81 @@ mod superimpose_style_sections {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ansi_term::Color;
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_file_tab_widths() {
+        let diff = "\
+diff --git a/a.go b/a.go
+index 223ca50..e69de29 100644
+--- a/a.go
++++ b/a.go
+@@ -1 +1 @@
+-\ta := 1
++\ta := 2
+diff --git a/b.py b/b.py
+index 223ca50..e69de29 100644
+--- a/b.py
++++ b/b.py
+@@ -1 +1 @@
+-\tb = 1
++\tb = 2
+diff --git a/c.py c/c.py
+index 223ca50..e69de29 100644
+--- a/c.py
++++ b/c.py
+@@ -1 +1 @@
+-\tc = 1
++\tc = 2
+";
+        let config = make_config_from_args(&["--file-tabs", "b.py=2"]);
+        let output = run_delta(diff, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\n        a := 2\n"));
+        assert!(output.contains("\n  b = 2\n"));
+        assert!(output.contains("\n    c = 2\n"));
+
+        let config = make_config_from_args(&["--file-tabs", "*.go=3"]);
+        let output = strip_ansi_codes(&run_delta(diff, &config)).to_string();
+        assert!(output.contains("\n   a := 2\n"));
+    }
+
+    #[test]
+    fn test_ansi_buffer_agrees_with_ansi_strings() {
+        let red = ansi_term::Style::new().fg(Color::Red);
+        let sections = [
+            (ansi_term::Style::new(), "a"),
+            (red, "b"),
+            (red.bold(), "c"),
+            (red, "d"),
+            (red.on(Color::Blue), ""),
+            (ansi_term::Style::new(), "e"),
+            (red, "f"),
+        ];
+        for n in 0..=sections.len() {
+            let ansi_strings: Vec<_> = sections[..n]
+                .iter()
+                .map(|(style, text)| style.paint(*text))
+                .collect();
+            let mut buffer = "x".to_string();
+            let mut ansi_buffer = AnsiBuffer::new(&mut buffer);
+            for (style, text) in &sections[..n] {
+                ansi_buffer.push(*style, text);
+            }
+            ansi_buffer.finish();
+            assert_eq!(
+                buffer,
+                format!("x{}", ansi_term::ANSIStrings(&ansi_strings))
+            );
+        }
+    }
+}
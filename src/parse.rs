@@ -5,7 +5,50 @@ use std::path::Path;
 use crate::config::Config;
 
 // https://git-scm.com/docs/git-config#Documentation/git-config.txt-diffmnemonicPrefix
-const DIFF_PREFIXES: [&str; 6] = ["a/", "b/", "c/", "i/", "o/", "w/"];
+pub const DIFF_PREFIXES: [&str; 6] = ["a/", "b/", "c/", "i/", "o/", "w/"];
+
+/// The kind of a line of input, as far as it can be determined from the leading bytes alone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LinePrefix {
+    Commit,      // "commit ", or the "From <commit>" line which starts a patch email
+    Diff,        // "diff "
+    MinusFile,   // "--- " or "rename from "
+    PlusFile,    // "+++ " or "rename to "
+    HunkHeader,  // "@@"
+    OnlyIn,      // "Only in "
+    Submodule,   // "Submodule "
+    BinaryFiles, // "Binary files "
+    Other,
+}
+
+/// Classify a line by its prefix. This is called on every line of input, so it dispatches on the
+/// first byte rather than testing each candidate prefix in turn: the great majority of lines are
+/// hunk lines starting with ' ', '+' or '-', and these are classified after a single comparison.
+pub fn classify_line_prefix(line: &[u8]) -> LinePrefix {
+    match line.first() {
+        Some(b'c') if line.starts_with(b"commit ") => LinePrefix::Commit,
+        Some(b'F') if is_patch_email_from_line(line) => LinePrefix::Commit,
+        Some(b'd') if line.starts_with(b"diff ") => LinePrefix::Diff,
+        Some(b'-') if line.starts_with(b"--- ") => LinePrefix::MinusFile,
+        Some(b'+') if line.starts_with(b"+++ ") => LinePrefix::PlusFile,
+        Some(b'r') if line.starts_with(b"rename from ") => LinePrefix::MinusFile,
+        Some(b'r') if line.starts_with(b"rename to ") => LinePrefix::PlusFile,
+        Some(b'@') if line.starts_with(b"@@") && is_hunk_header(line) => LinePrefix::HunkHeader,
+        Some(b'O') if line.starts_with(b"Only in ") => LinePrefix::OnlyIn,
+        Some(b'S') if line.starts_with(b"Submodule ") => LinePrefix::Submodule,
+        Some(b'B') if line.starts_with(b"Binary files ") => LinePrefix::BinaryFiles,
+        _ => LinePrefix::Other,
+    }
+}
+
+/// Is this the first line of a patch email written by `git format-patch`, e.g.
+/// "From 94907c0f136f46dc46ffae2dc92dca9af7eb7c2e Mon Sep 17 00:00:00 2001"?
+fn is_patch_email_from_line(line: &[u8]) -> bool {
+    line.starts_with(b"From ")
+        && line.len() > 46
+        && line[5..45].iter().all(u8::is_ascii_hexdigit)
+        && line[45] == b' '
+}
 
 /// Given input like
 /// "--- one.rs	2019-11-20 06:16:08.000000000 +0100"
@@ -13,34 +56,161 @@ const DIFF_PREFIXES: [&str; 6] = ["a/", "b/", "c/", "i/", "o/", "w/"];
 pub fn get_file_extension_from_marker_line(line: &str) -> Option<&str> {
     line.split('\t')
         .next()
-        .and_then(|column| column.split(' ').nth(1))
+        .and_then(|column| column.get(4..))
         .and_then(|file| file.split('.').last())
+        .map(|extension| extension.trim_end_matches('"'))
 }
 
-pub fn get_file_path_from_file_meta_line(line: &str, git_diff_name: bool) -> String {
-    match line {
-        line if line.starts_with("rename from ") => {
-            let offset = "rename from ".len();
-            &line[offset..]
+/// Return the path of a "---", "+++" or "rename" line. The path of a git diff's "---" or "+++"
+/// line is returned without the first of `diff_prefixes` which it starts with, and without the tab
+/// which git appends to a path containing a space.
+pub fn get_file_path_from_file_meta_line(
+    line: &str,
+    git_diff_name: bool,
+    diff_prefixes: &[String],
+) -> String {
+    let path = match line {
+        line if line.starts_with("rename from ") => &line["rename from ".len()..],
+        line if line.starts_with("rename to ") => &line["rename to ".len()..],
+        line if line.starts_with("--- ") || line.starts_with("+++ ") => {
+            let path = &line[4..];
+            if git_diff_name {
+                path.strip_suffix('\t').unwrap_or(path)
+            } else {
+                path
+            }
         }
-        line if line.starts_with("rename to ") => {
-            let offset = "rename to ".len();
-            &line[offset..]
+        _ => return "".to_string(),
+    };
+    let is_rename = line.starts_with("rename ");
+    match unquote_path(path) {
+        Some(path) if git_diff_name && !is_rename => strip_diff_prefix(&path, diff_prefixes)
+            .unwrap_or(&path)
+            .to_string(),
+        Some(path) => path,
+        None => match path {
+            path if path == "/dev/null" => "/dev/null",
+            path if git_diff_name && !is_rename => {
+                strip_diff_prefix(path, diff_prefixes).unwrap_or(path)
+            }
+            path if git_diff_name || is_rename => &path,
+            path => path.split('\t').next().unwrap_or(""),
         }
-        line if line.starts_with("--- ") || line.starts_with("+++ ") => {
-            let offset = 4;
-            match &line[offset..] {
-                path if path == "/dev/null" => "/dev/null",
-                path if git_diff_name && DIFF_PREFIXES.iter().any(|s| path.starts_with(s)) => {
-                    &path[2..]
-                }
-                path if git_diff_name => &path,
-                path => path.split('\t').next().unwrap_or(""),
+        .to_string(),
+    }
+}
+
+/// Return the path of the new version of the file of a "diff --git" line. As in git, unquoted
+/// paths are found by splitting the line in two halves which name the same file, since they
+/// differ only if the file is renamed. The new path of a renamed file is found only if it has no
+/// space; it is also given by the "rename to" line.
+pub fn get_file_path_from_diff_line(line: &str, diff_prefixes: &[String]) -> Option<String> {
+    let paths = line.strip_prefix("diff --git ")?;
+    let plus_path = if let Some(length) = get_quoted_path_length(paths) {
+        paths.get(length..)?.strip_prefix(' ')?
+    } else if paths.ends_with('"') {
+        // A quoted path does not contain ' "', since its quotes are escaped.
+        &paths[paths.rfind(" \"")? + 1..]
+    } else {
+        let middle = paths.len() / 2;
+        match (paths.get(..middle), paths.get(middle..)) {
+            (Some(minus_path), Some(plus_path))
+                if paths.len() % 2 == 1
+                    && plus_path.starts_with(' ')
+                    && strip_diff_prefix(minus_path, diff_prefixes).unwrap_or(minus_path)
+                        == strip_diff_prefix(&plus_path[1..], diff_prefixes)
+                            .unwrap_or(&plus_path[1..]) =>
+            {
+                &plus_path[1..]
             }
+            _ => paths.rsplit_once(' ')?.1,
         }
-        _ => "",
+    };
+    Some(get_file_path_from_file_meta_line(
+        &format!("+++ {}", plus_path),
+        true,
+        diff_prefixes,
+    ))
+}
+
+/// Return the length, including its quotes, of the quoted path which `s` starts with, if any.
+fn get_quoted_path_length(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    if bytes.first() != Some(&b'"') {
+        return None;
+    }
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn strip_diff_prefix<'p>(path: &'p str, diff_prefixes: &[String]) -> Option<&'p str> {
+    diff_prefixes
+        .iter()
+        .find_map(|prefix| path.strip_prefix(prefix.as_str()))
+}
+
+/// Decode a path which git has quoted because it contains special characters: given input like
+/// "a/caf\303\251.rs" (including the double quotes)
+/// Return a/café.rs. Return None if `s` does not start with a quoted string. Anything following
+/// the closing quote (e.g. a timestamp) is ignored.
+fn unquote_path(s: &str) -> Option<String> {
+    let s = s.strip_prefix('"')?.as_bytes();
+    let mut bytes = Vec::new();
+    let mut i = 0;
+    loop {
+        match *s.get(i)? {
+            b'"' => return Some(String::from_utf8_lossy(&bytes).into_owned()),
+            b'\\' => {
+                i += 1;
+                let byte = match *s.get(i)? {
+                    b'a' => 0x07,
+                    b'b' => 0x08,
+                    b't' => b'\t',
+                    b'n' => b'\n',
+                    b'v' => 0x0b,
+                    b'f' => 0x0c,
+                    b'r' => b'\r',
+                    // A byte as three octal digits
+                    b'0'..=b'7' => {
+                        let octal = std::str::from_utf8(s.get(i..i + 3)?).ok()?;
+                        i += 2;
+                        u8::from_str_radix(octal, 8).ok()?
+                    }
+                    byte => byte,
+                };
+                bytes.push(byte);
+            }
+            byte => bytes.push(byte),
+        }
+        i += 1;
     }
-    .to_string()
+}
+
+/// Rewrite a file path for display, according to --relative-to and --strip-path-prefix.
+pub fn rewrite_file_path(path: String, config: &Config) -> String {
+    if path == "/dev/null" {
+        return path;
+    }
+    let path = match config.relative_to.as_deref().and_then(|dir| {
+        path.strip_prefix(dir)
+            .and_then(|rest| rest.strip_prefix('/'))
+    }) {
+        Some(rest) => rest.to_string(),
+        None => path,
+    };
+    for (prefix, replacement) in &config.strip_path_prefix {
+        if let Some(rest) = path.strip_prefix(prefix.as_str()) {
+            return format!("{}{}", replacement, rest);
+        }
+    }
+    path
 }
 
 pub fn get_file_extension_from_file_meta_line_file_path(path: &str) -> Option<&str> {
@@ -57,8 +227,28 @@ pub fn get_file_change_description_from_file_paths(
     comparing: bool,
     config: &Config,
 ) -> String {
+    // Paths such as /dev/fd/63, from `diff <(cmd1) <(cmd2)`, do not name the files compared, so
+    // the change is not described as a rename.
+    let is_unnamed_comparison = config.file_labels.is_some()
+        || is_process_substitution_path(minus_file) && is_process_substitution_path(plus_file);
+    let (minus_file, plus_file) = match &config.file_labels {
+        Some((minus_label, plus_label)) => (
+            if minus_file == "/dev/null" {
+                minus_file
+            } else {
+                minus_label
+            },
+            if plus_file == "/dev/null" {
+                plus_file
+            } else {
+                plus_label
+            },
+        ),
+        None => (minus_file, plus_file),
+    };
+    let arrow = if config.ascii { "->" } else { "⟶  " };
     if comparing {
-        format!("comparing: {} ⟶   {}", minus_file, plus_file)
+        format!("comparing: {} {} {}", minus_file, arrow, plus_file)
     } else {
         let format_label = |label: &str| {
             if label.len() > 0 {
@@ -67,28 +257,56 @@ pub fn get_file_change_description_from_file_paths(
                 "".to_string()
             }
         };
+        // Under --keep-diff-prefixes, e.g. a/src/a.rs and b/src/a.rs are the same file.
+        let is_same_file = minus_file == plus_file
+            || config.keep_diff_prefixes
+                && strip_diff_prefix(minus_file, &config.diff_prefixes).is_some()
+                && strip_diff_prefix(minus_file, &config.diff_prefixes)
+                    == strip_diff_prefix(plus_file, &config.diff_prefixes);
         match (minus_file, plus_file) {
-            (minus_file, plus_file) if minus_file == plus_file => format!(
-                "{}{}",
-                format_label(&config.file_modified_label),
-                minus_file
-            ),
+            (_, plus_file) if is_same_file => {
+                format!("{}{}", format_label(&config.file_modified_label), plus_file)
+            }
             (minus_file, "/dev/null") => {
                 format!("{}{}", format_label(&config.file_removed_label), minus_file)
             }
             ("/dev/null", plus_file) => {
                 format!("{}{}", format_label(&config.file_added_label), plus_file)
             }
+            (minus_file, plus_file) if is_unnamed_comparison => {
+                format!("{} {} {}", minus_file, arrow, plus_file)
+            }
             (minus_file, plus_file) => format!(
-                "{}{} ⟶   {}",
+                "{}{} {} {}",
                 format_label(&config.file_renamed_label),
                 minus_file,
+                arrow,
                 plus_file
             ),
         }
     }
 }
 
+fn is_process_substitution_path(path: &str) -> bool {
+    let path = path.trim_start_matches('/');
+    path.starts_with("dev/fd/") || path.starts_with("proc/self/fd/")
+}
+
+lazy_static! {
+    static ref SUBMODULE_RANGE_REGEX: Regex =
+        Regex::new(r"^Submodule (.+?) [0-9a-f]+\.\.\.?[0-9a-f]+(?: \([^()]*\))?:?$").unwrap();
+}
+
+/// Given input like
+/// "Submodule src/vendor 8c1d2f3..1a2b3c4:"
+/// Return "src/vendor". Under `git diff --submodule=diff` or `--submodule=log`, such a line is
+/// followed by the changes in the submodule.
+pub fn get_submodule_path(line: &str) -> Option<&str> {
+    SUBMODULE_RANGE_REGEX
+        .captures(line)
+        .map(|caps| caps.get(1).unwrap().as_str())
+}
+
 lazy_static! {
     static ref HUNK_HEADER_REGEX: Regex = Regex::new(r"@+ ([^@]+)@+(.*\s?)").unwrap();
 }
@@ -109,28 +327,51 @@ lazy_static! {
     .unwrap();
 }
 
+/// Does this line starting with "@@" have the line numbers of a hunk header? Other lines starting
+/// with "@@" are not treated as hunk headers.
+fn is_hunk_header(line: &[u8]) -> bool {
+    match std::str::from_utf8(line)
+        .ok()
+        .and_then(|line| HUNK_HEADER_REGEX.captures(line))
+    {
+        Some(caps) => {
+            HUNK_HEADER_FILE_COORDINATE_REGEX
+                .find_iter(&caps[1])
+                .count()
+                >= 2
+        }
+        None => false,
+    }
+}
+
 /// Given input like
 /// "@@ -74,15 +74,14 @@ pub fn delta("
 /// Return " pub fn delta(" and a vector of (line_number, hunk_length) tuples.
 pub fn parse_hunk_header(line: &str) -> (String, Vec<(usize, usize)>) {
-    let caps = HUNK_HEADER_REGEX.captures(line).unwrap();
+    try_parse_hunk_header(line)
+        .unwrap_or_else(|| panic!("invalid hunk header: '{}'", line.trim_end()))
+}
+
+/// Like parse_hunk_header, but return None if the line numbers cannot be parsed.
+pub fn try_parse_hunk_header(line: &str) -> Option<(String, Vec<(usize, usize)>)> {
+    let caps = HUNK_HEADER_REGEX.captures(line)?;
     let file_coordinates = &caps[1];
     let line_numbers_and_hunk_lengths = HUNK_HEADER_FILE_COORDINATE_REGEX
         .captures_iter(file_coordinates)
         .map(|caps| {
-            (
-                caps[1].parse::<usize>().unwrap(),
+            Some((
+                caps[1].parse::<usize>().ok()?,
                 caps.get(2)
                     .map(|m| m.as_str())
                     // Per the specs linked above, if the hunk length is absent then it is 1.
                     .unwrap_or("1")
                     .parse::<usize>()
-                    .unwrap(),
-            )
+                    .ok()?,
+            ))
         })
-        .collect();
+        .collect::<Option<_>>()?;
     let code_fragment = &caps[2];
-    return (code_fragment.to_string(), line_numbers_and_hunk_lengths);
+    Some((code_fragment.to_string(), line_numbers_and_hunk_lengths))
 }
 
 /// Attempt to parse input as a file path and return extension as a &str.
@@ -144,7 +385,55 @@ fn get_extension(s: &str) -> Option<&str> {
 
 #[cfg(test)]
 mod tests {
+    use console::strip_ansi_codes;
+
     use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    fn prefixes() -> Vec<String> {
+        DIFF_PREFIXES.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_classify_line_prefix() {
+        for (line, expected) in &[
+            (
+                "commit 94907c0f136f46dc46ffae2dc92dca9af7eb7c2e",
+                LinePrefix::Commit,
+            ),
+            (
+                "From 94907c0f136f46dc46ffae2dc92dca9af7eb7c2e Mon Sep 17 00:00:00 2001",
+                LinePrefix::Commit,
+            ),
+            ("From: Delta <delta@example.com>", LinePrefix::Other),
+            ("diff --git a/a.rs b/a.rs", LinePrefix::Diff),
+            ("--- a/a.rs", LinePrefix::MinusFile),
+            ("rename from a.rs", LinePrefix::MinusFile),
+            ("+++ b/a.rs", LinePrefix::PlusFile),
+            ("rename to b.rs", LinePrefix::PlusFile),
+            ("@@ -1 +1 @@", LinePrefix::HunkHeader),
+            ("Only in a: b", LinePrefix::OnlyIn),
+            (
+                "Submodule x/y/z contains untracked content",
+                LinePrefix::Submodule,
+            ),
+            (
+                "Binary files a/a.png and b/a.png differ",
+                LinePrefix::BinaryFiles,
+            ),
+            ("-- a comment", LinePrefix::Other),
+            ("++x;", LinePrefix::Other),
+            ("@", LinePrefix::Other),
+            ("@@ not a hunk header @@", LinePrefix::Other),
+            ("@@", LinePrefix::Other),
+            (" commit ", LinePrefix::Other),
+            ("", LinePrefix::Other),
+        ] {
+            assert_eq!(classify_line_prefix(line.as_bytes()), *expected, "{}", line);
+        }
+    }
 
     #[test]
     fn test_get_file_extension_from_marker_line() {
@@ -192,9 +481,10 @@ mod tests {
         );
     }
 
-    // We should only strip the prefixes if they are "a/" or "b/". This will be correct except for
-    // the case of a user with `diff.noprefix = true` who has directories named "a" or "b", which
-    // is an irresolvable ambiguity. Ideally one would only strip the prefixes if we have confirmed
+    // We should only strip the prefixes if they are "a/" or "b/" (or mnemonic or configured
+    // prefixes). This will be correct except for the case of a user who runs `git diff
+    // --no-prefix` and has directories named "a" or "b", which is an irresolvable ambiguity; the
+    // diff.noprefix setting is respected. Ideally one would only strip the prefixes if we have confirmed
     // that we are looking at something like
     //
     // --- a/src/parse.rs
@@ -209,53 +499,163 @@ mod tests {
     #[test]
     fn test_get_file_path_from_git_file_meta_line() {
         assert_eq!(
-            get_file_path_from_file_meta_line("--- /dev/null", true),
+            get_file_path_from_file_meta_line("--- /dev/null", true, &prefixes()),
             "/dev/null"
         );
         for prefix in &DIFF_PREFIXES {
             assert_eq!(
-                get_file_path_from_file_meta_line(&format!("--- {}src/delta.rs", prefix), true),
+                get_file_path_from_file_meta_line(
+                    &format!("--- {}src/delta.rs", prefix),
+                    true,
+                    &prefixes()
+                ),
                 "src/delta.rs"
             );
         }
         assert_eq!(
-            get_file_path_from_file_meta_line("--- src/delta.rs", true),
+            get_file_path_from_file_meta_line("--- src/delta.rs", true, &prefixes()),
             "src/delta.rs"
         );
         assert_eq!(
-            get_file_path_from_file_meta_line("+++ src/delta.rs", true),
+            get_file_path_from_file_meta_line("+++ src/delta.rs", true, &prefixes()),
+            "src/delta.rs"
+        );
+    }
+
+    #[test]
+    fn test_get_file_path_from_git_file_meta_line_with_custom_prefixes() {
+        let prefixes = vec!["old/".to_string(), "new/".to_string()];
+        assert_eq!(
+            get_file_path_from_file_meta_line("+++ new/src/delta.rs", true, &prefixes),
             "src/delta.rs"
         );
+        assert_eq!(
+            get_file_path_from_file_meta_line("+++ b/src/delta.rs", true, &prefixes),
+            "b/src/delta.rs"
+        );
+        // Under diff.noprefix, or --keep-diff-prefixes, no prefix is removed.
+        assert_eq!(
+            get_file_path_from_file_meta_line("+++ a/src/delta.rs", true, &[]),
+            "a/src/delta.rs"
+        );
+    }
+
+    #[test]
+    fn test_keep_diff_prefixes() {
+        let diff = "\
+diff --git a/src/a.rs b/src/a.rs
+index 223ca50..e69de29 100644
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1 +1 @@
+-a
++b
+";
+        let config = make_config_from_args(&["--keep-diff-prefixes", "--file-style", "raw"]);
+        let output = run_delta(diff, &config);
+        assert!(output.contains("\nb/src/a.rs\n"));
+        let config = make_config_from_args(&["--file-style", "raw"]);
+        let output = run_delta(diff, &config);
+        assert!(output.contains("\nsrc/a.rs\n"));
     }
 
     #[test]
     fn test_get_file_path_from_git_file_meta_line_containing_spaces() {
         assert_eq!(
-            get_file_path_from_file_meta_line("+++ a/my src/delta.rs", true),
+            get_file_path_from_file_meta_line("+++ a/my src/delta.rs", true, &prefixes()),
             "my src/delta.rs"
         );
         assert_eq!(
-            get_file_path_from_file_meta_line("+++ my src/delta.rs", true),
+            get_file_path_from_file_meta_line("+++ my src/delta.rs", true, &prefixes()),
             "my src/delta.rs"
         );
         assert_eq!(
-            get_file_path_from_file_meta_line("+++ a/src/my delta.rs", true),
+            get_file_path_from_file_meta_line("+++ a/src/my delta.rs", true, &prefixes()),
             "src/my delta.rs"
         );
         assert_eq!(
-            get_file_path_from_file_meta_line("+++ a/my src/my delta.rs", true),
+            get_file_path_from_file_meta_line("+++ a/my src/my delta.rs", true, &prefixes()),
             "my src/my delta.rs"
         );
         assert_eq!(
-            get_file_path_from_file_meta_line("+++ b/my src/my enough/my delta.rs", true),
+            get_file_path_from_file_meta_line(
+                "+++ b/my src/my enough/my delta.rs",
+                true,
+                &prefixes()
+            ),
             "my src/my enough/my delta.rs"
         );
+        // git ends the line with a tab if the path contains a space.
+        assert_eq!(
+            get_file_path_from_file_meta_line("--- a/sp ace.py\t", true, &prefixes()),
+            "sp ace.py"
+        );
+        assert_eq!(
+            get_file_path_from_file_meta_line("+++ b/sp ace.py\t", true, &prefixes()),
+            "sp ace.py"
+        );
+    }
+
+    #[test]
+    fn test_path_containing_a_space_is_selected() {
+        let diff = "\
+diff --git a/sp ace.py b/sp ace.py
+index 223ca50..e69de29 100644
+--- a/sp ace.py\t
++++ b/sp ace.py\t
+@@ -1 +1 @@
+-a = 1
++a = 2
+";
+        for args in [
+            &["--include-path", "sp ace.py"][..],
+            &["--select", "sp ace.py:1"][..],
+        ] {
+            let config = make_config_from_args(&[args, &["--file-style", "raw"]].concat());
+            let output = strip_ansi_codes(&run_delta(diff, &config)).to_string();
+            assert!(output.contains("\nsp ace.py\n"), "{}", args.join(" "));
+            assert!(output.contains("\na = 2\n"), "{}", args.join(" "));
+        }
+    }
+
+    #[test]
+    fn test_get_file_path_from_diff_line() {
+        let get_path = |line| get_file_path_from_diff_line(line, &prefixes());
+        assert_eq!(
+            get_path("diff --git a/src/a.rs b/src/a.rs"),
+            Some("src/a.rs".to_string())
+        );
+        assert_eq!(
+            get_path("diff --git a/sp ace2.py b/sp ace2.py"),
+            Some("sp ace2.py".to_string())
+        );
+        assert_eq!(
+            get_path("diff --git a/a b/b c/d b/a b/b c/d"),
+            Some("a b/b c/d".to_string())
+        );
+        assert_eq!(
+            get_path("diff --git a/old.rs b/new.rs"),
+            Some("new.rs".to_string())
+        );
+        assert_eq!(
+            get_path("diff --git \"a/caf\\303\\251 x.rs\" \"b/caf\\303\\251 x.rs\""),
+            Some("café x.rs".to_string())
+        );
+        assert_eq!(
+            get_path("diff --git \"a/\\\"q\\\" x.rs\" b/y.rs"),
+            Some("y.rs".to_string())
+        );
+        assert_eq!(
+            get_path("diff --git a/x y.rs \"b/tab\\there.rs\""),
+            Some("tab\there.rs".to_string())
+        );
+        assert_eq!(get_path("diff -u a b"), None);
     }
 
     #[test]
     fn test_get_file_path_from_git_file_meta_line_rename() {
         assert_eq!(
-            get_file_path_from_file_meta_line("rename from nospace/file2.el", true),
+            get_file_path_from_file_meta_line("rename from nospace/file2.el", true, &prefixes()),
             "nospace/file2.el"
         );
     }
@@ -263,7 +663,7 @@ mod tests {
     #[test]
     fn test_get_file_path_from_git_file_meta_line_rename_containing_spaces() {
         assert_eq!(
-            get_file_path_from_file_meta_line("rename from with space/file1.el", true),
+            get_file_path_from_file_meta_line("rename from with space/file1.el", true, &prefixes()),
             "with space/file1.el"
         );
     }
@@ -271,15 +671,110 @@ mod tests {
     #[test]
     fn test_get_file_path_from_file_meta_line() {
         assert_eq!(
-            get_file_path_from_file_meta_line("--- src/delta.rs", false),
+            get_file_path_from_file_meta_line("--- src/delta.rs", false, &prefixes()),
             "src/delta.rs"
         );
         assert_eq!(
-            get_file_path_from_file_meta_line("+++ src/delta.rs", false),
+            get_file_path_from_file_meta_line("+++ src/delta.rs", false, &prefixes()),
             "src/delta.rs"
         );
     }
 
+    #[test]
+    fn test_get_file_path_from_quoted_file_meta_line() {
+        assert_eq!(
+            get_file_path_from_file_meta_line(
+                "--- \"a/caf\\303\\251 \\\"x\\\".rs\"",
+                true,
+                &prefixes()
+            ),
+            "café \"x\".rs"
+        );
+        assert_eq!(
+            get_file_path_from_file_meta_line("+++ \"b/tab\\there.rs\"", true, &prefixes()),
+            "tab\there.rs"
+        );
+        assert_eq!(
+            get_file_path_from_file_meta_line("rename to \"b/tab\\there.rs\"", true, &prefixes()),
+            "b/tab\there.rs"
+        );
+        assert_eq!(
+            get_file_path_from_file_meta_line(
+                "--- \"tab\\there.rs\"\t2019-11-20 06:16:08.000000000 +0100",
+                false,
+                &prefixes()
+            ),
+            "tab\there.rs"
+        );
+        assert_eq!(unquote_path("\"unterminated"), None);
+        assert_eq!(unquote_path("src/a.rs"), None);
+    }
+
+    #[test]
+    fn test_get_file_extension_from_marker_line_containing_spaces() {
+        assert_eq!(
+            get_file_extension_from_marker_line(
+                "--- src/my file.rs	2019-11-20 06:47:56.000000000 +0100"
+            ),
+            Some("rs")
+        );
+    }
+
+    #[test]
+    fn test_get_file_change_description_for_process_substitution() {
+        let config = make_config_from_args(&[]);
+        assert_eq!(
+            get_file_change_description_from_file_paths("dev/fd/63", "dev/fd/62", false, &config),
+            "dev/fd/63 ⟶   dev/fd/62"
+        );
+        let config = make_config_from_args(&["--file-labels", "before,after"]);
+        assert_eq!(
+            get_file_change_description_from_file_paths("/dev/fd/63", "/dev/fd/62", false, &config),
+            "before ⟶   after"
+        );
+        assert_eq!(
+            get_file_change_description_from_file_paths("/dev/null", "/dev/fd/62", false, &config),
+            "added: after"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_file_path() {
+        let config = make_config_from_args(&[
+            "--relative-to",
+            "/builds/project/",
+            "--strip-path-prefix",
+            "a/",
+            "--strip-path-prefix",
+            "vendor/=>third_party/",
+        ]);
+        for (path, expected) in &[
+            ("/builds/project/src/a.rs", "src/a.rs"),
+            ("/builds/project2/src/a.rs", "/builds/project2/src/a.rs"),
+            ("a/src/a.rs", "src/a.rs"),
+            ("vendor/x.rs", "third_party/x.rs"),
+            ("/dev/null", "/dev/null"),
+        ] {
+            assert_eq!(rewrite_file_path(path.to_string(), &config), *expected);
+        }
+    }
+
+    #[test]
+    fn test_get_submodule_path() {
+        assert_eq!(
+            get_submodule_path("Submodule src/vendor 8c1d2f3..1a2b3c4:"),
+            Some("src/vendor")
+        );
+        assert_eq!(
+            get_submodule_path("Submodule sub 0000000...1a2b3c4 (new submodule)"),
+            Some("sub")
+        );
+        assert_eq!(
+            get_submodule_path("Submodule sub contains modified content"),
+            None
+        );
+    }
+
     #[test]
     fn test_parse_hunk_header() {
         let parsed = parse_hunk_header("@@ -74,15 +75,14 @@ pub fn delta(\n");
@@ -32,6 +32,21 @@ impl Style {
         }
     }
 
+    /// As from_str, with no default or decoration style, but return an error, rather than exiting,
+    /// if the style string is invalid.
+    pub fn try_from_str(style_string: &str, true_color: bool) -> Result<Self, String> {
+        let (ansi_term_style, is_omitted, is_raw, is_syntax_highlighted) =
+            try_parse_ansi_term_style(style_string, None, true_color)?;
+        Ok(Self {
+            ansi_term_style,
+            is_emph: false,
+            is_omitted,
+            is_raw,
+            is_syntax_highlighted,
+            decoration_style: DecorationStyle::NoDecoration,
+        })
+    }
+
     /// Construct Style but interpreting 'ul', 'box', etc as applying to the decoration style.
     fn from_str_with_handling_of_special_decoration_attributes(
         style_string: &str,
@@ -200,6 +215,17 @@ fn parse_ansi_term_style(
     default: Option<Style>,
     true_color: bool,
 ) -> (ansi_term::Style, bool, bool, bool) {
+    try_parse_ansi_term_style(s, default, true_color).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    })
+}
+
+fn try_parse_ansi_term_style(
+    s: &str,
+    default: Option<Style>,
+    true_color: bool,
+) -> Result<(ansi_term::Style, bool, bool, bool), String> {
     let mut style = ansi_term::Style::new();
     let mut seen_foreground = false;
     let mut seen_background = false;
@@ -245,30 +271,29 @@ fn parse_ansi_term_style(
                 style.foreground = default.and_then(|s| s.ansi_term_style.foreground);
                 is_syntax_highlighted = default.map(|s| s.is_syntax_highlighted).unwrap_or(false);
             } else {
-                style.foreground = color::parse_color(word, true_color);
+                style.foreground = color::try_parse_color(word, true_color)?;
             }
             seen_foreground = true;
         } else if !seen_background {
             if word == "syntax" {
-                eprintln!(
+                return Err(
                     "You have used the special color 'syntax' as a background color \
                      (second color in a style string). It may only be used as a foreground \
                      color (first color in a style string)."
+                        .to_string(),
                 );
-                process::exit(1);
             } else if word == "auto" {
                 background_is_auto = true;
                 style.background = default.and_then(|s| s.ansi_term_style.background);
             } else {
-                style.background = color::parse_color(word, true_color);
+                style.background = color::try_parse_color(word, true_color)?;
             }
             seen_background = true;
         } else {
-            eprintln!(
+            return Err(format!(
                 "Invalid style string: {}. See the STYLES section of delta --help.",
                 s
-            );
-            process::exit(1);
+            ));
         }
     }
     if foreground_is_auto && background_is_auto {
@@ -279,7 +304,7 @@ fn parse_ansi_term_style(
             is_raw = default.map(|s| s.is_raw).unwrap_or(false);
         }
     }
-    (style, is_omitted, is_raw, is_syntax_highlighted)
+    Ok((style, is_omitted, is_raw, is_syntax_highlighted))
 }
 
 /// Extract set of 'special decoration attributes' and return it along with modified style string.
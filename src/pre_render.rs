@@ -0,0 +1,230 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use crate::config::Config;
+
+/// Runs the --pre-render-filter commands, and diffs the filtered versions of a file. Command
+/// output is cached, so that a version of a file which occurs more than once in the input (e.g.
+/// in `git log -p` output) is filtered once.
+#[derive(Default)]
+pub struct PreRenderFilter {
+    cache: HashMap<(String, u64), Option<Vec<u8>>>,
+}
+
+impl PreRenderFilter {
+    /// Return the --pre-render-filter command for files at `path`, if any. The first matching glob
+    /// wins.
+    pub fn get_command<'c>(path: &str, config: &'c Config) -> Option<&'c str> {
+        config
+            .pre_render_filters
+            .iter()
+            .find(|(glob, _)| glob.is_match(path))
+            .map(|(_, command)| command.as_str())
+    }
+
+    /// Return the hunk lines of a diff between the two versions of a file after both have been
+    /// passed through `command`. `file_meta` is the file's metadata lines, from which the blob ids
+    /// of a git diff are read. The diff has `context_lines` lines of context, if given, and
+    /// otherwise git's default of 3. Return None if either version of the file cannot be read or the
+    /// command fails.
+    pub fn make_hunks(
+        &mut self,
+        command: &str,
+        minus_file: &str,
+        plus_file: &str,
+        file_meta: &[String],
+        is_git_diff: bool,
+        context_lines: Option<u32>,
+    ) -> Option<Vec<String>> {
+        let (minus, plus) = read_file_versions(minus_file, plus_file, file_meta, is_git_diff)?;
+        let minus = self.run(command, &minus)?;
+        let plus = self.run(command, &plus)?;
+        diff_file_versions(&minus, &plus, minus_file, plus_file, context_lines)
+    }
+
+    /// Return the output of `command` run with `input` as its standard input.
+    fn run(&mut self, command: &str, input: &[u8]) -> Option<Vec<u8>> {
+        let mut hasher = DefaultHasher::new();
+        input.hash(&mut hasher);
+        let key = (command.to_string(), hasher.finish());
+        self.cache
+            .entry(key)
+            .or_insert_with(|| run_command(command, input))
+            .clone()
+    }
+}
+
+fn run_command(command: &str, input: &[u8]) -> Option<Vec<u8>> {
+    let words = shell_words::split(command).ok()?;
+    let (program, args) = words.split_first()?;
+    let mut process = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    // The input is written from another thread, so that a command which writes output before it
+    // has read all of its input cannot block.
+    let mut stdin = process.stdin.take()?;
+    let input = input.to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let output = process.wait_with_output().ok()?;
+    writer.join().ok()?.ok()?;
+    if output.status.success() {
+        Some(output.stdout)
+    } else {
+        None
+    }
+}
+
+/// Return the contents of the two versions of a file. `file_meta` is the file's metadata lines,
+/// from which the blob ids of a git diff are read.
+pub fn read_file_versions(
+    minus_file: &str,
+    plus_file: &str,
+    file_meta: &[String],
+    is_git_diff: bool,
+) -> Option<(Vec<u8>, Vec<u8>)> {
+    let (minus_blob, plus_blob) = match file_meta.iter().find_map(|line| parse_index_line(line)) {
+        Some((minus_blob, plus_blob)) => (Some(minus_blob), Some(plus_blob)),
+        None => (None, None),
+    };
+    Some((
+        read_file_version(minus_file, minus_blob, is_git_diff)?,
+        read_file_version(plus_file, plus_blob, is_git_diff)?,
+    ))
+}
+
+/// Return the hunk lines of a diff between two versions of a file, with `context_lines` lines of
+/// context, if given, and otherwise git's default of 3.
+pub fn diff_file_versions(
+    minus: &[u8],
+    plus: &[u8],
+    minus_file: &str,
+    plus_file: &str,
+    context_lines: Option<u32>,
+) -> Option<Vec<String>> {
+    let mut diff_options = git2::DiffOptions::new();
+    if let Some(context_lines) = context_lines {
+        diff_options.context_lines(context_lines);
+    }
+    let mut patch = git2::Patch::from_buffers(
+        minus,
+        Some(Path::new(minus_file)),
+        plus,
+        Some(Path::new(plus_file)),
+        Some(&mut diff_options),
+    )
+    .ok()?;
+    let patch = patch.to_buf().ok()?;
+    Some(
+        String::from_utf8_lossy(&patch)
+            .lines()
+            .skip_while(|line| !line.starts_with("@@"))
+            .map(String::from)
+            .collect(),
+    )
+}
+
+/// Parse the blob ids from a line of the form "index 1111111..2222222 100644".
+pub fn parse_index_line(line: &str) -> Option<(&str, &str)> {
+    line.strip_prefix("index ")?
+        .split_whitespace()
+        .next()?
+        .split_once("..")
+}
+
+/// Return the contents of one version of a file. In a git diff, this is the blob given by the
+/// index line: it is read from the repository, or else from the file system if the file there is
+/// that blob, as the new version of a file in `git diff` is; a path in a git diff is relative to
+/// the top of the repository. Otherwise, the file is read from the file system.
+pub fn read_file_version(path: &str, blob: Option<&str>, is_git_diff: bool) -> Option<Vec<u8>> {
+    if path == "/dev/null" || blob.map_or(false, |blob| blob.chars().all(|c| c == '0')) {
+        return Some(Vec::new());
+    }
+    if !is_git_diff {
+        return fs::read(path).ok();
+    }
+    // Without a blob id, the file system could hold any version of the file.
+    let blob = blob?;
+    let repo = git2::Repository::discover(".").ok();
+    if let Some(repo) = &repo {
+        if let Ok(blob) = repo
+            .revparse_single(blob)
+            .and_then(|object| object.peel_to_blob())
+        {
+            return Some(blob.content().to_vec());
+        }
+    }
+    let path = match repo.as_ref().and_then(|repo| repo.workdir()) {
+        Some(workdir) => workdir.join(path),
+        None => PathBuf::from(path),
+    };
+    let contents = fs::read(path).ok()?;
+    let id = git2::Oid::hash_object(git2::ObjectType::Blob, &contents).ok()?;
+    if id.to_string().starts_with(blob) {
+        Some(contents)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_index_line() {
+        assert_eq!(
+            parse_index_line("index 1111111..2222222 100644"),
+            Some(("1111111", "2222222"))
+        );
+        assert_eq!(
+            parse_index_line("index 1111111..2222222"),
+            Some(("1111111", "2222222"))
+        );
+        assert_eq!(parse_index_line("new file mode 100644"), None);
+    }
+
+    #[test]
+    fn test_read_file_version() {
+        let dir =
+            std::env::temp_dir().join(format!("delta-test-file-version-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a.txt");
+        let path = path.to_str().unwrap();
+        fs::write(path, "a\n").unwrap();
+        // git hash-object a.txt
+        let blob = "78981922613b2afb6025042ff6bd878ac1994e85";
+        assert_eq!(read_file_version(path, None, false), Some(b"a\n".to_vec()));
+        assert_eq!(
+            read_file_version(path, Some(&blob[..7]), true),
+            Some(b"a\n".to_vec())
+        );
+        assert_eq!(read_file_version(path, Some("1111111"), true), None);
+        assert_eq!(read_file_version(path, None, true), None);
+        assert_eq!(
+            read_file_version(path, Some("0000000"), true),
+            Some(Vec::new())
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_command() {
+        let mut filter = PreRenderFilter::default();
+        assert_eq!(filter.run("tr a-z A-Z", b"abc\n"), Some(b"ABC\n".to_vec()));
+        assert_eq!(filter.cache.len(), 1);
+        assert_eq!(filter.run("tr a-z A-Z", b"abc\n"), Some(b"ABC\n".to_vec()));
+        assert_eq!(filter.cache.len(), 1);
+        assert_eq!(filter.run("false", b"abc\n"), None);
+    }
+}
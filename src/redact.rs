@@ -0,0 +1,153 @@
+use std::borrow::Cow;
+use std::process;
+
+use regex::Regex;
+
+use crate::config::Config;
+use crate::paint;
+use crate::style::Style;
+
+const DEFAULT_REPLACEMENT: &str = "********";
+
+/// A --redact rule: text matching the regex is replaced by the replacement text. If the regex has
+/// capture groups, only the text of the groups is replaced.
+#[derive(Clone, Debug)]
+pub struct Redaction {
+    regex: Regex,
+    replacement: String,
+}
+
+impl Redaction {
+    /// Parse a --redact value, 'REGEX' or 'REGEX=>REPLACEMENT'.
+    pub fn from_str(s: &str) -> Self {
+        let (regex, replacement) = match s.rsplit_once("=>") {
+            Some((regex, replacement)) => (regex, replacement),
+            None => (s, DEFAULT_REPLACEMENT),
+        };
+        let regex = Regex::new(regex).unwrap_or_else(|_| {
+            eprintln!(
+                "Invalid redact: {}. \
+                 The value must be a valid Rust regular expression. \
+                 See https://docs.rs/regex.",
+                regex
+            );
+            process::exit(1);
+        });
+        Self {
+            regex,
+            replacement: replacement.to_string(),
+        }
+    }
+
+    fn redact(&self, line: &str) -> String {
+        let mut redacted = String::with_capacity(line.len());
+        let mut end = 0;
+        for captures in self.regex.captures_iter(line) {
+            let matches: Vec<_> = if captures.len() > 1 {
+                captures.iter().skip(1).flatten().collect()
+            } else {
+                captures.get(0).into_iter().collect()
+            };
+            for m in matches {
+                // Skip empty matches, and groups nested in a group which has been replaced.
+                if m.start() == m.end() || m.start() < end {
+                    continue;
+                }
+                redacted.push_str(&line[end..m.start()]);
+                redacted.push_str(&self.replacement);
+                end = m.end();
+            }
+        }
+        redacted.push_str(&line[end..]);
+        redacted
+    }
+}
+
+/// Return the line with the text matched by the --redact rules replaced.
+pub fn redact<'a>(line: &'a str, config: &Config) -> Cow<'a, str> {
+    let mut line = Cow::Borrowed(line);
+    for redaction in &config.redactions {
+        if redaction.regex.is_match(&line) {
+            line = Cow::Owned(redaction.redact(&line));
+        }
+    }
+    line
+}
+
+/// Split the style sections of a painted line so that the replacement text of the --redact rules
+/// is displayed in redacted-style.
+pub fn style_redacted_sections(
+    sections: Vec<(Style, String)>,
+    config: &Config,
+) -> Vec<(Style, String)> {
+    let line: String = sections.iter().map(|(_, text)| text.as_str()).collect();
+    let mut ranges: Vec<(usize, usize)> = config
+        .redactions
+        .iter()
+        .filter(|redaction| !redaction.replacement.is_empty())
+        .flat_map(|redaction| {
+            line.match_indices(redaction.replacement.as_str())
+                .map(|(i, replacement)| (i, i + replacement.len()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    ranges.sort_unstable();
+    paint::restyle_ranges(sections, &ranges, |style, range| match range {
+        Some(_) => config.redacted_style,
+        None => style,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_redact() {
+        let redaction = Redaction::from_str("token=(\\w+)");
+        assert_eq!(
+            redaction.redact("a token=abc b token=def"),
+            "a token=******** b token=********"
+        );
+        let redaction = Redaction::from_str("ghp_\\w+=>[github token]");
+        assert_eq!(
+            redaction.redact("key = \"ghp_abc123\""),
+            "key = \"[github token]\""
+        );
+        let redaction = Redaction::from_str("x*");
+        assert_eq!(redaction.redact("axb"), "a********b");
+    }
+
+    #[test]
+    fn test_redacted_hunk_lines() {
+        let config = make_config_from_args(&[
+            "--redact",
+            "password = \"(.*)\"",
+            "--redacted-style",
+            "bold red",
+        ]);
+        let output = run_delta(DIFF, &config);
+        assert!(!output.contains("hunter2"));
+        assert!(!output.contains("swordfish"));
+        assert!(output.contains(&config.redacted_style.paint("********").to_string()));
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("password = \"********\""));
+        assert!(output.contains("user = \"delta\""));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.toml b/a.toml
+index 223ca50..e69de29 100644
+--- a/a.toml
++++ b/a.toml
+@@ -1,2 +1,2 @@
+ user = \"delta\"
+-password = \"hunter2\"
++password = \"swordfish\"
+";
+}
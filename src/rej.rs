@@ -0,0 +1,181 @@
+use std::fs;
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use bytelines::ByteLines;
+
+use crate::bat::output::OutputType;
+use crate::config::Config;
+use crate::delta::delta;
+use crate::paint::Painter;
+use crate::show;
+
+// `git apply --reject` writes the hunks that could not be applied to a .rej file, under a line of
+// the form
+//
+// diff a/src/delta.rs b/src/delta.rs	(rejected hunks)
+//
+// GNU patch writes them under "---" and "+++" lines instead.
+const REJECTED_HUNKS_SUFFIX: &str = "\t(rejected hunks)";
+
+/// If this is the first line of a .rej file written by `git apply --reject`, return the path of
+/// the file to which the hunks could not be applied.
+pub fn parse_rejected_hunks_line(line: &str) -> Option<&str> {
+    line.strip_suffix(REJECTED_HUNKS_SUFFIX)?
+        .strip_prefix("diff a/")?
+        .rsplit_once(" b/")
+        .map(|(_, path)| path)
+}
+
+/// Display the .rej file at `path`, as in `delta file.rs.rej`.
+pub fn show_rej_file(path: &Path, config: &Config) -> io::Result<()> {
+    let contents = fs::read(path)?;
+    let mut input = Vec::new();
+    if !contents.starts_with(b"diff ") {
+        // A .rej file written by GNU patch. Its "---" and "+++" lines are replaced by a line of
+        // the form written by git, so that the target file is known.
+        let target = path.with_extension("");
+        writeln!(
+            input,
+            "diff a/{0} b/{0}{1}",
+            target.display(),
+            REJECTED_HUNKS_SUFFIX
+        )?;
+        let mut lines = contents.split(|&byte| byte == b'\n').peekable();
+        while let Some(line) = lines.peek() {
+            if line.starts_with(b"@@") {
+                break;
+            }
+            lines.next();
+        }
+        for line in lines {
+            input.extend_from_slice(line);
+            input.push(b'\n');
+        }
+    } else {
+        input = contents;
+    }
+
+    let mut output_type = OutputType::from_mode(config.paging_mode, None, config).unwrap();
+    let mut writer = output_type.handle().unwrap();
+    delta(
+        ByteLines::new(BufReader::new(&input[..])),
+        &mut writer,
+        config,
+    )
+}
+
+/// Following a rejected hunk, display the lines of the target file, as it is now, which the hunk
+/// expected to change. `line_range` is the start line and length of the minus side of the hunk.
+pub fn paint_target_file_context(
+    painter: &mut Painter,
+    path: &str,
+    line_range: (usize, usize),
+    config: &Config,
+) -> io::Result<()> {
+    let (start, length) = line_range;
+    let contents = match fs::read(find_target_file(path)) {
+        Ok(contents) => contents,
+        Err(error) => {
+            return write_notice(painter, &format!("{}: {}", path, error), config);
+        }
+    };
+    let contents = String::from_utf8_lossy(&contents);
+    let lines: Vec<String> = contents
+        .lines()
+        .skip(start.saturating_sub(1))
+        .take(length)
+        .map(String::from)
+        .collect();
+    if lines.is_empty() {
+        return write_notice(
+            painter,
+            &format!("{} now has fewer than {} lines", path, start),
+            config,
+        );
+    }
+    write_notice(
+        painter,
+        &format!(
+            "{} now has, at lines {}-{}:",
+            path,
+            start,
+            start + lines.len() - 1
+        ),
+        config,
+    )?;
+    show::paint_plain_file_lines(painter, &lines, start)
+}
+
+/// The paths in a .rej file are relative to the top of the repository, which is not necessarily
+/// the current directory.
+fn find_target_file(path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    if path.is_file() {
+        return path;
+    }
+    match git2::Repository::discover(".")
+        .ok()
+        .and_then(|repo| repo.workdir().map(|dir| dir.join(&path)))
+    {
+        Some(repo_path) if repo_path.is_file() => repo_path,
+        _ => path,
+    }
+}
+
+fn write_notice(painter: &mut Painter, notice: &str, config: &Config) -> io::Result<()> {
+    writeln!(
+        painter.writer,
+        "{}",
+        config.filter_notice_style.paint(notice)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_parse_rejected_hunks_line() {
+        assert_eq!(
+            parse_rejected_hunks_line("diff a/src/a b.rs b/src/a b.rs\t(rejected hunks)"),
+            Some("src/a b.rs")
+        );
+        assert_eq!(
+            parse_rejected_hunks_line("diff --git a/src/a.rs b/src/a.rs"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rejected_hunk_is_followed_by_target_file_context() {
+        let config = make_config_from_args(&[]);
+        let input = "\
+diff a/Cargo.toml b/Cargo.toml\t(rejected hunks)
+@@ -1,2 +1,2 @@
+ [package]
+-name = \"not-delta\"
++name = \"still-not-delta\"
+";
+        let output = run_delta(input, &config);
+        let output = strip_ansi_codes(&output);
+        let mut lines = output
+            .lines()
+            .skip_while(|line| !line.contains("rejected hunks"));
+        assert_eq!(lines.next().unwrap(), "Cargo.toml (rejected hunks)");
+        let context: Vec<&str> = lines.skip_while(|line| !line.contains("now has")).collect();
+        assert_eq!(
+            context,
+            vec![
+                "Cargo.toml now has, at lines 1-2:",
+                "[package]",
+                "name = \"git-delta\""
+            ]
+        );
+    }
+}
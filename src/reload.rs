@@ -0,0 +1,184 @@
+use std::ffi::OsString;
+use std::io::{self, BufRead, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::ansi;
+use crate::bat::assets::HighlightingAssets;
+use crate::cli;
+use crate::git_config::GitConfig;
+
+// When delta receives SIGUSR1 while displaying its input, it reloads its configuration from the
+// command line and git config at the start of the next commit or file, continuing to write to the
+// same pager. The display of the input is continued rather than restarted, e.g. the numbering of
+// files under --numbered, and what follows the input, such as --stats, is written once. If the
+// reloaded configuration is in the same light or dark mode as before, then the mode is switched,
+// so that a script following the operating system's appearance can flip a long-running session,
+// e.g. `git log -p`, with `pkill -USR1 delta`.
+
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn handle_sigusr1(_: libc::c_int) {
+    RELOAD_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Request a reload of the configuration on SIGUSR1.
+#[cfg(unix)]
+pub fn install_signal_handler() {
+    unsafe {
+        libc::signal(
+            libc::SIGUSR1,
+            handle_sigusr1 as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+#[cfg(not(unix))]
+pub fn install_signal_handler() {}
+
+/// Reads the input up to the start of the first commit or file following a request to reload the
+/// configuration, where it reports the end of the input.
+pub struct StopAtReload<R: BufRead> {
+    reader: R,
+    is_at_line_start: bool,
+    is_stopped: bool,
+}
+
+impl<R: BufRead> StopAtReload<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            is_at_line_start: true,
+            is_stopped: false,
+        }
+    }
+
+    /// Whether the input was stopped for a reload, rather than having ended.
+    pub fn is_stopped(&self) -> bool {
+        self.is_stopped
+    }
+}
+
+impl<R: BufRead> Read for StopAtReload<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for StopAtReload<R> {
+    /// Return the remainder of the current line, or as much of it as is buffered, so that the
+    /// start of each line is examined.
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.is_stopped {
+            return Ok(&[]);
+        }
+        let is_at_line_start = self.is_at_line_start;
+        let buf = self.reader.fill_buf()?;
+        let line = match buf.iter().position(|&b| b == b'\n') {
+            Some(i) => &buf[..=i],
+            None => buf,
+        };
+        if is_at_line_start
+            && !line.is_empty()
+            && RELOAD_REQUESTED.load(Ordering::SeqCst)
+            && is_commit_or_file_start(line)
+        {
+            RELOAD_REQUESTED.store(false, Ordering::SeqCst);
+            self.is_stopped = true;
+            return Ok(&[]);
+        }
+        Ok(line)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if amt > 0 {
+            if let Ok(buf) = self.reader.fill_buf() {
+                self.is_at_line_start = buf.get(amt - 1) == Some(&b'\n');
+            }
+        }
+        self.reader.consume(amt);
+    }
+}
+
+fn is_commit_or_file_start(line: &[u8]) -> bool {
+    let line = String::from_utf8_lossy(line);
+    let line = ansi::strip_ansi_codes(&line);
+    line.starts_with("commit ") || line.starts_with("diff ")
+}
+
+/// Return the options given by the command-line arguments and git config, in the other light or
+/// dark mode than `is_light_mode` if they are in the same mode.
+pub fn reload_options(args: &[OsString], is_light_mode: bool) -> cli::Opt {
+    let args: Vec<OsString> = args
+        .iter()
+        .filter(|arg| *arg != "--light" && *arg != "--dark")
+        .cloned()
+        .collect();
+    let opt = cli::Opt::from_iter_and_git_config(
+        &args,
+        &mut GitConfig::try_create(),
+        HighlightingAssets::new(),
+    );
+    if opt.computed.is_light_mode != is_light_mode {
+        return opt;
+    }
+    let mode = if is_light_mode { "--dark" } else { "--light" };
+    cli::Opt::from_iter_and_git_config(
+        args.iter().cloned().chain(std::iter::once(mode.into())),
+        &mut GitConfig::try_create(),
+        HighlightingAssets::new(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use bytelines::ByteLinesReader;
+
+    use super::*;
+
+    #[test]
+    fn test_stop_at_reload() {
+        let input =
+            "commit 1\n\ndiff --git a/a b/a\n-a\n\x1b[33mcommit 2\x1b[m\ndiff --git a/b b/b\n";
+        let mut reader = Cursor::new(input.as_bytes());
+        let mut stop_at_reload = StopAtReload::new(&mut reader);
+        let mut lines = (&mut stop_at_reload).byte_lines();
+        assert_eq!(lines.next().unwrap().unwrap(), b"commit 1");
+        assert_eq!(lines.next().unwrap().unwrap(), b"");
+        RELOAD_REQUESTED.store(true, Ordering::SeqCst);
+        assert!(lines.next().is_none());
+        assert!(stop_at_reload.is_stopped());
+
+        let mut stop_at_reload = StopAtReload::new(&mut reader);
+        let mut lines = (&mut stop_at_reload).byte_lines();
+        assert_eq!(lines.next().unwrap().unwrap(), b"diff --git a/a b/a");
+        RELOAD_REQUESTED.store(true, Ordering::SeqCst);
+        assert_eq!(lines.next().unwrap().unwrap(), b"-a");
+        assert!(lines.next().is_none());
+        assert!(stop_at_reload.is_stopped());
+
+        let mut stop_at_reload = StopAtReload::new(&mut reader);
+        let mut lines = (&mut stop_at_reload).byte_lines();
+        assert_eq!(lines.next().unwrap().unwrap(), b"\x1b[33mcommit 2\x1b[m");
+        assert_eq!(lines.next().unwrap().unwrap(), b"diff --git a/b b/b");
+        assert!(lines.next().is_none());
+        assert!(!stop_at_reload.is_stopped());
+    }
+
+    #[test]
+    fn test_reload_options() {
+        // A mode given on the command line is not kept.
+        let args: Vec<OsString> = ["delta", "--no-gitconfig", "--light"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+        assert!(reload_options(&args, false).computed.is_light_mode);
+        assert!(!reload_options(&args, true).computed.is_light_mode);
+    }
+}
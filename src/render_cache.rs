@@ -0,0 +1,474 @@
+use std::cell::{Cell, RefCell};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::cli;
+use crate::config::Config;
+
+// The size of the cache, in bytes, over which the entries written longest ago are deleted
+const MAX_CACHE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The rendered hunks of files of git diffs, cached under --render-cache so that a diff which is
+/// viewed repeatedly (e.g. during a rebase, or on re-running `git log -p`) is not highlighted
+/// again. An entry is found by the blob ids of the two versions of a file, the file's path and the
+/// options, and is used only if the file's hunks are those from which it was rendered, since they
+/// depend on the options given to `git diff`.
+pub struct RenderCache {
+    dir: PathBuf,
+    options_hash: u64,
+    // Whether entries have been written to the cache by this process
+    is_written: Cell<bool>,
+}
+
+/// The hunk lines of a file, which are held back until the end of the file so that they can be
+/// compared with a cached rendering.
+pub struct BufferedHunks {
+    key: String,
+    hasher: StableHasher,
+    pub lines: Vec<BufferedHunkLine>,
+}
+
+/// A 64-bit FNV-1a hash. Unlike that of DefaultHasher, whose algorithm is unspecified and may
+/// change between releases of Rust, its value is the same in every build of delta, so that the
+/// entries of the cache remain valid.
+struct StableHasher(u64);
+
+pub enum BufferedHunkLine {
+    HunkHeader {
+        line: String,
+        raw_line: String,
+        label: Option<String>,
+    },
+    HunkLine {
+        line: String,
+        raw_line: String,
+    },
+}
+
+impl RenderCache {
+    pub fn new(config: &Config) -> Option<Self> {
+        let (dir, options_hash) = config.render_cache.clone()?;
+        Some(Self {
+            dir,
+            options_hash,
+            is_written: Cell::new(false),
+        })
+    }
+
+    /// Start holding back the hunk lines of a file. The terminal width is part of the key, since
+    /// it may have changed since the options were hashed.
+    pub fn start_file(
+        &self,
+        path: &str,
+        minus_blob: &str,
+        plus_blob: &str,
+        terminal_width: usize,
+    ) -> BufferedHunks {
+        let mut hasher = StableHasher::new();
+        hasher.write_str(&format!("{:016x}", self.options_hash));
+        hasher.write_str(path);
+        hasher.write_str(minus_blob);
+        hasher.write_str(plus_blob);
+        hasher.write_str(&terminal_width.to_string());
+        BufferedHunks {
+            key: format!("{:016x}", hasher.finish()),
+            hasher: StableHasher::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    /// Return the cached rendering of the file's hunks, if any.
+    pub fn get(&self, hunks: &BufferedHunks) -> Option<Vec<u8>> {
+        let entry = fs::read(self.dir.join(&hunks.key)).ok()?;
+        let separator = entry.iter().position(|&byte| byte == b'\n')?;
+        if entry[..separator] == *hunks.input_hash().as_bytes() {
+            Some(entry[separator + 1..].to_vec())
+        } else {
+            None
+        }
+    }
+
+    /// Cache the rendering of the file's hunks. Failure to write the cache is not an error.
+    pub fn put(&self, hunks: &BufferedHunks, rendered: &[u8]) {
+        let write_entry = || -> io::Result<()> {
+            fs::create_dir_all(&self.dir)?;
+            // The entry is written under a temporary name, so that a delta process reading the
+            // cache concurrently does not see a partial entry.
+            let path = self.dir.join(&hunks.key);
+            let temp_path = self
+                .dir
+                .join(format!("{}.{}.tmp", hunks.key, std::process::id()));
+            let mut file = fs::File::create(&temp_path)?;
+            writeln!(file, "{}", hunks.input_hash())?;
+            file.write_all(rendered)?;
+            fs::rename(temp_path, path)
+        };
+        if write_entry().is_ok() {
+            self.is_written.set(true);
+        }
+    }
+
+    /// If entries have been written to the cache, and it has grown larger than MAX_CACHE_SIZE,
+    /// delete the entries written longest ago.
+    pub fn limit_size(&self) {
+        if self.is_written.get() {
+            delete_oldest_entries(&self.dir, MAX_CACHE_SIZE);
+        }
+    }
+}
+
+/// If the files in this directory are larger than `max_size` in total, delete files, those
+/// modified longest ago first, until they are no larger than three quarters of it, so that the
+/// directory is not scanned again by each of the processes which follow.
+fn delete_oldest_entries(dir: &Path, max_size: u64) {
+    let mut entries: Vec<_> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let metadata = entry.metadata().ok()?;
+                Some((metadata.modified().ok()?, entry.path(), metadata.len()))
+            })
+            .collect(),
+        Err(_) => return,
+    };
+    let mut size: u64 = entries.iter().map(|(_, _, len)| len).sum();
+    if size <= max_size {
+        return;
+    }
+    entries.sort();
+    for (_, path, len) in entries {
+        if size <= max_size / 4 * 3 {
+            break;
+        }
+        if fs::remove_file(path).is_ok() {
+            size -= len;
+        }
+    }
+}
+
+impl BufferedHunks {
+    pub fn push_hunk_header(&mut self, line: &str, raw_line: &str, label: Option<String>) {
+        self.hasher.write_str("@@");
+        self.hasher.write_str(raw_line);
+        self.hasher.write_str(label.as_deref().unwrap_or(""));
+        self.lines.push(BufferedHunkLine::HunkHeader {
+            line: line.to_string(),
+            raw_line: raw_line.to_string(),
+            label,
+        });
+    }
+
+    pub fn push_hunk_line(&mut self, line: &str, raw_line: &str) {
+        self.hasher.write_str(raw_line);
+        self.lines.push(BufferedHunkLine::HunkLine {
+            line: line.to_string(),
+            raw_line: raw_line.to_string(),
+        });
+    }
+
+    fn input_hash(&self) -> String {
+        format!("{:016x}", self.hasher.finish())
+    }
+}
+
+impl StableHasher {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Hash a string, followed by a byte which cannot occur in UTF-8, so that the strings hashed
+    /// in turn are not simply concatenated.
+    fn write_str(&mut self, s: &str) {
+        self.write(s.as_bytes());
+        self.write(&[0xff]);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Return the directory of the render cache, and a hash of the options which determine how the
+/// hunks of a file are rendered. The delta version is included, since output may differ between
+/// versions.
+pub fn make_render_cache(opt: &cli::Opt) -> Option<(PathBuf, u64)> {
+    let dir = dirs::cache_dir()?.join("delta").join("render");
+    let mut hasher = StableHasher::new();
+    hasher.write_str(env!("CARGO_PKG_VERSION"));
+    // An option which is added, and which affects the rendering of hunks, must be added here.
+    macro_rules! hash_options {
+        ($($($field:ident).+),* $(,)?) => {
+            $(hasher.write_str(&format!("{:?}", opt.$($field).+));)*
+        };
+    }
+    hash_options!(
+        // The layout of hunk lines
+        line_numbers,
+        side_by_side,
+        side_by_side_context_fill,
+        side_by_side_align_edits,
+        keep_plus_minus_markers,
+        accessible,
+        ascii,
+        navigate,
+        word_diff_mode,
+        max_line_distance,
+        max_line_length,
+        tab_width,
+        file_tabs,
+        line_length_limit,
+        file_line_length_limits,
+        line_numbers_left_format,
+        line_numbers_right_format,
+        line_numbers_primary_side,
+        computed.decorations_width,
+        computed.background_color_extends_to_terminal_width,
+        // The content of hunks
+        structured_diff,
+        notebook_diff,
+        no_lockfile_summary,
+        no_sanitize_control_chars,
+        pre_render_filter,
+        hide_hunks_matching,
+        redact,
+        highlight,
+        default_language,
+        max_hunk_lines,
+        reverse_hunks,
+        invert,
+        expand_context,
+        distant_context,
+        collapse_distant_context,
+        collapse_noise_hunks,
+        diff_highlight,
+        diff_so_fancy,
+        foreground_emph,
+        raw,
+        color_only,
+        tokenization_regex,
+        hunk_lang_directives,
+        lang_for_hunks_matching,
+        computed.diff_context_lines,
+        computed.diff_prefixes,
+        computed.xfuncname_patterns,
+        computed.is_diff_filter,
+        // Hunk headers
+        hunk_header_format,
+        inline_hunk_headers,
+        hunk_label,
+        hunk_badges,
+        hunk_badge,
+        keep_diff_prefixes,
+        strip_path_prefix,
+        relative_to,
+        // Colors and styles
+        computed.is_light_mode,
+        computed.true_color,
+        computed.is_mono,
+        computed.terminal_profile,
+        syntax_theme,
+        minus_style,
+        zero_style,
+        plus_style,
+        minus_emph_style,
+        minus_non_emph_style,
+        plus_emph_style,
+        plus_non_emph_style,
+        side_by_side_minus_non_emph_style,
+        side_by_side_plus_non_emph_style,
+        hunk_header_style,
+        hunk_header_decoration_style,
+        hunk_header_funcname_style,
+        inline_hunk_header_style,
+        expanded_context_style,
+        distant_context_style,
+        filter_notice_style,
+        line_numbers_minus_style,
+        line_numbers_zero_style,
+        line_numbers_plus_style,
+        line_numbers_left_style,
+        line_numbers_right_style,
+        line_numbers_primary_style,
+        line_numbers_secondary_style,
+        line_length_marker_style,
+        minus_empty_line_marker_style,
+        plus_empty_line_marker_style,
+        no_newline_badge_style,
+        whitespace_error_style,
+        highlight_style,
+        redacted_style,
+        control_char_style,
+        deprecated_minus_background_color,
+        deprecated_minus_emph_background_color,
+        deprecated_plus_background_color,
+        deprecated_plus_emph_background_color,
+        deprecated_highlight_minus_lines,
+        deprecated_hunk_style,
+        deprecated_hunk_color,
+        deprecated_theme,
+    );
+    Some((dir, hasher.finish()))
+}
+
+/// A writer which keeps a copy of what is written to it while recording, and counts the lines
+/// written to it.
+pub struct RecordingWriter<W: Write> {
+    writer: W,
+    recording: Rc<RefCell<Option<Vec<u8>>>>,
+    n_lines: Rc<Cell<usize>>,
+}
+
+impl<W: Write> RecordingWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            recording: Rc::new(RefCell::new(None)),
+            n_lines: Rc::new(Cell::new(0)),
+        }
+    }
+
+    /// Return a handle holding the number of lines written so far.
+    pub fn line_count(&self) -> Rc<Cell<usize>> {
+        Rc::clone(&self.n_lines)
+    }
+
+    /// Return a handle with which recording is started and stopped: recording starts when it
+    /// holds Some buffer.
+    pub fn recording(&self) -> Rc<RefCell<Option<Vec<u8>>>> {
+        Rc::clone(&self.recording)
+    }
+}
+
+impl<W: Write> Write for RecordingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.writer.write(buf)?;
+        self.n_lines
+            .set(self.n_lines.get() + buf[..n].iter().filter(|&&b| b == b'\n').count());
+        if let Some(recording) = self.recording.borrow_mut().as_mut() {
+            recording.extend_from_slice(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use console::strip_ansi_codes;
+
+    use super::delete_oldest_entries;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_render_cache() {
+        let dir =
+            std::env::temp_dir().join(format!("delta-test-render-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut config = make_config_from_args(&["--render-cache"]);
+        let options_hash = config.render_cache.as_ref().unwrap().1;
+        config.render_cache = Some((dir.clone(), options_hash));
+
+        let uncached_output = run_delta(DIFF, &make_config_from_args(&[]));
+        let output = run_delta(DIFF, &config);
+        assert_eq!(output, uncached_output);
+        let entries: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(entries.len(), 2);
+
+        // The cached renderings are used: replace them to show that the hunks are not rendered
+        // again.
+        for entry in entries {
+            let path = entry.unwrap().path();
+            let cached = fs::read_to_string(&path).unwrap();
+            let input_hash = cached.lines().next().unwrap();
+            fs::write(&path, format!("{}\ncached rendering\n", input_hash)).unwrap();
+        }
+        let output = strip_ansi_codes(&run_delta(DIFF, &config)).to_string();
+        assert_eq!(output.matches("cached rendering").count(), 2);
+        assert!(!output.contains("b = 3"));
+
+        // A cached rendering of different hunks of the same blobs is not used.
+        let diff = DIFF.replacen("@@ -1,2 +1,2 @@\n a = 1\n", "@@ -2,1 +2,1 @@\n", 1);
+        let output = strip_ansi_codes(&run_delta(&diff, &config)).to_string();
+        assert_eq!(output.matches("cached rendering").count(), 1);
+        assert!(output.contains("b = 3"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_options_hash_is_stable() {
+        let options_hash = |args: &[&str]| make_config_from_args(args).render_cache.unwrap().1;
+        assert_eq!(
+            options_hash(&["--render-cache"]),
+            options_hash(&["--render-cache"])
+        );
+        assert_ne!(
+            options_hash(&["--render-cache"]),
+            options_hash(&["--render-cache", "--line-numbers"])
+        );
+        // Options which do not affect the rendering of hunks do not invalidate the cache.
+        assert_eq!(
+            options_hash(&["--render-cache"]),
+            options_hash(&["--render-cache", "--stats"])
+        );
+    }
+
+    #[test]
+    fn test_delete_oldest_entries() {
+        let dir = std::env::temp_dir().join(format!(
+            "delta-test-render-cache-size-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        // Entries written at the same time are deleted in order of their names.
+        for name in &["a", "b", "c", "d"] {
+            fs::write(dir.join(name), "x".repeat(10)).unwrap();
+        }
+        delete_oldest_entries(&dir, 40);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 4);
+        delete_oldest_entries(&dir, 30);
+        let mut names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["c", "d"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@
+ a = 1
+-b = 2
++b = 3
+diff --git a/b.py b/b.py
+index 323ca50..f69de29 100644
+--- a/b.py
++++ b/b.py
+@@ -1,2 +1,3 @@
+ a = 1
+-b = 2
++b = 3
++c = 4
+";
+}
@@ -0,0 +1,232 @@
+// `diff -y` (and sdiff) display two files side by side: each line of the output holds a line of
+// the first file in the left column and the corresponding line of the second file in the right
+// column, separated by a gutter whose marker character shows how the two lines are related:
+//
+// common line                          common line
+// changed line                      |  changed line, version 2
+// removed line                      <
+//                                   >  added line
+//
+// The columns are padded with tabs, whose stops are every 8 columns. Input which is not a diff is
+// held back and displayed as a plain file, so, when all of it has this form, delta would display
+// the gutter and both columns as the text of a file. Instead, it is converted to a unified diff
+// hunk and displayed as any other hunk, in delta's own layout.
+
+const TAB_WIDTH: usize = 8;
+
+// The gutter of `diff -y` output is near the middle of its --width, which is 130 by default. A
+// narrower gutter is not accepted, so that a line such as "a | b" is not taken for a diff.
+const MIN_GUTTER_COLUMN: usize = 16;
+
+/// If these lines are the output of `diff -y`, return them as a unified diff hunk, starting with
+/// its "@@" header line. The lines of the first file are those of the left column, and the lines
+/// of the second file are those of the right column, so the line numbers of the hunk are correct
+/// unless common lines were omitted by --suppress-common-lines.
+pub fn parse_sdiff_lines(lines: &[String]) -> Option<Vec<String>> {
+    let lines: Vec<Vec<char>> = lines.iter().map(|line| expand_tabs(line)).collect();
+    let gutter = find_gutter_column(&lines)?;
+    let right_column = find_right_column(&lines, gutter);
+    let mut hunk_lines = vec![String::new()];
+    let mut minus_lines = Vec::new();
+    let mut plus_lines = Vec::new();
+    let (mut n_minus, mut n_plus) = (0, 0);
+    for line in &lines {
+        let (left, marker, right) = split_sdiff_line(line, gutter, right_column)?;
+        if marker != ' ' {
+            if marker != '>' {
+                minus_lines.push(format!("-{}", left));
+                n_minus += 1;
+            }
+            if marker != '<' {
+                plus_lines.push(format!("+{}", right));
+                n_plus += 1;
+            }
+            continue;
+        }
+        // The removed and added lines preceding a common line are a single subhunk.
+        hunk_lines.append(&mut minus_lines);
+        hunk_lines.append(&mut plus_lines);
+        hunk_lines.push(format!(" {}", left));
+        n_minus += 1;
+        n_plus += 1;
+    }
+    hunk_lines.append(&mut minus_lines);
+    hunk_lines.append(&mut plus_lines);
+    hunk_lines[0] = format!("@@ -1,{} +1,{} @@", n_minus, n_plus);
+    Some(hunk_lines)
+}
+
+/// Could this line be a line of `diff -y` output? It could be if it is empty, or if it has a space
+/// followed by a gutter marker or another space, from MIN_GUTTER_COLUMN on. Input containing a
+/// line which could not be need not be held back to be parsed by `parse_sdiff_lines`.
+pub fn may_be_sdiff_line(line: &str) -> bool {
+    let line = expand_tabs(line);
+    line.is_empty()
+        || line
+            .iter()
+            .enumerate()
+            .skip(MIN_GUTTER_COLUMN)
+            .any(|(i, c)| matches!(c, ' ' | '<' | '>' | '|') && line[i - 1] == ' ')
+}
+
+/// Return the column of the gutter markers: the column, from MIN_GUTTER_COLUMN on, at which most
+/// lines have a '<', '>' or '|' between spaces (or at the end of the line).
+fn find_gutter_column(lines: &[Vec<char>]) -> Option<usize> {
+    let mut counts: Vec<usize> = Vec::new();
+    for line in lines {
+        for (i, c) in line.iter().enumerate().skip(1) {
+            if matches!(c, '<' | '>' | '|')
+                && line[i - 1] == ' '
+                && line.get(i + 1).map_or(true, |c| *c == ' ')
+            {
+                if counts.len() <= i {
+                    counts.resize(i + 1, 0);
+                }
+                counts[i] += 1;
+            }
+        }
+    }
+    let (gutter, n_lines) = counts
+        .iter()
+        .enumerate()
+        .skip(MIN_GUTTER_COLUMN)
+        .max_by_key(|(i, n)| (**n, std::cmp::Reverse(*i)))?;
+    if *n_lines == 0 {
+        return None;
+    }
+    Some(gutter)
+}
+
+/// Return the column at which the right column starts: the first column after the gutter at
+/// which any line has text.
+fn find_right_column(lines: &[Vec<char>], gutter: usize) -> usize {
+    lines
+        .iter()
+        .filter_map(|line| {
+            line.iter()
+                .enumerate()
+                .skip(gutter + 1)
+                .find(|(_, c)| **c != ' ')
+                .map(|(i, _)| i)
+        })
+        .min()
+        .unwrap_or(gutter + 2)
+}
+
+/// Split a line of `diff -y` output into the text of its left column, its gutter marker, and the
+/// text of its right column. Return None if the line does not have this form, or if it is a
+/// common line whose columns differ.
+fn split_sdiff_line(
+    line: &[char],
+    gutter: usize,
+    right_column: usize,
+) -> Option<(String, char, String)> {
+    let column = |start: usize, end: usize| -> String {
+        let end = end.min(line.len());
+        if start >= end {
+            return String::new();
+        }
+        line[start..end]
+            .iter()
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    };
+    let is_blank = |start: usize, end: usize| line.iter().take(end).skip(start).all(|c| *c == ' ');
+    let marker = line.get(gutter).copied().unwrap_or(' ');
+    if !is_blank(gutter - 1, gutter) || !is_blank(gutter + 1, right_column) {
+        return None;
+    }
+    let left = column(0, gutter - 1);
+    let right = column(right_column, line.len());
+    match marker {
+        '<' if line.len() == gutter + 1 => Some((left, marker, right)),
+        '>' if left.is_empty() => Some((left, marker, right)),
+        '|' => Some((left, marker, right)),
+        ' ' if left == right => Some((left, marker, right)),
+        _ => None,
+    }
+}
+
+fn expand_tabs(line: &str) -> Vec<char> {
+    let mut expanded = Vec::with_capacity(line.len());
+    for c in line.chars() {
+        if c == '\t' {
+            let n_spaces = TAB_WIDTH - expanded.len() % TAB_WIDTH;
+            expanded.extend(std::iter::repeat(' ').take(n_spaces));
+        } else {
+            expanded.push(c);
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Option<Vec<String>> {
+        parse_sdiff_lines(&input.lines().map(String::from).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_parse_sdiff_lines() {
+        // diff -y a.txt b.txt
+        let input = "\
+x\t\t\t\t\t\t\t\tx
+y\t\t\t\t\t\t\t      |\tz
+
+\tindented\t\t\t\t\t\t\tindented
+last\t\t\t\t\t\t\t      <
+\t\t\t\t\t\t\t      >\tnew
+\t\t\t\t\t\t\t      >\textra
+";
+        assert_eq!(
+            parse(input).unwrap(),
+            vec![
+                "@@ -1,5 +1,6 @@",
+                " x",
+                "-y",
+                "+z",
+                " ",
+                "         indented",
+                "-last",
+                "+new",
+                "+extra",
+            ]
+        );
+        // diff -y -t -W 60 a.txt b.txt
+        let input = "\
+x                               x
+y                            |  z
+last                         <
+";
+        assert_eq!(
+            parse(input).unwrap(),
+            vec!["@@ -1,3 +1,2 @@", " x", "-y", "-last", "+z"]
+        );
+    }
+
+    #[test]
+    fn test_parse_sdiff_lines_rejects_other_input() {
+        assert_eq!(parse("a | b\n"), None);
+        assert_eq!(parse("x\t\t\t\t\t\t\t\tx\n"), None);
+        assert_eq!(parse("x\t\t\t\t\t\t\t\ty\nz\t\t\t\t\t\t\t      <\n"), None);
+        assert_eq!(
+            parse("fn main() {\n    let a = b || c;\t\t\t\t  |  d\n}\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_may_be_sdiff_line() {
+        assert!(may_be_sdiff_line(""));
+        assert!(may_be_sdiff_line("common line					common line"));
+        assert!(may_be_sdiff_line("removed line				      <"));
+        assert!(may_be_sdiff_line("					      >	added line"));
+        assert!(!may_be_sdiff_line("* master"));
+        assert!(!may_be_sdiff_line(
+            "commit 0123456789abcdef0123456789abcdef01234567"
+        ));
+    }
+}
@@ -0,0 +1,466 @@
+use std::fs;
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use bytelines::ByteLines;
+use flate2::read::GzDecoder;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::bat::output::OutputType;
+use crate::config::Config;
+use crate::delta::{self, State};
+use crate::features::line_numbers::LineNumbersData;
+use crate::paint::{Painter, StyleSections};
+use crate::parse;
+
+/// Display the file at `path` as in `delta file.rs`.
+pub fn show_file(path: &Path, config: &Config) -> std::io::Result<()> {
+    let contents = fs::read(path)?;
+    let contents = String::from_utf8_lossy(&contents);
+    let lines: Vec<String> = contents.lines().map(String::from).collect();
+
+    let mut output_type = OutputType::from_mode(config.paging_mode, None, config).unwrap();
+    let mut writer = output_type.handle().unwrap();
+    let mut painter = Painter::new(&mut writer, config);
+    paint_plain_file(&mut painter, &lines, Some(&path.to_string_lossy()))?;
+    painter.writer.flush()
+}
+
+/// Is this a patch file, which `delta changes.patch` displays as a diff? A patch file compressed
+/// with gzip or zstd is recognized by its additional extension.
+pub fn is_patch_file(path: &Path) -> bool {
+    let path = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("gz") | Some("zst") => path.with_extension(""),
+        _ => path.to_path_buf(),
+    };
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("patch") | Some("diff")
+    )
+}
+
+/// Are the files given as arguments to be displayed as patches, rather than compared? A single
+/// patch file is displayed, but two files are compared, whatever their names, unless --patches is
+/// given.
+pub fn is_patches_display(paths: &[PathBuf], patches: bool) -> bool {
+    match paths {
+        [] => false,
+        [path] => patches || is_patch_file(path),
+        _ => patches,
+    }
+}
+
+/// Display patch files as diffs, one after another. Each file is processed separately, so that
+/// nothing is carried over from one file to the next.
+pub fn show_patch_files(paths: &[PathBuf], config: &Config) -> io::Result<()> {
+    let mut output_type = OutputType::from_mode(config.paging_mode, None, config).unwrap();
+    let mut writer = output_type.handle().unwrap();
+    write_patch_files(paths, &mut writer, config)
+}
+
+fn write_patch_files(paths: &[PathBuf], writer: &mut dyn Write, config: &Config) -> io::Result<()> {
+    for path in paths {
+        let contents = read_patch_file(path).map_err(|error| {
+            io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+        })?;
+        delta::delta(
+            ByteLines::new(BufReader::new(contents.as_slice())),
+            writer,
+            config,
+        )?;
+    }
+    Ok(())
+}
+
+/// Display the diffs read from the --input sources, one after another, each under a header naming
+/// its source if there are several.
+pub fn show_inputs(sources: &[String], config: &Config) -> io::Result<()> {
+    let mut output_type = OutputType::from_mode(config.paging_mode, None, config).unwrap();
+    let mut writer = output_type.handle().unwrap();
+    for source in sources {
+        let reader = open_input(source)
+            .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", source, error)))?;
+        write_input(source, reader, sources.len() > 1, &mut writer, config)?;
+    }
+    Ok(())
+}
+
+fn write_input(
+    source: &str,
+    reader: Box<dyn Read>,
+    write_header: bool,
+    writer: &mut dyn Write,
+    config: &Config,
+) -> io::Result<()> {
+    if write_header {
+        let mut painter = Painter::new(writer, config);
+        let header = if source == "-" { "stdin" } else { source };
+        delta::handle_generic_file_meta_header_line(&mut painter, header, header, config)?;
+    }
+    delta::delta(ByteLines::new(BufReader::new(reader)), writer, config)
+}
+
+/// Open an --input source: '-' for standard input, 'fd:N' for file descriptor N, or a file path.
+fn open_input(source: &str) -> io::Result<Box<dyn Read>> {
+    if source == "-" {
+        return Ok(Box::new(io::stdin()));
+    }
+    let path = match source.strip_prefix("fd:") {
+        Some(fd) => match fd.parse::<u32>() {
+            Ok(fd) => PathBuf::from(format!("/dev/fd/{}", fd)),
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "a file descriptor should be of the form 'fd:N'",
+                ))
+            }
+        },
+        None => PathBuf::from(source),
+    };
+    Ok(Box::new(fs::File::open(path)?))
+}
+
+/// Read a patch file, decompressing it if its extension is .gz or .zst.
+fn read_patch_file(path: &Path) -> io::Result<Vec<u8>> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("gz") => {
+            let mut contents = Vec::new();
+            GzDecoder::new(fs::File::open(path)?).read_to_end(&mut contents)?;
+            Ok(contents)
+        }
+        // There is no zstd decoder among delta's dependencies, so the zstd program is run.
+        Some("zst") => {
+            let output = Command::new("zstd")
+                .args(["--decompress", "--stdout", "--"])
+                .arg(path)
+                .output()
+                .map_err(|error| match error.kind() {
+                    io::ErrorKind::NotFound => io::Error::new(
+                        error.kind(),
+                        "decompressing a .zst file requires the zstd program, which was not found",
+                    ),
+                    _ => io::Error::new(error.kind(), format!("failed to run zstd: {}", error)),
+                })?;
+            if output.status.success() {
+                Ok(output.stdout)
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                ))
+            }
+        }
+        _ => fs::read(path),
+    }
+}
+
+/// Display input which is not a diff: the lines are syntax-highlighted as a whole file, with line
+/// numbers if line numbers are enabled, under a file header if the file name is known. If the file
+/// name is not known, the language is guessed from the first line (e.g. a shebang line).
+pub fn paint_plain_file(
+    painter: &mut Painter,
+    lines: &[String],
+    file_name: Option<&str>,
+) -> std::io::Result<()> {
+    let config = painter.config;
+    painter.emit()?;
+    let extension = match file_name {
+        Some(file_name) => {
+            delta::handle_generic_file_meta_header_line(painter, file_name, file_name, config)?;
+            painter.set_tab_width(Some(file_name));
+            parse::get_file_extension_from_file_meta_line_file_path(file_name).map(String::from)
+        }
+        None => lines
+            .first()
+            .and_then(|line| detect_extension_from_first_line(line, config)),
+    };
+    painter.set_syntax(extension.as_deref());
+    paint_plain_file_lines(painter, lines, 1)
+}
+
+/// Is input which is not a diff, and which starts with this line, displayed as a file? It is if
+/// its language is known, from the line or from --default-language: otherwise displaying it would
+/// only repaint it, as it would the output of `git branch`.
+pub fn is_plain_file(first_line: &str, config: &Config) -> bool {
+    config.syntax_theme.is_some()
+        && (config.default_language.is_some()
+            || detect_extension_from_first_line(first_line, config).is_some())
+}
+
+/// Start displaying input which is not a diff as a file, with the lines read so far. The lines
+/// which follow are displayed by `paint_plain_file_line` as they are read. Return the line numbers
+/// data to restore when the file ends.
+pub fn start_plain_file<'a>(
+    painter: &mut Painter<'a>,
+    lines: &[String],
+) -> std::io::Result<Option<LineNumbersData<'a>>> {
+    let config = painter.config;
+    painter.emit()?;
+    let extension = lines
+        .first()
+        .and_then(|line| detect_extension_from_first_line(line, config));
+    painter.set_syntax(extension.as_deref());
+    painter.set_highlighter();
+    let saved_line_numbers_data = set_plain_file_line_numbers(painter, 1, lines.len());
+    for line in lines {
+        paint_plain_file_line(painter, line)?;
+    }
+    Ok(saved_line_numbers_data)
+}
+
+/// Display lines of a file, in the syntax currently set on the painter, numbered from
+/// `first_line_number` if line numbers are enabled.
+pub fn paint_plain_file_lines(
+    painter: &mut Painter,
+    lines: &[String],
+    first_line_number: usize,
+) -> std::io::Result<()> {
+    painter.set_highlighter();
+    let saved_line_numbers_data =
+        set_plain_file_line_numbers(painter, first_line_number, lines.len());
+    for line in lines {
+        paint_plain_file_line(painter, line)?;
+    }
+    if let Some(line_numbers_data) = saved_line_numbers_data {
+        painter.line_numbers_data = line_numbers_data;
+    }
+    Ok(())
+}
+
+/// If line numbers are enabled, number the lines painted from now on as lines of a file, from
+/// `first_line_number`, and return the line numbers data which this replaces.
+fn set_plain_file_line_numbers<'a>(
+    painter: &mut Painter<'a>,
+    first_line_number: usize,
+    n_lines: usize,
+) -> Option<LineNumbersData<'a>> {
+    let config = painter.config;
+    if !config.line_numbers {
+        return None;
+    }
+    // Both line numbers are the same, so only the right-hand field is shown.
+    let mut line_numbers_data =
+        LineNumbersData::from_format_strings("", &config.line_numbers_right_format);
+    line_numbers_data.initialize_hunk(vec![
+        (first_line_number, n_lines),
+        (first_line_number, n_lines),
+    ]);
+    Some(std::mem::replace(
+        &mut painter.line_numbers_data,
+        line_numbers_data,
+    ))
+}
+
+fn detect_extension_from_first_line(first_line: &str, config: &Config) -> Option<String> {
+    config.syntax_theme.as_ref()?;
+    config
+        .syntax_set
+        .get()
+        .find_syntax_by_first_line(first_line)
+        .and_then(|syntax| syntax.file_extensions.first().cloned())
+}
+
+/// Display a line of a file, in the syntax and with the line numbers currently set on the painter.
+pub fn paint_plain_file_line(painter: &mut Painter, line: &str) -> std::io::Result<()> {
+    let config = painter.config;
+    let lines = vec![format!(
+        " {}\n",
+        painter.truncate(painter.expand_tabs(line.graphemes(true)))
+    )];
+    let syntax_style_sections = Painter::get_syntax_style_sections_for_lines(
+        &lines,
+        &State::HunkZero,
+        &mut painter.highlighter,
+        config,
+    );
+    let style_sections = StyleSections {
+        syntax: syntax_style_sections,
+        diff: vec![vec![(config.zero_style, lines[0].as_str())]],
+    };
+    let line_numbers_data = &mut painter.line_numbers_data;
+    Painter::paint_lines(
+        style_sections,
+        &State::HunkZero,
+        &mut painter.output_buffer,
+        config,
+        &mut if config.line_numbers {
+            Some(line_numbers_data)
+        } else {
+            None
+        },
+        "",
+        0,
+    );
+    painter.emit()
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_is_patch_file() {
+        assert!(is_patch_file(Path::new("a.patch")));
+        assert!(is_patch_file(Path::new("dir/a.diff")));
+        assert!(is_patch_file(Path::new("a.patch.gz")));
+        assert!(is_patch_file(Path::new("a.diff.zst")));
+        assert!(!is_patch_file(Path::new("a.rs")));
+        assert!(!is_patch_file(Path::new("a.gz")));
+        assert!(!is_patch_file(Path::new("patch")));
+    }
+
+    #[test]
+    fn test_is_patches_display() {
+        let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert!(is_patches_display(&paths(&["a.patch"]), false));
+        assert!(!is_patches_display(&paths(&["a.rs"]), false));
+        assert!(!is_patches_display(
+            &paths(&["old.patch", "new.patch"]),
+            false
+        ));
+        assert!(is_patches_display(
+            &paths(&["a.patch", "b.diff.gz", "c.patch"]),
+            true
+        ));
+        assert!(!is_patches_display(&[], true));
+    }
+
+    #[test]
+    fn test_write_patch_files() {
+        let dir =
+            std::env::temp_dir().join(format!("delta-test-patch-files-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // The first file's last hunk is incomplete, and its lines must not be taken as part of the
+        // second file.
+        let a = dir.join("a.patch");
+        fs::write(
+            &a,
+            "--- a/a.py\n+++ b/a.py\n@@ -1,3 +1,3 @@\n-a = 1\n+a = 2",
+        )
+        .unwrap();
+        let b = dir.join("b.diff.gz");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(b"--- a/b.py\n+++ b/b.py\n@@ -1 +1 @@\n-b = 1\n+b = 2\n")
+            .unwrap();
+        fs::write(&b, encoder.finish().unwrap()).unwrap();
+
+        let config = make_config_from_args(&[]);
+        let mut output = Vec::new();
+        write_patch_files(&[a, b], &mut output, &config).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("a = 2"));
+        println!("{}", output);
+        assert!(output.contains("\ncomparing: a/b.py ⟶   b/b.py\n"));
+        assert!(output.contains("b = 2"));
+
+        let error =
+            write_patch_files(&[dir.join("c.patch")], &mut Vec::new(), &config).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with(&dir.join("c.patch").display().to_string()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_inputs() {
+        let config = make_config_from_args(&["--file-decoration-style", "none"]);
+        let mut output = Vec::new();
+        for (source, input) in &[
+            // The first input's last hunk is incomplete.
+            (
+                "fd:3",
+                "--- a/a.py\n+++ b/a.py\n@@ -1,3 +1,3 @@\n-a = 1\n+a = 2\n",
+            ),
+            (
+                "fd:4",
+                "--- a/b.py\n+++ b/b.py\n@@ -1 +1 @@\n-b = 1\n+b = 2\n",
+            ),
+        ] {
+            let reader: Box<dyn Read> = Box::new(input.as_bytes());
+            write_input(source, reader, true, &mut output, &config).unwrap();
+        }
+        let output = String::from_utf8(output).unwrap();
+        let output = strip_ansi_codes(&output);
+        let lines: Vec<&str> = output.lines().filter(|line| !line.is_empty()).collect();
+        assert_eq!(lines[0], "fd:3");
+        let fd_4 = lines.iter().position(|line| *line == "fd:4").unwrap();
+        assert!(lines[..fd_4].contains(&"a = 2"));
+        assert_eq!(lines[fd_4 + 1], "comparing: a/b.py ⟶   b/b.py");
+        assert!(lines[fd_4..].contains(&"b = 2"));
+
+        assert!(open_input("fd:x").is_err());
+    }
+
+    const PLAIN_FILE: &str = "\
+#!/usr/bin/env python
+def f():
+\treturn 1
+";
+
+    #[test]
+    fn test_plain_file_is_displayed_with_syntax_highlighting() {
+        let config = make_config_from_args(&["--tabs", "2"]);
+        let output = run_delta(PLAIN_FILE, &config);
+        assert_ne!(output, strip_ansi_codes(&output));
+        let output = strip_ansi_codes(&output);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines,
+            vec!["#!/usr/bin/env python", "def f():", "  return 1"]
+        );
+    }
+
+    #[test]
+    fn test_plain_file_with_line_numbers() {
+        let config = make_config_from_args(&["--line-numbers"]);
+        let output = run_delta(PLAIN_FILE, &config);
+        let output = strip_ansi_codes(&output);
+        let mut lines = output.lines();
+        assert_eq!(lines.next().unwrap(), " 1  │#!/usr/bin/env python");
+        assert_eq!(lines.next().unwrap(), " 2  │def f():");
+    }
+
+    #[test]
+    fn test_input_containing_a_diff_is_not_a_plain_file() {
+        let config = make_config_from_args(&["--line-numbers"]);
+        let input = "Some text\n\ndiff --git a/a.py b/a.py\n";
+        let output = run_delta(input, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.starts_with("Some text\n\n"));
+    }
+
+    #[test]
+    fn test_input_of_unknown_language_is_written_unaltered() {
+        let config = make_config_from_args(&["--line-numbers"]);
+        let input = "* master\n  dev\n";
+        assert_eq!(run_delta(input, &config), input);
+    }
+
+    #[test]
+    fn test_long_plain_file_followed_by_a_diff() {
+        let config = make_config_from_args(&["--line-numbers"]);
+        let mut input = String::from("#!/usr/bin/env python\n");
+        for i in 2..=1500 {
+            input.push_str(&format!("x = {}\n", i));
+        }
+        input.push_str("--- a/a.py\n+++ b/a.py\n@@ -1 +1 @@\n-a = 1\n+a = 2\n");
+        let output = run_delta(&input, &config);
+        let output = strip_ansi_codes(&output);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], " 1  │#!/usr/bin/env python");
+        assert_eq!(lines[1499], "1500│x = 1500");
+        // The line numbers of the diff which follows the file are in both columns.
+        assert!(lines[1500..].contains(&" 1  ⋮    │a = 1"));
+        assert!(lines[1500..].contains(&"    ⋮ 1  │a = 2"));
+    }
+}
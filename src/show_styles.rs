@@ -0,0 +1,114 @@
+use std::io::{self, BufReader, Write};
+
+use bytelines::ByteLines;
+
+use crate::config::Config;
+use crate::delta::delta;
+
+/// Render a diff in which every styled element is labeled with the name of the option that
+/// controls its style, using the active configuration. This is for trying out the options of a
+/// custom theme.
+pub fn show_styles(writer: &mut dyn Write, config: &Config) -> io::Result<()> {
+    let input = make_styles_diff(config);
+    delta(
+        ByteLines::new(BufReader::new(input.as_bytes())),
+        writer,
+        config,
+    )
+}
+
+/// Return a diff exercising each style, whose lines name the options that control their styles.
+/// Changes are separated by unchanged lines, so that each is painted independently.
+fn make_styles_diff(config: &Config) -> String {
+    // With line numbers, each line also names the style of its line numbers.
+    let label = |style: &str, line_numbers_style: &str| {
+        if config.line_numbers {
+            format!("{}, line-numbers-{}", style, line_numbers_style)
+        } else {
+            style.to_string()
+        }
+    };
+    let zero = format!(" // {}", label("zero-style", "zero-style"));
+    let minus = |text: &str| format!("-{}", text);
+    let plus = |text: &str| format!("+{}", text);
+    let lines = vec![
+        zero.clone(),
+        minus(&format!("// {}", label("minus-style", "minus-style"))),
+        zero.clone(),
+        minus("let minus_emph_style = 1; // minus-non-emph-style"),
+        plus("let plus_emph_style = 1; // plus-non-emph-style"),
+        zero.clone(),
+        plus(&format!("// {}", label("plus-style", "plus-style"))),
+        zero.clone(),
+        minus("// minus-empty-line-marker-style: the next removed line is empty"),
+        minus(""),
+        zero.clone(),
+        plus("// plus-empty-line-marker-style: the next added line is empty"),
+        plus(""),
+        zero.clone(),
+        plus("// whitespace-error-style: the next added line contains only whitespace"),
+        plus("    "),
+        zero,
+    ];
+    let n_minus = lines.iter().filter(|line| !line.starts_with('+')).count();
+    let n_plus = lines.iter().filter(|line| !line.starts_with('-')).count();
+    format!(
+        "\
+commit 0000000000000000000000000000000000000000 (commit-style, commit-decoration-style)
+Author: Delta <delta@example.com>
+Date:   Thu Jan 1 00:00:00 1970 +0000
+
+    Labels name the options that control the styles in which they are displayed
+
+diff --git a/file-style, file-decoration-style.rs b/file-style, file-decoration-style.rs
+index 0000000..1111111 100644
+--- a/file-style, file-decoration-style.rs
++++ b/file-style, file-decoration-style.rs
+@@ -1,{} +1,{} @@ // hunk-header-style, hunk-header-decoration-style
+{}
+",
+        n_minus,
+        n_plus,
+        lines.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::make_config_from_args;
+
+    #[test]
+    fn test_show_styles() {
+        for args in &[vec![], vec!["--line-numbers"]] {
+            let config = make_config_from_args(args);
+            let mut output = Vec::new();
+            show_styles(&mut output, &config).unwrap();
+            let output = String::from_utf8(output).unwrap();
+            let output = strip_ansi_codes(&output);
+            for label in &[
+                "commit-style",
+                "file-style, file-decoration-style.rs",
+                "hunk-header-style, hunk-header-decoration-style",
+                "zero-style",
+                "minus-style",
+                "minus-non-emph-style",
+                "plus-style",
+                "plus-non-emph-style",
+                "minus-empty-line-marker-style",
+                "plus-empty-line-marker-style",
+                "whitespace-error-style",
+            ] {
+                assert!(output.contains(label), "{}", label);
+            }
+            assert_eq!(
+                output.contains("line-numbers-zero-style"),
+                config.line_numbers
+            );
+            // The hunk is parsed to its end.
+            assert!(!output.contains("+    "));
+        }
+    }
+}
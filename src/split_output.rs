@@ -0,0 +1,241 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Component, Path, PathBuf};
+
+use bytelines::ByteLines;
+
+use crate::ansi;
+use crate::config::Config;
+use crate::delta::delta;
+use crate::filter::HunkLineCounter;
+use crate::parse::{self, LinePrefix};
+
+/// Render each file section of the input to a separate file under `dir`, at the file's path with
+/// ".diff" appended. Lines preceding a file section, such as commit metadata, are rendered with
+/// that file. If the same path occurs more than once (e.g. in `git log -p` output), the sections
+/// are appended to the same file. Lines following the last file section are written to `writer`.
+pub fn split_output<I>(
+    mut lines: ByteLines<I>,
+    dir: &Path,
+    writer: &mut dyn Write,
+    config: &Config,
+) -> io::Result<()>
+where
+    I: BufRead,
+{
+    let mut splitter = Splitter::new(dir, config);
+    while let Some(line) = lines.next() {
+        splitter.process_line(line?)?;
+    }
+    splitter.end_file()?;
+    render(&splitter.buffer, writer, config)
+}
+
+struct Splitter<'a> {
+    dir: &'a Path,
+    config: &'a Config,
+    // The lines of the current file section, and any lines preceding it
+    buffer: Vec<u8>,
+    in_file: bool,
+    is_git_diff: bool,
+    minus_file: String,
+    plus_file: String,
+    n_hunks: usize,
+    hunk_line_counter: Option<HunkLineCounter>,
+    written_paths: HashSet<PathBuf>,
+}
+
+impl<'a> Splitter<'a> {
+    fn new(dir: &'a Path, config: &'a Config) -> Self {
+        Self {
+            dir,
+            config,
+            buffer: Vec::new(),
+            in_file: false,
+            is_git_diff: false,
+            minus_file: "".to_string(),
+            plus_file: "".to_string(),
+            n_hunks: 0,
+            hunk_line_counter: None,
+            written_paths: HashSet::new(),
+        }
+    }
+
+    fn process_line(&mut self, raw_line: &[u8]) -> io::Result<()> {
+        let line = String::from_utf8_lossy(raw_line);
+        let line = if raw_line.contains(&0x1b) {
+            ansi::strip_ansi_codes(&line).to_string()
+        } else {
+            line.to_string()
+        };
+        let is_hunk_line = match self.hunk_line_counter.as_mut() {
+            Some(counter) => counter.accepts(&line),
+            None => false,
+        };
+        if !is_hunk_line {
+            self.hunk_line_counter = None;
+            match parse::classify_line_prefix(line.as_bytes()) {
+                LinePrefix::Commit => self.end_file()?,
+                LinePrefix::Diff => {
+                    self.end_file()?;
+                    self.start_file(line.starts_with("diff --git "));
+                    if self.is_git_diff {
+                        // The path of a file without "---" and "+++" lines, e.g. a binary file: the
+                        // text following the last prefix.
+                        let start = self
+                            .config
+                            .diff_prefixes
+                            .iter()
+                            .filter_map(|prefix| {
+                                line.rfind(&format!(" {}", prefix))
+                                    .map(|i| i + 1 + prefix.len())
+                            })
+                            .max();
+                        if let Some(start) = start {
+                            self.plus_file = line[start..].to_string();
+                        }
+                    }
+                }
+                prefix @ LinePrefix::MinusFile | prefix @ LinePrefix::PlusFile => {
+                    if !self.in_file || self.n_hunks > 0 {
+                        // A diff -u file section, which has no "diff" line
+                        self.end_file()?;
+                        self.start_file(false);
+                    }
+                    let path = parse::get_file_path_from_file_meta_line(
+                        &line,
+                        self.is_git_diff,
+                        self.config.get_diff_prefixes_to_remove(),
+                    );
+                    if prefix == LinePrefix::MinusFile {
+                        self.minus_file = path;
+                    } else {
+                        self.plus_file = path;
+                    }
+                }
+                LinePrefix::HunkHeader if self.in_file => {
+                    let (_, line_numbers) = parse::parse_hunk_header(&line);
+                    self.n_hunks += 1;
+                    self.hunk_line_counter = Some(HunkLineCounter::new(&line_numbers));
+                }
+                _ => {}
+            }
+        }
+        self.buffer.extend_from_slice(raw_line);
+        self.buffer.push(b'\n');
+        Ok(())
+    }
+
+    fn start_file(&mut self, is_git_diff: bool) {
+        self.in_file = true;
+        self.is_git_diff = is_git_diff;
+        self.minus_file.clear();
+        self.plus_file.clear();
+        self.n_hunks = 0;
+    }
+
+    /// Render the current file section to its output file.
+    fn end_file(&mut self) -> io::Result<()> {
+        if !self.in_file {
+            return Ok(());
+        }
+        self.in_file = false;
+        let path = self.dir.join(self.get_output_file_name());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(self.written_paths.contains(&path))
+            .truncate(!self.written_paths.contains(&path))
+            .open(&path)?;
+        render(&self.buffer, &mut file, self.config)?;
+        self.buffer.clear();
+        self.written_paths.insert(path);
+        Ok(())
+    }
+
+    /// Return the path of the output file, relative to the output directory. Components of the
+    /// file's path which could lead outside the output directory are dropped.
+    fn get_output_file_name(&self) -> PathBuf {
+        let path = if self.plus_file.is_empty() || self.plus_file == "/dev/null" {
+            &self.minus_file
+        } else {
+            &self.plus_file
+        };
+        let mut name: PathBuf = Path::new(path)
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect();
+        if name.file_name().is_none() {
+            name.push("unknown");
+        }
+        let mut name = name.into_os_string();
+        name.push(".diff");
+        PathBuf::from(name)
+    }
+}
+
+fn render(input: &[u8], writer: &mut dyn Write, config: &Config) -> io::Result<()> {
+    if input.is_empty() {
+        return Ok(());
+    }
+    delta(ByteLines::new(BufReader::new(input)), writer, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::make_config_from_args;
+
+    const TWO_FILES_DIFF: &str = "\
+diff --git a/src/a.rs b/src/a.rs
+index 1111111..2222222 100644
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1,2 +1,2 @@
+ fn a() {}
+--- removed comment
++fn b() {}
+diff --git a/../b.rs b/../b.rs
+index 3333333..4444444 100644
+--- a/../b.rs
++++ b/../b.rs
+@@ -1 +1 @@
+-fn c() {}
++fn d() {}
+";
+
+    #[test]
+    fn test_split_output() {
+        let dir =
+            std::env::temp_dir().join(format!("delta-test-split-output-{}", std::process::id()));
+        let config = make_config_from_args(&[]);
+        let mut stdout = Vec::new();
+        split_output(
+            ByteLines::new(BufReader::new(TWO_FILES_DIFF.as_bytes())),
+            &dir,
+            &mut stdout,
+            &config,
+        )
+        .unwrap();
+        assert!(stdout.is_empty());
+
+        let a = fs::read_to_string(dir.join("src/a.rs.diff")).unwrap();
+        let a = strip_ansi_codes(&a);
+        assert!(a.contains("fn b() {}"));
+        assert!(a.contains("removed comment"));
+        assert!(!a.contains("fn d() {}"));
+
+        let b = fs::read_to_string(dir.join("b.rs.diff")).unwrap();
+        let b = strip_ansi_codes(&b);
+        assert!(b.contains("fn d() {}"));
+        assert!(!b.contains("fn b() {}"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
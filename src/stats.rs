@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::ansi;
+use crate::config::Config;
+
+// The maximum width of the bar of +/- characters displayed for each file
+const MAX_BAR_WIDTH: usize = 40;
+
+struct FileStats {
+    path: String,
+    n_insertions: usize,
+    n_deletions: usize,
+}
+
+/// The number of lines added and removed in each file of the input, as displayed by --stats.
+#[derive(Default)]
+pub struct DiffStats {
+    files: Vec<FileStats>,
+    // The index in `files` of each path, so that the changes to a file which occurs more than once
+    // in the input (e.g. in `git log -p` output) are counted together.
+    indices: HashMap<String, usize>,
+    current_file: Option<usize>,
+}
+
+impl DiffStats {
+    /// Count the hunk lines which follow as changes to the file with these paths.
+    pub fn start_file(&mut self, minus_file: &str, plus_file: &str) {
+        let path = if plus_file == "/dev/null" {
+            minus_file
+        } else {
+            plus_file
+        };
+        let files = &mut self.files;
+        let index = *self.indices.entry(path.to_string()).or_insert_with(|| {
+            files.push(FileStats {
+                path: path.to_string(),
+                n_insertions: 0,
+                n_deletions: 0,
+            });
+            files.len() - 1
+        });
+        self.current_file = Some(index);
+    }
+
+    pub fn count_hunk_line(&mut self, line: &str) {
+        if let Some(file) = self.current_file.map(|index| &mut self.files[index]) {
+            if line.starts_with('+') {
+                file.n_insertions += 1;
+            } else if line.starts_with('-') {
+                file.n_deletions += 1;
+            }
+        }
+    }
+}
+
+/// Write a summary of the changes in the input: the number of lines added and removed in each
+/// file, largest changes first, followed by the totals.
+pub fn write_stats(
+    writer: &mut dyn Write,
+    stats: &DiffStats,
+    available_terminal_width: usize,
+    config: &Config,
+) -> io::Result<()> {
+    if stats.files.is_empty() {
+        return Ok(());
+    }
+    let mut files: Vec<&FileStats> = stats.files.iter().collect();
+    // A stable sort, so that files with equal numbers of changes remain in input order.
+    files.sort_by_key(|file| std::cmp::Reverse(file.n_insertions + file.n_deletions));
+
+    let path_width = files
+        .iter()
+        .map(|file| ansi::measure_text_width(&file.path))
+        .max()
+        .unwrap_or(0);
+    let max_changes = files[0].n_insertions + files[0].n_deletions;
+    let count_width = max_changes.to_string().len();
+    let bar_width = MAX_BAR_WIDTH.min(
+        available_terminal_width
+            .saturating_sub(path_width + count_width + 5)
+            .max(1),
+    );
+
+    writeln!(writer)?;
+    for file in &files {
+        let (n_plus, n_minus) =
+            scale_bar(file.n_insertions, file.n_deletions, max_changes, bar_width);
+        writeln!(
+            writer,
+            " {}{} | {:>count_width$} {}{}",
+            config.file_style.paint(file.path.as_str()),
+            " ".repeat(path_width - ansi::measure_text_width(&file.path)),
+            file.n_insertions + file.n_deletions,
+            config.plus_style.paint("+".repeat(n_plus)),
+            config.minus_style.paint("-".repeat(n_minus)),
+            count_width = count_width,
+        )?;
+    }
+    let n_insertions: usize = files.iter().map(|file| file.n_insertions).sum();
+    let n_deletions: usize = files.iter().map(|file| file.n_deletions).sum();
+    writeln!(
+        writer,
+        " {} file{} changed, {} insertion{}(+), {} deletion{}(-)",
+        files.len(),
+        if files.len() == 1 { "" } else { "s" },
+        n_insertions,
+        if n_insertions == 1 { "" } else { "s" },
+        n_deletions,
+        if n_deletions == 1 { "" } else { "s" },
+    )
+}
+
+/// Return the numbers of + and - characters to display for a file, scaled so that the file with
+/// the most changes fills the bar. A file with any insertions or deletions is given at least one
+/// character for each.
+fn scale_bar(
+    n_insertions: usize,
+    n_deletions: usize,
+    max_changes: usize,
+    bar_width: usize,
+) -> (usize, usize) {
+    if max_changes <= bar_width {
+        return (n_insertions, n_deletions);
+    }
+    let scale = |n: usize| {
+        if n == 0 {
+            0
+        } else {
+            (n * bar_width / max_changes).max(1)
+        }
+    };
+    (scale(n_insertions), scale(n_deletions))
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_scale_bar() {
+        assert_eq!(scale_bar(3, 2, 5, 40), (3, 2));
+        assert_eq!(scale_bar(60, 20, 80, 40), (30, 10));
+        assert_eq!(scale_bar(1, 0, 80, 40), (1, 0));
+    }
+
+    #[test]
+    fn test_stats() {
+        let config = make_config_from_args(&["--stats"]);
+        let output = run_delta(DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        let lines: Vec<&str> = output.lines().rev().take(4).collect();
+        assert_eq!(
+            lines,
+            vec![
+                " 2 files changed, 3 insertions(+), 2 deletions(-)",
+                " a.py | 2 +-",
+                " b.py | 3 ++-",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_stats_by_default() {
+        let config = make_config_from_args(&[]);
+        let output = run_delta(DIFF, &config);
+        assert!(!output.contains("files changed"));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@
+ a = 1
+-b = 2
++b = 3
+diff --git a/b.py b/b.py
+index 223ca50..e69de29 100644
+--- a/b.py
++++ b/b.py
+@@ -1,2 +1,3 @@
+ a = 1
+-b = 2
++b = 3
++c = 4
+";
+}
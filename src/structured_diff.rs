@@ -0,0 +1,219 @@
+use serde_json::{Map, Number, Value};
+use yaml_rust::{Yaml, YamlLoader};
+
+use crate::config::Config;
+use crate::notebook;
+use crate::pre_render;
+
+// Under --structured-diff, JSON and YAML files are compared as data rather than as text: both
+// versions of a file are parsed and flattened into one line per value, of the form
+// `.path.to[0].key = value`, sorted by path, and it is the diff of the flattened versions which
+// is displayed. Keys which have been reordered, and changes of formatting, therefore make no
+// difference.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Json,
+    Yaml,
+    // A Jupyter notebook, compared cell by cell under --notebook-diff
+    Notebook,
+}
+
+impl Format {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Json => "JSON data",
+            Self::Yaml => "YAML data",
+            Self::Notebook => "notebook cells",
+        }
+    }
+}
+
+/// Return the data format of the file at `path`, if it is to be compared as structured data.
+pub fn get_format(path: &str, config: &Config) -> Option<Format> {
+    match path.rsplit_once('.')?.1.to_lowercase().as_str() {
+        "json" if config.structured_diff => Some(Format::Json),
+        "yaml" | "yml" if config.structured_diff => Some(Format::Yaml),
+        "ipynb" if config.notebook_diff => Some(Format::Notebook),
+        _ => None,
+    }
+}
+
+/// Return the hunk lines of a diff between the flattened data of the two versions of a file.
+/// Return None if either version of the file cannot be read or parsed.
+pub fn make_hunks(
+    format: Format,
+    minus_file: &str,
+    plus_file: &str,
+    file_meta: &[String],
+    is_git_diff: bool,
+    context_lines: Option<u32>,
+) -> Option<Vec<String>> {
+    let (minus, plus) =
+        pre_render::read_file_versions(minus_file, plus_file, file_meta, is_git_diff)?;
+    let minus = flatten(format, &String::from_utf8_lossy(&minus))?;
+    let plus = flatten(format, &String::from_utf8_lossy(&plus))?;
+    pre_render::diff_file_versions(
+        minus.as_bytes(),
+        plus.as_bytes(),
+        minus_file,
+        plus_file,
+        context_lines,
+    )
+}
+
+/// Return the lines of `path = value` pairs of the data, sorted by path, or the rendered cells of
+/// a notebook. An empty file has no lines.
+fn flatten(format: Format, text: &str) -> Option<String> {
+    if text.trim().is_empty() {
+        return Some(String::new());
+    }
+    let value = match format {
+        Format::Json => serde_json::from_str(text).ok()?,
+        Format::Yaml => {
+            let mut documents = YamlLoader::load_from_str(text).ok()?;
+            if documents.len() == 1 {
+                yaml_to_json(documents.remove(0))
+            } else {
+                Value::Array(documents.into_iter().map(yaml_to_json).collect())
+            }
+        }
+        // The cells of a notebook are in order, and are not flattened.
+        Format::Notebook => return notebook::render_cells(text),
+    };
+    let mut lines = Vec::new();
+    flatten_value(&value, &mut String::new(), &mut lines);
+    lines.sort();
+    Some(lines.into_iter().map(|line| line + "\n").collect())
+}
+
+fn flatten_value(value: &Value, path: &mut String, lines: &mut Vec<String>) {
+    let path_len = path.len();
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                if !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+                {
+                    path.push('.');
+                    path.push_str(key);
+                } else {
+                    path.push_str(&format!("[{}]", Value::String(key.to_string())));
+                }
+                flatten_value(value, path, lines);
+                path.truncate(path_len);
+            }
+        }
+        Value::Array(values) if !values.is_empty() => {
+            for (i, value) in values.iter().enumerate() {
+                path.push_str(&format!("[{}]", i));
+                flatten_value(value, path, lines);
+                path.truncate(path_len);
+            }
+        }
+        _ => lines.push(format!(
+            "{} = {}",
+            if path.is_empty() { "." } else { path },
+            value
+        )),
+    }
+}
+
+fn yaml_to_json(yaml: Yaml) -> Value {
+    match yaml {
+        Yaml::Hash(hash) => Value::Object(
+            hash.into_iter()
+                .map(|(key, value)| (yaml_key_to_string(key), yaml_to_json(value)))
+                .collect::<Map<String, Value>>(),
+        ),
+        Yaml::Array(values) => Value::Array(values.into_iter().map(yaml_to_json).collect()),
+        Yaml::String(s) => Value::String(s),
+        Yaml::Integer(n) => Value::Number(n.into()),
+        Yaml::Real(s) => match s.parse().ok().and_then(Number::from_f64) {
+            Some(n) => Value::Number(n),
+            None => Value::String(s),
+        },
+        Yaml::Boolean(b) => Value::Bool(b),
+        Yaml::Null | Yaml::BadValue | Yaml::Alias(_) => Value::Null,
+    }
+}
+
+fn yaml_key_to_string(key: Yaml) -> String {
+    match key {
+        Yaml::String(s) | Yaml::Real(s) => s,
+        Yaml::Integer(n) => n.to_string(),
+        Yaml::Boolean(b) => b.to_string(),
+        key => yaml_to_json(key).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_flatten() {
+        assert_eq!(
+            flatten(
+                Format::Json,
+                r#"{"b": [1, {"c d": null}], "a": {"x": "y", "z": {}}}"#
+            )
+            .unwrap(),
+            "\
+.a.x = \"y\"
+.a.z = {}
+.b[0] = 1
+.b[1][\"c d\"] = null
+"
+        );
+        assert_eq!(
+            flatten(Format::Yaml, "b: [1, 2.5]\na:\n  x: y\n").unwrap(),
+            "\
+.a.x = \"y\"
+.b[0] = 1
+.b[1] = 2.5
+"
+        );
+        assert_eq!(flatten(Format::Json, "").unwrap(), "");
+        assert_eq!(flatten(Format::Json, "{"), None);
+    }
+
+    #[test]
+    fn test_structured_diff() {
+        let dir =
+            std::env::temp_dir().join(format!("delta-test-structured-diff-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b, c) = (dir.join("a.json"), dir.join("b.json"), dir.join("c.json"));
+        std::fs::write(&a, "{\"x\": 1, \"y\": [2, 3]}\n").unwrap();
+        std::fs::write(&b, "{\n  \"y\": [2, 3],\n  \"x\": 1\n}\n").unwrap();
+        std::fs::write(&c, "{\n  \"y\": [2, 4],\n  \"x\": 1\n}\n").unwrap();
+        let make_diff = |minus: &std::path::Path, plus: &std::path::Path| {
+            format!(
+                "--- {}\n+++ {}\n@@ -1 +1,4 @@\n-{{\"x\": 1, \"y\": [2, 3]}}\n+{{\n+  \"y\": [2, 3],\n+  \"x\": 1\n+}}\n",
+                minus.display(),
+                plus.display()
+            )
+        };
+        let config = make_config_from_args(&["--structured-diff"]);
+
+        let output = run_delta(&make_diff(&a, &b), &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("no differences in the JSON data"));
+        assert!(!output.contains("\"y\""));
+
+        let output = run_delta(&make_diff(&a, &c), &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("compared as JSON data"));
+        assert!(output.contains("\n.y[1] = 3\n"));
+        assert!(output.contains("\n.y[1] = 4\n"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,181 @@
+use std::process;
+
+use crate::cli::FileMarks;
+use crate::env;
+use crate::git_config::GitConfig;
+
+// A terminal profile records which escape sequences a terminal emulator understands, so that
+// delta emits only those, rather than sequences which an older or simpler terminal would display
+// as garbage. Unless --terminal-profile names a profile, the terminal is detected from the
+// environment. The builtin profiles can be overridden, and new profiles defined, in git config:
+//
+// [delta "terminal.kitty"]
+//     true-color = false
+//     marks = none
+
+/// The capabilities of a terminal emulator.
+#[derive(Clone, Debug, Default)]
+pub struct TerminalProfile {
+    pub name: String,
+    pub true_color: bool,
+    pub hyperlinks: bool,
+    pub marks: FileMarks,
+}
+
+// (name, true color, hyperlinks, marks)
+type BuiltinProfile = (&'static str, bool, bool, FileMarks);
+
+const BUILTIN_PROFILES: &[BuiltinProfile] = &[
+    ("alacritty", true, true, FileMarks::None),
+    ("apple-terminal", false, false, FileMarks::None),
+    ("ghostty", true, true, FileMarks::Osc133),
+    ("iterm2", true, true, FileMarks::ITerm2),
+    ("kitty", true, true, FileMarks::Osc133),
+    ("linux", false, false, FileMarks::None),
+    ("tmux", false, false, FileMarks::None),
+    ("vscode", true, true, FileMarks::Osc133),
+    ("wezterm", true, true, FileMarks::Osc133),
+    ("windows-terminal", true, true, FileMarks::Osc133),
+];
+
+/// Return the profile named `name`, or, if `name` is "auto", that of the detected terminal, with
+/// the capabilities set in its git config section. A profile which is neither builtin nor
+/// defined in git config has none of the capabilities.
+pub fn get_terminal_profile(name: &str, git_config: &Option<GitConfig>) -> TerminalProfile {
+    let name = if name == "auto" {
+        detect_terminal(env::get_env_var)
+    } else {
+        name.to_string()
+    };
+    let mut profile = match BUILTIN_PROFILES.iter().find(|profile| profile.0 == name) {
+        Some(&(_, true_color, hyperlinks, marks)) => TerminalProfile {
+            name: name.clone(),
+            true_color,
+            hyperlinks,
+            marks,
+        },
+        None => TerminalProfile {
+            name: name.clone(),
+            ..TerminalProfile::default()
+        },
+    };
+    if let Some(git_config) = git_config {
+        let key = |capability: &str| format!("delta.terminal.{}.{}", name, capability);
+        if let Some(true_color) = git_config.get::<bool>(&key("true-color")) {
+            profile.true_color = true_color;
+        }
+        if let Some(hyperlinks) = git_config.get::<bool>(&key("hyperlinks")) {
+            profile.hyperlinks = hyperlinks;
+        }
+        if let Some(marks) = git_config.get::<String>(&key("marks")) {
+            profile.marks = FileMarks::from_name(&marks).unwrap_or_else(|| {
+                eprintln!(
+                    "Invalid value for {}: {} (valid values are \"none\", \"osc133\", and \"iterm2\")",
+                    key("marks"),
+                    marks
+                );
+                process::exit(1);
+            });
+        }
+    }
+    profile
+}
+
+/// Return the name of the profile of the terminal, according to the environment variables set by
+/// terminal emulators, or "unknown".
+fn detect_terminal<F>(get_env_var: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if get_env_var("WT_SESSION").is_some() {
+        return "windows-terminal".to_string();
+    }
+    let term_program = get_env_var("TERM_PROGRAM").unwrap_or_default();
+    let term = get_env_var("TERM").unwrap_or_default();
+    let name = match (term_program.as_str(), term.as_str()) {
+        ("iTerm.app", _) => "iterm2",
+        ("WezTerm", _) => "wezterm",
+        ("vscode", _) => "vscode",
+        ("ghostty", _) | (_, "xterm-ghostty") => "ghostty",
+        ("Apple_Terminal", _) => "apple-terminal",
+        (_, "xterm-kitty") => "kitty",
+        (_, "alacritty") => "alacritty",
+        (_, "linux") => "linux",
+        ("tmux", _) => "tmux",
+        (_, term) if term.starts_with("tmux") || term.starts_with("screen") => "tmux",
+        _ => "unknown",
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::remove_file;
+
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::make_options_from_args_and_git_config;
+
+    #[test]
+    fn test_detect_terminal() {
+        let detect = |vars: &[(&str, &str)]| {
+            detect_terminal(|key| {
+                vars.iter()
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+        assert_eq!(detect(&[("TERM", "xterm-kitty")]), "kitty");
+        assert_eq!(
+            detect(&[("TERM_PROGRAM", "iTerm.app"), ("TERM", "xterm-256color")]),
+            "iterm2"
+        );
+        assert_eq!(
+            detect(&[("WT_SESSION", "1"), ("TERM", "xterm-256color")]),
+            "windows-terminal"
+        );
+        assert_eq!(detect(&[("TERM", "screen-256color")]), "tmux");
+        assert_eq!(detect(&[("TERM", "xterm-256color")]), "unknown");
+    }
+
+    #[test]
+    fn test_terminal_profile() {
+        let git_config_contents = b"
+[delta \"terminal.kitty\"]
+    true-color = false
+    marks = none
+[delta \"terminal.my-terminal\"]
+    true-color = true
+    marks = iterm2
+";
+        let git_config_path = "delta__test_terminal_profile.gitconfig";
+
+        let opt = make_options_from_args_and_git_config(
+            &["--terminal-profile", "kitty", "--24-bit-color", "auto"],
+            Some(git_config_contents),
+            Some(git_config_path),
+        );
+        let profile = &opt.computed.terminal_profile;
+        assert_eq!(profile.name, "kitty");
+        assert!(!profile.true_color);
+        assert!(profile.hyperlinks);
+        assert_eq!(profile.marks, FileMarks::None);
+
+        let opt = make_options_from_args_and_git_config(
+            &[
+                "--terminal-profile",
+                "my-terminal",
+                "--24-bit-color",
+                "auto",
+            ],
+            Some(git_config_contents),
+            Some(git_config_path),
+        );
+        let profile = &opt.computed.terminal_profile;
+        assert!(profile.true_color);
+        assert!(!profile.hyperlinks);
+        assert_eq!(profile.marks, FileMarks::ITerm2);
+        assert!(opt.computed.true_color);
+
+        remove_file(git_config_path).unwrap();
+    }
+}
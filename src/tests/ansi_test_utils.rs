@@ -122,17 +122,22 @@ pub mod ansi_test_utils {
         painter.set_highlighter();
         let line = format!(" {}", line); // TODO: a leading space must be added, as delta::prepare() does
         let lines = vec![&line];
-        let syntax_style_sections = painter.highlighter.highlight(&line, &config.syntax_set);
+        let syntax_style_sections = painter
+            .highlighter
+            .as_mut()
+            .unwrap()
+            .highlight(&line, config.syntax_set.get());
         paint::Painter::paint_lines(
-            vec![syntax_style_sections],
-            vec![vec![(syntax_highlighted_style, lines[0])]],
+            paint::StyleSections {
+                syntax: vec![syntax_style_sections],
+                diff: vec![vec![(syntax_highlighted_style, lines[0])]],
+            },
             &State::Unknown,
             &mut output_buffer,
             config,
             &mut None,
             "",
-            None,
-            None,
+            0,
         );
         output_buffer
     }
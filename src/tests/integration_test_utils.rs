@@ -8,6 +8,7 @@ pub mod integration_test_utils {
     use console::strip_ansi_codes;
     use itertools;
 
+    use crate::bat::assets::HighlightingAssets;
     use crate::cli;
     use crate::config;
     use crate::delta::delta;
@@ -28,7 +29,7 @@ pub mod integration_test_utils {
                 None
             }
         };
-        cli::Opt::from_iter_and_git_config(args, &mut git_config)
+        cli::Opt::from_iter_and_git_config(args, &mut git_config, HighlightingAssets::new())
     }
 
     pub fn make_options_from_args(args: &[&str]) -> cli::Opt {
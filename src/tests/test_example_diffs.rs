@@ -1,7 +1,11 @@
 #[cfg(test)]
 mod tests {
+    use std::io::BufReader;
+
+    use bytelines::ByteLines;
     use console::strip_ansi_codes;
 
+    use crate::delta;
     use crate::paint;
     use crate::style;
     use crate::tests::ansi_test_utils::ansi_test_utils;
@@ -34,6 +38,107 @@ mod tests {
         assert!(output.contains("\nadded: nospace/file2\n"));
     }
 
+    #[test]
+    fn test_file_marks() {
+        let config = integration_test_utils::make_config_from_args(&["--file-marks", "osc133"]);
+        let output =
+            integration_test_utils::run_delta(ADDED_FILES_DIRECTORY_PATH_CONTAINING_SPACE, &config);
+        assert_eq!(output.matches("\x1b]133;D\x07\x1b]133;C\x07").count(), 2);
+        assert!(output.ends_with("\x1b]133;D\x07"));
+
+        let config = integration_test_utils::make_config_from_args(&["--file-marks", "iterm2"]);
+        let output =
+            integration_test_utils::run_delta(ADDED_FILES_DIRECTORY_PATH_CONTAINING_SPACE, &config);
+        assert_eq!(output.matches("\x1b]1337;SetMark\x07").count(), 2);
+
+        let config = integration_test_utils::make_config_from_args(&[]);
+        let output =
+            integration_test_utils::run_delta(ADDED_FILES_DIRECTORY_PATH_CONTAINING_SPACE, &config);
+        assert!(!output.contains("\x1b]"));
+    }
+
+    #[test]
+    fn test_copy_hunk_location() {
+        // The clipboard is written once, with "nospace/file2:1".
+        let config = integration_test_utils::make_config_from_args(&["--copy-hunk-location"]);
+        let output =
+            integration_test_utils::run_delta(ADDED_FILES_DIRECTORY_PATH_CONTAINING_SPACE, &config);
+        assert_eq!(output.matches("\x1b]52;").count(), 1);
+        assert!(output.contains("\x1b]52;c;bm9zcGFjZS9maWxlMjox\x07"));
+
+        // "with space/file1:1"
+        let config = integration_test_utils::make_config_from_args(&[
+            "--copy-hunk-location",
+            "--select",
+            "with space/file1:1",
+        ]);
+        let output =
+            integration_test_utils::run_delta(ADDED_FILES_DIRECTORY_PATH_CONTAINING_SPACE, &config);
+        assert_eq!(output.matches("\x1b]52;").count(), 1);
+        assert!(output.contains("\x1b]52;c;d2l0aCBzcGFjZS9maWxlMTox\x07"));
+    }
+
+    #[test]
+    fn test_input_displayed_in_parts() {
+        // As when the configuration is reloaded, the second file is displayed with another
+        // configuration, continuing the numbering of the files, and the input is ended once.
+        let (first_part, second_part) = ADDED_FILES_DIRECTORY_PATH_CONTAINING_SPACE.split_at(
+            ADDED_FILES_DIRECTORY_PATH_CONTAINING_SPACE
+                .find("diff --git a/with")
+                .unwrap(),
+        );
+        let config = integration_test_utils::make_config_from_args(&["--numbered"]);
+        let reloaded_config =
+            integration_test_utils::make_config_from_args(&["--numbered", "--light"]);
+        let mut progress = delta::InputProgress::default();
+        let mut writer = Vec::new();
+        for (part, config) in [(first_part, &config), (second_part, &reloaded_config)] {
+            delta::delta_part(
+                ByteLines::new(BufReader::new(part.as_bytes())),
+                &mut writer,
+                config,
+                &mut progress,
+            )
+            .unwrap();
+        }
+        delta::end_input(&mut writer, &progress, &reloaded_config).unwrap();
+        let output = strip_ansi_codes(std::str::from_utf8(&writer).unwrap()).to_string();
+        assert!(output.contains("[2] added: with space/file1"));
+        assert_eq!(output.matches("[1] nospace/file2").count(), 1);
+        assert!(output.ends_with("[2] with space/file1\n    [2.1] line 1\n"));
+    }
+
+    #[test]
+    fn test_concatenated_diffs() {
+        let config = integration_test_utils::make_config_from_args(&["--line-numbers"]);
+        let output =
+            integration_test_utils::run_delta(CONCATENATED_PATCH_EMAIL_AND_DIFF_U, &config);
+        let output = strip_ansi_codes(&output);
+        // The email signature following the hunk is not displayed as part of the hunk.
+        assert!(output.contains("\n-- \n2.30.0\n"));
+        // The second diff is not parsed as if it were a git diff.
+        assert!(output.contains("\ncomparing: old/c.txt ⟶   new/c.txt\n"));
+    }
+
+    #[test]
+    fn test_render_hunk() {
+        let config = integration_test_utils::make_config_from_args(&["--line-numbers"]);
+        let hunk = "@@ -1,2 +1,2 @@ def f():\n a = 1\n-b = 2\n+b = 3\n";
+        let output = delta::render_hunk(hunk, "a.py", "a.py", &config).unwrap();
+        let plain_output = strip_ansi_codes(&output);
+        assert!(!plain_output.contains("a.py"));
+        assert!(plain_output.contains("def f():"));
+        assert!(plain_output.contains(" 2  ⋮    │b = 2"));
+        assert!(plain_output.contains("    ⋮ 2  │b = 3"));
+        // The language is determined by the file name.
+        let output_without_file_name = delta::render_hunk(hunk, "", "", &config).unwrap();
+        assert_eq!(plain_output, strip_ansi_codes(&output_without_file_name));
+        assert_ne!(
+            output.lines().last(),
+            output_without_file_name.lines().last()
+        );
+    }
+
     #[test]
     fn test_renamed_file() {
         let config = integration_test_utils::make_config_from_args(&[]);
@@ -82,6 +187,41 @@ mod tests {
         ansi_test_utils::assert_has_plus_color_only(&output, &config);
     }
 
+    #[test]
+    fn test_syntax_set_is_loaded_lazily() {
+        let config = integration_test_utils::make_config_from_args(&["--syntax-theme", "none"]);
+        integration_test_utils::run_delta(ADDED_FILE_INPUT, &config);
+        assert!(!config.syntax_set.is_loaded());
+
+        let config = integration_test_utils::make_config_from_args(&[]);
+        assert!(!config.syntax_set.is_loaded());
+        integration_test_utils::run_delta(ADDED_FILE_INPUT, &config);
+        assert!(config.syntax_set.is_loaded());
+    }
+
+    #[test]
+    fn test_max_output_lines() {
+        let config = integration_test_utils::make_config_from_args(&["--max-output-lines", "3"]);
+        let output = integration_test_utils::run_delta(DIFF_UNIFIED_TWO_DIRECTORIES, &config);
+        let output = strip_ansi_codes(&output);
+        // The first hunk is displayed in full.
+        assert!(output.contains("This is different from a"));
+        assert!(!output.contains("more_difference"));
+        assert!(!output.contains("Only in"));
+        assert!(output.ends_with("1 more hunk in 3 files not displayed (--max-output-lines 3)\n"));
+
+        let config = integration_test_utils::make_config_from_args(&["--max-output-lines", "1"]);
+        let output = integration_test_utils::run_delta(DIFF_UNIFIED_TWO_FILES, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("Hello ruster"));
+        assert!(!output.contains("Change four"));
+        assert!(output.ends_with("1 more hunk in 1 file not displayed (--max-output-lines 1)\n"));
+
+        let config = integration_test_utils::make_config_from_args(&["--max-output-lines", "1000"]);
+        let output = integration_test_utils::run_delta(DIFF_UNIFIED_TWO_DIRECTORIES, &config);
+        assert!(!output.contains("--max-output-lines"));
+    }
+
     #[test]
     fn test_diff_unified_two_files() {
         let config = integration_test_utils::make_config_from_args(&[]);
@@ -189,6 +329,98 @@ mod tests {
         assert!(output.contains("\nSubmodule x/y/z contains untracked content\n"));
     }
 
+    #[test]
+    fn test_submodule_diff() {
+        let config = integration_test_utils::make_config_from_args(&[]);
+        let output = integration_test_utils::run_delta(SUBMODULE_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\nSubmodule sub ccc9a35..b9d0d51:\n"));
+        assert!(output.contains("\nsub ▸ a.rs\n"));
+        assert!(output.contains("\ntop.txt\n"));
+    }
+
+    #[test]
+    fn test_submodule_log() {
+        let config = integration_test_utils::make_config_from_args(&[]);
+        let output = integration_test_utils::run_delta(SUBMODULE_LOG, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\n  > Add b\n  < Remove c\n"));
+    }
+
+    #[test]
+    fn test_diff_of_a_diff() {
+        let config = integration_test_utils::make_config_from_args(&[]);
+        let output = integration_test_utils::run_delta(DIFF_OF_A_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        let mut lines = output
+            .lines()
+            .skip_while(|line| !line.starts_with("-- a/f"));
+        assert_eq!(lines.next().unwrap(), "-- a/f");
+        assert_eq!(lines.next().unwrap(), "--- a/g");
+        assert_eq!(lines.next().unwrap(), "+++ b/f");
+        assert_eq!(lines.next().unwrap(), "@@ -1 +1 @@");
+        assert_eq!(lines.next().unwrap(), "@@ -2 +2 @@");
+        assert!(!output.contains("comparing: a/f"));
+    }
+
+    #[test]
+    fn test_input_with_hyperlinks_and_colon_separated_colors() {
+        let config = integration_test_utils::make_config_from_args(&[]);
+        let output =
+            integration_test_utils::run_delta(DIFF_WITH_HYPERLINKS_AND_TRUE_COLORS, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(!output.contains("8;;"));
+        let mut lines = output.lines().skip_while(|line| *line != "f.txt");
+        assert_eq!(lines.next().unwrap(), "f.txt");
+        assert!(lines.any(|line| line == "X"));
+    }
+
+    #[test]
+    fn test_zero_context_hunks_with_line_numbers() {
+        let config = integration_test_utils::make_config_from_args(&["--line-numbers"]);
+        let output = integration_test_utils::run_delta(ZERO_CONTEXT_DIFF, &config);
+        let output = strip_ansi_codes(&output);
+        let lines: Vec<&str> = output
+            .lines()
+            .filter(|line| line.contains('│'))
+            .map(|line| line.trim_end())
+            .collect();
+        assert_eq!(lines, vec!["    ⋮ 4  │X", " 6  ⋮    │6", "    ⋮ 11 │Y"]);
+    }
+
+    #[test]
+    fn test_input_is_written_unaltered_after_failure() {
+        let config = integration_test_utils::make_config_from_args(&[]);
+        let output = integration_test_utils::run_delta(DIFF_WITH_CORRUPT_HUNK_HEADER, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.ends_with("\n@@ -99999999999999999999999 +1 @@\n-d\nafter\n"));
+    }
+
+    #[test]
+    fn test_long_lines_are_truncated() {
+        let config = integration_test_utils::make_config_from_args(&["--max-line-length", "10"]);
+        let input = format!(
+            "--- a.js\n+++ a.js\n@@ -1 +1 @@\n-{}\n+{}\n",
+            "a".repeat(10000),
+            "b".repeat(10000)
+        );
+        let output = integration_test_utils::run_delta(&input, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains(&format!("\n{}…\n", "a".repeat(10))));
+        assert!(output.contains(&format!("\n{}…\n", "b".repeat(10))));
+    }
+
+    #[test]
+    fn test_long_lines_are_not_truncated_under_color_only() {
+        let config = integration_test_utils::make_config_from_args(&["--color-only"]);
+        let input = format!(
+            "--- a.js\n+++ a.js\n@@ -1 +1 @@\n-a\n+{}\n",
+            "b".repeat(1500)
+        );
+        let output = integration_test_utils::run_delta(&input, &config);
+        assert!(strip_ansi_codes(&output).contains(&format!("\n+{}\n", "b".repeat(1500))));
+    }
+
     #[test]
     fn test_triple_dash_at_beginning_of_line_in_code() {
         let config = integration_test_utils::make_config_from_args(&[]);
@@ -794,6 +1026,139 @@ src/align.rs
         assert!(output.contains("@@ -71,11 +71,8 @@ impl<'a> Alignment<'a> {"));
     }
 
+    #[test]
+    fn test_hunk_header_format_with_escaped_braces() {
+        let config = integration_test_utils::make_config_from_args(&[
+            "--hunk-header-format",
+            "{{{line}}} {context}",
+            "--hunk-header-style",
+            "normal",
+            "--hunk-header-decoration-style",
+            "omit",
+        ]);
+        let output = integration_test_utils::run_delta(GIT_DIFF_SINGLE_HUNK, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\n{71} impl<'a> Alignment<'a> {\n"));
+    }
+
+    #[test]
+    fn test_hunk_header_format_funcname_without_diff_driver() {
+        let config = integration_test_utils::make_config_from_args(&[
+            "--hunk-header-format",
+            "{funcname}:{line}",
+            "--hunk-header-style",
+            "normal",
+            "--hunk-header-decoration-style",
+            "omit",
+        ]);
+        let output = integration_test_utils::run_delta(GIT_DIFF_SINGLE_HUNK, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\nimpl<'a> Alignment<'a> {:71\n"));
+    }
+
+    #[test]
+    fn test_hunk_header_format_with_line_number_arithmetic() {
+        let config = integration_test_utils::make_config_from_args(&[
+            "--hunk-header-format",
+            "{line+3} {line-1} {line-100} {line*2} {file+1}",
+            "--hunk-header-style",
+            "normal",
+            "--hunk-header-decoration-style",
+            "omit",
+        ]);
+        let output = integration_test_utils::run_delta(GIT_DIFF_SINGLE_HUNK, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\n74 70 0 {line*2} {file+1}\n"));
+    }
+
+    #[test]
+    fn test_flush_per_line() {
+        // Records the output written at each flush.
+        struct FlushRecorder {
+            output: Vec<u8>,
+            flushed: Vec<String>,
+        }
+
+        impl std::io::Write for FlushRecorder {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.output.write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                let output = String::from_utf8_lossy(&self.output);
+                self.flushed.push(strip_ansi_codes(&output).to_string());
+                Ok(())
+            }
+        }
+
+        let config = integration_test_utils::make_config_from_args(&["--flush-per-line"]);
+        let mut writer = FlushRecorder {
+            output: Vec::new(),
+            flushed: Vec::new(),
+        };
+        delta::delta(
+            ByteLines::new(BufReader::new(GIT_DIFF_SINGLE_HUNK.as_bytes())),
+            &mut writer,
+            &config,
+        )
+        .unwrap();
+        assert!(writer.flushed.len() > GIT_DIFF_SINGLE_HUNK.lines().count());
+        // A context line is written before the next line is read.
+        assert!(writer
+            .flushed
+            .iter()
+            .any(|output| output
+                .ends_with("            for (j, y_j) in self.y.iter().enumerate() {\n")));
+    }
+
+    #[test]
+    fn test_line_length_limit() {
+        let config = integration_test_utils::make_config_from_args(&[
+            "--line-length-limit",
+            "30",
+            "--file-line-length-limits",
+            "*.md=100",
+        ]);
+        let output = integration_test_utils::run_delta(GIT_DIFF_SINGLE_HUNK, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("self.index(i + 1, j)); ← 83\n"));
+        assert!(output.contains("\n                let (left, diag, up) = ← 38\n"));
+        // Only added lines are marked.
+        assert!(output.contains("\n                let (left, diag, up) = (\n"));
+        assert!(output.contains("\n        for (i, x_i) in self.x.iter().enumerate() {\n"));
+
+        let input = GIT_DIFF_SINGLE_HUNK.replace("src/align.rs", "README.md");
+        let output = integration_test_utils::run_delta(&input, &config);
+        assert!(!strip_ansi_codes(&output).contains('←'));
+    }
+
+    #[test]
+    fn test_inline_hunk_headers() {
+        let config = integration_test_utils::make_config_from_args(&[
+            "--inline-hunk-headers",
+            "--inline-hunk-header-style",
+            "red",
+        ]);
+        let output = integration_test_utils::run_delta(GIT_DIFF_SINGLE_HUNK, &config);
+        assert!(output.contains(&config.inline_hunk_header_style.paint("71-78:").to_string()));
+        let output = strip_ansi_codes(&output);
+        assert!(!output.contains("impl<'a> Alignment<'a>"));
+        assert!(output.contains("─\n71-78: \n        for (i, x_i)"));
+    }
+
+    #[test]
+    fn test_sdiff_input() {
+        let config = integration_test_utils::make_config_from_args(&["--side-by-side"]);
+        let input = "x\t\t\t\t\t\t\t\tx\nfoo bar\t\t\t\t\t\t\t      |\tfoo baz\n";
+        let output = integration_test_utils::run_delta(input, &config);
+        let output = strip_ansi_codes(&output);
+        assert!(!output.contains('|'));
+        let mut lines = output.lines().skip(1);
+        let (line_1, line_2) = (lines.next().unwrap(), lines.next().unwrap());
+        assert!(line_1.starts_with("│ 1  │x ") && line_1.ends_with("│ 1  │x"));
+        assert!(line_2.starts_with("│ 2  │foo bar ") && line_2.ends_with("│ 2  │foo baz"));
+    }
+
     #[test]
     fn test_hunk_header_style_colored_input_color_is_stripped_under_normal() {
         let config = integration_test_utils::make_config_from_args(&[
@@ -1124,6 +1489,52 @@ impl<'a> Alignment<'a> { │
         );
     }
 
+    #[test]
+    fn test_diff_filter_mode_preserves_lines() {
+        // git's interactive.diffFilter must output one line for each line of its input, whatever
+        // the options, and git applies the input rather than what is displayed.
+        let input = [GIT_DIFF_SINGLE_HUNK, ZERO_CONTEXT_DIFF, RENAMED_FILE_INPUT].concat();
+        let line_altering_args: &[&[&str]] = &[
+            &["--side-by-side"],
+            &["--line-numbers"],
+            &["--word-diff-mode"],
+            &["--distant-context", "1", "--collapse-distant-context"],
+            &["--expand-context", "5"],
+            &["--max-hunk-lines", "1"],
+            &["--max-output-lines", "2"],
+            &["--numbered"],
+            &["--stats"],
+            &["--structured-diff"],
+            &["--select", "1"],
+            &["--hide-hunks-matching", "self"],
+            &["--hide-files-matching", "align"],
+            &["--include-path", "*.txt"],
+            &["--exclude-path", "*.rs"],
+            &["--invert"],
+            &["--reverse-hunks"],
+            &["--collapse-noise-hunks"],
+            &["--inline-hunk-headers"],
+            &["--commit-message-markdown"],
+            &["--file-stats"],
+            &["--file-marks", "osc133"],
+            &["--hunk-badges"],
+            &["--line-length-limit", "10"],
+            &["--emit-patch"],
+        ];
+        for args in line_altering_args {
+            let config = integration_test_utils::make_config_from_args(
+                &[&["--diff-filter-mode"], *args].concat(),
+            );
+            let output = integration_test_utils::run_delta(&input, &config);
+            assert_eq!(
+                strip_ansi_codes(&output),
+                input,
+                "--diff-filter-mode {}",
+                args.join(" ")
+            );
+        }
+    }
+
     #[test]
     fn test_color_only() {
         let config = integration_test_utils::make_config_from_args(&["--color-only"]);
@@ -1305,6 +1716,35 @@ index 0000000..84d55c5
 +++ b/with space/file1
 @@ -0,0 +1 @@
 +file1 contents
+";
+
+    const CONCATENATED_PATCH_EMAIL_AND_DIFF_U: &str = "\
+From 94907c0f136f46dc46ffae2dc92dca9af7eb7c2e Mon Sep 17 00:00:00 2001
+From: Delta <delta@example.com>
+Date: Thu, 1 Jan 1970 00:00:00 +0000
+Subject: [PATCH] Change a
+
+---
+ a.py | 2 +-
+ 1 file changed, 1 insertion(+), 1 deletion(-)
+
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,2 +1,2 @@
+ a = 1
+-b = 2
++b = 3
+-- 
+2.30.0
+
+diff -u old/c.txt new/c.txt
+--- old/c.txt	2020-01-01 00:00:00.000000000 +0000
++++ new/c.txt	2020-01-01 00:00:00.000000000 +0000
+@@ -1 +1 @@
+-c
++d
 ";
 
     const RENAMED_FILE_INPUT: &str = "\
@@ -1382,6 +1822,83 @@ This is a regular file that contains:
  y
  x
 Submodule x/y/z contains untracked content
+";
+
+    const SUBMODULE_DIFF: &str = "\
+Submodule sub ccc9a35..b9d0d51:
+diff --git a/sub/a.rs b/sub/a.rs
+index ca05282..83021ca 100644
+--- a/sub/a.rs
++++ b/sub/a.rs
+@@ -1 +1,2 @@
+ fn a() {}
++fn b() {}
+diff --git a/top.txt b/top.txt
+index 587be6b..b77b4eb 100644
+--- a/top.txt
++++ b/top.txt
+@@ -1 +1,2 @@
+ x
++y
+";
+
+    const SUBMODULE_LOG: &str = "\
+Submodule sub ccc9a35..b9d0d51:
+  > Add b
+  < Remove c
+diff --git a/top.txt b/top.txt
+index 587be6b..b77b4eb 100644
+--- a/top.txt
++++ b/top.txt
+@@ -1 +1,2 @@
+ x
++y
+";
+
+    const DIFF_OF_A_DIFF: &str = "\
+--- x.patch	2020-06-01 12:00:00.000000000 +0100
++++ x.patch	2020-06-01 12:00:00.000000000 +0100
+@@ -1,3 +1,3 @@
+--- a/f
++--- a/g
+ +++ b/f
+-@@ -1 +1 @@
++@@ -2 +2 @@
+";
+
+    const ZERO_CONTEXT_DIFF: &str = "\
+diff --git a/f.txt b/f.txt
+index f00c965..8b0df7e 100644
+--- a/f.txt
++++ b/f.txt
+@@ -3,0 +4 @@
++X
+@@ -6 +6,0 @@
+-6
+@@ -10,0 +11 @@
++Y
+";
+
+    const DIFF_WITH_HYPERLINKS_AND_TRUE_COLORS: &str = "\
+\x1b[1mdiff --git a/f.txt b/f.txt\x1b[m
+\x1b[1mindex f00c965..8b0df7e 100644\x1b[m
+\x1b[1m--- a/\x1b]8;;file:///f.txt\x1b\\f.txt\x1b]8;;\x1b\\\x1b[m
+\x1b[1m+++ b/\x1b]8;;file:///f.txt\x1b\\f.txt\x1b]8;;\x1b\\\x1b[m
+\x1b[38:2::0:128:128m@@ -3,0 +4 @@\x1b[m
+\x1b[38:2::0:255:0m+X\x1b[m
+";
+
+    const DIFF_WITH_CORRUPT_HUNK_HEADER: &str = "\
+diff --git a/x b/x
+--- a/x
++++ b/x
+@@ -1,2 +1,2 @@
+-a
++b
+ c
+@@ -99999999999999999999999 +1 @@
+-d
+after
 ";
 
     const TRIPLE_DASH_AT_BEGINNING_OF_LINE_IN_CODE: &str = "\
@@ -0,0 +1,323 @@
+use std::io::{self, BufReader, Write};
+
+use bytelines::ByteLines;
+use console::strip_ansi_codes;
+
+use crate::config::Config;
+use crate::delta::delta;
+use crate::parse;
+
+// Under --validate, the input is displayed as usual, and the output is then checked against the
+// input, so that tools which rely on the correspondence between them (e.g. git's
+// interactive.diffFilter, which requires one line of output per line of input) can be checked.
+// Under the raw and color-only features, the output, with colors removed, must be the input, line
+// for line. Otherwise, each line of each hunk must be displayed as a line of the output, in order,
+// and consecutive hunk lines as consecutive output lines. Side-by-side hunks are not checked.
+
+// Hunk lines are compared by their leading non-whitespace characters, which survive tab expansion,
+// line numbers, and truncation of long lines.
+const COMPARED_CHARS: usize = 64;
+
+/// Display `input` with delta and return the violations of the input/output correspondence.
+pub fn render_and_validate(
+    input: &[u8],
+    writer: &mut dyn Write,
+    features: &str,
+    config: &Config,
+) -> io::Result<Vec<String>> {
+    let mut output = Vec::new();
+    delta(ByteLines::new(BufReader::new(input)), &mut output, config)?;
+    writer.write_all(&output)?;
+    let color_only = features
+        .split_whitespace()
+        .any(|feature| feature == "raw" || feature == "color-only");
+    Ok(find_violations(
+        &String::from_utf8_lossy(input),
+        &String::from_utf8_lossy(&output),
+        color_only,
+        config,
+    ))
+}
+
+fn find_violations(input: &str, output: &str, color_only: bool, config: &Config) -> Vec<String> {
+    let input_lines: Vec<String> = input.lines().map(|l| strip_ansi_codes(l).into()).collect();
+    let output_lines: Vec<String> = output.lines().map(|l| strip_ansi_codes(l).into()).collect();
+    let mut violations = Vec::new();
+    if color_only {
+        if input_lines.len() != output_lines.len() {
+            violations.push(format!(
+                "the output has {} lines but the input has {}",
+                output_lines.len(),
+                input_lines.len()
+            ));
+        }
+        for (i, (input_line, output_line)) in input_lines.iter().zip(&output_lines).enumerate() {
+            if input_line != output_line {
+                violations.push(format!(
+                    "input line {}: output line differs when colors are removed",
+                    i + 1
+                ));
+            }
+        }
+        return violations;
+    }
+    if config.side_by_side {
+        return violations;
+    }
+    let output_lines: Vec<String> = output_lines.iter().map(|l| normalize(l, None)).collect();
+    let mut cursor = 0;
+    for run in get_hunk_line_runs(&input_lines) {
+        // A line of a run may also be found in the decorations, e.g. in a hunk header, so the run
+        // is found where all its lines are displayed consecutively.
+        let is_displayed_at = |start: usize| {
+            run.iter().enumerate().all(|(i, (_, content))| {
+                output_lines
+                    .get(start + i)
+                    .map_or(false, |line| line.contains(content))
+            })
+        };
+        if let Some(start) = (cursor..output_lines.len()).find(|&start| is_displayed_at(start)) {
+            cursor = start + run.len();
+            continue;
+        }
+        // Otherwise, find the run by its first non-blank line, since a blank line matches any
+        // output line, and report the first line which is not displayed after it.
+        let anchor = match run.iter().position(|(_, content)| !content.is_empty()) {
+            Some(anchor) => anchor,
+            None => continue,
+        };
+        let start = match (cursor + anchor..output_lines.len())
+            .find(|&j| output_lines[j].contains(&run[anchor].1))
+        {
+            Some(j) => j - anchor,
+            None => {
+                violations.push(format!(
+                    "input line {}: hunk line not found in the output",
+                    run[anchor].0
+                ));
+                continue;
+            }
+        };
+        for (i, (line_number, content)) in run.iter().enumerate() {
+            if !output_lines
+                .get(start + i)
+                .map_or(false, |line| line.contains(content))
+            {
+                violations.push(format!(
+                    "input line {}: hunk line not displayed as output line {}",
+                    line_number,
+                    start + i + 1
+                ));
+                break;
+            }
+        }
+        cursor = start + run.len();
+    }
+    violations
+}
+
+/// Return the runs of consecutive hunk lines of the input, as (line number, normalized content)
+/// pairs. Hunks of combined diffs are skipped.
+fn get_hunk_line_runs(input_lines: &[String]) -> Vec<Vec<(usize, String)>> {
+    let mut runs = Vec::new();
+    let mut run = Vec::new();
+    let mut remaining: Option<(usize, usize)> = None;
+    for (i, line) in input_lines.iter().enumerate() {
+        if let Some((minus, plus)) = remaining {
+            let counts = match line.chars().next() {
+                Some(' ') | None => Some((minus.saturating_sub(1), plus.saturating_sub(1))),
+                Some('-') => Some((minus.saturating_sub(1), plus)),
+                Some('+') => Some((minus, plus.saturating_sub(1))),
+                // "\ No newline at end of file"
+                Some('\\') => {
+                    runs.push(std::mem::take(&mut run));
+                    continue;
+                }
+                _ => None,
+            };
+            if counts.is_some() {
+                let content = line.get(1..).unwrap_or("");
+                run.push((i + 1, normalize(content, Some(COMPARED_CHARS))));
+            }
+            remaining = counts.filter(|&counts| counts != (0, 0));
+            if remaining.is_some() {
+                continue;
+            }
+            runs.push(std::mem::take(&mut run));
+        }
+        if line.starts_with("@@ ") {
+            if let Some((_, coordinates)) = parse::try_parse_hunk_header(line) {
+                if let [(_, minus), (_, plus)] = coordinates[..] {
+                    remaining = Some((minus, plus)).filter(|&counts| counts != (0, 0));
+                }
+            }
+        }
+    }
+    runs.push(run);
+    runs.retain(|run| !run.is_empty());
+    runs
+}
+
+/// Remove the whitespace from a line, and keep at most `max_chars` characters.
+fn normalize(line: &str, max_chars: Option<usize>) -> String {
+    line.chars()
+        .filter(|c| !c.is_whitespace())
+        .take(max_chars.unwrap_or(usize::MAX))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, make_options_from_args,
+    };
+
+    fn validate(args: &[&str], input: &str) -> Vec<String> {
+        let config = make_config_from_args(args);
+        let features = make_options_from_args(args).features;
+        let mut writer = Vec::new();
+        render_and_validate(input.as_bytes(), &mut writer, &features, &config).unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&[], DIFF).is_empty());
+        assert!(validate(&["--line-numbers", "--keep-plus-minus-markers"], DIFF).is_empty());
+        assert!(validate(&["--color-only"], DIFF).is_empty());
+        assert!(validate(&["--color-only"], DIFF_WITH_SPECIAL_LINES).is_empty());
+        assert!(validate(&["--raw"], DIFF).is_empty());
+        assert!(validate(&["--side-by-side"], DIFF).is_empty());
+    }
+
+    #[test]
+    fn test_validate_diff_filter_mode() {
+        // As git's interactive.diffFilter, delta must output its input with colors added, whatever
+        // the other options.
+        let input = [
+            COMMIT,
+            DIFF,
+            &DIFF_WITH_SPECIAL_LINES.replace("LONG", &"x".repeat(1100)),
+        ]
+        .concat();
+        let line_altering_args: &[&[&str]] = &[
+            &[],
+            &["--features", "diff-so-fancy"],
+            &["--diff-so-fancy"],
+            &["--diff-highlight"],
+            &["--accessible"],
+            &["--ascii"],
+            &["--navigate"],
+            &["--redact", r"token = (\S+)"],
+            &["--max-line-length", "10"],
+            &["--tabs", "2"],
+            &["--file-tabs", "*.py=2"],
+            &["--hunk-header-format", "{file}:{line}"],
+            &["--hunk-label", "#"],
+            &["--inline-hunk-headers"],
+            &["--file-labels", "x,y"],
+            &["--file-stats"],
+            &["--strip-path-prefix", "a"],
+            &["--commit-date-format", "relative"],
+            &["--commit-message-markdown"],
+            &["--highlight", "a"],
+            &["--blame"],
+            &["--grep"],
+            &["--expand-context", "5"],
+            &["--word-diff-mode"],
+            &["--line-numbers"],
+            &["--side-by-side"],
+            &["--copy-hunk-location"],
+            &["--commit-links"],
+        ];
+        for args in line_altering_args {
+            let args = [&["--diff-filter-mode"], *args].concat();
+            assert_eq!(
+                validate(&args, &input),
+                Vec::<String>::new(),
+                "{}",
+                args.join(" ")
+            );
+        }
+    }
+
+    #[test]
+    fn test_hunk_line_found_in_hunk_header() {
+        let diff = "\
+diff --git a/a.rs b/a.rs
+index 223ca50..e69de29 100644
+--- a/a.rs
++++ b/a.rs
+@@ -10,3 +10,3 @@ fn f(a: u8)
+ )
+-    b
++    c
+ }
+";
+        assert_eq!(validate(&[], diff), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_find_violations() {
+        let config = make_config_from_args(&[]);
+        let output = "a.py\n a = 1\n b = 2\n c = 3\n    return x\n";
+        assert_eq!(
+            find_violations(DIFF, output, false, &config),
+            vec![
+                "input line 8: hunk line not displayed as output line 4",
+                "input line 11: hunk line not found in the output"
+            ]
+        );
+        assert_eq!(
+            find_violations(DIFF, "", false, &config),
+            vec![
+                "input line 6: hunk line not found in the output",
+                "input line 11: hunk line not found in the output"
+            ]
+        );
+        let output = DIFF.replacen("b = 2", "b = 1", 1);
+        assert_eq!(
+            find_violations(DIFF, &output, true, &config),
+            vec!["input line 7: output line differs when colors are removed"]
+        );
+        assert_eq!(
+            find_violations(DIFF, "a\n", true, &config)[0],
+            "the output has 1 lines but the input has 11"
+        );
+    }
+
+    const COMMIT: &str = "\
+commit 94907c0f136f46dc46ffae2dc92dca9af7eb7c2e
+Author: Dan Davison <dandavison7@gmail.com>
+Date:   Thu May 14 11:13:17 2020 -0400
+
+    **Bold** `code`
+
+";
+
+    const DIFF_WITH_SPECIAL_LINES: &str = "\
+diff --git a/b.py b/b.py
+index 223ca50..e69de29 100644
+--- a/b.py
++++ b/b.py
+@@ -1,2 +1,3 @@ def g():
+ \ta = 1
+-token = abc\x07
++token = def\x07
++LONG
+";
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,3 +1,3 @@
+ a = 1
+-b = 2
++b = 3
+ c = 3
+@@ -10 +10 @@ def f():
+-\treturn x
+";
+}
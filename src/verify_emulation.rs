@@ -0,0 +1,152 @@
+use std::fs;
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+use std::process;
+
+use bytelines::ByteLines;
+use console::strip_ansi_codes;
+
+use crate::config::Config;
+use crate::delta::delta;
+
+/// For every file in `corpus_dir`, compare delta's output with that of the script being emulated
+/// (diff-highlight or diff-so-fancy, according to the active features). Colors are ignored. Return
+/// true if all outputs agree.
+pub fn verify_emulation(corpus_dir: &Path, features: &str, config: &Config) -> io::Result<bool> {
+    let command = match get_emulated_command(features) {
+        Some(command) => command,
+        None => {
+            eprintln!("--verify-emulation requires the diff-highlight or diff-so-fancy feature.");
+            process::exit(1);
+        }
+    };
+    let mut paths = fs::read_dir(corpus_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.retain(|path| path.is_file());
+    paths.sort();
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let mut n_agreed = 0;
+    for path in &paths {
+        let input = fs::read(path)?;
+        let expected = run_command(command, &input)?;
+        let mut actual = Vec::new();
+        delta(
+            ByteLines::new(BufReader::new(&input[..])),
+            &mut actual,
+            config,
+        )?;
+        match find_first_difference(
+            &String::from_utf8_lossy(&expected),
+            &String::from_utf8_lossy(&actual),
+        ) {
+            None => {
+                n_agreed += 1;
+                writeln!(stdout, "ok      {}", path.display())?;
+            }
+            Some((line_number, expected_line, actual_line)) => {
+                writeln!(stdout, "differs {}:{}", path.display(), line_number)?;
+                writeln!(stdout, "    {}: {}", command, expected_line)?;
+                writeln!(stdout, "    delta: {}", actual_line)?;
+            }
+        }
+    }
+    writeln!(
+        stdout,
+        "{} of {} files agree with {}",
+        n_agreed,
+        paths.len(),
+        command
+    )?;
+    Ok(n_agreed == paths.len())
+}
+
+fn get_emulated_command(features: &str) -> Option<&'static str> {
+    let features: Vec<&str> = features.split_whitespace().collect();
+    if features.contains(&"diff-so-fancy") {
+        Some("diff-so-fancy")
+    } else if features.contains(&"diff-highlight") {
+        Some("diff-highlight")
+    } else {
+        None
+    }
+}
+
+fn run_command(command: &str, input: &[u8]) -> io::Result<Vec<u8>> {
+    let mut child = process::Command::new(command)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|error| {
+            eprintln!("Failed to run {}: {}", command, error);
+            process::exit(1);
+        });
+    // Write the input from a separate thread, so that a script which writes its output before it
+    // has read all of its input cannot deadlock.
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    writer.join().unwrap()?;
+    Ok(output.stdout)
+}
+
+/// Return the (1-based) line number and the two lines at the first point at which the outputs
+/// differ, ignoring colors and trailing whitespace.
+fn find_first_difference(expected: &str, actual: &str) -> Option<(usize, String, String)> {
+    let expected = strip_ansi_codes(expected);
+    let actual = strip_ansi_codes(actual);
+    let mut expected_lines = expected.lines().map(str::trim_end);
+    let mut actual_lines = actual.lines().map(str::trim_end);
+    let mut line_number = 0;
+    loop {
+        line_number += 1;
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => return None,
+            (Some(e), Some(a)) if e == a => continue,
+            (e, a) => {
+                return Some((
+                    line_number,
+                    e.unwrap_or("<end of output>").to_string(),
+                    a.unwrap_or("<end of output>").to_string(),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_emulated_command() {
+        assert_eq!(
+            get_emulated_command("diff-highlight"),
+            Some("diff-highlight")
+        );
+        assert_eq!(
+            get_emulated_command("line-numbers diff-so-fancy"),
+            Some("diff-so-fancy")
+        );
+        assert_eq!(get_emulated_command("diff-highlighter"), None);
+    }
+
+    #[test]
+    fn test_find_first_difference() {
+        assert_eq!(
+            find_first_difference("a\n\x1b[31m-b\x1b[m\n", "a\n-b  \n"),
+            None
+        );
+        assert_eq!(
+            find_first_difference("a\n-b\n", "a\nb\n"),
+            Some((2, "-b".to_string(), "b".to_string()))
+        );
+        assert_eq!(
+            find_first_difference("a\n", "a\nb\n"),
+            Some((2, "<end of output>".to_string(), "b".to_string()))
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use serde_json::json;
+
+use crate::cli::WarningFormat;
+use crate::config::Config;
+
+// Warnings are written to stderr, one per line, rather than to the output, which may be in a
+// pager. Each has a kind, so that programs which run delta, such as editors, can recognize and
+// display them: under --warning-format=text a warning starts with "delta: warning: KIND: ", and
+// under --warning-format=json it is a JSON object.
+//
+// The kinds are
+//   degraded:            expensive features have been disabled for the remainder of the input
+//   input-not-processed: delta failed to process the input and is displaying the rest unaltered
+//   line-plugin:         the --line-plugin command has failed
+//   no-selection:        the --select file or hunk is not in the input
+//   unknown-option:      the git config has a delta setting which is not an option
+
+/// Write a warning to stderr, in the --warning-format of the config.
+pub fn warn(kind: &str, message: &str, config: &Config) {
+    eprintln!("{}", format_warning(kind, message, config.warning_format));
+}
+
+fn format_warning(kind: &str, message: &str, format: WarningFormat) -> String {
+    match format {
+        WarningFormat::Text => format!("delta: warning: {}: {}", kind, message),
+        WarningFormat::Json => json!({
+            "source": "delta",
+            "level": "warning",
+            "kind": kind,
+            "message": message,
+        })
+        .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_warning() {
+        assert_eq!(
+            format_warning(
+                "no-selection",
+                "--select 2: no such file",
+                WarningFormat::Text
+            ),
+            "delta: warning: no-selection: --select 2: no such file"
+        );
+        assert_eq!(
+            format_warning("degraded", "a \"b\"", WarningFormat::Json),
+            r#"{"kind":"degraded","level":"warning","message":"a \"b\"","source":"delta"}"#
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use syntect::highlighting::Style as SyntectStyle;
+
+use crate::config::Config;
+use crate::control_chars::{self, SymbolRanges};
+use crate::delta::State;
+use crate::edits;
+use crate::features::line_numbers;
+use crate::paint::{LineSections, Painter, StyleSections};
+use crate::style::Style;
+
+// Under --word-diff-mode, each homologous pair of removed and added lines is displayed as a
+// single line, in which the removed words are struck through and the added words follow them.
+// Removed and added lines which have no homolog are displayed as usual.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operation {
+    Unchanged,
+    Deletion,
+    Insertion,
+}
+
+/// Paint the minus and plus lines of a hunk's run of changed lines, merging homologous pairs.
+pub fn paint_minus_and_plus_lines_merged(
+    (minus_lines, plus_lines): (&[String], &[String]),
+    (minus_symbol_ranges, plus_symbol_ranges): (&[SymbolRanges], &[SymbolRanges]),
+    (minus_syntax_style_sections, plus_syntax_style_sections): (
+        Vec<LineSections<SyntectStyle>>,
+        Vec<LineSections<SyntectStyle>>,
+    ),
+    output_buffer: &mut String,
+    config: &Config,
+    line_numbers_data: &mut Option<&mut line_numbers::LineNumbersData>,
+    line_length_limit: usize,
+) {
+    let (annotated_minus_lines, annotated_plus_lines, line_alignment) = edits::infer_edits(
+        minus_lines,
+        plus_lines,
+        Operation::Unchanged,
+        Operation::Deletion,
+        Operation::Unchanged,
+        Operation::Insertion,
+        &config.tokenization_regex,
+        config.max_line_distance,
+        config.max_line_distance_for_naively_paired_lines,
+    );
+    let deletion_style = Style {
+        ansi_term_style: config.minus_emph_style.ansi_term_style.strikethrough(),
+        ..config.minus_emph_style
+    };
+    for (minus_index, plus_index) in line_alignment {
+        let (syntax_sections, diff_sections, state, marker) = match (minus_index, plus_index) {
+            (Some(i), Some(j)) => {
+                let (syntax_sections, diff_sections) = merge(
+                    (
+                        &annotated_minus_lines[i],
+                        &minus_syntax_style_sections[i],
+                        &minus_symbol_ranges[i],
+                    ),
+                    (
+                        &annotated_plus_lines[j],
+                        &plus_syntax_style_sections[j],
+                        &plus_symbol_ranges[j],
+                    ),
+                    deletion_style,
+                    config,
+                );
+                (syntax_sections, diff_sections, State::HunkZero, " ")
+            }
+            (Some(i), None) => (
+                minus_syntax_style_sections[i].clone(),
+                control_chars::style_control_char_sections(
+                    vec![(config.minus_style, minus_lines[i].as_str())],
+                    &minus_symbol_ranges[i],
+                    config,
+                ),
+                State::HunkMinus,
+                "-",
+            ),
+            (None, Some(j)) => (
+                plus_syntax_style_sections[j].clone(),
+                control_chars::style_control_char_sections(
+                    vec![(config.plus_style, plus_lines[j].as_str())],
+                    &plus_symbol_ranges[j],
+                    config,
+                ),
+                State::HunkPlus,
+                "+",
+            ),
+            (None, None) => continue,
+        };
+        Painter::paint_lines(
+            StyleSections {
+                syntax: vec![syntax_sections],
+                diff: vec![diff_sections],
+            },
+            &state,
+            output_buffer,
+            config,
+            line_numbers_data,
+            Painter::get_line_prefix(&state, marker, config),
+            line_length_limit,
+        );
+    }
+}
+
+// A line of a homologous pair: its annotated sections, syntax style sections, and control
+// character symbol ranges
+type MergedLine<'a, 'b> = (
+    &'b [(Operation, &'a str)],
+    &'b [(SyntectStyle, &'a str)],
+    &'b [(usize, usize)],
+);
+
+/// Merge the annotated sections of a homologous pair of minus and plus lines into the sections of
+/// a single line: removed text precedes added text, and unchanged text is taken from the plus
+/// line. The newline which may terminate the minus line is dropped, since the plus line supplies
+/// the terminator of the merged line. Each line is given as its annotated sections, its syntax
+/// style sections, and the ranges of its control character symbols. Return the syntax and diff
+/// style sections of the merged line.
+fn merge<'a>(
+    (minus_sections, minus_syntax_sections, minus_symbol_ranges): MergedLine<'a, '_>,
+    (plus_sections, plus_syntax_sections, plus_symbol_ranges): MergedLine<'a, '_>,
+    deletion_style: Style,
+    config: &Config,
+) -> (LineSections<'a, SyntectStyle>, LineSections<'a, Style>) {
+    let mut syntax_sections = Vec::new();
+    let mut diff_sections = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let (mut minus_offset, mut plus_offset) = (0, 0);
+    loop {
+        let (syntax, style, text, offset, symbol_ranges) =
+            match (minus_sections.get(i), plus_sections.get(j)) {
+                (Some((Operation::Deletion, text)), _) => {
+                    i += 1;
+                    let start = minus_offset;
+                    minus_offset += text.len();
+                    let text = text.trim_end_matches('\n');
+                    (
+                        syntax_sections_in_range(minus_syntax_sections, start, start + text.len()),
+                        deletion_style,
+                        text,
+                        start,
+                        minus_symbol_ranges,
+                    )
+                }
+                (_, Some((operation, text))) => {
+                    if *operation == Operation::Unchanged {
+                        // The unchanged section of the minus line has the same text.
+                        if let Some((_, minus_text)) = minus_sections.get(i) {
+                            minus_offset += minus_text.len();
+                        }
+                        i += 1;
+                    }
+                    j += 1;
+                    plus_offset += text.len();
+                    (
+                        syntax_sections_in_range(
+                            plus_syntax_sections,
+                            plus_offset - text.len(),
+                            plus_offset,
+                        ),
+                        if *operation == Operation::Insertion {
+                            config.plus_emph_style
+                        } else {
+                            config.zero_style
+                        },
+                        *text,
+                        plus_offset - text.len(),
+                        plus_symbol_ranges,
+                    )
+                }
+                (Some((_, text)), None) => {
+                    i += 1;
+                    let start = minus_offset;
+                    minus_offset += text.len();
+                    let text = text.trim_end_matches('\n');
+                    (
+                        syntax_sections_in_range(minus_syntax_sections, start, start + text.len()),
+                        config.zero_style,
+                        text,
+                        start,
+                        minus_symbol_ranges,
+                    )
+                }
+                (None, None) => break,
+            };
+        syntax_sections.extend(syntax);
+        diff_sections.extend(control_chars::style_control_chars_in_section(
+            (style, text),
+            offset,
+            symbol_ranges,
+            config,
+        ));
+    }
+    (syntax_sections, diff_sections)
+}
+
+/// Return the parts of the syntax style sections of a line which lie within the byte range.
+fn syntax_sections_in_range<'a>(
+    sections: &[(SyntectStyle, &'a str)],
+    start: usize,
+    end: usize,
+) -> Vec<(SyntectStyle, &'a str)> {
+    let mut sections_in_range = Vec::new();
+    let mut offset = 0;
+    for (style, text) in sections {
+        let (section_start, section_end) = (offset, offset + text.len());
+        offset = section_end;
+        let (i, j) = (section_start.max(start), section_end.min(end));
+        if i < j {
+            sections_in_range.push((*style, &text[i - section_start..j - section_start]));
+        }
+    }
+    sections_in_range
+}
+
+#[cfg(test)]
+mod tests {
+    use console::strip_ansi_codes;
+
+    use crate::style::Style;
+    use crate::tests::integration_test_utils::integration_test_utils::{
+        make_config_from_args, run_delta,
+    };
+
+    #[test]
+    fn test_word_diff_mode() {
+        let config = make_config_from_args(&[
+            "--word-diff-mode",
+            "--minus-emph-style",
+            "red",
+            "--plus-emph-style",
+            "green",
+            "--zero-style",
+            "normal",
+            "--syntax-theme",
+            "none",
+        ]);
+        let output = run_delta(DIFF, &config);
+        let deletion_style = Style::from_str("red strike", None, None, true, false);
+        let insertion_style = Style::from_str("green", None, None, true, false);
+        assert!(output.contains(&deletion_style.paint("2").to_string()));
+        assert!(output.contains(&insertion_style.paint("3").to_string()));
+        let output = strip_ansi_codes(&output);
+        assert!(output.contains("\na = 1\nb = 23\nc = 4\nd = 5\n"));
+    }
+
+    #[test]
+    fn test_word_diff_mode_line_numbers() {
+        let config = make_config_from_args(&["--word-diff-mode", "--line-numbers"]);
+        let output = strip_ansi_codes(&run_delta(DIFF, &config)).to_string();
+        assert!(output.contains(" 2  ⋮ 2  │b = 23\n"));
+        assert!(output.contains("    ⋮ 3  │c = 4\n"));
+        assert!(output.contains(" 3  ⋮ 4  │d = 5\n"));
+    }
+
+    #[test]
+    fn test_word_diff_mode_accessible() {
+        let config = make_config_from_args(&["--word-diff-mode", "--accessible"]);
+        let output = strip_ansi_codes(&run_delta(DIFF, &config)).to_string();
+        assert!(output.contains("\nremoved: b = 2\nadded: b = 3\n"));
+    }
+
+    const DIFF: &str = "\
+diff --git a/a.py b/a.py
+index 223ca50..e69de29 100644
+--- a/a.py
++++ b/a.py
+@@ -1,3 +1,4 @@
+ a = 1
+-b = 2
++b = 3
++c = 4
+ d = 5
+";
+}